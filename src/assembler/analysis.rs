@@ -0,0 +1,138 @@
+//! Static summary of a disassembled program
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::Serialize;
+
+use super::disassembler::Disassembly;
+use crate::opcodes::*;
+use crate::types::RunMode;
+
+/// Run modes a program can be evaluated in, judged by its opcodes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModeRequirement {
+    /// Every opcode runs in both modes
+    Any,
+    /// Some opcode only runs as a logic signature
+    Signature,
+    /// Some opcode only runs in an application
+    Application,
+    /// Opcodes of both single-mode kinds, so the program cannot run at all
+    Conflicting,
+}
+
+impl fmt::Display for ModeRequirement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Any => "signature or application",
+            Self::Signature => "signature only",
+            Self::Application => "application only",
+            Self::Conflicting => "none (mixes signature and application opcodes)",
+        })
+    }
+}
+
+/// A subroutine called with `callsub`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Subroutine {
+    pub label: String,
+    pub pc: usize,
+    /// Number of `callsub` instructions calling it
+    pub calls: usize,
+}
+
+/// Summary of a program
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Analysis {
+    /// Version from the program header
+    pub version: u64,
+    /// Lowest version providing every opcode used
+    pub min_version: u8,
+    pub mode: ModeRequirement,
+    pub size: usize,
+    pub instructions: usize,
+    /// Sum of the opcode costs, counting each instruction once
+    pub static_cost: u64,
+    /// Uses of each opcode, most used first
+    pub opcodes: Vec<(String, usize)>,
+    /// Values of each `intcblock`
+    pub int_blocks: Vec<Vec<String>>,
+    /// Values of each `bytecblock`
+    pub byte_blocks: Vec<Vec<String>>,
+    /// Subroutine entry points in pc order
+    pub subroutines: Vec<Subroutine>,
+}
+
+impl Analysis {
+    /// Analyze a program
+    pub fn new(disassembly: &Disassembly, size: usize) -> Self {
+        let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+        let mut calls: BTreeMap<usize, usize> = BTreeMap::new();
+        let mut analysis = Self {
+            version: disassembly.version,
+            min_version: 1,
+            mode: ModeRequirement::Any,
+            size,
+            instructions: disassembly.instructions.len(),
+            static_cost: 0,
+            opcodes: Vec::new(),
+            int_blocks: Vec::new(),
+            byte_blocks: Vec::new(),
+            subroutines: Vec::new(),
+        };
+
+        for instruction in &disassembly.instructions {
+            let Some(spec) = opcode_by_byte(instruction.opcode) else {
+                *counts
+                    .entry(format!("unknown_{:02x}", instruction.opcode))
+                    .or_default() += 1;
+                continue;
+            };
+            *counts.entry(spec.name.clone()).or_default() += 1;
+            analysis.static_cost += spec.cost;
+            analysis.min_version = analysis.min_version.max(spec.min_version);
+            analysis.mode = match (
+                analysis.mode,
+                spec.modes.contains(&RunMode::Signature),
+                spec.modes.contains(&RunMode::Application),
+            ) {
+                (ModeRequirement::Any, false, true) => ModeRequirement::Application,
+                (ModeRequirement::Any, true, false) => ModeRequirement::Signature,
+                (ModeRequirement::Signature, false, true)
+                | (ModeRequirement::Application, true, false) => ModeRequirement::Conflicting,
+                (mode, _, _) => mode,
+            };
+
+            match instruction.opcode {
+                OP_INTCBLOCK => analysis.int_blocks.push(instruction.args.clone()),
+                OP_BYTECBLOCK => analysis.byte_blocks.push(instruction.args.clone()),
+                OP_CALLSUB => {
+                    let target = disassembly
+                        .labels
+                        .iter()
+                        .find(|(_, label)| instruction.args.first() == Some(*label));
+                    if let Some((pc, _)) = target {
+                        *calls.entry(*pc).or_default() += 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        analysis.opcodes = counts.into_iter().collect();
+        analysis
+            .opcodes
+            .sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+        analysis.subroutines = calls
+            .into_iter()
+            .map(|(pc, calls)| Subroutine {
+                label: disassembly.labels[&pc].clone(),
+                pc,
+                calls,
+            })
+            .collect();
+        analysis
+    }
+}
@@ -0,0 +1,366 @@
+//! Constant blocks built from `int` and `byte` pseudo-ops
+//!
+//! Constants are placed the way `goal clerk compile` places them. Before v4
+//! every constant goes into a prepended block in order of first use. From v4 the
+//! blocks hold only constants used more than once, most used first, and the
+//! rest are pushed with `pushint` and `pushbytes`.
+
+use super::diagnostics::{AsmResult, Problem, codes};
+use super::{Assembler, ConstRef, push_indexed};
+use crate::opcodes::*;
+use crate::varuint::encode_varuint;
+
+/// First version whose constant blocks are sorted by use
+pub(super) const OPTIMIZE_VERSION: u8 = 4;
+
+impl Assembler {
+    /// Assemble an `int` pseudo-op
+    pub(super) fn int_literal(&mut self, bytecode: &mut Vec<u8>, value: u64) -> AsmResult<()> {
+        // With handwritten blocks it is unclear which block is live here
+        if self.intc_blocks > 1
+            || (self.intc_blocks > 0 && self.program_version() >= OPTIMIZE_VERSION)
+        {
+            if self.program_version() < 3 {
+                return Err(Problem::new(
+                    codes::CONSTANT,
+                    format!("int {value} used with manual intcblocks, use intc"),
+                ));
+            }
+            bytecode.push(OP_PUSHINT);
+            bytecode.extend_from_slice(&encode_varuint(value));
+            return Ok(());
+        }
+
+        let index = match self.intc.iter().position(|constant| *constant == value) {
+            Some(index) => index,
+            None if self.intc_blocks > 0 => {
+                return Err(Problem::new(
+                    codes::CONSTANT,
+                    format!("Value {value} does not appear in existing intcblock"),
+                ));
+            }
+            None => {
+                self.intc.push(value);
+                self.intc.len() - 1
+            }
+        };
+        self.int_refs.push(ConstRef {
+            position: bytecode.len(),
+            value,
+        });
+        self.intc_index(bytecode, index)
+    }
+
+    /// Assemble a `byte`, `addr` or `method` pseudo-op
+    pub(super) fn byte_literal(&mut self, bytecode: &mut Vec<u8>, value: Vec<u8>) -> AsmResult<()> {
+        if self.bytec_blocks > 1
+            || (self.bytec_blocks > 0 && self.program_version() >= OPTIMIZE_VERSION)
+        {
+            if self.program_version() < 3 {
+                return Err(Problem::new(
+                    codes::CONSTANT,
+                    "byte constant used with manual bytecblocks, use bytec",
+                ));
+            }
+            push_bytes(bytecode, &value);
+            return Ok(());
+        }
+
+        let index = match self.bytec.iter().position(|constant| *constant == value) {
+            Some(index) => index,
+            None if self.bytec_blocks > 0 => {
+                return Err(Problem::new(
+                    codes::CONSTANT,
+                    format!(
+                        "Value 0x{} does not appear in existing bytecblock",
+                        hex::encode(&value)
+                    ),
+                ));
+            }
+            None => {
+                self.bytec.push(value.clone());
+                self.bytec.len() - 1
+            }
+        };
+        self.byte_refs.push(ConstRef {
+            position: bytecode.len(),
+            value,
+        });
+        self.bytec_index(bytecode, index)
+    }
+
+    /// Emit a load of integer constant `index`
+    pub(super) fn intc_index(&mut self, bytecode: &mut Vec<u8>, index: usize) -> AsmResult<()> {
+        if index >= self.intc.len() {
+            return Err(Problem::new(
+                codes::CONSTANT,
+                format!("intc {index} is not defined"),
+            ));
+        }
+        if index > 255 {
+            return Err(Problem::new(
+                codes::CONSTANT,
+                "Cannot have more than 256 int constants",
+            ));
+        }
+        push_indexed(bytecode, OP_INTC_0, OP_INTC, index);
+        Ok(())
+    }
+
+    /// Emit a load of byte constant `index`
+    pub(super) fn bytec_index(&mut self, bytecode: &mut Vec<u8>, index: usize) -> AsmResult<()> {
+        if index >= self.bytec.len() {
+            return Err(Problem::new(
+                codes::CONSTANT,
+                format!("bytec {index} is not defined"),
+            ));
+        }
+        if index > 255 {
+            return Err(Problem::new(
+                codes::CONSTANT,
+                "Cannot have more than 256 byte constants",
+            ));
+        }
+        push_indexed(bytecode, OP_BYTEC_0, OP_BYTEC, index);
+        Ok(())
+    }
+
+    /// Record the values of a handwritten `intcblock`
+    pub(super) fn set_intc_block(&mut self, values: Vec<u64>) -> AsmResult<()> {
+        if !self.int_refs.is_empty() {
+            return Err(Problem::new(codes::CONSTANT, "intcblock following int"));
+        }
+        self.intc = values;
+        self.intc_blocks += 1;
+        Ok(())
+    }
+
+    /// Record the values of a handwritten `bytecblock`
+    pub(super) fn set_bytec_block(&mut self, values: Vec<Vec<u8>>) -> AsmResult<()> {
+        if !self.byte_refs.is_empty() {
+            return Err(Problem::new(
+                codes::CONSTANT,
+                "bytecblock following byte, addr or method",
+            ));
+        }
+        self.bytec = values;
+        self.bytec_blocks += 1;
+        Ok(())
+    }
+
+    /// Lay out the generated constant blocks and rewrite the loads to match
+    ///
+    /// By default this follows goal: constants used more than once go into the
+    /// block, most used first, and the rest are pushed. With optimization the
+    /// layout with the smallest encoding is chosen instead.
+    pub(super) fn layout_constants(&mut self, bytecode: &mut Vec<u8>) {
+        let can_push = self.program_version() >= 3;
+        if self.intc_blocks == 0 {
+            let refs = std::mem::take(&mut self.int_refs);
+            let uses = count_uses(&self.intc, &refs);
+            let block = if self.optimize {
+                smallest_layout(uses, can_push, |value| encode_varuint(*value).len())
+            } else {
+                goal_layout(uses)
+            };
+            self.rewrite_refs(bytecode, &block, refs, |value, index| match index {
+                Some(index) => indexed(OP_INTC_0, OP_INTC, index),
+                None => [vec![OP_PUSHINT], encode_varuint(*value)].concat(),
+            });
+            self.intc = block;
+        }
+        if self.bytec_blocks == 0 {
+            let refs = std::mem::take(&mut self.byte_refs);
+            let uses = count_uses(&self.bytec, &refs);
+            let block = if self.optimize {
+                smallest_layout(uses, can_push, |value| {
+                    encode_varuint(value.len() as u64).len() + value.len()
+                })
+            } else {
+                goal_layout(uses)
+            };
+            self.rewrite_refs(bytecode, &block, refs, |value, index| match index {
+                Some(index) => indexed(OP_BYTEC_0, OP_BYTEC, index),
+                None => {
+                    let mut push = vec![OP_PUSHBYTES];
+                    push_bytes_immediate(&mut push, value);
+                    push
+                }
+            });
+            self.bytec = block;
+        }
+    }
+
+    /// Rewrite every constant load to use `block`, pushing values not in it
+    ///
+    /// `encode` gives the instruction loading a value from its block index, or
+    /// pushing it when it has none.
+    fn rewrite_refs<T: PartialEq>(
+        &mut self,
+        bytecode: &mut Vec<u8>,
+        block: &[T],
+        mut refs: Vec<ConstRef<T>>,
+        encode: impl Fn(&T, Option<usize>) -> Vec<u8>,
+    ) {
+        // Rewrite from the end so earlier positions stay valid
+        refs.sort_by(|a, b| b.position.cmp(&a.position));
+        for reference in refs {
+            let index = block.iter().position(|value| *value == reference.value);
+            let load = encode(&reference.value, index);
+
+            let old_len = match bytecode[reference.position] {
+                OP_INTC | OP_BYTEC => 2,
+                _ => 1,
+            };
+            let position = reference.position;
+            bytecode.splice(position..position + old_len, load.iter().copied());
+            self.shift_positions(position, load.len() as isize - old_len as isize);
+        }
+    }
+
+    /// Move everything after the instruction at `position` by `delta` bytes
+    fn shift_positions(&mut self, position: usize, delta: isize) {
+        if delta == 0 {
+            return;
+        }
+        let shift = |pc: &mut usize| {
+            if *pc > position {
+                *pc = pc.wrapping_add_signed(delta);
+            }
+        };
+        self.labels.values_mut().for_each(shift);
+        for reference in &mut self.label_refs {
+            shift(&mut reference.position);
+            shift(&mut reference.end);
+        }
+        self.int_refs
+            .iter_mut()
+            .for_each(|r| shift(&mut r.position));
+        self.byte_refs
+            .iter_mut()
+            .for_each(|r| shift(&mut r.position));
+        self.locations
+            .iter_mut()
+            .for_each(|(position, _)| shift(position));
+    }
+
+    /// Write the constant blocks built from pseudo-ops after the program header
+    pub(super) fn write_constant_blocks(&self, program: &mut Vec<u8>) {
+        if !self.intc.is_empty() && self.intc_blocks == 0 {
+            program.push(OP_INTCBLOCK);
+            program.extend_from_slice(&encode_varuint(self.intc.len() as u64));
+            for value in &self.intc {
+                program.extend_from_slice(&encode_varuint(*value));
+            }
+        }
+        if !self.bytec.is_empty() && self.bytec_blocks == 0 {
+            program.push(OP_BYTECBLOCK);
+            program.extend_from_slice(&encode_varuint(self.bytec.len() as u64));
+            for value in &self.bytec {
+                push_bytes_immediate(program, value);
+            }
+        }
+    }
+}
+
+/// Each constant of `block` with its number of loads, in order of first use
+fn count_uses<T: PartialEq + Clone>(block: &[T], refs: &[ConstRef<T>]) -> Vec<(T, usize)> {
+    block
+        .iter()
+        .map(|value| {
+            let count = refs.iter().filter(|r| r.value == *value).count();
+            (value.clone(), count)
+        })
+        .collect()
+}
+
+/// The block goal builds: constants used more than once, most used first
+fn goal_layout<T>(mut uses: Vec<(T, usize)>) -> Vec<T> {
+    // A stable sort keeps equally used constants in order of first use
+    uses.sort_by(|a, b| b.1.cmp(&a.1));
+    uses.into_iter()
+        .take_while(|(_, count)| *count > 1)
+        .map(|(value, _)| value)
+        .collect()
+}
+
+/// The block giving the smallest program, given each value's encoded size
+///
+/// A value pushed `n` times costs `n * (1 + size)` bytes. In the block it costs
+/// `size` plus one byte per load from the first four slots, or two bytes per
+/// load from later ones. Without push opcodes every value must be in the block.
+fn smallest_layout<T>(uses: Vec<(T, usize)>, can_push: bool, size: impl Fn(&T) -> usize) -> Vec<T> {
+    if !can_push {
+        let mut uses = uses;
+        uses.sort_by(|a, b| b.1.cmp(&a.1));
+        return uses.into_iter().map(|(value, _)| value).collect();
+    }
+
+    // Bytes saved by loading a value from a short slot and from a later slot
+    let savings = |value: &T, count: usize| {
+        let pushed = (count * (1 + size(value))) as isize;
+        let short = pushed - (size(value) + count) as isize;
+        let long = pushed - (size(value) + 2 * count) as isize;
+        (short, long)
+    };
+
+    // The short slots go to the values gaining the most over their next best
+    // placement, a later slot or a push
+    let mut candidates: Vec<(usize, isize)> = uses
+        .iter()
+        .enumerate()
+        .map(|(index, (value, count))| {
+            let (short, long) = savings(value, *count);
+            (index, short - long.max(0))
+        })
+        .filter(|(_, gain)| *gain > 0)
+        .collect();
+    candidates.sort_by(|a, b| b.1.cmp(&a.1));
+    let mut short: Vec<usize> = candidates.iter().take(4).map(|(index, _)| *index).collect();
+    short.sort_unstable();
+
+    let long: Vec<usize> = (0..uses.len())
+        .filter(|index| !short.contains(index))
+        .filter(|&index| savings(&uses[index].0, uses[index].1).1 > 0)
+        .take(252)
+        .collect();
+
+    let saved: isize = short
+        .iter()
+        .map(|&index| savings(&uses[index].0, uses[index].1).0)
+        .chain(
+            long.iter()
+                .map(|&index| savings(&uses[index].0, uses[index].1).1),
+        )
+        .sum();
+    let header = 1 + encode_varuint((short.len() + long.len()) as u64).len() as isize;
+    if saved <= header {
+        return Vec::new();
+    }
+
+    let mut uses: Vec<Option<T>> = uses.into_iter().map(|(value, _)| Some(value)).collect();
+    short
+        .into_iter()
+        .chain(long)
+        .map(|index| uses[index].take().unwrap())
+        .collect()
+}
+
+/// Instruction loading constant `index` with the short opcodes where they exist
+fn indexed(base: u8, opcode: u8, index: usize) -> Vec<u8> {
+    let mut load = Vec::new();
+    push_indexed(&mut load, base, opcode, index);
+    load
+}
+
+/// Emit a `pushbytes` of the given value
+fn push_bytes(bytecode: &mut Vec<u8>, bytes: &[u8]) {
+    bytecode.push(OP_PUSHBYTES);
+    push_bytes_immediate(bytecode, bytes);
+}
+
+/// Emit a varuint length-prefixed byte string
+fn push_bytes_immediate(bytecode: &mut Vec<u8>, bytes: &[u8]) {
+    bytecode.extend_from_slice(&encode_varuint(bytes.len() as u64));
+    bytecode.extend_from_slice(bytes);
+}
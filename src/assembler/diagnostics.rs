@@ -0,0 +1,183 @@
+//! Structured assembler diagnostics
+//!
+//! Problems found while assembling a statement are [`Problem`]s, which the
+//! assembler turns into [`Diagnostic`]s located in the source and carries on
+//! with the next statement, so one pass reports every error.
+
+use std::fmt;
+use std::ops::Range;
+
+use serde::Serialize;
+
+/// Diagnostic error codes
+pub mod codes {
+    /// Malformed source text, such as an unterminated string
+    pub const SYNTAX: &str = "E0001";
+    /// Invalid or misplaced `#pragma`
+    pub const PRAGMA: &str = "E0002";
+    /// No opcode or pseudo-op has the name
+    pub const UNKNOWN_OPCODE: &str = "E0003";
+    /// Opcode introduced after the program version
+    pub const VERSION: &str = "E0004";
+    /// Wrong number of immediate arguments
+    pub const ARGUMENT_COUNT: &str = "E0005";
+    /// Invalid or out of range integer
+    pub const INTEGER: &str = "E0006";
+    /// Invalid byte string
+    pub const BYTES: &str = "E0007";
+    /// Unknown field name
+    pub const FIELD: &str = "E0008";
+    /// Label defined more than once
+    pub const DUPLICATE_LABEL: &str = "E0009";
+    /// Branch to a label that is never defined
+    pub const UNDEFINED_LABEL: &str = "E0010";
+    /// Branch the program version cannot encode
+    pub const BRANCH: &str = "E0011";
+    /// Constant load inconsistent with the constant blocks
+    pub const CONSTANT: &str = "E0012";
+    /// Invalid Algorand address
+    pub const ADDRESS: &str = "E0013";
+    /// Stack value of the wrong type, found by `#pragma typetrack`
+    pub const TYPE: &str = "E0014";
+    /// Too few stack values, found by `#pragma typetrack`
+    pub const STACK: &str = "E0015";
+}
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        })
+    }
+}
+
+/// A problem located in the assembled source
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    /// Source file name, when known
+    pub file: Option<String>,
+    /// One-based line number
+    pub line: usize,
+    /// Zero-based byte columns of the offending text on the line
+    pub columns: Range<usize>,
+    pub severity: Severity,
+    pub code: &'static str,
+    pub message: String,
+    /// Suggestion for fixing the problem
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    /// Render with the source line and carets under the offending text
+    pub fn render(&self, source: &str) -> String {
+        let mut out = format!("{}[{}]: {}\n", self.severity, self.code, self.message);
+        let gutter = " ".repeat(self.line.to_string().len());
+        let file = self.file.as_deref().unwrap_or("<source>");
+        out.push_str(&format!(
+            "{gutter}--> {file}:{}:{}\n",
+            self.line,
+            self.columns.start + 1
+        ));
+
+        if let Some(text) = source.lines().nth(self.line.saturating_sub(1)) {
+            let start = self.columns.start.min(text.len());
+            let width = self.columns.len().max(1);
+            out.push_str(&format!("{gutter} |\n"));
+            out.push_str(&format!("{} | {text}\n", self.line));
+            out.push_str(&format!(
+                "{gutter} | {}{}\n",
+                " ".repeat(text[..start].chars().count()),
+                "^".repeat(width)
+            ));
+        }
+        if let Some(help) = &self.help {
+            out.push_str(&format!("{gutter} = help: {help}\n"));
+        }
+        out
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}] at ", self.severity, self.code)?;
+        if let Some(file) = &self.file {
+            write!(f, "{file}:")?;
+        }
+        write!(
+            f,
+            "{}:{}: {}",
+            self.line,
+            self.columns.start + 1,
+            self.message
+        )
+    }
+}
+
+/// A problem found while assembling one statement
+#[derive(Debug)]
+pub(super) struct Problem {
+    pub(super) code: &'static str,
+    pub(super) message: String,
+    pub(super) help: Option<String>,
+    /// Text of the field at fault, when narrower than the statement
+    pub(super) field: Option<String>,
+}
+
+/// Result of assembling part of a statement
+pub(super) type AsmResult<T> = Result<T, Problem>;
+
+impl Problem {
+    pub(super) fn new(code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            help: None,
+            field: None,
+        }
+    }
+
+    /// Point at `field` rather than the whole statement
+    pub(super) fn at(mut self, field: &str) -> Self {
+        self.field = Some(field.to_string());
+        self
+    }
+
+    pub(super) fn help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+}
+
+/// The name in `names` closest to `name`, if it is a plausible typo
+pub(super) fn closest<'a>(name: &str, names: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    names
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= name.len().div_ceil(3))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
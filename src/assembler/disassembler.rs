@@ -0,0 +1,314 @@
+//! Disassembler producing TEAL that assembles back to the same program
+//!
+//! Branch and `callsub` targets are named `label1`, `label2` and so on in
+//! program order, loads from the constant blocks are commented with the value
+//! they load, and field immediates are written by name. Programs whose
+//! branches land inside an instruction, or that use encodings the assembler
+//! never emits, such as `intc 0` for `intc_0`, cannot round trip.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use crate::error::AvmResult;
+use crate::opcodes::*;
+use crate::varuint::decode_varuint;
+
+/// A decoded immediate argument
+pub(crate) enum Operand {
+    /// Text of the argument as written in TEAL
+    Text(String),
+    /// Pc a branch lands on
+    Target(i64),
+}
+
+/// One disassembled instruction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    /// Pc of the opcode
+    pub pc: usize,
+    /// Size in bytes, including immediates
+    pub size: usize,
+    /// Opcode byte
+    pub opcode: u8,
+    /// Immediates as TEAL
+    pub args: Vec<String>,
+    /// Opcode and immediates as TEAL, with branch targets written as labels
+    pub text: String,
+    /// Value loaded from a constant block, for `intc` and `bytec` loads
+    pub constant: Option<String>,
+}
+
+/// A disassembled program
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Disassembly {
+    /// Version from the program header
+    pub version: u64,
+    /// Instructions in program order
+    pub instructions: Vec<Instruction>,
+    /// Label of each branch target
+    pub labels: BTreeMap<usize, String>,
+}
+
+impl Disassembly {
+    /// Decode a program
+    pub fn new(bytecode: &[u8]) -> AvmResult<Self> {
+        let (version, start) = crate::vm::program_version(bytecode)?;
+
+        // First pass: decode, collecting branch targets
+        let mut decoded = Vec::new();
+        let mut targets = Vec::new();
+        let mut pc = start;
+        while pc < bytecode.len() {
+            let opcode = bytecode[pc];
+            let operands = opcode_by_byte(opcode)
+                .map(|spec| (spec, disassemble_immediates(spec, bytecode, pc + 1)));
+            let end = match &operands {
+                Some((_, Some((operands, end)))) => {
+                    targets.extend(operands.iter().filter_map(|operand| match operand {
+                        Operand::Target(target) => usize::try_from(*target).ok(),
+                        Operand::Text(_) => None,
+                    }));
+                    *end
+                }
+                _ => pc + 1,
+            };
+            decoded.push((pc, end, operands));
+            pc = end;
+        }
+
+        targets.sort_unstable();
+        targets.dedup();
+        let labels: BTreeMap<usize, String> = targets
+            .into_iter()
+            .enumerate()
+            .map(|(index, target)| (target, format!("label{}", index + 1)))
+            .collect();
+
+        // Second pass: write the instructions, following the constant blocks
+        let mut intc: Vec<String> = Vec::new();
+        let mut bytec: Vec<String> = Vec::new();
+        let instructions = decoded
+            .into_iter()
+            .map(|(pc, end, operands)| {
+                let opcode = bytecode[pc];
+                let Some((spec, operands)) = operands else {
+                    return Instruction {
+                        pc,
+                        size: 1,
+                        opcode,
+                        args: Vec::new(),
+                        text: format!("unknown_{opcode:02x}"),
+                        constant: None,
+                    };
+                };
+                let Some((operands, _)) = operands else {
+                    return Instruction {
+                        pc,
+                        size: 1,
+                        opcode,
+                        args: Vec::new(),
+                        text: format!("{} <invalid>", spec.name),
+                        constant: None,
+                    };
+                };
+
+                let args: Vec<String> = operands
+                    .into_iter()
+                    .map(|operand| match operand {
+                        Operand::Text(text) => text,
+                        Operand::Target(target) => usize::try_from(target)
+                            .ok()
+                            .and_then(|target| labels.get(&target).cloned())
+                            .unwrap_or_else(|| target.to_string()),
+                    })
+                    .collect();
+                let index = |base: u8| match opcode.checked_sub(base) {
+                    Some(index @ 0..4) => Some(index as usize),
+                    _ => args.first().and_then(|arg| arg.parse().ok()),
+                };
+                let constant = match opcode {
+                    OP_INTCBLOCK => {
+                        intc = args.clone();
+                        None
+                    }
+                    OP_BYTECBLOCK => {
+                        bytec = args.clone();
+                        None
+                    }
+                    OP_INTC | OP_INTC_0..=OP_INTC_3 => {
+                        index(OP_INTC_0).and_then(|index| intc.get(index).cloned())
+                    }
+                    OP_BYTEC | OP_BYTEC_0..=OP_BYTEC_3 => {
+                        index(OP_BYTEC_0).and_then(|index| bytec.get(index).cloned())
+                    }
+                    _ => None,
+                };
+
+                let mut text = spec.name.clone();
+                for arg in &args {
+                    text.push(' ');
+                    text.push_str(arg);
+                }
+                Instruction {
+                    pc,
+                    size: end - pc,
+                    opcode,
+                    args,
+                    text,
+                    constant,
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            version,
+            instructions,
+            labels,
+        })
+    }
+
+    /// Write as TEAL source, commenting each instruction with its pc if `pcs`
+    pub fn to_source(&self, pcs: bool) -> String {
+        let mut source = format!("#pragma version {}\n", self.version);
+        let label_line = |source: &mut String, pc: usize| {
+            if let Some(label) = self.labels.get(&pc) {
+                let _ = writeln!(source, "{label}:");
+            }
+        };
+
+        for instruction in &self.instructions {
+            label_line(&mut source, instruction.pc);
+            source.push_str(&instruction.text);
+            match (pcs, &instruction.constant) {
+                (true, Some(constant)) => {
+                    let _ = write!(source, " // {:04x}: {constant}", instruction.pc);
+                }
+                (true, None) => {
+                    let _ = write!(source, " // {:04x}", instruction.pc);
+                }
+                (false, Some(constant)) => {
+                    let _ = write!(source, " // {constant}");
+                }
+                (false, None) => {}
+            }
+            source.push('\n');
+        }
+        // Branches may jump to the end of the program
+        if let Some(last) = self.instructions.last() {
+            label_line(&mut source, last.pc + last.size);
+        }
+        source
+    }
+
+    /// The instruction starting at `pc`
+    pub fn instruction_at(&self, pc: usize) -> Option<&Instruction> {
+        self.instructions
+            .binary_search_by_key(&pc, |instruction| instruction.pc)
+            .ok()
+            .map(|index| &self.instructions[index])
+    }
+}
+
+/// Disassemble bytecode to TEAL source
+///
+/// The output starts with the `#pragma version` line read from the program
+/// header, and assembles back to the same bytes.
+pub fn disassemble(bytecode: &[u8]) -> AvmResult<String> {
+    Ok(Disassembly::new(bytecode)?.to_source(false))
+}
+
+/// Decode the immediates of the instruction whose arguments start at `pc`
+///
+/// Returns each immediate and the pc after the instruction, or `None` if the
+/// program ends before the immediates do.
+pub(crate) fn disassemble_immediates(
+    spec: &OpSpec,
+    bytecode: &[u8],
+    mut pc: usize,
+) -> Option<(Vec<Operand>, usize)> {
+    let mut operands = Vec::new();
+    for immediate in &spec.immediates {
+        match immediate.kind {
+            ImmediateKind::Uint8 => {
+                operands.push(Operand::Text(bytecode.get(pc)?.to_string()));
+                pc += 1;
+            }
+            ImmediateKind::Int8 => {
+                operands.push(Operand::Text((*bytecode.get(pc)? as i8).to_string()));
+                pc += 1;
+            }
+            ImmediateKind::Varuint => {
+                let (value, size) = decode_varuint(bytecode.get(pc..)?).ok()?;
+                operands.push(Operand::Text(value.to_string()));
+                pc += size;
+            }
+            ImmediateKind::Bytes => {
+                let (bytes, end) = read_bytes(bytecode, pc)?;
+                operands.push(Operand::Text(format_bytes(bytes)));
+                pc = end;
+            }
+            ImmediateKind::Label => {
+                let offset = i16::from_be_bytes([*bytecode.get(pc)?, *bytecode.get(pc + 1)?]);
+                pc += 2;
+                operands.push(Operand::Target(pc as i64 + offset as i64));
+            }
+            ImmediateKind::Labels => {
+                let count = *bytecode.get(pc)? as usize;
+                let end = pc + 1 + 2 * count;
+                let offsets = bytecode.get(pc + 1..end)?;
+                for offset in offsets.chunks(2) {
+                    let offset = i16::from_be_bytes([offset[0], offset[1]]);
+                    operands.push(Operand::Target(end as i64 + offset as i64));
+                }
+                pc = end;
+            }
+            ImmediateKind::Varuints => {
+                let (count, size) = decode_varuint(bytecode.get(pc..)?).ok()?;
+                pc += size;
+                for _ in 0..count {
+                    let (value, size) = decode_varuint(bytecode.get(pc..)?).ok()?;
+                    operands.push(Operand::Text(value.to_string()));
+                    pc += size;
+                }
+            }
+            ImmediateKind::BytesList => {
+                let (count, size) = decode_varuint(bytecode.get(pc..)?).ok()?;
+                pc += size;
+                for _ in 0..count {
+                    let (bytes, end) = read_bytes(bytecode, pc)?;
+                    operands.push(Operand::Text(format_bytes(bytes)));
+                    pc = end;
+                }
+            }
+            ImmediateKind::Field(group) => {
+                let value = *bytecode.get(pc)?;
+                operands.push(Operand::Text(match group.field_name(value) {
+                    Some(name) => name.to_string(),
+                    None => value.to_string(),
+                }));
+                pc += 1;
+            }
+        }
+    }
+    Some((operands, pc))
+}
+
+/// Read a varuint length-prefixed byte string, returning it and the pc after it
+pub(super) fn read_bytes(bytecode: &[u8], pc: usize) -> Option<(&[u8], usize)> {
+    let (length, size) = decode_varuint(bytecode.get(pc..)?).ok()?;
+    let start = pc + size;
+    let end = start.checked_add(usize::try_from(length).ok()?)?;
+    Some((bytecode.get(start..end)?, end))
+}
+
+/// Format bytes as a string literal when printable, hex otherwise
+fn format_bytes(bytes: &[u8]) -> String {
+    let printable = bytes
+        .iter()
+        .all(|&b| b.is_ascii() && !b.is_ascii_control() && b != b'"' && b != b'\\');
+    if printable {
+        format!("\"{}\"", String::from_utf8_lossy(bytes))
+    } else {
+        format!("0x{}", hex::encode(bytes))
+    }
+}
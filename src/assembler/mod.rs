@@ -1,21 +1,95 @@
 //! TEAL assembler implementation
 
+pub mod analysis;
+mod constants;
+pub mod diagnostics;
+pub mod disassembler;
+pub mod optimizer;
+pub mod source_map;
+mod typetrack;
+
 use crate::error::{AvmError, AvmResult};
 use crate::opcodes::*;
+use crate::types::StackValue;
 use crate::varuint::encode_varuint;
+use diagnostics::{AsmResult, Diagnostic, Problem, Severity, closest, codes};
+use source_map::{SourceLocation, SourceMap};
 use std::collections::HashMap;
+use std::ops::Range;
+use typetrack::{TrackedType, TypeTracker};
+
+pub use disassembler::disassemble;
+
+/// Version of programs without a `#pragma version`, as in `goal clerk compile`
+pub const DEFAULT_VERSION: u8 = 1;
+
+/// First version allowing backward branches
+const BACK_BRANCH_VERSION: u8 = 4;
 
 /// TEAL assembler
 #[derive(Debug, Default)]
 pub struct Assembler {
     /// Program version
     version: u8,
+    /// Version used when the source has no `#pragma version`
+    default_version: Option<u8>,
     /// Type tracking enabled
     typetrack: bool,
+    /// Stack types tracked while `typetrack` is on
+    types: TypeTracker,
     /// Label to address mapping
     labels: HashMap<String, usize>,
-    /// Forward label references to resolve
-    forward_refs: Vec<(usize, String)>,
+    /// Label references to resolve once all labels are known
+    label_refs: Vec<LabelRef>,
+    /// Integer constants loaded by `intc`
+    intc: Vec<u64>,
+    /// Byte constants loaded by `bytec`
+    bytec: Vec<Vec<u8>>,
+    /// `int` pseudo-ops, rewritten once the final `intcblock` is known
+    int_refs: Vec<ConstRef<u64>>,
+    /// `byte`, `addr` and `method` pseudo-ops, rewritten like `int_refs`
+    byte_refs: Vec<ConstRef<Vec<u8>>>,
+    /// Number of `intcblock`s written in the source
+    intc_blocks: usize,
+    /// Number of `bytecblock`s written in the source
+    bytec_blocks: usize,
+    /// Choose the smallest constant block layout rather than goal's, and run
+    /// the peephole optimizer
+    optimize: bool,
+    /// Rewrites made by the peephole optimizer
+    rewrites: Vec<optimizer::Rewrite>,
+    /// Position and source location of each instruction
+    locations: Vec<(usize, SourceLocation)>,
+    /// Source map of the last assembled program
+    source_map: SourceMap,
+    /// Name of the source file
+    source_name: Option<String>,
+    /// Problems found by the last `assemble`
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// A pseudo-op loading a constant from a constant block
+#[derive(Debug)]
+struct ConstRef<T> {
+    /// Position of the loading instruction
+    position: usize,
+    /// Loaded value
+    value: T,
+}
+
+/// A branch offset to patch once all labels are defined
+#[derive(Debug)]
+struct LabelRef {
+    /// Position of the two offset bytes
+    position: usize,
+    /// Pc after the branching instruction, which offsets are relative to
+    end: usize,
+    /// Target label
+    label: String,
+    /// One-based line of the reference
+    line: usize,
+    /// Columns of the label on its line
+    columns: Range<usize>,
 }
 
 impl Assembler {
@@ -24,95 +98,329 @@ impl Assembler {
         Self::default()
     }
 
+    /// Assemble sources without a `#pragma version` for `version` instead of v1
+    pub fn with_default_version(mut self, version: u8) -> Self {
+        self.default_version = Some(version);
+        self
+    }
+
+    /// Lay out generated constant blocks for size at any version, and optimize
+    /// the assembled program
+    pub fn with_optimize(mut self, optimize: bool) -> Self {
+        self.optimize = optimize;
+        self
+    }
+
+    /// Name of the source file, for diagnostics and source maps
+    pub fn with_source_name(mut self, name: impl Into<String>) -> Self {
+        self.source_name = Some(name.into());
+        self
+    }
+
+    /// Forget everything about the previous program, keeping only the options
+    /// set with the `with_*` builders
+    fn reset(&mut self) {
+        *self = Self {
+            default_version: self.default_version,
+            optimize: self.optimize,
+            source_name: self.source_name.take(),
+            ..Self::default()
+        };
+    }
+
     /// Assemble TEAL source code to bytecode
+    ///
+    /// Assembly carries on past errors, and fails with every problem found as
+    /// an [`AvmError::AssemblyDiagnostics`].
     pub fn assemble(&mut self, source: &str) -> AvmResult<Vec<u8>> {
         let mut bytecode = Vec::new();
-        let lines: Vec<&str> = source.lines().collect();
+        self.reset();
 
         // First pass: collect labels and generate bytecode
-        for (line_num, line) in lines.iter().enumerate() {
-            let line = line.trim();
+        for (line_num, line) in source.lines().enumerate() {
+            let statements = match tokenize(line) {
+                Ok(statements) => statements,
+                Err(problem) => {
+                    self.report(problem, line_num + 1, line, &[line.trim()]);
+                    continue;
+                }
+            };
+            for fields in statements {
+                let start = bytecode.len();
+                let refs = (
+                    self.label_refs.len(),
+                    self.int_refs.len(),
+                    self.byte_refs.len(),
+                );
+                if let Err(problem) = self.assemble_statement(&mut bytecode, &fields) {
+                    // Drop whatever the statement wrote before failing
+                    bytecode.truncate(start);
+                    self.label_refs.truncate(refs.0);
+                    self.int_refs.truncate(refs.1);
+                    self.byte_refs.truncate(refs.2);
+                    self.report(problem, line_num + 1, line, &fields);
+                    continue;
+                }
+                if let Err(problem) = self.track_types(&fields) {
+                    self.report(problem, line_num + 1, line, &fields);
+                }
 
-            // Skip empty lines and comments
-            if line.is_empty() || line.starts_with("//") || line.starts_with(";") {
-                continue;
+                for reference in &mut self.label_refs[refs.0..] {
+                    let field = fields.iter().rev().find(|f| **f == reference.label);
+                    reference.line = line_num + 1;
+                    reference.columns = columns(line, field.unwrap_or(&fields[0]));
+                }
+                if bytecode.len() > start {
+                    // Locate the instruction rather than a label before it
+                    let field = match fields[..] {
+                        [label, instruction, ..] if label.ends_with(':') => instruction,
+                        _ => fields[0],
+                    };
+                    let column = field.as_ptr() as usize - line.as_ptr() as usize;
+                    self.locations.push((
+                        start,
+                        SourceLocation {
+                            line: line_num,
+                            column,
+                        },
+                    ));
+                }
             }
+        }
 
-            // Handle inline comments (remove everything after ; or //)
-            let line = if let Some(pos) = line.find(';') {
-                line[..pos].trim()
-            } else if let Some(pos) = line.find("//") {
-                line[..pos].trim()
-            } else {
-                line
-            };
+        if self.optimize || self.program_version() >= constants::OPTIMIZE_VERSION {
+            self.layout_constants(&mut bytecode);
+        }
 
-            // Skip if line becomes empty after comment removal
-            if line.is_empty() {
-                continue;
+        // Second pass: resolve label references
+        self.resolve_label_refs(&mut bytecode);
+        self.diagnostics
+            .sort_by_key(|diagnostic| (diagnostic.line, diagnostic.columns.start));
+        if self
+            .diagnostics
+            .iter()
+            .any(|d| d.severity == Severity::Error)
+        {
+            return Err(AvmError::AssemblyDiagnostics(self.diagnostics.clone()));
+        }
+
+        // Branch offsets are relative, so the header and constant blocks can
+        // go in front last
+        let mut program = encode_varuint(self.program_version() as u64);
+        self.write_constant_blocks(&mut program);
+        let header_len = program.len();
+        program.extend_from_slice(&bytecode);
+
+        let mut source_map = SourceMap {
+            sources: self.source_name.iter().cloned().collect(),
+            locations: self
+                .locations
+                .iter()
+                .map(|(position, location)| (header_len + position, *location))
+                .collect(),
+            labels: self
+                .labels
+                .iter()
+                .map(|(label, position)| (label.clone(), header_len + position))
+                .collect(),
+        };
+
+        if self.optimize {
+            let optimized = optimizer::optimize(&program)?;
+            let moved: HashMap<usize, usize> = optimized.moved.iter().copied().collect();
+            source_map.locations = source_map
+                .locations
+                .into_iter()
+                .filter_map(|(pc, location)| Some((*moved.get(&pc)?, location)))
+                .collect();
+            for pc in source_map.labels.values_mut() {
+                *pc = optimized.new_pc(*pc);
             }
+            self.source_map = source_map;
+            self.rewrites = optimized.rewrites;
+            return Ok(optimized.program);
+        }
+        self.source_map = source_map;
+        Ok(program)
+    }
 
-            // Handle pragma directives
-            if line.starts_with("#pragma") {
-                let parts: Vec<&str> = line.split_whitespace().collect();
-                if parts.len() >= 3 {
-                    match parts[1] {
-                        "version" => {
-                            self.version = parts[2].parse().map_err(|_| {
-                                AvmError::assembly_error(format!(
-                                    "Invalid version on line {}",
-                                    line_num + 1
-                                ))
-                            })?;
-                        }
-                        "typetrack" => {
-                            self.typetrack = parts[2].parse().map_err(|_| {
-                                AvmError::assembly_error(format!(
-                                    "Invalid typetrack value on line {}",
-                                    line_num + 1
-                                ))
-                            })?;
-                        }
-                        _ => {
-                            return Err(AvmError::assembly_error(format!(
-                                "Unknown pragma directive '{}' on line {}",
-                                parts[1],
-                                line_num + 1
-                            )));
-                        }
-                    }
-                } else {
-                    return Err(AvmError::assembly_error(format!(
-                        "Invalid pragma syntax on line {}",
-                        line_num + 1
-                    )));
-                }
-                continue;
+    /// Rewrites made by the peephole optimizer in the last `assemble`
+    pub fn rewrites(&self) -> &[optimizer::Rewrite] {
+        &self.rewrites
+    }
+
+    /// Source map of the program from the last `assemble`
+    pub fn source_map(&self) -> &SourceMap {
+        &self.source_map
+    }
+
+    /// Diagnostics from the last `assemble`
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Parse a constant written as the argument of `int`, or of `byte` or `addr` if `bytes`
+    pub fn parse_constant(&self, text: &str, bytes: bool) -> AvmResult<StackValue> {
+        let text = text.trim();
+        let value = if !bytes {
+            self.parse_integer(text).map(StackValue::Uint)
+        } else if text.len() == 58
+            && let Ok(address) = self.parse_algorand_address(text)
+        {
+            Ok(StackValue::Bytes(address))
+        } else if text.starts_with('"') {
+            self.parse_bytes(&[text]).map(StackValue::Bytes)
+        } else {
+            let args: Vec<&str> = text.split_whitespace().collect();
+            self.parse_bytes(&args).map(StackValue::Bytes)
+        };
+        value.map_err(|problem| AvmError::assembly_error(problem.message))
+    }
+
+    /// Record a problem with a statement of `fields` on `line`
+    fn report(&mut self, problem: Problem, line_num: usize, line: &str, fields: &[&str]) {
+        let field = problem
+            .field
+            .as_ref()
+            .and_then(|text| fields.iter().find(|f| **f == text.as_str()));
+        let columns = match field {
+            Some(field) => columns(line, field),
+            None => {
+                let first = columns(line, fields[0]);
+                let last = columns(line, fields[fields.len() - 1]);
+                first.start..last.end
             }
+        };
+        self.diagnostics.push(Diagnostic {
+            file: self.source_name.clone(),
+            line: line_num,
+            columns,
+            severity: Severity::Error,
+            code: problem.code,
+            message: problem.message,
+            help: problem.help,
+        });
+    }
 
-            // Handle labels
-            if line.ends_with(':') {
-                let label = line.strip_suffix(':').unwrap();
-                self.labels.insert(label.to_string(), bytecode.len());
-                continue;
+    /// Check an assembled statement against the tracked stack types
+    fn track_types(&mut self, fields: &[&str]) -> AsmResult<()> {
+        if fields[0] == "#pragma" {
+            return Ok(());
+        }
+        let mut fields = fields;
+        if fields[0].ends_with(':') {
+            self.types.label();
+            fields = &fields[1..];
+        }
+        let Some((&opcode, args)) = fields.split_first() else {
+            return Ok(());
+        };
+        if !self.typetrack {
+            return Ok(());
+        }
+
+        let constants = match opcode {
+            "int" => vec![TrackedType::Uint64],
+            "byte" | "pushbytes" => {
+                vec![TrackedType::Bytes(
+                    self.parse_bytes(args).ok().map(|b| b.len()),
+                )]
             }
+            "pushbytess" => args
+                .iter()
+                .map(|arg| TrackedType::Bytes(self.parse_bytes(&[arg]).ok().map(|b| b.len())))
+                .collect(),
+            "addr" => vec![TrackedType::Bytes(Some(32))],
+            "method" => vec![TrackedType::Bytes(Some(4))],
+            _ => match opcode_by_name(opcode) {
+                Some(spec) => return self.types.track(spec, args),
+                None => return Ok(()),
+            },
+        };
+        self.types.push_constants(constants);
+        Ok(())
+    }
 
-            // Parse instruction
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.is_empty() {
-                continue;
+    /// Assemble one statement: a pragma, a label or an instruction
+    fn assemble_statement(&mut self, bytecode: &mut Vec<u8>, fields: &[&str]) -> AsmResult<()> {
+        if fields[0] == "#pragma" {
+            return self.assemble_pragma(bytecode, fields);
+        }
+
+        // A label may be followed by an instruction on the same line
+        let mut fields = fields;
+        if let Some(label) = fields[0].strip_suffix(':') {
+            if self
+                .labels
+                .insert(label.to_string(), bytecode.len())
+                .is_some()
+            {
+                return Err(Problem::new(
+                    codes::DUPLICATE_LABEL,
+                    format!("Duplicate label {label}"),
+                )
+                .at(fields[0]));
+            }
+            fields = &fields[1..];
+            if fields.is_empty() {
+                return Ok(());
             }
+        }
 
-            let opcode = parts[0];
-            let args = &parts[1..];
+        self.assemble_instruction(bytecode, fields[0], &fields[1..])
+    }
 
-            self.assemble_instruction(&mut bytecode, opcode, args, line_num + 1)?;
+    /// Apply a `#pragma` directive
+    fn assemble_pragma(&mut self, bytecode: &[u8], parts: &[&str]) -> AsmResult<()> {
+        if parts.len() < 3 {
+            return Err(Problem::new(codes::PRAGMA, "Invalid pragma syntax")
+                .help("write `#pragma version N` or `#pragma typetrack true|false`"));
         }
 
-        // Second pass: resolve forward references
-        self.resolve_forward_refs(&mut bytecode)?;
+        match parts[1] {
+            "version" => {
+                if !bytecode.is_empty() {
+                    return Err(Problem::new(
+                        codes::PRAGMA,
+                        "#pragma version is only allowed before instructions",
+                    ));
+                }
+                self.version = parts[2]
+                    .parse()
+                    .map_err(|_| Problem::new(codes::PRAGMA, "Invalid version").at(parts[2]))?;
+            }
+            "typetrack" => {
+                self.typetrack = parts[2].parse().map_err(|_| {
+                    Problem::new(codes::PRAGMA, "Invalid typetrack value")
+                        .at(parts[2])
+                        .help("typetrack is `true` or `false`")
+                })?;
+                // Nothing is known about the stack where tracking resumes
+                if !bytecode.is_empty() {
+                    self.types.label();
+                }
+            }
+            _ => {
+                return Err(Problem::new(
+                    codes::PRAGMA,
+                    format!("Unknown pragma directive '{}'", parts[1]),
+                )
+                .at(parts[1]));
+            }
+        }
+        Ok(())
+    }
 
-        Ok(bytecode)
+    /// Version written to the program header
+    ///
+    /// Programs without a `#pragma version` are assembled for the default
+    /// version, v1 unless set with [`Assembler::with_default_version`].
+    pub fn program_version(&self) -> u8 {
+        if self.version == 0 {
+            self.default_version.unwrap_or(DEFAULT_VERSION)
+        } else {
+            self.version
+        }
     }
 
     /// Assemble a single instruction
@@ -121,461 +429,327 @@ impl Assembler {
         bytecode: &mut Vec<u8>,
         opcode: &str,
         args: &[&str],
-        line_num: usize,
-    ) -> AvmResult<()> {
+    ) -> AsmResult<()> {
+        // Pseudo-ops that load a constant
         match opcode {
-            // Arithmetic operations
-            "+" => bytecode.push(OP_PLUS),
-            "-" => bytecode.push(OP_MINUS),
-            "*" => bytecode.push(OP_MUL),
-            "/" => bytecode.push(OP_DIV),
-            "%" => bytecode.push(OP_MOD),
-            "<" => bytecode.push(OP_LT),
-            ">" => bytecode.push(OP_GT),
-            "<=" => bytecode.push(OP_LE),
-            ">=" => bytecode.push(OP_GE),
-            "==" => bytecode.push(OP_EQ),
-            "!=" => bytecode.push(OP_NE),
-            "&&" => bytecode.push(OP_AND),
-            "||" => bytecode.push(OP_OR),
-            "!" => bytecode.push(OP_NOT),
-            "|" => bytecode.push(OP_BITWISE_OR),
-            "&" => bytecode.push(OP_BITWISE_AND),
-            "^" => bytecode.push(OP_BITWISE_XOR),
-            "~" => bytecode.push(OP_BITWISE_NOT),
-
-            // Stack operations
-            "pop" => bytecode.push(OP_POP),
-            "dup" => bytecode.push(OP_DUP),
-            "dup2" => bytecode.push(OP_DUP2),
-            "dig" => {
-                bytecode.push(OP_DIG);
-                self.assemble_byte_immediate(bytecode, args, line_num)?;
-            }
-            "bury" => {
-                bytecode.push(OP_BURY);
-                self.assemble_byte_immediate(bytecode, args, line_num)?;
-            }
-            "cover" => {
-                bytecode.push(OP_COVER);
-                self.assemble_byte_immediate(bytecode, args, line_num)?;
-            }
-            "uncover" => {
-                bytecode.push(OP_UNCOVER);
-                self.assemble_byte_immediate(bytecode, args, line_num)?;
-            }
-            "swap" => bytecode.push(OP_SWAP),
-            "select" => bytecode.push(OP_SELECT),
-            "dupn" => {
-                bytecode.push(OP_DUPN);
-                self.assemble_byte_immediate(bytecode, args, line_num)?;
-            }
-            "popn" => {
-                bytecode.push(OP_POPN);
-                self.assemble_byte_immediate(bytecode, args, line_num)?;
-            }
-
-            // Flow control
-            "bnz" => {
-                bytecode.push(OP_BNZ);
-                self.assemble_branch_target(bytecode, args, line_num)?;
-            }
-            "bz" => {
-                bytecode.push(OP_BZ);
-                self.assemble_branch_target(bytecode, args, line_num)?;
-            }
-            "b" => {
-                bytecode.push(OP_B);
-                self.assemble_branch_target(bytecode, args, line_num)?;
-            }
-            "return" => bytecode.push(OP_RETURN),
-            "assert" => bytecode.push(OP_ASSERT),
-            "callsub" => {
-                bytecode.push(OP_CALLSUB);
-                self.assemble_branch_target(bytecode, args, line_num)?;
-            }
-            "retsub" => bytecode.push(OP_RETSUB),
-            "proto" => {
-                bytecode.push(OP_PROTO);
-                // Proto takes 2 byte immediates: args and returns
-                if args.len() < 2 {
-                    return Err(AvmError::assembly_error(format!(
-                        "proto requires args and returns count on line {line_num}"
-                    )));
-                }
-                self.assemble_byte_immediate(bytecode, &[args[0]], line_num)?;
-                self.assemble_byte_immediate(bytecode, &[args[1]], line_num)?;
-            }
-            "frame_dig" => {
-                bytecode.push(OP_FRAME_DIG);
-                self.assemble_byte_immediate(bytecode, args, line_num)?;
-            }
-            "frame_bury" => {
-                bytecode.push(OP_FRAME_BURY);
-                self.assemble_byte_immediate(bytecode, args, line_num)?;
-            }
-            "switch" => {
-                bytecode.push(OP_SWITCH);
-                self.assemble_branch_target(bytecode, args, line_num)?;
-            }
-            "match" => {
-                bytecode.push(OP_MATCH);
-                self.assemble_branch_target(bytecode, args, line_num)?;
-            }
-
-            // Constants (high-level syntax)
             "int" => {
-                bytecode.push(OP_PUSHINT);
-                self.assemble_int_immediate(bytecode, args, line_num)?;
+                let arg = single_arg(opcode, args)?;
+                let value = match named_int_constant(arg) {
+                    Some(value) => value,
+                    None => self.parse_integer(arg)?,
+                };
+                return self.int_literal(bytecode, value);
             }
             "byte" => {
-                bytecode.push(OP_PUSHBYTES);
-                self.assemble_bytes_immediate(bytecode, args, line_num)?;
+                let bytes = self.parse_bytes(args)?;
+                return self.byte_literal(bytecode, bytes);
             }
             "addr" => {
-                bytecode.push(OP_PUSHBYTES);
-                self.assemble_addr_immediate(bytecode, args, line_num)?;
+                let address = single_arg(opcode, args)?;
+                let bytes = self.parse_algorand_address(address)?;
+                return self.byte_literal(bytecode, bytes);
             }
             "method" => {
-                bytecode.push(OP_PUSHBYTES);
-                self.assemble_method_immediate(bytecode, args, line_num)?;
-            }
-            // Low-level opcodes (for direct use if needed)
-            "pushint" => {
-                bytecode.push(OP_PUSHINT);
-                self.assemble_int_immediate(bytecode, args, line_num)?;
-            }
-            "pushbytes" => {
-                bytecode.push(OP_PUSHBYTES);
-                self.assemble_bytes_immediate(bytecode, args, line_num)?;
+                let signature = self.parse_bytes(args)?;
+                let selector = self.compute_method_selector(&signature);
+                return self.byte_literal(bytecode, selector.to_vec());
             }
+            _ => {}
+        }
 
-            // Constant block opcodes
-            "intcblock" => {
-                bytecode.push(OP_INTCBLOCK);
-                self.assemble_intcblock(bytecode, args, line_num)?;
-            }
-            "intc" => {
-                bytecode.push(OP_INTC);
-                self.assemble_byte_immediate(bytecode, args, line_num)?;
-            }
-            "intc_0" => bytecode.push(OP_INTC_0),
-            "intc_1" => bytecode.push(OP_INTC_1),
-            "intc_2" => bytecode.push(OP_INTC_2),
-            "intc_3" => bytecode.push(OP_INTC_3),
-            "bytecblock" => {
-                bytecode.push(OP_BYTECBLOCK);
-                self.assemble_bytecblock(bytecode, args, line_num)?;
-            }
-            "bytec" => {
-                bytecode.push(OP_BYTEC);
-                self.assemble_byte_immediate(bytecode, args, line_num)?;
-            }
-            "bytec_0" => bytecode.push(OP_BYTEC_0),
-            "bytec_1" => bytecode.push(OP_BYTEC_1),
-            "bytec_2" => bytecode.push(OP_BYTEC_2),
-            "bytec_3" => bytecode.push(OP_BYTEC_3),
-
-            // Arguments
-            "arg" => {
-                bytecode.push(OP_ARG);
-                self.assemble_byte_immediate(bytecode, args, line_num)?;
-            }
-            "arg_0" => bytecode.push(OP_ARG_0),
-            "arg_1" => bytecode.push(OP_ARG_1),
-            "arg_2" => bytecode.push(OP_ARG_2),
-            "arg_3" => bytecode.push(OP_ARG_3),
-
-            // Utility
-            "len" => bytecode.push(OP_LEN),
-            "itob" => bytecode.push(OP_ITOB),
-            "btoi" => bytecode.push(OP_BTOI),
-            "concat" => bytecode.push(OP_CONCAT),
-            "substring" => {
-                bytecode.push(OP_SUBSTRING);
-                self.assemble_substring_args(bytecode, args, line_num)?;
-            }
-            "substring3" => bytecode.push(OP_SUBSTRING3),
-            "getbit" => bytecode.push(OP_GETBIT),
-            "setbit" => bytecode.push(OP_SETBIT),
-            "getbyte" => bytecode.push(OP_GETBYTE),
-            "setbyte" => bytecode.push(OP_SETBYTE),
-            "extract" => {
-                bytecode.push(OP_EXTRACT);
-                self.assemble_substring_args(bytecode, args, line_num)?;
-            }
-            "extract3" => bytecode.push(OP_EXTRACT3),
-            "extract_uint16" => {
-                bytecode.push(OP_EXTRACT_UINT16);
-                self.assemble_byte_immediate(bytecode, args, line_num)?;
-            }
-            "extract_uint32" => {
-                bytecode.push(OP_EXTRACT_UINT32);
-                self.assemble_byte_immediate(bytecode, args, line_num)?;
-            }
-            "extract_uint64" => {
-                bytecode.push(OP_EXTRACT_UINT64);
-                self.assemble_byte_immediate(bytecode, args, line_num)?;
-            }
-            "replace2" => {
-                bytecode.push(OP_REPLACE2);
-                self.assemble_byte_immediate(bytecode, args, line_num)?;
-            }
-            "replace3" => bytecode.push(OP_REPLACE3),
-            "base64_decode" => {
-                bytecode.push(OP_BASE64_DECODE);
-                self.assemble_byte_immediate(bytecode, args, line_num)?;
-            }
-            "json_ref" => {
-                bytecode.push(OP_JSON_REF);
-                self.assemble_byte_immediate(bytecode, args, line_num)?;
-            }
+        // `txn f i` and friends are shorthand for the array forms
+        let opcode = match (opcode, args.len()) {
+            ("txn", 2) => "txna",
+            ("gtxn", 3) => "gtxna",
+            ("gtxns", 2) => "gtxnsa",
+            ("itxn", 2) => "itxna",
+            ("gitxn", 3) => "gitxna",
+            _ => opcode,
+        };
 
-            // Crypto
-            "sha256" => bytecode.push(OP_SHA256),
-            "keccak256" => bytecode.push(OP_KECCAK256),
-            "sha512_256" => bytecode.push(OP_SHA512_256),
-            "sha3_256" => bytecode.push(OP_SHA3_256),
-            "ed25519verify" => bytecode.push(OP_ED25519VERIFY),
-            "ed25519verify_bare" => bytecode.push(OP_ED25519VERIFY_BARE),
-            "vrf_verify" => bytecode.push(OP_VRF_VERIFY),
-
-            // Scratch space
-            "load" => {
-                bytecode.push(OP_LOAD);
-                self.assemble_byte_immediate(bytecode, args, line_num)?;
-            }
-            "store" => {
-                bytecode.push(OP_STORE);
-                self.assemble_byte_immediate(bytecode, args, line_num)?;
+        let spec = opcode_by_name(opcode).ok_or_else(|| {
+            let problem = Problem::new(codes::UNKNOWN_OPCODE, format!("Unknown opcode '{opcode}'"))
+                .at(opcode);
+            let names = standard_opcodes().iter().map(|spec| spec.name.as_str());
+            let names = names.chain(["int", "byte", "addr", "method"]);
+            match closest(opcode, names) {
+                Some(name) => problem.help(format!("did you mean `{name}`?")),
+                None => problem,
             }
+        })?;
+        if spec.min_version > self.program_version() {
+            return Err(Problem::new(
+                codes::VERSION,
+                format!("{opcode} opcode was introduced in v{}", spec.min_version),
+            )
+            .at(opcode)
+            .help(format!(
+                "the program is v{}; use `#pragma version {}` or later",
+                self.program_version(),
+                spec.min_version
+            )));
+        }
 
-            // Transaction fields
-            "txn" => {
-                bytecode.push(OP_TXN);
-                self.assemble_txn_field(bytecode, args, line_num)?;
-            }
-            "gtxn" => {
-                bytecode.push(OP_GTXN);
-                self.assemble_gtxn_args(bytecode, args, line_num)?;
+        match spec.opcode {
+            // The first constants and arguments have single byte opcodes
+            OP_INTC | OP_BYTEC | OP_ARG => {
+                let arg = single_arg(opcode, args)?;
+                let index = self.parse_integer(arg)?;
+                let index = u8::try_from(index).map_err(|_| {
+                    Problem::new(
+                        codes::INTEGER,
+                        format!("{opcode} index must be at most 255"),
+                    )
+                    .at(arg)
+                })?;
+                match spec.opcode {
+                    OP_INTC => self.intc_index(bytecode, index as usize),
+                    OP_BYTEC => self.bytec_index(bytecode, index as usize),
+                    _ => {
+                        push_indexed(bytecode, OP_ARG_0, OP_ARG, index as usize);
+                        Ok(())
+                    }
+                }
             }
-            "global" => {
-                bytecode.push(OP_GLOBAL);
-                self.assemble_global_field(bytecode, args, line_num)?;
+            OP_INTCBLOCK => {
+                let values = args
+                    .iter()
+                    .map(|arg| self.parse_integer(arg))
+                    .collect::<AsmResult<Vec<_>>>()?;
+                bytecode.push(spec.opcode);
+                self.assemble_immediates(bytecode, spec, args)?;
+                self.set_intc_block(values)
             }
-
-            // Application state
-            "app_opted_in" => bytecode.push(OP_APP_OPTED_IN),
-            "app_local_get" => bytecode.push(OP_APP_LOCAL_GET),
-            "app_local_get_ex" => bytecode.push(OP_APP_LOCAL_GET_EX),
-            "app_global_get" => bytecode.push(OP_APP_GLOBAL_GET),
-            "app_global_get_ex" => bytecode.push(OP_APP_GLOBAL_GET_EX),
-            "app_local_put" => bytecode.push(OP_APP_LOCAL_PUT),
-            "app_global_put" => bytecode.push(OP_APP_GLOBAL_PUT),
-            "app_local_del" => bytecode.push(OP_APP_LOCAL_DEL),
-            "app_global_del" => bytecode.push(OP_APP_GLOBAL_DEL),
-            "asset_holding_get" => bytecode.push(OP_ASSET_HOLDING_GET),
-            "asset_params_get" => bytecode.push(OP_ASSET_PARAMS_GET),
-            "app_params_get" => bytecode.push(OP_APP_PARAMS_GET),
-            "acct_params_get" => bytecode.push(OP_ACCT_PARAMS_GET),
-            "balance" => bytecode.push(OP_BALANCE),
-            "min_balance" => bytecode.push(OP_MIN_BALANCE),
-
-            // Box operations (v8+)
-            "box_create" => bytecode.push(OP_BOX_CREATE),
-            "box_extract" => bytecode.push(OP_BOX_EXTRACT),
-            "box_replace" => bytecode.push(OP_BOX_REPLACE),
-            "box_del" => bytecode.push(OP_BOX_DEL),
-            "box_len" => bytecode.push(OP_BOX_LEN),
-            "box_get" => bytecode.push(OP_BOX_GET),
-            "box_put" => bytecode.push(OP_BOX_PUT),
-            "box_splice" => bytecode.push(OP_BOX_SPLICE),
-            "box_resize" => bytecode.push(OP_BOX_RESIZE),
-
-            // Block operations
-            "block" => {
-                bytecode.push(OP_BLOCK);
-                self.assemble_byte_immediate(bytecode, args, line_num)?;
+            OP_BYTECBLOCK => {
+                let values = args
+                    .iter()
+                    .map(|arg| self.parse_bytes(&[arg]))
+                    .collect::<AsmResult<Vec<_>>>()?;
+                bytecode.push(spec.opcode);
+                self.assemble_immediates(bytecode, spec, args)?;
+                self.set_bytec_block(values)
             }
-
-            "err" => bytecode.push(OP_ERR),
-
             _ => {
-                return Err(AvmError::assembly_error(format!(
-                    "Unknown opcode '{opcode}' on line {line_num}"
-                )));
+                bytecode.push(spec.opcode);
+                self.assemble_immediates(bytecode, spec, args)
             }
         }
-
-        Ok(())
     }
 
-    /// Assemble branch target (may be forward reference)
-    fn assemble_branch_target(
+    /// Assemble the immediate arguments described by an opcode specification
+    fn assemble_immediates(
         &mut self,
         bytecode: &mut Vec<u8>,
+        spec: &OpSpec,
         args: &[&str],
-        line_num: usize,
-    ) -> AvmResult<()> {
-        if args.is_empty() {
-            return Err(AvmError::assembly_error(format!(
-                "Missing branch target on line {line_num}"
-            )));
-        }
+    ) -> AsmResult<()> {
+        let mut rest = args;
+        for immediate in &spec.immediates {
+            let missing = || {
+                Problem::new(
+                    codes::ARGUMENT_COUNT,
+                    format!("{} is missing immediate {}", spec.name, immediate.name),
+                )
+                .help(format!("{} takes {}", spec.name, immediate_names(spec)))
+            };
 
-        let target = args[0];
-
-        // Check if it's a label
-        if let Some(&addr) = self.labels.get(target) {
-            // The offset is calculated from the PC position when the branch executes
-            // At that point, PC has advanced past the entire instruction (opcode + 2 bytes)
-            // So the target calculation is: target_addr = (pc_after_instruction) + offset
-            // Therefore: offset = target_addr - pc_after_instruction
-            let pc_after_instruction = bytecode.len() + 2; // bytecode.len() is where offset goes, +2 gets past it
-            let offset = (addr as i32) - (pc_after_instruction as i32);
-            bytecode.extend_from_slice(&(offset as i16).to_be_bytes());
-        } else {
-            // Forward reference - add placeholder and record for later resolution
-            self.forward_refs.push((bytecode.len(), target.to_string()));
-            bytecode.extend_from_slice(&[0, 0]); // Placeholder
+            match immediate.kind {
+                ImmediateKind::Uint8 => {
+                    let (arg, tail) = rest.split_first().ok_or_else(missing)?;
+                    rest = tail;
+                    let value = self.parse_integer(arg)?;
+                    let value = u8::try_from(value).map_err(|_| {
+                        Problem::new(
+                            codes::INTEGER,
+                            format!(
+                                "{} immediate {} must be at most 255",
+                                spec.name, immediate.name
+                            ),
+                        )
+                        .at(arg)
+                    })?;
+                    bytecode.push(value);
+                }
+                ImmediateKind::Int8 => {
+                    let (arg, tail) = rest.split_first().ok_or_else(missing)?;
+                    rest = tail;
+                    let value: i8 = arg.parse().map_err(|_| {
+                        Problem::new(codes::INTEGER, format!("Invalid signed byte '{arg}'")).at(arg)
+                    })?;
+                    bytecode.push(value as u8);
+                }
+                ImmediateKind::Varuint => {
+                    let (arg, tail) = rest.split_first().ok_or_else(missing)?;
+                    rest = tail;
+                    let value = self.parse_integer(arg)?;
+                    bytecode.extend_from_slice(&encode_varuint(value));
+                }
+                ImmediateKind::Bytes => {
+                    if rest.is_empty() {
+                        return Err(missing());
+                    }
+                    // `base64 AAAA` spans two arguments
+                    let count = if matches!(rest[0], "base64" | "b64" | "base32" | "b32") {
+                        2
+                    } else {
+                        1
+                    };
+                    let (arg, tail) = rest.split_at(count.min(rest.len()));
+                    rest = tail;
+                    let bytes = self.parse_bytes(arg)?;
+                    bytecode.extend_from_slice(&encode_varuint(bytes.len() as u64));
+                    bytecode.extend_from_slice(&bytes);
+                }
+                ImmediateKind::Label => {
+                    let (arg, tail) = rest.split_first().ok_or_else(missing)?;
+                    rest = tail;
+                    let end = bytecode.len() + 2;
+                    self.assemble_label(bytecode, arg, end);
+                }
+                ImmediateKind::Labels => {
+                    let count = u8::try_from(rest.len()).map_err(|_| {
+                        Problem::new(
+                            codes::ARGUMENT_COUNT,
+                            format!("{} has more than 255 targets", spec.name),
+                        )
+                    })?;
+                    bytecode.push(count);
+                    // Offsets are relative to the end of the whole target list
+                    let end = bytecode.len() + 2 * rest.len();
+                    for arg in std::mem::take(&mut rest) {
+                        self.assemble_label(bytecode, arg, end);
+                    }
+                }
+                ImmediateKind::Varuints => {
+                    bytecode.extend_from_slice(&encode_varuint(rest.len() as u64));
+                    for arg in std::mem::take(&mut rest) {
+                        let value = self.parse_integer(arg)?;
+                        bytecode.extend_from_slice(&encode_varuint(value));
+                    }
+                }
+                ImmediateKind::BytesList => {
+                    bytecode.extend_from_slice(&encode_varuint(rest.len() as u64));
+                    for arg in std::mem::take(&mut rest) {
+                        let bytes = self.parse_bytes(&[arg])?;
+                        bytecode.extend_from_slice(&encode_varuint(bytes.len() as u64));
+                        bytecode.extend_from_slice(&bytes);
+                    }
+                }
+                ImmediateKind::Field(group) => {
+                    let (arg, tail) = rest.split_first().ok_or_else(missing)?;
+                    rest = tail;
+                    let value = group.value(arg).ok_or_else(|| {
+                        let problem = Problem::new(
+                            codes::FIELD,
+                            format!("Unknown {} '{arg}'", group.description()),
+                        )
+                        .at(arg);
+                        match closest(arg, group.names().iter().copied()) {
+                            Some(name) => problem.help(format!("did you mean `{name}`?")),
+                            None => problem,
+                        }
+                    })?;
+                    bytecode.push(value);
+                }
+            }
         }
 
+        if !rest.is_empty() {
+            return Err(Problem::new(
+                codes::ARGUMENT_COUNT,
+                format!(
+                    "{} expects {} immediate arguments",
+                    spec.name,
+                    spec.immediates.len()
+                ),
+            )
+            .at(rest[0])
+            .help(format!("{} takes {}", spec.name, immediate_names(spec))));
+        }
         Ok(())
     }
 
-    /// Assemble integer immediate value
-    fn assemble_int_immediate(
-        &mut self,
-        bytecode: &mut Vec<u8>,
-        args: &[&str],
-        line_num: usize,
-    ) -> AvmResult<()> {
-        if args.is_empty() {
-            return Err(AvmError::assembly_error(format!(
-                "Missing integer value on line {line_num}"
-            )));
-        }
-
-        let value = self.parse_integer(args[0], line_num)?;
-        bytecode.extend_from_slice(&value.to_be_bytes());
-        Ok(())
+    /// Assemble a branch offset relative to `end`, the pc after the instruction
+    ///
+    /// Offsets are patched once all labels are known, since rewriting constant
+    /// loads may still move code.
+    fn assemble_label(&mut self, bytecode: &mut Vec<u8>, label: &str, end: usize) {
+        self.label_refs.push(LabelRef {
+            position: bytecode.len(),
+            end,
+            label: label.to_string(),
+            line: 0,
+            columns: 0..0,
+        });
+        bytecode.extend_from_slice(&[0, 0]);
     }
 
-    /// Parse integer from various formats (decimal, hex, octal, binary)
-    fn parse_integer(&self, input: &str, line_num: usize) -> AvmResult<u64> {
-        let value = if input.starts_with("0x") || input.starts_with("0X") {
-            // Hexadecimal
-            u64::from_str_radix(&input[2..], 16)
-        } else if input.starts_with("0o") || input.starts_with("0O") {
-            // Octal
-            u64::from_str_radix(&input[2..], 8)
-        } else if input.starts_with("0b") || input.starts_with("0B") {
-            // Binary
-            u64::from_str_radix(&input[2..], 2)
+    /// Parse an integer like Go's `strconv.ParseUint` with base 0, as goal does
+    ///
+    /// `0x`, `0o` and `0b` prefixes select the base, as does a leading `0` for
+    /// octal. Underscores may separate digits.
+    fn parse_integer(&self, input: &str) -> AsmResult<u64> {
+        let prefix = input.get(..2).map(str::to_ascii_lowercase);
+        let (radix, digits, prefixed) = match prefix.as_deref() {
+            Some("0x") => (16, &input[2..], true),
+            Some("0o") => (8, &input[2..], true),
+            Some("0b") => (2, &input[2..], true),
+            _ if input.len() > 1 && input.starts_with('0') => (8, &input[1..], true),
+            _ => (10, input, false),
+        };
+
+        let value = if separators_valid(digits, prefixed) {
+            u64::from_str_radix(&digits.replace('_', ""), radix).ok()
         } else {
-            // Decimal
-            input.parse::<u64>()
+            None
         };
 
-        value.map_err(|_| {
-            AvmError::assembly_error(format!("Invalid integer '{input}' on line {line_num}"))
+        value.ok_or_else(|| {
+            Problem::new(codes::INTEGER, format!("Invalid integer '{input}'")).at(input)
         })
     }
 
-    /// Assemble byte immediate value
-    fn assemble_byte_immediate(
-        &mut self,
-        bytecode: &mut Vec<u8>,
-        args: &[&str],
-        line_num: usize,
-    ) -> AvmResult<()> {
-        if args.is_empty() {
-            return Err(AvmError::assembly_error(format!(
-                "Missing byte value on line {line_num}"
-            )));
-        }
-
-        let value: u8 = args[0].parse().map_err(|_| {
-            AvmError::assembly_error(format!(
-                "Invalid byte value '{}' on line {}",
-                args[0], line_num
-            ))
-        })?;
-
-        bytecode.push(value);
-        Ok(())
-    }
-
-    /// Assemble bytes immediate value
-    fn assemble_bytes_immediate(
-        &mut self,
-        bytecode: &mut Vec<u8>,
-        args: &[&str],
-        line_num: usize,
-    ) -> AvmResult<()> {
-        if args.is_empty() {
-            return Err(AvmError::assembly_error(format!(
-                "Missing bytes value on line {line_num}"
-            )));
-        }
-
-        let bytes = self.parse_bytes(args, line_num)?;
-
-        if bytes.len() > 255 {
-            return Err(AvmError::assembly_error(format!(
-                "Bytes too long ({} > 255) on line {}",
-                bytes.len(),
-                line_num
-            )));
-        }
-
-        bytecode.push(bytes.len() as u8);
-        bytecode.extend_from_slice(&bytes);
-        Ok(())
-    }
-
     /// Parse bytes from various formats
-    fn parse_bytes(&self, args: &[&str], line_num: usize) -> AvmResult<Vec<u8>> {
-        if args.is_empty() {
-            return Err(AvmError::assembly_error(format!(
-                "Missing bytes value on line {line_num}"
-            )));
-        }
-
-        // Handle different byte formats
-        if args.len() >= 2 && (args[0] == "base64" || args[0] == "b64") {
-            // base64 format: byte base64 AAAA... or byte b64 AAAA...
-            let b64_data = args[1..].join("");
-            use base64::{Engine as _, engine::general_purpose};
-            general_purpose::STANDARD
-                .decode(b64_data)
-                .map_err(|_| AvmError::assembly_error(format!("Invalid base64 on line {line_num}")))
-        } else if args.len() == 1 {
-            let arg = args[0];
-            if let Some(stripped) = arg.strip_prefix("0x") {
-                // Hex format: byte 0x1234...
-                hex::decode(stripped).map_err(|_| {
-                    AvmError::assembly_error(format!(
-                        "Invalid hex bytes '{arg}' on line {line_num}"
-                    ))
-                })
-            } else if arg.starts_with('"') && arg.ends_with('"') {
-                // String literal: byte "hello"
-                let content = &arg[1..arg.len() - 1];
-                Ok(self.parse_string_literal(content)?)
-            } else {
-                // Try to parse as base32 (Algorand address)
-                self.try_parse_base32(arg, line_num)
+    fn parse_bytes(&self, args: &[&str]) -> AsmResult<Vec<u8>> {
+        let invalid_format = || {
+            Problem::new(codes::BYTES, "Invalid bytes format")
+                .help("write bytes as 0x hex, a \"string\", or base64/base32 data")
+        };
+        match args {
+            [] => Err(Problem::new(codes::BYTES, "Missing bytes value")),
+            // byte base64 AAAA... or byte b32 AAAA...
+            [encoding, data] => match *encoding {
+                "base64" | "b64" => decode_base64(data),
+                "base32" | "b32" => decode_base32(data),
+                _ => Err(invalid_format()),
+            },
+            [arg] => {
+                if let Some(stripped) = arg.strip_prefix("0x") {
+                    // Hex format: byte 0x1234...
+                    hex::decode(stripped).map_err(|_| {
+                        Problem::new(codes::BYTES, format!("Invalid hex bytes '{arg}'")).at(arg)
+                    })
+                } else if arg.len() >= 2 && arg.starts_with('"') && arg.ends_with('"') {
+                    // String literal: byte "hello"
+                    let content = &arg[1..arg.len() - 1];
+                    self.parse_string_literal(content).map_err(|p| p.at(arg))
+                } else if let Some(data) = encoded_argument(arg, &["base64", "b64"]) {
+                    // byte base64(AAAA...)
+                    decode_base64(data).map_err(|p| p.at(arg))
+                } else if let Some(data) = encoded_argument(arg, &["base32", "b32"]) {
+                    // byte b32(AAAA...)
+                    decode_base32(data).map_err(|p| p.at(arg))
+                } else {
+                    // Try to parse as base32 (Algorand address)
+                    self.try_parse_base32(arg)
+                }
             }
-        } else {
-            Err(AvmError::assembly_error(format!(
-                "Invalid bytes format on line {line_num}"
-            )))
+            _ => Err(invalid_format()),
         }
     }
 
     /// Parse string literal with escape sequences
-    fn parse_string_literal(&self, content: &str) -> AvmResult<Vec<u8>> {
+    fn parse_string_literal(&self, content: &str) -> AsmResult<Vec<u8>> {
         let mut result = Vec::new();
         let mut chars = content.chars();
 
@@ -596,21 +770,18 @@ impl Assembler {
                             if let Ok(byte) = u8::from_str_radix(&hex, 16) {
                                 result.push(byte);
                             } else {
-                                return Err(AvmError::assembly_error(
-                                    "Invalid hex escape sequence".to_string(),
+                                return Err(Problem::new(
+                                    codes::BYTES,
+                                    "Invalid hex escape sequence",
                                 ));
                             }
                         } else {
-                            return Err(AvmError::assembly_error(
-                                "Invalid hex escape sequence".to_string(),
-                            ));
+                            return Err(Problem::new(codes::BYTES, "Invalid hex escape sequence"));
                         }
                     }
                     Some(c) => result.push(c as u8),
                     None => {
-                        return Err(AvmError::assembly_error(
-                            "Incomplete escape sequence".to_string(),
-                        ));
+                        return Err(Problem::new(codes::BYTES, "Incomplete escape sequence"));
                     }
                 }
             } else {
@@ -622,7 +793,7 @@ impl Assembler {
     }
 
     /// Try to parse as base32 (Algorand address format)
-    fn try_parse_base32(&self, input: &str, line_num: usize) -> AvmResult<Vec<u8>> {
+    fn try_parse_base32(&self, input: &str) -> AsmResult<Vec<u8>> {
         use base32::{Alphabet, decode};
 
         // Algorand uses a specific base32 alphabet (RFC4648 without padding)
@@ -632,95 +803,56 @@ impl Assembler {
                 if bytes.len() == 32 {
                     Ok(bytes)
                 } else {
-                    Err(AvmError::assembly_error(format!(
-                        "Invalid address length: expected 32 bytes, got {} on line {line_num}",
-                        bytes.len()
-                    )))
+                    Err(Problem::new(
+                        codes::BYTES,
+                        format!(
+                            "Invalid address length: expected 32 bytes, got {}",
+                            bytes.len()
+                        ),
+                    )
+                    .at(input))
                 }
             }
-            None => Err(AvmError::assembly_error(format!(
-                "Invalid base32 encoding in address '{input}' on line {line_num}"
-            ))),
+            None => Err(Problem::new(
+                codes::BYTES,
+                format!("Invalid base32 encoding in address '{input}'"),
+            )
+            .at(input)),
         }
     }
 
-    /// Assemble address immediate value
-    fn assemble_addr_immediate(
-        &mut self,
-        bytecode: &mut Vec<u8>,
-        args: &[&str],
-        line_num: usize,
-    ) -> AvmResult<()> {
-        if args.is_empty() {
-            return Err(AvmError::assembly_error(format!(
-                "Missing address value on line {line_num}"
-            )));
-        }
-
-        // Parse Algorand address (base32) and convert to bytes
-        let addr_bytes = self.parse_algorand_address(args[0], line_num)?;
-
-        if addr_bytes.len() > 255 {
-            return Err(AvmError::assembly_error(format!(
-                "Address too long ({} > 255) on line {}",
-                addr_bytes.len(),
-                line_num
-            )));
-        }
-
-        bytecode.push(addr_bytes.len() as u8);
-        bytecode.extend_from_slice(&addr_bytes);
-        Ok(())
-    }
-
-    /// Assemble method selector immediate value
-    fn assemble_method_immediate(
-        &mut self,
-        bytecode: &mut Vec<u8>,
-        args: &[&str],
-        line_num: usize,
-    ) -> AvmResult<()> {
-        if args.is_empty() {
-            return Err(AvmError::assembly_error(format!(
-                "Missing method signature on line {line_num}"
-            )));
-        }
-
-        // Join all args to form method signature, then compute selector
-        let method_sig = args.join(" ");
-        let selector = self.compute_method_selector(&method_sig)?;
-
-        bytecode.push(4); // Method selector is always 4 bytes
-        bytecode.extend_from_slice(&selector);
-        Ok(())
-    }
-
     /// Parse Algorand address from base32 format
-    fn parse_algorand_address(&self, addr: &str, line_num: usize) -> AvmResult<Vec<u8>> {
+    fn parse_algorand_address(&self, addr: &str) -> AsmResult<Vec<u8>> {
         use base32::{Alphabet, decode};
         use sha2::{Digest, Sha512_256};
 
         // Algorand addresses are 58 characters in base32
         if addr.len() != 58 {
-            return Err(AvmError::assembly_error(format!(
-                "Invalid Algorand address length on line {line_num}: expected 58 characters, got {}",
-                addr.len()
-            )));
+            return Err(Problem::new(
+                codes::ADDRESS,
+                format!(
+                    "Invalid Algorand address length: expected 58 characters, got {}",
+                    addr.len()
+                ),
+            )
+            .at(addr));
         }
 
         // Decode the base32 address
         let decoded = decode(Alphabet::Rfc4648 { padding: false }, addr).ok_or_else(|| {
-            AvmError::assembly_error(format!(
-                "Invalid base32 encoding in address on line {line_num}"
-            ))
+            Problem::new(codes::ADDRESS, "Invalid base32 encoding in address").at(addr)
         })?;
 
         // Algorand addresses contain 32 bytes + 4 byte checksum = 36 bytes total
         if decoded.len() != 36 {
-            return Err(AvmError::assembly_error(format!(
-                "Invalid decoded address length on line {line_num}: expected 36 bytes, got {}",
-                decoded.len()
-            )));
+            return Err(Problem::new(
+                codes::ADDRESS,
+                format!(
+                    "Invalid decoded address length: expected 36 bytes, got {}",
+                    decoded.len()
+                ),
+            )
+            .at(addr));
         }
 
         // Split address and checksum
@@ -733,535 +865,219 @@ impl Assembler {
         let expected_checksum = &hash[hash.len() - 4..];
 
         if checksum != expected_checksum {
-            return Err(AvmError::assembly_error(format!(
-                "Invalid address checksum on line {line_num}"
-            )));
+            return Err(Problem::new(codes::ADDRESS, "Invalid address checksum").at(addr));
         }
 
         Ok(address_bytes.to_vec())
     }
 
     /// Compute ARC-4 method selector from method signature
-    fn compute_method_selector(&self, method_sig: &str) -> AvmResult<[u8; 4]> {
-        use sha2::{Digest, Sha256};
+    fn compute_method_selector(&self, method_sig: &[u8]) -> [u8; 4] {
+        use sha2::{Digest, Sha512_256};
 
-        // Compute SHA-256 hash of method signature
-        let mut hasher = Sha256::new();
-        hasher.update(method_sig.as_bytes());
+        // ARC-4 selectors come from the SHA-512/256 hash of the signature
+        let mut hasher = Sha512_256::new();
+        hasher.update(method_sig);
         let hash = hasher.finalize();
 
         // Take first 4 bytes as method selector
         let mut selector = [0u8; 4];
         selector.copy_from_slice(&hash[..4]);
-        Ok(selector)
+        selector
     }
 
-    /// Assemble substring arguments
-    fn assemble_substring_args(
-        &mut self,
-        bytecode: &mut Vec<u8>,
-        args: &[&str],
-        line_num: usize,
-    ) -> AvmResult<()> {
-        if args.len() < 2 {
-            return Err(AvmError::assembly_error(format!(
-                "substring requires start and length on line {line_num}"
-            )));
+    /// Resolve label references, reporting every one that cannot be
+    fn resolve_label_refs(&mut self, bytecode: &mut [u8]) {
+        let mut problems = Vec::new();
+        for reference in &self.label_refs {
+            let Some(&target) = self.labels.get(&reference.label) else {
+                let problem = Problem::new(
+                    codes::UNDEFINED_LABEL,
+                    format!("Undefined label: {}", reference.label),
+                );
+                let problem =
+                    match closest(&reference.label, self.labels.keys().map(String::as_str)) {
+                        Some(label) => problem.help(format!("did you mean `{label}`?")),
+                        None => problem,
+                    };
+                problems.push((reference, problem));
+                continue;
+            };
+            let Ok(offset) = i16::try_from(target as i64 - reference.end as i64) else {
+                let message = format!("Label {} is too far away", reference.label);
+                problems.push((reference, Problem::new(codes::BRANCH, message)));
+                continue;
+            };
+            if offset < 0 && self.program_version() < BACK_BRANCH_VERSION {
+                let message = format!(
+                    "Label {} is a back reference, back jump support was introduced in v{BACK_BRANCH_VERSION}",
+                    reference.label
+                );
+                let help = format!("use `#pragma version {BACK_BRANCH_VERSION}` or later");
+                problems.push((reference, Problem::new(codes::BRANCH, message).help(help)));
+                continue;
+            }
+            bytecode[reference.position..reference.position + 2]
+                .copy_from_slice(&offset.to_be_bytes());
         }
 
-        let start: u8 = args[0].parse().map_err(|_| {
-            AvmError::assembly_error(format!(
-                "Invalid start value '{}' on line {}",
-                args[0], line_num
-            ))
-        })?;
-
-        let length: u8 = args[1].parse().map_err(|_| {
-            AvmError::assembly_error(format!(
-                "Invalid length value '{}' on line {}",
-                args[1], line_num
-            ))
-        })?;
-
-        bytecode.push(start);
-        bytecode.push(length);
-        Ok(())
+        let diagnostics = problems.into_iter().map(|(reference, problem)| Diagnostic {
+            file: self.source_name.clone(),
+            line: reference.line,
+            columns: reference.columns.clone(),
+            severity: Severity::Error,
+            code: problem.code,
+            message: problem.message,
+            help: problem.help,
+        });
+        self.diagnostics.extend(diagnostics.collect::<Vec<_>>());
     }
+}
 
-    /// Assemble transaction field
-    fn assemble_txn_field(
-        &mut self,
-        bytecode: &mut Vec<u8>,
-        args: &[&str],
-        line_num: usize,
-    ) -> AvmResult<()> {
-        if args.is_empty() {
-            return Err(AvmError::assembly_error(format!(
-                "Missing transaction field on line {line_num}"
-            )));
-        }
-
-        let field_id = match args[0] {
-            "Sender" => 0,
-            "Fee" => 1,
-            "FirstValid" => 2,
-            "FirstValidTime" => 3,
-            "LastValid" => 4,
-            "Note" => 5,
-            "Lease" => 6,
-            "Receiver" => 7,
-            "Amount" => 8,
-            "CloseRemainderTo" => 9,
-            "VotePK" => 10,
-            "SelectionPK" => 11,
-            "VoteFirst" => 12,
-            "VoteLast" => 13,
-            "VoteKeyDilution" => 14,
-            "Type" => 15,
-            "TypeEnum" => 16,
-            "XferAsset" => 17,
-            "AssetAmount" => 18,
-            "AssetSender" => 19,
-            "AssetReceiver" => 20,
-            "AssetCloseTo" => 21,
-            "GroupIndex" => 22,
-            "TxID" => 23,
-            "ApplicationID" => 24,
-            "OnCompletion" => 25,
-            "ApplicationArgs" => 26,
-            "NumAppArgs" => 27,
-            "Accounts" => 28,
-            "NumAccounts" => 29,
-            "ApprovalProgram" => 30,
-            "ClearStateProgram" => 31,
-            "RekeyTo" => 32,
-            "ConfigAsset" => 33,
-            "ConfigAssetTotal" => 34,
-            "ConfigAssetDecimals" => 35,
-            "ConfigAssetDefaultFrozen" => 36,
-            "ConfigAssetUnitName" => 37,
-            "ConfigAssetName" => 38,
-            "ConfigAssetURL" => 39,
-            "ConfigAssetMetadataHash" => 40,
-            "ConfigAssetManager" => 41,
-            "ConfigAssetReserve" => 42,
-            "ConfigAssetFreeze" => 43,
-            "ConfigAssetClawback" => 44,
-            "FreezeAsset" => 45,
-            "FreezeAssetAccount" => 46,
-            "FreezeAssetFrozen" => 47,
-            "Assets" => 48,
-            "NumAssets" => 49,
-            "Applications" => 50,
-            "NumApplications" => 51,
-            "GlobalNumUint" => 52,
-            "GlobalNumByteSlice" => 53,
-            "LocalNumUint" => 54,
-            "LocalNumByteSlice" => 55,
-            "ExtraProgramPages" => 56,
-            "Nonparticipation" => 57,
-            "Logs" => 58,
-            "NumLogs" => 59,
-            "CreatedAssetID" => 60,
-            "CreatedApplicationID" => 61,
-            "LastLog" => 62,
-            "StateProofPK" => 63,
-            "ApprovalProgramPages" => 64,
-            "NumApprovalProgramPages" => 65,
-            "ClearStateProgramPages" => 66,
-            "NumClearStateProgramPages" => 67,
-            _ => {
-                return Err(AvmError::assembly_error(format!(
-                    "Unknown transaction field '{}' on line {}",
-                    args[0], line_num
-                )));
-            }
-        };
-
-        bytecode.push(field_id);
-        Ok(())
-    }
+/// Columns of `field`, a slice of `line`
+fn columns(line: &str, field: &str) -> Range<usize> {
+    let start = field.as_ptr() as usize - line.as_ptr() as usize;
+    start..start + field.len()
+}
 
-    /// Assemble group transaction arguments
-    fn assemble_gtxn_args(
-        &mut self,
-        bytecode: &mut Vec<u8>,
-        args: &[&str],
-        line_num: usize,
-    ) -> AvmResult<()> {
-        if args.len() < 2 {
-            return Err(AvmError::assembly_error(format!(
-                "gtxn requires group index and field on line {line_num}"
-            )));
+/// Names of an opcode's immediates, for help notes
+fn immediate_names(spec: &OpSpec) -> String {
+    match spec.immediates.len() {
+        0 => "no immediate arguments".to_string(),
+        _ => {
+            let names: Vec<_> = spec.immediates.iter().map(|i| i.name).collect();
+            format!("immediates {}", names.join(" "))
         }
-
-        let group_index: u8 = args[0].parse().map_err(|_| {
-            AvmError::assembly_error(format!(
-                "Invalid group index '{}' on line {}",
-                args[0], line_num
-            ))
-        })?;
-
-        bytecode.push(group_index);
-        self.assemble_txn_field(bytecode, &args[1..], line_num)?;
-        Ok(())
     }
+}
 
-    /// Assemble global field
-    fn assemble_global_field(
-        &mut self,
-        bytecode: &mut Vec<u8>,
-        args: &[&str],
-        line_num: usize,
-    ) -> AvmResult<()> {
-        if args.is_empty() {
-            return Err(AvmError::assembly_error(format!(
-                "Missing global field on line {line_num}"
-            )));
-        }
-
-        let field_id = match args[0] {
-            "MinTxnFee" => 0,
-            "MinBalance" => 1,
-            "MaxTxnLife" => 2,
-            "ZeroAddress" => 3,
-            "GroupSize" => 4,
-            "LogicSigVersion" => 5,
-            "Round" => 6,
-            "LatestTimestamp" => 7,
-            "CurrentApplicationID" => 8,
-            "CreatorAddress" => 9,
-            "CurrentApplicationAddress" => 10,
-            "GroupID" => 11,
-            "OpcodeBudget" => 12,
-            "CallerApplicationID" => 13,
-            "CallerApplicationAddress" => 14,
-            "AssetCreateMinBalance" => 15,
-            "AssetOptInMinBalance" => 16,
-            "GenesisHash" => 17,
+/// Split a source line into statements of whitespace separated fields
+///
+/// String literals stay whole, quotes included. A field starting with `//`
+/// begins a comment and `;` ends a statement.
+fn tokenize(line: &str) -> AsmResult<Vec<Vec<&str>>> {
+    let bytes = line.as_bytes();
+    let mut statements = vec![Vec::new()];
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b';' => {
+                statements.push(Vec::new());
+                pos += 1;
+            }
+            byte if byte.is_ascii_whitespace() => pos += 1,
+            b'/' if bytes.get(pos + 1) == Some(&b'/') => break,
             _ => {
-                return Err(AvmError::assembly_error(format!(
-                    "Unknown global field '{}' on line {}",
-                    args[0], line_num
-                )));
+                let start = pos;
+                let mut quoted = false;
+                while pos < bytes.len() {
+                    match bytes[pos] {
+                        b'\\' if quoted => pos += 1,
+                        b'"' => quoted = !quoted,
+                        byte if !quoted && (byte == b';' || byte.is_ascii_whitespace()) => break,
+                        _ => {}
+                    }
+                    pos += 1;
+                }
+                if quoted {
+                    return Err(Problem::new(codes::SYNTAX, "Unterminated string literal")
+                        .help("close the string with `\"`"));
+                }
+                statements
+                    .last_mut()
+                    .unwrap()
+                    .push(&line[start..pos.min(bytes.len())]);
             }
-        };
-
-        bytecode.push(field_id);
-        Ok(())
-    }
-
-    /// Assemble integer constant block
-    fn assemble_intcblock(
-        &mut self,
-        bytecode: &mut Vec<u8>,
-        args: &[&str],
-        line_num: usize,
-    ) -> AvmResult<()> {
-        if args.is_empty() {
-            return Err(AvmError::assembly_error(format!(
-                "intcblock requires at least one integer constant on line {line_num}"
-            )));
         }
-
-        // Write count as varuint
-        let count_bytes = encode_varuint(args.len() as u64);
-        bytecode.extend_from_slice(&count_bytes);
-
-        // Write each integer constant as varuint
-        for arg in args {
-            let value = self.parse_integer(arg, line_num)?;
-            let value_bytes = encode_varuint(value);
-            bytecode.extend_from_slice(&value_bytes);
-        }
-
-        Ok(())
     }
 
-    /// Assemble byte constant block
-    fn assemble_bytecblock(
-        &mut self,
-        bytecode: &mut Vec<u8>,
-        args: &[&str],
-        line_num: usize,
-    ) -> AvmResult<()> {
-        if args.is_empty() {
-            return Err(AvmError::assembly_error(format!(
-                "bytecblock requires at least one byte constant on line {line_num}"
-            )));
-        }
-
-        // Write count as varuint
-        let count_bytes = encode_varuint(args.len() as u64);
-        bytecode.extend_from_slice(&count_bytes);
-
-        // Write each byte constant
-        for arg in args {
-            let bytes = self.parse_bytes(&[arg], line_num)?;
-
-            // Write length as varuint followed by the bytes
-            let length_bytes = encode_varuint(bytes.len() as u64);
-            bytecode.extend_from_slice(&length_bytes);
-            bytecode.extend_from_slice(&bytes);
-        }
-
-        Ok(())
-    }
+    statements.retain(|fields| !fields.is_empty());
+    Ok(statements)
+}
 
-    /// Resolve forward label references
-    fn resolve_forward_refs(&self, bytecode: &mut [u8]) -> AvmResult<()> {
-        for (addr, label) in &self.forward_refs {
-            let target_addr = self
-                .labels
-                .get(label)
-                .ok_or_else(|| AvmError::assembly_error(format!("Undefined label: {label}")))?;
-
-            // The offset is calculated from the PC position when the branch executes
-            // At that point, PC has advanced past the entire instruction (opcode + 2 bytes)
-            // So the target calculation is: target_addr = (pc_after_instruction) + offset
-            // Therefore: offset = target_addr - pc_after_instruction
-            let pc_after_instruction = *addr + 2; // addr points to offset bytes, +2 gets past them
-            let offset = (*target_addr as i32) - (pc_after_instruction as i32);
-            let offset_bytes = (offset as i16).to_be_bytes();
-            bytecode[*addr] = offset_bytes[0];
-            bytecode[*addr + 1] = offset_bytes[1];
+/// Whether underscores in integer digits each sit between two digits
+///
+/// A base prefix counts as a digit before the first underscore.
+fn separators_valid(digits: &str, prefixed: bool) -> bool {
+    let mut after_digit = prefixed;
+    let mut chars = digits.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '_' if after_digit && chars.peek().is_some_and(char::is_ascii_alphanumeric) => {
+                after_digit = false;
+            }
+            c if c.is_ascii_alphanumeric() => after_digit = true,
+            _ => return false,
         }
-
-        Ok(())
     }
+    !digits.is_empty()
 }
 
-/// Disassemble bytecode to TEAL source
-pub fn disassemble(bytecode: &[u8]) -> AvmResult<String> {
-    use crate::varuint::decode_varuint;
-    let mut result = String::new();
-    let mut pc = 0;
-
-    while pc < bytecode.len() {
-        let opcode = bytecode[pc];
-
-        result.push_str(&format!("{pc:04x}: "));
-
-        let (instruction, size) = match opcode {
-            OP_ERR => ("err".to_string(), 1),
-            OP_PLUS => ("+".to_string(), 1),
-            OP_MINUS => ("-".to_string(), 1),
-            OP_MUL => ("*".to_string(), 1),
-            OP_DIV => ("/".to_string(), 1),
-            OP_MOD => ("%".to_string(), 1),
-            OP_LT => ("<".to_string(), 1),
-            OP_GT => (">".to_string(), 1),
-            OP_LE => ("<=".to_string(), 1),
-            OP_GE => (">=".to_string(), 1),
-            OP_EQ => ("==".to_string(), 1),
-            OP_NE => ("!=".to_string(), 1),
-            OP_AND => ("&&".to_string(), 1),
-            OP_OR => ("||".to_string(), 1),
-            OP_NOT => ("!".to_string(), 1),
-            OP_BITWISE_OR => ("|".to_string(), 1),
-            OP_BITWISE_AND => ("&".to_string(), 1),
-            OP_BITWISE_XOR => ("^".to_string(), 1),
-            OP_BITWISE_NOT => ("~".to_string(), 1),
-            OP_POP => ("pop".to_string(), 1),
-            OP_DUP => ("dup".to_string(), 1),
-            OP_DUP2 => ("dup2".to_string(), 1),
-            OP_SWAP => ("swap".to_string(), 1),
-            OP_SELECT => ("select".to_string(), 1),
-            OP_BNZ => {
-                if pc + 2 < bytecode.len() {
-                    let offset = i16::from_be_bytes([bytecode[pc + 1], bytecode[pc + 2]]);
-                    let target = (pc as i32 + 3 + offset as i32) as usize;
-                    (format!("bnz {target:04x}"), 3)
-                } else {
-                    ("bnz <invalid>".to_string(), 1)
-                }
-            }
-            OP_BZ => {
-                if pc + 2 < bytecode.len() {
-                    let offset = i16::from_be_bytes([bytecode[pc + 1], bytecode[pc + 2]]);
-                    let target = (pc as i32 + 3 + offset as i32) as usize;
-                    (format!("bz {target:04x}"), 3)
-                } else {
-                    ("bz <invalid>".to_string(), 1)
-                }
-            }
-            OP_B => {
-                if pc + 2 < bytecode.len() {
-                    let offset = i16::from_be_bytes([bytecode[pc + 1], bytecode[pc + 2]]);
-                    let target = (pc as i32 + 3 + offset as i32) as usize;
-                    (format!("b {target:04x}"), 3)
-                } else {
-                    ("b <invalid>".to_string(), 1)
-                }
-            }
-            OP_RETURN => ("return".to_string(), 1),
-            OP_ASSERT => ("assert".to_string(), 1),
-            OP_RETSUB => ("retsub".to_string(), 1),
-            OP_SHA256 => ("sha256".to_string(), 1),
-            OP_KECCAK256 => ("keccak256".to_string(), 1),
-            OP_SHA512_256 => ("sha512_256".to_string(), 1),
-            OP_ED25519VERIFY => ("ed25519verify".to_string(), 1),
-            OP_LEN => ("len".to_string(), 1),
-            OP_ITOB => ("itob".to_string(), 1),
-            OP_BTOI => ("btoi".to_string(), 1),
-            OP_CONCAT => ("concat".to_string(), 1),
-            OP_SUBSTRING3 => ("substring3".to_string(), 1),
-            OP_APP_GLOBAL_GET => ("app_global_get".to_string(), 1),
-            OP_APP_GLOBAL_PUT => ("app_global_put".to_string(), 1),
-            OP_APP_GLOBAL_DEL => ("app_global_del".to_string(), 1),
-            OP_APP_LOCAL_GET => ("app_local_get".to_string(), 1),
-            OP_APP_LOCAL_PUT => ("app_local_put".to_string(), 1),
-            OP_APP_LOCAL_DEL => ("app_local_del".to_string(), 1),
-            OP_BALANCE => ("balance".to_string(), 1),
-            OP_MIN_BALANCE => ("min_balance".to_string(), 1),
-
-            // Constant block opcodes
-            OP_INTCBLOCK => {
-                let mut offset = pc + 1;
-
-                // Read count as varuint
-                if let Ok((count, consumed)) = decode_varuint(&bytecode[offset..]) {
-                    offset += consumed;
-                    let count = count as usize;
-                    let mut constants = Vec::new();
-
-                    // Read each integer constant as varuint
-                    for _ in 0..count {
-                        if let Ok((value, consumed)) = decode_varuint(&bytecode[offset..]) {
-                            constants.push(value.to_string());
-                            offset += consumed;
-                        } else {
-                            break;
-                        }
-                    }
+/// Value of a named integer constant: a transaction type or an `OnCompletion` action
+fn named_int_constant(name: &str) -> Option<u64> {
+    const TXN_TYPES: &[&str] = &["unknown", "pay", "keyreg", "acfg", "axfer", "afrz", "appl"];
+    const ON_COMPLETIONS: &[&str] = &[
+        "NoOp",
+        "OptIn",
+        "CloseOut",
+        "ClearState",
+        "UpdateApplication",
+        "DeleteApplication",
+    ];
+
+    TXN_TYPES
+        .iter()
+        .position(|constant| *constant == name)
+        .or_else(|| ON_COMPLETIONS.iter().position(|constant| *constant == name))
+        .map(|index| index as u64)
+}
 
-                    if constants.len() == count {
-                        (format!("intcblock {}", constants.join(" ")), offset - pc)
-                    } else {
-                        ("intcblock <invalid>".to_string(), 1)
-                    }
-                } else {
-                    ("intcblock <invalid>".to_string(), 1)
-                }
-            }
-            OP_INTC => {
-                if pc + 1 < bytecode.len() {
-                    let index = bytecode[pc + 1];
-                    (format!("intc {index}"), 2)
-                } else {
-                    ("intc <invalid>".to_string(), 1)
-                }
-            }
-            OP_INTC_0 => ("intc_0".to_string(), 1),
-            OP_INTC_1 => ("intc_1".to_string(), 1),
-            OP_INTC_2 => ("intc_2".to_string(), 1),
-            OP_INTC_3 => ("intc_3".to_string(), 1),
-            OP_BYTECBLOCK => {
-                let mut offset = pc + 1;
-
-                // Read count as varuint
-                if let Ok((count, consumed)) = decode_varuint(&bytecode[offset..]) {
-                    offset += consumed;
-                    let count = count as usize;
-                    let mut constants = Vec::new();
-
-                    // Read each byte constant (length-prefixed)
-                    for _ in 0..count {
-                        if let Ok((length, consumed)) = decode_varuint(&bytecode[offset..]) {
-                            offset += consumed;
-                            let length = length as usize;
-
-                            if offset + length <= bytecode.len() {
-                                let bytes = &bytecode[offset..offset + length];
-                                if bytes.iter().all(|&b| b.is_ascii() && !b.is_ascii_control()) {
-                                    constants
-                                        .push(format!("\"{}\"", String::from_utf8_lossy(bytes)));
-                                } else {
-                                    constants.push(format!("0x{}", hex::encode(bytes)));
-                                }
-                                offset += length;
-                            } else {
-                                break;
-                            }
-                        } else {
-                            break;
-                        }
-                    }
+/// Data of an argument written as `encoding(data)`
+fn encoded_argument<'a>(arg: &'a str, encodings: &[&str]) -> Option<&'a str> {
+    encodings.iter().find_map(|encoding| {
+        arg.strip_prefix(encoding)?
+            .strip_prefix('(')?
+            .strip_suffix(')')
+    })
+}
 
-                    if constants.len() == count {
-                        (format!("bytecblock {}", constants.join(" ")), offset - pc)
-                    } else {
-                        ("bytecblock <invalid>".to_string(), 1)
-                    }
-                } else {
-                    ("bytecblock <invalid>".to_string(), 1)
-                }
-            }
-            OP_BYTEC => {
-                if pc + 1 < bytecode.len() {
-                    let index = bytecode[pc + 1];
-                    (format!("bytec {index}"), 2)
-                } else {
-                    ("bytec <invalid>".to_string(), 1)
-                }
-            }
-            OP_BYTEC_0 => ("bytec_0".to_string(), 1),
-            OP_BYTEC_1 => ("bytec_1".to_string(), 1),
-            OP_BYTEC_2 => ("bytec_2".to_string(), 1),
-            OP_BYTEC_3 => ("bytec_3".to_string(), 1),
-
-            // Argument opcodes
-            OP_ARG => {
-                if pc + 1 < bytecode.len() {
-                    let index = bytecode[pc + 1];
-                    (format!("arg {index}"), 2)
-                } else {
-                    ("arg <invalid>".to_string(), 1)
-                }
-            }
-            OP_ARG_0 => ("arg_0".to_string(), 1),
-            OP_ARG_1 => ("arg_1".to_string(), 1),
-            OP_ARG_2 => ("arg_2".to_string(), 1),
-            OP_ARG_3 => ("arg_3".to_string(), 1),
-
-            OP_PUSHINT => {
-                if pc + 8 < bytecode.len() {
-                    let value = u64::from_be_bytes(bytecode[pc + 1..pc + 9].try_into().unwrap());
-                    (format!("int {value}"), 9)
-                } else {
-                    ("int <invalid>".to_string(), 1)
-                }
-            }
+/// Decode standard base64, as written after `base64` or `b64`
+fn decode_base64(data: &str) -> AsmResult<Vec<u8>> {
+    use base64::{Engine as _, engine::general_purpose};
 
-            OP_PUSHBYTES => {
-                if pc + 1 < bytecode.len() {
-                    let len = bytecode[pc + 1] as usize;
-                    if pc + 1 + len < bytecode.len() {
-                        let bytes = &bytecode[pc + 2..pc + 2 + len];
-                        if bytes.iter().all(|&b| b.is_ascii() && !b.is_ascii_control()) {
-                            (
-                                format!("byte \"{}\"", String::from_utf8_lossy(bytes)),
-                                2 + len,
-                            )
-                        } else {
-                            (format!("byte 0x{}", hex::encode(bytes)), 2 + len)
-                        }
-                    } else {
-                        ("byte <invalid>".to_string(), 1)
-                    }
-                } else {
-                    ("byte <invalid>".to_string(), 1)
-                }
-            }
+    general_purpose::STANDARD
+        .decode(data)
+        .map_err(|_| Problem::new(codes::BYTES, "Invalid base64").at(data))
+}
 
-            _ => (format!("unknown_{opcode:02x}"), 1),
-        };
+/// Decode base32 with optional padding, as written after `base32` or `b32`
+fn decode_base32(data: &str) -> AsmResult<Vec<u8>> {
+    use base32::{Alphabet, decode};
 
-        result.push_str(&instruction);
-        result.push('\n');
+    decode(
+        Alphabet::Rfc4648 { padding: false },
+        data.trim_end_matches('='),
+    )
+    .ok_or_else(|| Problem::new(codes::BYTES, "Invalid base32").at(data))
+}
 
-        pc += size;
+/// The only argument of a pseudo-op
+fn single_arg<'a>(opcode: &str, args: &[&'a str]) -> AsmResult<&'a str> {
+    match args {
+        [arg] => Ok(arg),
+        _ => Err(Problem::new(
+            codes::ARGUMENT_COUNT,
+            format!("{opcode} expects one argument"),
+        )),
     }
+}
 
-    Ok(result)
+/// Emit a load of `index` using the short opcodes `base + index` when they exist
+fn push_indexed(bytecode: &mut Vec<u8>, base: u8, opcode: u8, index: usize) {
+    if index < 4 {
+        bytecode.push(base + index as u8);
+    } else {
+        bytecode.extend_from_slice(&[opcode, index as u8]);
+    }
 }
@@ -0,0 +1,484 @@
+//! Peephole optimization of assembled programs
+//!
+//! The program is decoded into instructions whose branch targets are other
+//! instructions, rewritten until nothing changes, and encoded again with the
+//! branch offsets recomputed. Rewrites never span an instruction that is
+//! branched to, and branches to a removed instruction move to the one after
+//! it.
+//!
+//! Programs are assumed to be valid where they are rewritten: removing
+//! `dup; pop` also removes the stack underflow it would cause on an empty
+//! stack.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use super::disassembler::{disassemble_immediates, read_bytes};
+use crate::error::{AvmError, AvmResult};
+use crate::opcodes::*;
+use crate::varuint::{decode_varuint, encode_varuint};
+
+/// Longest byte string the AVM allows on the stack
+const MAX_STRING_SIZE: usize = 4096;
+
+/// A rewrite made by the optimizer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rewrite {
+    /// Pc of the first rewritten instruction in the unoptimized program
+    pub pc: usize,
+    /// What was rewritten
+    pub kind: RewriteKind,
+    /// Bytes removed from the program
+    pub bytes_saved: usize,
+    /// Opcode cost no longer spent each time the rewritten code runs
+    pub cost_saved: u64,
+}
+
+/// Kinds of peephole rewrites
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RewriteKind {
+    /// Pure arithmetic on pushed constants replaced by its result
+    ConstantFold,
+    /// `dup; pop` removed
+    DupPop,
+    /// `swap; swap` removed
+    SwapSwap,
+    /// Branch to an unconditional branch sent straight to its target
+    JumpThread,
+    /// Unconditional branch to the next instruction removed
+    JumpToNext,
+    /// Unreachable code after `b`, `return`, `retsub` or `err` removed
+    DeadCode,
+}
+
+impl fmt::Display for RewriteKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            RewriteKind::ConstantFold => "constant folding",
+            RewriteKind::DupPop => "dup; pop elimination",
+            RewriteKind::SwapSwap => "swap; swap elimination",
+            RewriteKind::JumpThread => "jump threading",
+            RewriteKind::JumpToNext => "jump to next instruction",
+            RewriteKind::DeadCode => "dead code",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A decoded instruction
+#[derive(Debug, Clone)]
+struct Instruction {
+    /// Pc in the unoptimized program
+    pc: usize,
+    /// Encoding, with branch offsets left as they were
+    bytes: Vec<u8>,
+    /// Indexes of the instructions branched to, the instruction count for the
+    /// end of the program
+    targets: Vec<usize>,
+}
+
+impl Instruction {
+    /// Instruction without branches, synthesized by a rewrite
+    fn new(pc: usize, bytes: Vec<u8>) -> Self {
+        Self {
+            pc,
+            bytes,
+            targets: Vec::new(),
+        }
+    }
+
+    fn opcode(&self) -> u8 {
+        self.bytes[0]
+    }
+
+    fn cost(&self) -> u64 {
+        opcode_by_byte(self.opcode()).map_or(0, |spec| spec.cost)
+    }
+
+    /// Value pushed by `pushint`
+    fn pushed_int(&self) -> Option<u64> {
+        if self.opcode() != OP_PUSHINT {
+            return None;
+        }
+        decode_varuint(&self.bytes[1..])
+            .ok()
+            .map(|(value, _)| value)
+    }
+
+    /// Value pushed by `pushbytes`
+    fn pushed_bytes(&self) -> Option<&[u8]> {
+        if self.opcode() != OP_PUSHBYTES {
+            return None;
+        }
+        read_bytes(&self.bytes, 1).map(|(bytes, _)| bytes)
+    }
+
+    /// Positions of the branch offsets in the encoding
+    fn offset_positions(&self) -> Vec<usize> {
+        match self.opcode() {
+            OP_SWITCH | OP_MATCH => (0..self.targets.len()).map(|i| 2 + 2 * i).collect(),
+            _ if self.targets.len() == 1 => vec![1],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Whether execution never continues to the next instruction
+    fn is_terminator(&self) -> bool {
+        matches!(self.opcode(), OP_B | OP_RETURN | OP_RETSUB | OP_ERR)
+    }
+}
+
+/// An optimized program
+#[derive(Debug, Clone)]
+pub struct Optimized {
+    pub program: Vec<u8>,
+    pub rewrites: Vec<Rewrite>,
+    /// Pc of each kept instruction before and after optimization, in order
+    pub moved: Vec<(usize, usize)>,
+}
+
+impl Optimized {
+    /// New pc of the code at `pc` in the unoptimized program
+    ///
+    /// Code that was removed maps to the first instruction kept after it.
+    pub fn new_pc(&self, pc: usize) -> usize {
+        let index = self.moved.partition_point(|(old, _)| *old < pc);
+        self.moved
+            .get(index)
+            .map_or(self.program.len(), |(_, new)| *new)
+    }
+}
+
+/// Optimize an assembled program
+pub fn optimize(program: &[u8]) -> AvmResult<Optimized> {
+    let (_, start) = crate::vm::program_version(program)?;
+    let mut instructions = decode(program, start)?;
+    let mut rewrites = Vec::new();
+
+    loop {
+        let before = rewrites.len();
+        thread_jumps(&mut instructions, &mut rewrites);
+        let mut removed = vec![false; instructions.len()];
+        rewrite_windows(&mut instructions, &mut removed, &mut rewrites);
+        compact(&mut instructions, &removed);
+        if rewrites.len() == before {
+            break;
+        }
+    }
+
+    let mut optimized = program[..start].to_vec();
+    let (bytecode, pcs) = encode(&instructions)?;
+    optimized.extend(bytecode);
+    let moved = instructions
+        .iter()
+        .zip(pcs)
+        .map(|(instruction, pc)| (instruction.pc, start + pc))
+        .collect();
+    Ok(Optimized {
+        program: optimized,
+        rewrites,
+        moved,
+    })
+}
+
+/// Split the program after its header into instructions
+fn decode(program: &[u8], start: usize) -> AvmResult<Vec<Instruction>> {
+    let mut instructions = Vec::new();
+    let mut target_pcs = Vec::new();
+    let mut pc = start;
+    while pc < program.len() {
+        let spec = opcode_by_byte(program[pc]).ok_or_else(|| {
+            AvmError::invalid_program(format!("Unknown opcode 0x{:02x} at {pc}", program[pc]))
+        })?;
+        let (_, end) = disassemble_immediates(spec, program, pc + 1)
+            .ok_or_else(|| AvmError::invalid_program(format!("Truncated {} at {pc}", spec.name)))?;
+
+        let instruction = Instruction::new(pc, program[pc..end].to_vec());
+        // Branch offsets are relative to the end of the instruction
+        let pcs = branch_offsets(&instruction)
+            .into_iter()
+            .map(|offset| end as i64 + offset as i64)
+            .collect::<Vec<_>>();
+        instructions.push(instruction);
+        target_pcs.push(pcs);
+        pc = end;
+    }
+
+    let pcs: Vec<usize> = instructions.iter().map(|i| i.pc).collect();
+    for (instruction, targets) in instructions.iter_mut().zip(target_pcs) {
+        for target in targets {
+            let index = if target == program.len() as i64 {
+                pcs.len()
+            } else {
+                pcs.iter()
+                    .position(|&pc| pc as i64 == target)
+                    .ok_or_else(|| {
+                        AvmError::invalid_program(format!(
+                            "Branch at {} targets {target}, which is not an instruction",
+                            instruction.pc
+                        ))
+                    })?
+            };
+            instruction.targets.push(index);
+        }
+    }
+    Ok(instructions)
+}
+
+/// Branch offsets of a freshly decoded instruction
+fn branch_offsets(instruction: &Instruction) -> Vec<i16> {
+    let bytes = &instruction.bytes;
+    let offset = |at: usize| i16::from_be_bytes([bytes[at], bytes[at + 1]]);
+    match instruction.opcode() {
+        OP_B | OP_BZ | OP_BNZ | OP_CALLSUB => vec![offset(1)],
+        OP_SWITCH | OP_MATCH => (0..bytes[1] as usize).map(|i| offset(2 + 2 * i)).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Send branches to an unconditional branch straight to where it goes
+///
+/// Before v4 every branch goes forward, so threaded branches do too.
+fn thread_jumps(instructions: &mut [Instruction], rewrites: &mut Vec<Rewrite>) {
+    for index in 0..instructions.len() {
+        for slot in 0..instructions[index].targets.len() {
+            let mut target = instructions[index].targets[slot];
+            let mut seen = HashSet::new();
+            let mut hops = 0;
+            while let Some(next) = instructions.get(target) {
+                if next.opcode() != OP_B || !seen.insert(target) {
+                    break;
+                }
+                target = next.targets[0];
+                hops += 1;
+            }
+            if target != instructions[index].targets[slot] {
+                instructions[index].targets[slot] = target;
+                rewrites.push(Rewrite {
+                    pc: instructions[index].pc,
+                    kind: RewriteKind::JumpThread,
+                    bytes_saved: 0,
+                    cost_saved: hops,
+                });
+            }
+        }
+    }
+}
+
+/// Apply the rewrites looking at a few consecutive instructions
+fn rewrite_windows(
+    instructions: &mut [Instruction],
+    removed: &mut [bool],
+    rewrites: &mut Vec<Rewrite>,
+) {
+    let targets: HashSet<usize> = instructions
+        .iter()
+        .flat_map(|i| i.targets.iter().copied())
+        .collect();
+    let count = instructions.len();
+    // Whether the `len` instructions from `index` run one after the other
+    let straight = |index: usize, len: usize| {
+        index + len <= count && (index + 1..index + len).all(|i| !targets.contains(&i))
+    };
+
+    let mut index = 0;
+    while index < instructions.len() {
+        let window = &instructions[index..];
+        let pc = window[0].pc;
+
+        if window[0].is_terminator() && !targets.contains(&(index + 1)) {
+            let dead = (index + 1..instructions.len())
+                .take_while(|i| !targets.contains(i))
+                .collect::<Vec<_>>();
+            if !dead.is_empty() {
+                let bytes = dead.iter().map(|&i| instructions[i].bytes.len()).sum();
+                dead.iter().for_each(|&i| removed[i] = true);
+                rewrites.push(Rewrite {
+                    pc: instructions[dead[0]].pc,
+                    kind: RewriteKind::DeadCode,
+                    bytes_saved: bytes,
+                    cost_saved: 0,
+                });
+                index = dead[dead.len() - 1] + 1;
+                continue;
+            }
+        }
+
+        if window[0].opcode() == OP_B && window[0].targets[0] == index + 1 {
+            removed[index] = true;
+            rewrites.push(Rewrite {
+                pc,
+                kind: RewriteKind::JumpToNext,
+                bytes_saved: window[0].bytes.len(),
+                cost_saved: window[0].cost(),
+            });
+            index += 1;
+            continue;
+        }
+
+        if straight(index, 2) {
+            let kind = match (window[0].opcode(), window[1].opcode()) {
+                (OP_DUP, OP_POP) => Some(RewriteKind::DupPop),
+                (OP_SWAP, OP_SWAP) => Some(RewriteKind::SwapSwap),
+                _ => None,
+            };
+            if let Some(kind) = kind {
+                removed[index] = true;
+                removed[index + 1] = true;
+                rewrites.push(Rewrite {
+                    pc,
+                    kind,
+                    bytes_saved: window[0].bytes.len() + window[1].bytes.len(),
+                    cost_saved: window[0].cost() + window[1].cost(),
+                });
+                index += 2;
+                continue;
+            }
+        }
+
+        for len in [3, 2] {
+            if !straight(index, len) {
+                continue;
+            }
+            let Some(folded) = fold(&window[..len]) else {
+                continue;
+            };
+            let old = &window[..len];
+            let bytes: usize = old.iter().map(|i| i.bytes.len()).sum();
+            if folded.len() > bytes {
+                continue;
+            }
+            let folded = Instruction::new(pc, folded);
+            rewrites.push(Rewrite {
+                pc,
+                kind: RewriteKind::ConstantFold,
+                bytes_saved: bytes - folded.bytes.len(),
+                cost_saved: old.iter().map(Instruction::cost).sum::<u64>() - folded.cost(),
+            });
+            instructions[index] = folded;
+            (index + 1..index + len).for_each(|i| removed[i] = true);
+            index += len - 1;
+            break;
+        }
+        index += 1;
+    }
+}
+
+/// The push replacing an operation on pushed constants, when it cannot fail
+fn fold(window: &[Instruction]) -> Option<Vec<u8>> {
+    let op = window.last()?.opcode();
+    if let [a, _] = window {
+        return match (a.pushed_int(), a.pushed_bytes()) {
+            (Some(a), _) => match op {
+                OP_NOT => Some(push_int((a == 0) as u64)),
+                OP_BITWISE_NOT => Some(push_int(!a)),
+                _ => None,
+            },
+            (_, Some(a)) if op == OP_LEN => Some(push_int(a.len() as u64)),
+            _ => None,
+        };
+    }
+
+    let [a, b, _] = window else {
+        return None;
+    };
+    if let (Some(a), Some(b)) = (a.pushed_bytes(), b.pushed_bytes()) {
+        if op != OP_CONCAT || a.len() + b.len() > MAX_STRING_SIZE {
+            return None;
+        }
+        let mut push = vec![OP_PUSHBYTES];
+        push.extend(encode_varuint((a.len() + b.len()) as u64));
+        push.extend_from_slice(a);
+        push.extend_from_slice(b);
+        return Some(push);
+    }
+
+    let (a, b) = (a.pushed_int()?, b.pushed_int()?);
+    let value = match op {
+        OP_PLUS => a.checked_add(b)?,
+        OP_MINUS => a.checked_sub(b)?,
+        OP_MUL => a.checked_mul(b)?,
+        OP_DIV => a.checked_div(b)?,
+        OP_MOD => a.checked_rem(b)?,
+        OP_LT => (a < b) as u64,
+        OP_GT => (a > b) as u64,
+        OP_LE => (a <= b) as u64,
+        OP_GE => (a >= b) as u64,
+        OP_EQ => (a == b) as u64,
+        OP_NE => (a != b) as u64,
+        OP_AND => (a != 0 && b != 0) as u64,
+        OP_OR => (a != 0 || b != 0) as u64,
+        OP_BITWISE_OR => a | b,
+        OP_BITWISE_AND => a & b,
+        OP_BITWISE_XOR => a ^ b,
+        OP_SHL if b < 64 => a << b,
+        OP_SHR if b < 64 => a >> b,
+        _ => return None,
+    };
+    Some(push_int(value))
+}
+
+fn push_int(value: u64) -> Vec<u8> {
+    let mut push = vec![OP_PUSHINT];
+    push.extend(encode_varuint(value));
+    push
+}
+
+/// Drop removed instructions, moving branches to them on to the next one kept
+fn compact(instructions: &mut Vec<Instruction>, removed: &[bool]) {
+    // A removed instruction's targets land on the next kept one, whose new
+    // index is the number kept before it
+    let mut new_index = Vec::with_capacity(removed.len() + 1);
+    let mut kept = 0;
+    for &removed in removed {
+        new_index.push(kept);
+        kept += usize::from(!removed);
+    }
+    new_index.push(kept);
+
+    let mut index = 0;
+    instructions.retain(|_| {
+        index += 1;
+        !removed[index - 1]
+    });
+    for instruction in instructions.iter_mut() {
+        for target in &mut instruction.targets {
+            *target = new_index[*target];
+        }
+    }
+}
+
+/// Encode instructions, recomputing their branch offsets
+///
+/// Returns the bytecode and the pc of each instruction in it.
+fn encode(instructions: &[Instruction]) -> AvmResult<(Vec<u8>, Vec<usize>)> {
+    let mut pcs = Vec::with_capacity(instructions.len() + 1);
+    let mut pc = 0;
+    for instruction in instructions {
+        pcs.push(pc);
+        pc += instruction.bytes.len();
+    }
+    pcs.push(pc);
+
+    let mut bytecode = Vec::with_capacity(pc);
+    for (index, instruction) in instructions.iter().enumerate() {
+        let mut bytes = instruction.bytes.clone();
+        let end = pcs[index + 1] as i64;
+        for (position, &target) in instruction
+            .offset_positions()
+            .iter()
+            .zip(&instruction.targets)
+        {
+            let offset = i16::try_from(pcs[target] as i64 - end).map_err(|_| {
+                AvmError::assembly_error(format!(
+                    "Branch at {} is too far from its target",
+                    instruction.pc
+                ))
+            })?;
+            bytes[*position..*position + 2].copy_from_slice(&offset.to_be_bytes());
+        }
+        bytecode.extend(bytes);
+    }
+    pcs.pop();
+    Ok((bytecode, pcs))
+}
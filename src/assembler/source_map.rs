@@ -0,0 +1,189 @@
+//! Source maps relating program counters to TEAL source
+//!
+//! Maps are written in the Source Map v3 JSON format emitted by goal and the
+//! AlgoKit tools. Each pc of the program is a generated line in `mappings`,
+//! empty unless an instruction starts there, and each segment points at the
+//! line and column of the instruction in the first source. Line and column
+//! numbers are zero-based, as in the JSON.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AvmError, AvmResult};
+
+/// Source Map format version
+const SOURCE_MAP_VERSION: u32 = 3;
+
+/// Base64 digits of the VLQ encoding used in `mappings`
+const BASE64_DIGITS: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Zero-based position in a source file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SourceLocation {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Mapping from program counters to source locations
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceMap {
+    /// Source file names
+    pub sources: Vec<String>,
+    /// Location of the instruction starting at each pc
+    pub locations: BTreeMap<usize, SourceLocation>,
+    /// Pc of each label
+    pub labels: BTreeMap<String, usize>,
+}
+
+/// Source Map v3 JSON, with the labels as an `x_` extension field
+#[derive(Serialize, Deserialize)]
+struct SourceMapJson {
+    version: u32,
+    sources: Vec<String>,
+    names: Vec<String>,
+    mappings: String,
+    #[serde(default, rename = "x_labels")]
+    labels: BTreeMap<String, usize>,
+}
+
+impl SourceMap {
+    /// Location of the instruction containing `pc`
+    pub fn location(&self, pc: usize) -> Option<SourceLocation> {
+        self.locations
+            .range(..=pc)
+            .next_back()
+            .map(|(_, location)| *location)
+    }
+
+    /// First pc of the instructions on a zero-based source line
+    pub fn pc_for_line(&self, line: usize) -> Option<usize> {
+        self.locations
+            .iter()
+            .find(|(_, location)| location.line == line)
+            .map(|(pc, _)| *pc)
+    }
+
+    /// Encode as Source Map v3 JSON
+    pub fn to_json(&self) -> String {
+        let end = self.locations.keys().next_back().map_or(0, |pc| pc + 1);
+        let mut previous = SourceLocation { line: 0, column: 0 };
+        let lines: Vec<String> = (0..end)
+            .map(|pc| match self.locations.get(&pc) {
+                Some(location) => {
+                    let mut segment = String::new();
+                    for value in [
+                        0,
+                        0,
+                        location.line as i64 - previous.line as i64,
+                        location.column as i64 - previous.column as i64,
+                    ] {
+                        encode_vlq(&mut segment, value);
+                    }
+                    previous = *location;
+                    segment
+                }
+                None => String::new(),
+            })
+            .collect();
+
+        let json = SourceMapJson {
+            version: SOURCE_MAP_VERSION,
+            sources: self.sources.clone(),
+            names: Vec::new(),
+            mappings: lines.join(";"),
+            labels: self.labels.clone(),
+        };
+        serde_json::to_string(&json).expect("source maps serialize")
+    }
+
+    /// Decode Source Map v3 JSON
+    pub fn from_json(json: &str) -> AvmResult<Self> {
+        let json: SourceMapJson = serde_json::from_str(json)
+            .map_err(|e| AvmError::parse_error(format!("Invalid source map: {e}")))?;
+        if json.version != SOURCE_MAP_VERSION {
+            return Err(AvmError::parse_error(format!(
+                "Unsupported source map version {}",
+                json.version
+            )));
+        }
+
+        let mut locations = BTreeMap::new();
+        let (mut line, mut column) = (0i64, 0i64);
+        for (pc, segments) in json.mappings.split(';').enumerate() {
+            // Only the first segment of a generated line locates its pc
+            for (index, segment) in segments.split(',').filter(|s| !s.is_empty()).enumerate() {
+                let values = decode_vlq(segment)?;
+                if values.len() < 4 {
+                    continue;
+                }
+                line += values[2];
+                column += values[3];
+                if index == 0 {
+                    let location = SourceLocation {
+                        line: usize::try_from(line).map_err(|_| negative_position())?,
+                        column: usize::try_from(column).map_err(|_| negative_position())?,
+                    };
+                    locations.insert(pc, location);
+                }
+            }
+        }
+
+        Ok(Self {
+            sources: json.sources,
+            locations,
+            labels: json.labels,
+        })
+    }
+}
+
+fn negative_position() -> AvmError {
+    AvmError::parse_error("Invalid source map: negative source position")
+}
+
+/// Append `value` as base64 VLQ digits
+fn encode_vlq(out: &mut String, value: i64) {
+    // The sign is the lowest bit, then 5 bits per digit with a continuation bit
+    let mut vlq = if value < 0 {
+        ((-value as u64) << 1) | 1
+    } else {
+        (value as u64) << 1
+    };
+    loop {
+        let mut digit = (vlq & 0x1f) as usize;
+        vlq >>= 5;
+        if vlq > 0 {
+            digit |= 0x20;
+        }
+        out.push(BASE64_DIGITS[digit] as char);
+        if vlq == 0 {
+            break;
+        }
+    }
+}
+
+/// Decode the base64 VLQ values of a segment
+fn decode_vlq(segment: &str) -> AvmResult<Vec<i64>> {
+    let mut values = Vec::new();
+    let (mut vlq, mut shift) = (0u64, 0);
+    for char in segment.bytes() {
+        let digit = BASE64_DIGITS
+            .iter()
+            .position(|&d| d == char)
+            .ok_or_else(|| {
+                AvmError::parse_error(format!("Invalid source map mapping '{segment}'"))
+            })? as u64;
+        if shift > 60 {
+            return Err(AvmError::parse_error("Invalid source map: value too large"));
+        }
+        vlq |= (digit & 0x1f) << shift;
+        shift += 5;
+        if digit & 0x20 == 0 {
+            let magnitude = (vlq >> 1) as i64;
+            values.push(if vlq & 1 == 1 { -magnitude } else { magnitude });
+            (vlq, shift) = (0, 0);
+        }
+    }
+    Ok(values)
+}
@@ -0,0 +1,465 @@
+//! Static stack type checking for `#pragma typetrack`
+//!
+//! Like goal, the assembler follows the types of the stack values through each
+//! basic block using the opcode signatures, and reports instructions given
+//! values of the wrong type or too few values. Control may enter at a label
+//! with any stack, so tracking restarts there with a stack of unknown depth,
+//! and code after an unconditional branch is not checked until the next label.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use super::diagnostics::{AsmResult, Problem, codes};
+use crate::opcodes::{FieldGroup, ImmediateKind, OpSpec, StackType};
+
+/// Type of a value on the tracked stack
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum TrackedType {
+    Any,
+    Uint64,
+    /// A byte array, with its length when known
+    Bytes(Option<usize>),
+}
+
+impl TrackedType {
+    /// An address or hash
+    const BYTES_32: Self = Self::Bytes(Some(32));
+
+    /// Whether a value of this type may be given where `wanted` is expected
+    fn fits(self, wanted: Self) -> bool {
+        match (self, wanted) {
+            (Self::Any, _) | (_, Self::Any) => true,
+            (Self::Bytes(Some(got)), Self::Bytes(Some(wanted))) => got == wanted,
+            (Self::Bytes(_), Self::Bytes(_)) | (Self::Uint64, Self::Uint64) => true,
+            _ => false,
+        }
+    }
+
+    /// Type of a value that is either of two types
+    fn join(self, other: Self) -> Self {
+        match (self, other) {
+            _ if self == other => self,
+            (Self::Bytes(_), Self::Bytes(_)) => Self::Bytes(None),
+            _ => Self::Any,
+        }
+    }
+}
+
+impl From<StackType> for TrackedType {
+    fn from(stack_type: StackType) -> Self {
+        match stack_type {
+            StackType::Any => Self::Any,
+            StackType::Uint64 => Self::Uint64,
+            StackType::Bytes => Self::Bytes(None),
+        }
+    }
+}
+
+impl fmt::Display for TrackedType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Any => f.write_str("any"),
+            Self::Uint64 => f.write_str("uint64"),
+            Self::Bytes(None) => f.write_str("[]byte"),
+            Self::Bytes(Some(length)) => write!(f, "[{length}]byte"),
+        }
+    }
+}
+
+/// What is known about the stack and scratch space at the current statement
+#[derive(Debug)]
+pub(super) struct TypeTracker {
+    /// Known values, deepest first
+    stack: Vec<TrackedType>,
+    /// Whether unknown values may lie below `stack`
+    unknown_bottom: bool,
+    /// Whether the statement is unreachable without a label
+    dead: bool,
+    /// Types of stored scratch slots
+    scratch: HashMap<u8, TrackedType>,
+    /// Type of the other scratch slots
+    scratch_default: TrackedType,
+    /// First mismatched argument of the current instruction
+    mismatch: Option<Problem>,
+}
+
+impl Default for TypeTracker {
+    fn default() -> Self {
+        // Programs start with an empty stack and zeroed scratch space
+        Self {
+            stack: Vec::new(),
+            unknown_bottom: false,
+            dead: false,
+            scratch: HashMap::new(),
+            scratch_default: TrackedType::Uint64,
+            mismatch: None,
+        }
+    }
+}
+
+impl TypeTracker {
+    /// Forget everything, as control may arrive here from anywhere
+    pub(super) fn label(&mut self) {
+        *self = Self {
+            unknown_bottom: true,
+            scratch_default: TrackedType::Any,
+            ..Self::default()
+        };
+    }
+
+    /// Track a constant pushed by a pseudo-op
+    pub(super) fn push_constants(&mut self, types: impl IntoIterator<Item = TrackedType>) {
+        if !self.dead {
+            self.stack.extend(types);
+        }
+    }
+
+    /// Apply the stack effect of an instruction, checking its arguments
+    pub(super) fn track(&mut self, spec: &OpSpec, args: &[&str]) -> AsmResult<()> {
+        if self.dead {
+            return Ok(());
+        }
+        // Mismatched arguments are reported once the stack effect is applied
+        self.apply(spec, args)?;
+        self.mismatch.take().map_or(Ok(()), Err)
+    }
+
+    fn apply(&mut self, spec: &OpSpec, args: &[&str]) -> AsmResult<()> {
+        let name = spec.name.as_str();
+        let count = |index: usize| args.get(index).and_then(|arg| arg.parse::<usize>().ok());
+        match name {
+            "pop" => self.pop(name, 1).map(drop),
+            "popn" => self.pop(name, count(0).unwrap_or(0)).map(drop),
+            "dup" => {
+                let value = self.peek(name, 0)?;
+                self.stack.push(value);
+                Ok(())
+            }
+            "dup2" => {
+                let values = self.pop(name, 2)?;
+                self.stack.extend(values.iter().chain(&values));
+                Ok(())
+            }
+            "dupn" => {
+                let value = self.peek(name, 0)?;
+                self.stack
+                    .extend(std::iter::repeat_n(value, count(0).unwrap_or(0)));
+                Ok(())
+            }
+            "swap" => {
+                let values = self.pop(name, 2)?;
+                self.stack.extend([values[1], values[0]]);
+                Ok(())
+            }
+            "dig" => {
+                let value = self.peek(name, count(0).unwrap_or(0))?;
+                self.stack.push(value);
+                Ok(())
+            }
+            "bury" => {
+                let depth = count(0).unwrap_or(1).max(1);
+                self.reach(name, depth + 1)?;
+                let value = self.stack.pop().unwrap_or(TrackedType::Any);
+                let index = self.stack.len() - depth;
+                self.stack[index] = value;
+                Ok(())
+            }
+            "cover" => {
+                let depth = count(0).unwrap_or(0);
+                self.reach(name, depth + 1)?;
+                let value = self.stack.pop().unwrap_or(TrackedType::Any);
+                self.stack.insert(self.stack.len() - depth, value);
+                Ok(())
+            }
+            "uncover" => {
+                let depth = count(0).unwrap_or(0);
+                self.reach(name, depth + 1)?;
+                let value = self.stack.remove(self.stack.len() - 1 - depth);
+                self.stack.push(value);
+                Ok(())
+            }
+            "select" => {
+                let values = self.check(
+                    name,
+                    &[TrackedType::Any, TrackedType::Any, TrackedType::Uint64],
+                )?;
+                self.stack.push(values[0].join(values[1]));
+                Ok(())
+            }
+            "==" | "!=" => {
+                let values = self.pop(name, 2)?;
+                if !values[0].fits(values[1]) {
+                    self.mismatch = Some(Problem::new(
+                        codes::TYPE,
+                        format!("{name} compares {} with {}", values[0], values[1]),
+                    ));
+                }
+                self.stack.push(TrackedType::Uint64);
+                Ok(())
+            }
+            "setbit" => {
+                let values = self.check(
+                    name,
+                    &[TrackedType::Any, TrackedType::Uint64, TrackedType::Uint64],
+                )?;
+                self.stack.push(match values[0] {
+                    TrackedType::Bytes(_) => TrackedType::Bytes(None),
+                    other => other,
+                });
+                Ok(())
+            }
+            "load" => {
+                let slot = count(0).and_then(|slot| u8::try_from(slot).ok());
+                let value = slot.and_then(|slot| self.scratch.get(&slot).copied());
+                self.stack.push(value.unwrap_or(self.scratch_default));
+                Ok(())
+            }
+            "store" => {
+                let value = self.pop(name, 1)?[0];
+                if let Some(slot) = count(0).and_then(|slot| u8::try_from(slot).ok()) {
+                    self.scratch.insert(slot, value);
+                }
+                Ok(())
+            }
+            "stores" => {
+                let values = self.check(name, &[TrackedType::Uint64, TrackedType::Any])?;
+                // Any slot may have been written
+                let written = self.scratch_default.join(values[1]);
+                self.scratch.clear();
+                self.scratch_default = written;
+                Ok(())
+            }
+            "match" => {
+                let wanted = vec![TrackedType::Any; args.len() + 1];
+                self.check(name, &wanted).map(drop)
+            }
+            "callsub" => {
+                // The subroutine may leave anything on the stack
+                self.label();
+                Ok(())
+            }
+            "b" | "err" | "retsub" => {
+                self.dead = true;
+                Ok(())
+            }
+            "return" => {
+                self.dead = true;
+                self.check(name, &[TrackedType::Uint64]).map(drop)
+            }
+            _ => {
+                let (wanted, returns) = signature(spec, args);
+                self.check(name, &wanted)?;
+                self.stack.extend(returns);
+                Ok(())
+            }
+        }
+    }
+
+    /// Pop values of the wanted types, noting the first mismatch
+    fn check(&mut self, name: &str, wanted: &[TrackedType]) -> AsmResult<Vec<TrackedType>> {
+        let values = self.pop(name, wanted.len())?;
+        let mismatch = values
+            .iter()
+            .zip(wanted)
+            .position(|(got, wanted)| !got.fits(*wanted));
+        if let Some(index) = mismatch {
+            self.mismatch = Some(Problem::new(
+                codes::TYPE,
+                format!(
+                    "{name} arg {index} wanted type {} got {}",
+                    wanted[index], values[index]
+                ),
+            ));
+        }
+        Ok(values)
+    }
+
+    /// Pop the top `count` values, deepest first
+    fn pop(&mut self, name: &str, count: usize) -> AsmResult<Vec<TrackedType>> {
+        self.reach(name, count)?;
+        Ok(self.stack.split_off(self.stack.len() - count))
+    }
+
+    /// The value `depth` below the top
+    fn peek(&mut self, name: &str, depth: usize) -> AsmResult<TrackedType> {
+        self.reach(name, depth + 1)?;
+        Ok(self.stack[self.stack.len() - 1 - depth])
+    }
+
+    /// Make sure at least `count` values are tracked, filling in unknown ones
+    fn reach(&mut self, name: &str, count: usize) -> AsmResult<()> {
+        let height = self.stack.len();
+        if height >= count {
+            return Ok(());
+        }
+        if !self.unknown_bottom {
+            // Carry on without reporting the same missing values again
+            self.stack.clear();
+            self.unknown_bottom = true;
+            return Err(Problem::new(
+                codes::STACK,
+                format!("{name} expects {count} stack arguments but stack height is {height}"),
+            ));
+        }
+        let missing = vec![TrackedType::Any; count - height];
+        self.stack.splice(0..0, missing);
+        Ok(())
+    }
+}
+
+/// Argument and return types of an instruction, refined by its immediates
+fn signature(spec: &OpSpec, args: &[&str]) -> (Vec<TrackedType>, Vec<TrackedType>) {
+    let mut wanted: Vec<TrackedType> = spec.stack_args.iter().map(|&t| t.into()).collect();
+    let mut returns: Vec<TrackedType> = spec.stack_returns.iter().map(|&t| t.into()).collect();
+    let immediate = |index: usize| args.get(index).and_then(|arg| arg.parse::<usize>().ok());
+
+    match spec.name.as_str() {
+        "pushints" => returns = vec![TrackedType::Uint64; args.len()],
+        "pushbytess" => returns = vec![TrackedType::Bytes(None); args.len()],
+        "sha256" | "keccak256" | "sha512_256" | "sha3_256" => returns = vec![TrackedType::BYTES_32],
+        "itob" => returns = vec![TrackedType::Bytes(Some(8))],
+        "extract" => {
+            if let Some(length @ 1..) = immediate(1) {
+                returns = vec![TrackedType::Bytes(Some(length))];
+            }
+        }
+        "substring" => {
+            if let (Some(start), Some(end)) = (immediate(0), immediate(1))
+                && start <= end
+            {
+                returns = vec![TrackedType::Bytes(Some(end - start))];
+            }
+        }
+        "ed25519verify" | "ed25519verify_bare" => {
+            wanted[1] = TrackedType::Bytes(Some(64));
+            wanted[2] = TrackedType::BYTES_32;
+        }
+        "ecdsa_verify" => wanted = vec![TrackedType::BYTES_32; 5],
+        "ecdsa_pk_recover" => {
+            wanted = vec![
+                TrackedType::BYTES_32,
+                TrackedType::Uint64,
+                TrackedType::BYTES_32,
+                TrackedType::BYTES_32,
+            ];
+            returns = vec![TrackedType::BYTES_32; 2];
+        }
+        "ecdsa_pk_decompress" => {
+            wanted = vec![TrackedType::Bytes(Some(33))];
+            returns = vec![TrackedType::BYTES_32; 2];
+        }
+        "vrf_verify" => {
+            wanted[1] = TrackedType::Bytes(Some(80));
+            wanted[2] = TrackedType::BYTES_32;
+            returns[0] = TrackedType::Bytes(Some(64));
+        }
+        _ => {}
+    }
+
+    // Field immediates decide the type of the value read or written
+    for (immediate, arg) in spec.immediates.iter().zip(args) {
+        let ImmediateKind::Field(group) = immediate.kind else {
+            continue;
+        };
+        let field = field_type(group, arg);
+        if spec.name == "itxn_field" {
+            wanted = vec![field];
+        } else if let Some(first) = returns.first_mut()
+            && *first == TrackedType::Any
+        {
+            *first = field;
+        }
+    }
+    (wanted, returns)
+}
+
+/// Type of the value of a field
+fn field_type(group: FieldGroup, name: &str) -> TrackedType {
+    let (bytes32, bytes): (&[&str], &[&str]) = match group {
+        FieldGroup::Txn => (
+            &[
+                "Sender",
+                "Lease",
+                "Receiver",
+                "CloseRemainderTo",
+                "VotePK",
+                "SelectionPK",
+                "AssetSender",
+                "AssetReceiver",
+                "AssetCloseTo",
+                "TxID",
+                "Accounts",
+                "RekeyTo",
+                "ConfigAssetMetadataHash",
+                "ConfigAssetManager",
+                "ConfigAssetReserve",
+                "ConfigAssetFreeze",
+                "ConfigAssetClawback",
+                "FreezeAssetAccount",
+            ],
+            &[
+                "Note",
+                "Type",
+                "ApplicationArgs",
+                "ApprovalProgram",
+                "ClearStateProgram",
+                "ConfigAssetUnitName",
+                "ConfigAssetName",
+                "ConfigAssetURL",
+                "Logs",
+                "LastLog",
+                "ApprovalProgramPages",
+                "ClearStateProgramPages",
+            ],
+        ),
+        FieldGroup::Global => (
+            &[
+                "ZeroAddress",
+                "CreatorAddress",
+                "CurrentApplicationAddress",
+                "GroupID",
+                "CallerApplicationAddress",
+                "GenesisHash",
+            ],
+            &[],
+        ),
+        FieldGroup::AssetHolding => (&[], &[]),
+        FieldGroup::AssetParams => (
+            &[
+                "AssetMetadataHash",
+                "AssetManager",
+                "AssetReserve",
+                "AssetFreeze",
+                "AssetClawback",
+                "AssetCreator",
+            ],
+            &["AssetUnitName", "AssetName", "AssetURL"],
+        ),
+        FieldGroup::AppParams => (
+            &["AppCreator", "AppAddress"],
+            &["AppApprovalProgram", "AppClearStateProgram"],
+        ),
+        FieldGroup::AcctParams => (&["AcctAuthAddr"], &[]),
+        FieldGroup::Block => (
+            &["BlkSeed", "BlkProposer", "BlkBranch", "BlkFeeSink"],
+            &["BlkProtocol"],
+        ),
+        FieldGroup::JsonRefType => {
+            return match name {
+                "JSONUint64" => TrackedType::Uint64,
+                _ => TrackedType::Bytes(None),
+            };
+        }
+        _ => return TrackedType::Any,
+    };
+
+    if name == "StateProofPK" {
+        TrackedType::Bytes(Some(64))
+    } else if bytes32.contains(&name) {
+        TrackedType::BYTES_32
+    } else if bytes.contains(&name) {
+        TrackedType::Bytes(None)
+    } else {
+        TrackedType::Uint64
+    }
+}
@@ -1,6 +1,7 @@
 //! Assemble command implementation
 
-use crate::assembler::Assembler;
+use crate::assembler::{Assembler, optimizer::Rewrite};
+use crate::cli::commands::assembly_failure;
 use crate::cli::{AssembleCommand, BytecodeFormat, GlobalOptions};
 use anyhow::{Context, Result};
 use base64::{Engine, engine::general_purpose::STANDARD as BASE64_STANDARD};
@@ -19,10 +20,17 @@ pub fn handle(cmd: AssembleCommand, global: &GlobalOptions) -> Result<()> {
         .with_context(|| format!("Failed to read TEAL file: {:?}", cmd.input))?;
 
     // Assemble to bytecode
-    let mut assembler = Assembler::new();
+    let mut assembler = Assembler::new()
+        .with_optimize(cmd.optimize)
+        .with_source_name(cmd.input.display().to_string());
     let bytecode = assembler
         .assemble(&source)
-        .map_err(|e| anyhow::anyhow!("Assembly failed: {}", e))?;
+        .map_err(|e| assembly_failure(e, &source, global))?;
+
+    if let Some(path) = &cmd.source_map {
+        fs::write(path, assembler.source_map().to_json())
+            .with_context(|| format!("Failed to write source map: {path:?}"))?;
+    }
 
     // Format bytecode
     let formatted = format_bytecode(&bytecode, &cmd.output_format)?;
@@ -42,7 +50,7 @@ pub fn handle(cmd: AssembleCommand, global: &GlobalOptions) -> Result<()> {
 
     // Show statistics if requested
     if cmd.stats && !global.quiet {
-        show_assembly_stats(&bytecode, &source, global)?;
+        show_assembly_stats(&bytecode, &source, assembler.rewrites(), global)?;
     }
 
     Ok(())
@@ -63,7 +71,12 @@ fn format_bytecode(bytecode: &[u8], format: &BytecodeFormat) -> Result<String> {
 }
 
 /// Show assembly statistics
-fn show_assembly_stats(bytecode: &[u8], source: &str, global: &GlobalOptions) -> Result<()> {
+fn show_assembly_stats(
+    bytecode: &[u8],
+    source: &str,
+    rewrites: &[Rewrite],
+    global: &GlobalOptions,
+) -> Result<()> {
     let source_lines = source
         .lines()
         .filter(|line| {
@@ -88,13 +101,32 @@ fn show_assembly_stats(bytecode: &[u8], source: &str, global: &GlobalOptions) ->
             // Estimate cost (rough approximation)
             let estimated_cost = estimate_execution_cost(bytecode);
             println!("  Estimated cost: ~{estimated_cost} units");
+
+            if !rewrites.is_empty() {
+                println!("\n⚡ Optimizations:");
+                for rewrite in rewrites {
+                    println!(
+                        "  {:04x}: {} (-{} bytes, -{} cost)",
+                        rewrite.pc, rewrite.kind, rewrite.bytes_saved, rewrite.cost_saved
+                    );
+                }
+                let bytes: usize = rewrites.iter().map(|r| r.bytes_saved).sum();
+                let cost: u64 = rewrites.iter().map(|r| r.cost_saved).sum();
+                println!("  Total saved: {bytes} bytes, {cost} cost");
+            }
         }
         crate::cli::OutputFormat::Json => {
             let stats = serde_json::json!({
                 "source_lines": source_lines,
                 "bytecode_size": bytecode.len(),
                 "compression_ratio": source.len() as f64 / bytecode.len() as f64,
-                "estimated_cost": estimate_execution_cost(bytecode)
+                "estimated_cost": estimate_execution_cost(bytecode),
+                "optimizations": rewrites.iter().map(|rewrite| serde_json::json!({
+                    "pc": rewrite.pc,
+                    "kind": rewrite.kind.to_string(),
+                    "bytes_saved": rewrite.bytes_saved,
+                    "cost_saved": rewrite.cost_saved,
+                })).collect::<Vec<_>>()
             });
             println!("{}", serde_json::to_string_pretty(&stats)?);
         }
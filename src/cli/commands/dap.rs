@@ -0,0 +1,614 @@
+//! Debug Adapter Protocol server
+//!
+//! Serves one debug session over stdio so editors such as VS Code can step
+//! through TEAL. The `launch` request takes:
+//!
+//! - `program`: TEAL source, or a file of hex bytecode
+//! - `ledger`: optional ledger fixture, as for `execute --ledger`
+//! - `version`, `mode` (`signature` or `application`) and `budget`
+//! - `stopOnEntry`: stop before the first instruction
+//!
+//! The stack, scratch space and the variables of the current subroutine are
+//! shown as scopes, and each variable can be set or evaluated by the name
+//! the debugger uses for it, such as `scratch[3]` or `frame[-1]`.
+
+use crate::assembler::{Assembler, source_map::SourceMap};
+use crate::cli::{DapCommand, GlobalOptions, LedgerFixture};
+use crate::opcodes::opcode_by_name;
+use crate::state::{MockLedger, OverlayLedger};
+use crate::types::{RunMode, StackValue, TealVersion};
+use crate::vm::{Condition, Debugger, Expr, Location, StopReason};
+use crate::{ExecutionConfig, VirtualMachine};
+use anyhow::{Context, Result, anyhow, bail};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+/// The only thread of a session
+const THREAD_ID: u64 = 1;
+
+/// Variable references of the scopes
+const STACK_SCOPE: u64 = 1;
+const SCRATCH_SCOPE: u64 = 2;
+const FRAME_SCOPE: u64 = 3;
+
+/// Handle the dap command
+pub fn handle(_cmd: DapCommand, _global: &GlobalOptions) -> Result<()> {
+    serve(io::stdin().lock(), io::stdout().lock())
+}
+
+/// Serve one session, reading requests from `input` and writing to `output`
+pub fn serve(input: impl BufRead, output: impl Write) -> Result<()> {
+    let mut connection = Connection {
+        input,
+        output,
+        seq: 0,
+    };
+    let mut base = Base::default();
+
+    // Nothing can be inspected until a program is launched
+    let (request, launched) = loop {
+        let Some(request) = connection.read()? else {
+            return Ok(());
+        };
+        let arguments = &request["arguments"];
+        match request["command"].as_str().unwrap_or_default() {
+            "initialize" => {
+                base = Base {
+                    lines: arguments["linesStartAt1"].as_bool().unwrap_or(true),
+                    columns: arguments["columnsStartAt1"].as_bool().unwrap_or(true),
+                };
+                connection.respond(&request, Ok(capabilities()))?;
+            }
+            "launch" => match launch(arguments) {
+                Ok(launched) => break (request, launched),
+                Err(e) => connection.respond(&request, Err(e))?,
+            },
+            "disconnect" => return connection.respond(&request, Ok(json!({}))),
+            command => connection.respond(
+                &request,
+                Err(anyhow!("'{command}' needs a launched program")),
+            )?,
+        }
+    };
+
+    let Launched {
+        program,
+        mut ledger,
+        config,
+        stop_on_entry,
+    } = launched;
+    let vm = VirtualMachine::with_version(config.version);
+    let mut overlay = OverlayLedger::new(&mut ledger);
+    let debugger = match Debugger::new(&vm, &program.bytecode, config, &mut overlay) {
+        Ok(debugger) => debugger,
+        Err(e) => return connection.respond(&request, Err(e.into())),
+    };
+    let debugger = match program.source_map {
+        Some(source_map) => debugger.with_source_map(source_map),
+        None => debugger,
+    };
+    connection.respond(&request, Ok(json!({})))?;
+    connection.event("initialized", json!({}))?;
+
+    let mut session = Session {
+        connection,
+        debugger,
+        path: program.path,
+        base,
+        stop_on_entry,
+        terminated: false,
+    };
+    session.run()?;
+    // As with `execute`, only an approving program changes the ledger
+    let approved = session.debugger.result().unwrap_or(false);
+    drop(session);
+    if approved {
+        overlay.commit()?;
+    }
+    Ok(())
+}
+
+/// Size of a `callsub` instruction, to find the call site from a return pc
+fn callsub_size() -> usize {
+    let spec = opcode_by_name("callsub").expect("callsub is a standard opcode");
+    let immediates: usize = spec
+        .immediates
+        .iter()
+        .map(|immediate| {
+            immediate
+                .fixed_size()
+                .expect("callsub immediates have a fixed size")
+        })
+        .sum();
+    1 + immediates
+}
+
+/// Optional requests the adapter supports
+fn capabilities() -> Value {
+    json!({
+        "supportsConfigurationDoneRequest": true,
+        "supportsConditionalBreakpoints": true,
+        "supportsSetVariable": true,
+        "supportsTerminateRequest": true,
+    })
+}
+
+/// Whether the client counts lines and columns from one
+#[derive(Clone, Copy)]
+struct Base {
+    lines: bool,
+    columns: bool,
+}
+
+impl Default for Base {
+    fn default() -> Self {
+        Self {
+            lines: true,
+            columns: true,
+        }
+    }
+}
+
+/// Arguments of the `launch` request
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LaunchArguments {
+    program: PathBuf,
+    ledger: Option<PathBuf>,
+    version: Option<u8>,
+    #[serde(default)]
+    mode: LaunchMode,
+    budget: Option<u64>,
+    #[serde(default)]
+    stop_on_entry: bool,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+enum LaunchMode {
+    #[default]
+    Signature,
+    Application,
+}
+
+/// What a `launch` request loaded
+struct Launched {
+    program: Program,
+    ledger: MockLedger,
+    config: ExecutionConfig,
+    stop_on_entry: bool,
+}
+
+/// A program to debug
+struct Program {
+    bytecode: Vec<u8>,
+    source_map: Option<SourceMap>,
+    path: PathBuf,
+}
+
+/// Load the program and ledger a `launch` request names
+fn launch(arguments: &Value) -> Result<Launched> {
+    let arguments = LaunchArguments::deserialize(arguments).context("Invalid launch arguments")?;
+    let version = arguments
+        .version
+        .map(TealVersion::from_u8)
+        .transpose()
+        .context("Invalid TEAL version")?
+        .unwrap_or(TealVersion::latest());
+    let program = load_program(&arguments.program, version)?;
+
+    let mut ledger = MockLedger::default();
+    if let Some(path) = &arguments.ledger {
+        LedgerFixture::load(path)?.apply(&mut ledger)?;
+    }
+
+    let run_mode = match arguments.mode {
+        LaunchMode::Signature => RunMode::Signature,
+        LaunchMode::Application => RunMode::Application,
+    };
+    let mut config = ExecutionConfig::new(version).with_run_mode(run_mode);
+    if let Some(budget) = arguments.budget {
+        config = config.with_cost_budget(budget);
+    }
+
+    Ok(Launched {
+        program,
+        ledger,
+        config,
+        stop_on_entry: arguments.stop_on_entry,
+    })
+}
+
+/// Assemble TEAL source, or decode a file of hex bytecode
+fn load_program(path: &Path, version: TealVersion) -> Result<Program> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read program: {path:?}"))?;
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+
+    if content
+        .chars()
+        .all(|c| c.is_ascii_hexdigit() || c.is_whitespace())
+    {
+        let hex: String = content.split_whitespace().collect();
+        let bytecode = hex::decode(hex).context("Invalid hex bytecode")?;
+        return Ok(Program {
+            bytecode,
+            source_map: None,
+            path,
+        });
+    }
+
+    let mut assembler = Assembler::new()
+        .with_default_version(version.as_u8())
+        .with_source_name(path.to_string_lossy());
+    let bytecode = assembler.assemble(&content)?;
+    Ok(Program {
+        bytecode,
+        source_map: Some(assembler.source_map().clone()),
+        path,
+    })
+}
+
+/// Messages framed with a `Content-Length` header
+struct Connection<R, W> {
+    input: R,
+    output: W,
+    /// Sequence number of the last message sent
+    seq: u64,
+}
+
+impl<R: BufRead, W: Write> Connection<R, W> {
+    /// Read the next message, or `None` at the end of the input
+    fn read(&mut self) -> Result<Option<Value>> {
+        let mut length = None;
+        loop {
+            let mut line = String::new();
+            if self.input.read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':')
+                && name.eq_ignore_ascii_case("Content-Length")
+            {
+                length = Some(value.trim().parse::<usize>()?);
+            }
+        }
+        let length = length.ok_or_else(|| anyhow!("Message without a Content-Length"))?;
+        let mut body = vec![0; length];
+        self.input.read_exact(&mut body)?;
+        Ok(Some(serde_json::from_slice(&body)?))
+    }
+
+    fn send(&mut self, mut message: Value) -> Result<()> {
+        self.seq += 1;
+        message["seq"] = self.seq.into();
+        let body = serde_json::to_string(&message)?;
+        write!(self.output, "Content-Length: {}\r\n\r\n{body}", body.len())?;
+        self.output.flush()?;
+        Ok(())
+    }
+
+    /// Answer `request` with a body, or with an error message
+    fn respond(&mut self, request: &Value, body: Result<Value>) -> Result<()> {
+        let mut response = json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": body.is_ok(),
+        });
+        match body {
+            Ok(body) => response["body"] = body,
+            Err(e) => response["message"] = format!("{e:#}").into(),
+        }
+        self.send(response)
+    }
+
+    fn event(&mut self, event: &str, body: Value) -> Result<()> {
+        self.send(json!({ "type": "event", "event": event, "body": body }))
+    }
+}
+
+/// A launched program under the debugger
+struct Session<'a, R, W> {
+    connection: Connection<R, W>,
+    debugger: Debugger<'a>,
+    /// Canonical path of the program
+    path: PathBuf,
+    base: Base,
+    stop_on_entry: bool,
+    terminated: bool,
+}
+
+impl<R: BufRead, W: Write> Session<'_, R, W> {
+    fn run(&mut self) -> Result<()> {
+        while let Some(request) = self.connection.read()? {
+            let arguments = &request["arguments"];
+            let command = request["command"].as_str().unwrap_or_default();
+            let body = match command {
+                "setBreakpoints" => self.set_breakpoints(arguments),
+                "setExceptionBreakpoints" => Ok(json!({})),
+                "threads" => Ok(json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] })),
+                "stackTrace" => Ok(self.stack_trace()),
+                "scopes" => Ok(self.scopes(arguments)),
+                "variables" => self.variables(arguments),
+                "setVariable" => self.set_variable(arguments),
+                "evaluate" => self.evaluate(arguments),
+                "pause" => Ok(json!({})),
+                "configurationDone" | "continue" | "next" | "stepIn" | "stepOut" => {
+                    let body = match command {
+                        "continue" => json!({ "allThreadsContinued": true }),
+                        _ => json!({}),
+                    };
+                    self.connection.respond(&request, Ok(body))?;
+                    self.execute(command)?;
+                    continue;
+                }
+                "terminate" => {
+                    self.connection.respond(&request, Ok(json!({})))?;
+                    self.terminate()?;
+                    continue;
+                }
+                "disconnect" => return self.connection.respond(&request, Ok(json!({}))),
+                command => Err(anyhow!("Unsupported request '{command}'")),
+            };
+            self.connection.respond(&request, body)?;
+        }
+        Ok(())
+    }
+
+    /// Run as `command` asks and report where execution stopped
+    fn execute(&mut self, command: &str) -> Result<()> {
+        if self.terminated {
+            return Ok(());
+        }
+        if command == "configurationDone" && self.stop_on_entry {
+            return self.stopped("entry", json!({}));
+        }
+        // Continuing from a failed instruction ends the session
+        if self.debugger.is_finished() {
+            return self.finish();
+        }
+
+        let stop = match command {
+            "next" => self.debugger.step_over(),
+            "stepIn" => self.debugger.step(),
+            "stepOut" => self.debugger.step_out(),
+            _ => self.debugger.resume(),
+        };
+        match stop {
+            StopReason::Step => self.stopped("step", json!({})),
+            StopReason::Breakpoint(id) => {
+                self.stopped("breakpoint", json!({ "hitBreakpointIds": [id] }))
+            }
+            StopReason::Finished => self.finish(),
+            StopReason::Error { message, .. } => self.stopped(
+                "exception",
+                json!({ "description": "Program failed", "text": message }),
+            ),
+        }
+    }
+
+    fn stopped(&mut self, reason: &str, mut body: Value) -> Result<()> {
+        body["reason"] = reason.into();
+        body["threadId"] = THREAD_ID.into();
+        body["allThreadsStopped"] = true.into();
+        self.connection.event("stopped", body)
+    }
+
+    /// Report the outcome of the program and end the session
+    fn finish(&mut self) -> Result<()> {
+        let (output, exit_code) = match self.debugger.result() {
+            Ok(true) => ("Program approved".to_string(), 0),
+            Ok(false) => ("Program rejected".to_string(), 1),
+            Err(e) => (format!("Program failed: {e}"), 1),
+        };
+        self.connection.event(
+            "output",
+            json!({ "category": "console", "output": format!("{output}\n") }),
+        )?;
+        self.connection
+            .event("exited", json!({ "exitCode": exit_code }))?;
+        self.terminate()
+    }
+
+    fn terminate(&mut self) -> Result<()> {
+        if !self.terminated {
+            self.terminated = true;
+            self.connection.event("terminated", json!({}))?;
+        }
+        Ok(())
+    }
+
+    /// Replace the line breakpoints of the program
+    fn set_breakpoints(&mut self, arguments: &Value) -> Result<Value> {
+        let requested = arguments["breakpoints"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+        let path = arguments["source"]["path"].as_str().map(Path::new);
+        let ours = path.is_some_and(|path| {
+            fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()) == self.path
+        });
+        if ours {
+            self.debugger.clear_breakpoints();
+        }
+
+        let breakpoints: Vec<Value> = requested
+            .iter()
+            .map(|breakpoint| {
+                let line = breakpoint["line"].as_u64().unwrap_or_default() as usize;
+                let added = if ours {
+                    self.add_breakpoint(line, breakpoint["condition"].as_str())
+                } else {
+                    Err(anyhow!("Not the launched program"))
+                };
+                match added {
+                    Ok(id) => json!({ "id": id, "verified": true, "line": line }),
+                    Err(e) => json!({ "verified": false, "line": line, "message": e.to_string() }),
+                }
+            })
+            .collect();
+        Ok(json!({ "breakpoints": breakpoints }))
+    }
+
+    /// Add a breakpoint on a client line, with an optional condition
+    fn add_breakpoint(&mut self, line: usize, condition: Option<&str>) -> Result<usize> {
+        let line = if self.base.lines { line } else { line + 1 };
+        let condition = condition
+            .filter(|condition| !condition.trim().is_empty())
+            .map(str::parse::<Condition>)
+            .transpose()?;
+        Ok(self
+            .debugger
+            .add_breakpoint(Some(Location::Line(line)), condition)?)
+    }
+
+    /// The current instruction, then each call site, innermost first
+    fn stack_trace(&self) -> Value {
+        let ctx = self.debugger.context();
+        let calls = ctx.call_stack();
+        let mut frames = Vec::new();
+        let mut pc = ctx.pc();
+        for depth in (0..=calls.len()).rev() {
+            let name = match depth {
+                0 => "main".to_string(),
+                depth => self.subroutine_name(calls[depth - 1].return_pc),
+            };
+            frames.push(self.frame(depth, name, pc));
+            if depth > 0 {
+                pc = calls[depth - 1].return_pc.saturating_sub(callsub_size());
+            }
+        }
+        json!({ "stackFrames": frames, "totalFrames": frames.len() })
+    }
+
+    fn frame(&self, id: usize, name: String, pc: usize) -> Value {
+        let mut frame = json!({
+            "id": id,
+            "name": name,
+            "line": 0,
+            "column": 0,
+            "instructionPointerReference": pc.to_string(),
+        });
+        let location = self
+            .debugger
+            .source_map()
+            .and_then(|source_map| source_map.location(pc));
+        if let Some(location) = location {
+            frame["line"] = (location.line + usize::from(self.base.lines)).into();
+            frame["column"] = (location.column + usize::from(self.base.columns)).into();
+            frame["source"] = json!({
+                "name": self.path.file_name().map(|name| name.to_string_lossy()),
+                "path": self.path.to_string_lossy(),
+            });
+        }
+        frame
+    }
+
+    /// Label of the subroutine called by the `callsub` returning to `return_pc`
+    fn subroutine_name(&self, return_pc: usize) -> String {
+        let disassembly = self.debugger.disassembly();
+        return_pc
+            .checked_sub(callsub_size())
+            .and_then(|pc| disassembly.instruction_at(pc))
+            .and_then(|call| call.args.first())
+            .and_then(|label| {
+                let (pc, _) = disassembly.labels.iter().find(|(_, name)| *name == label)?;
+                Some(self.debugger.label_at(*pc).unwrap_or(label).to_string())
+            })
+            .unwrap_or_else(|| "subroutine".to_string())
+    }
+
+    /// Frame variables belong to the innermost frame only
+    fn scopes(&self, arguments: &Value) -> Value {
+        let depth = self.debugger.context().call_stack().len();
+        let mut scopes = vec![scope("Stack", STACK_SCOPE), scope("Scratch", SCRATCH_SCOPE)];
+        if depth > 0 && arguments["frameId"].as_u64() == Some(depth as u64) {
+            scopes.push(scope("Frame", FRAME_SCOPE));
+        }
+        json!({ "scopes": scopes })
+    }
+
+    fn variables(&self, arguments: &Value) -> Result<Value> {
+        let ctx = self.debugger.context();
+        let variables: Vec<Value> = match arguments["variablesReference"].as_u64() {
+            Some(STACK_SCOPE) => ctx
+                .stack()
+                .iter()
+                .enumerate()
+                .map(|(index, value)| variable(index.to_string(), value, Expr::Stack(index as i64)))
+                .collect(),
+            Some(SCRATCH_SCOPE) => ctx
+                .scratch()
+                .iter()
+                .enumerate()
+                .filter(|(_, value)| **value != StackValue::Uint(0))
+                .map(|(slot, value)| variable(slot.to_string(), value, Expr::Scratch(slot as u8)))
+                .collect(),
+            Some(FRAME_SCOPE) => {
+                let Some(frame) = ctx.call_stack().last() else {
+                    return Ok(json!({ "variables": [] }));
+                };
+                let args = frame.proto.map_or(0, |(args, _)| args) as i64;
+                let locals = ctx.stack_size().saturating_sub(frame.height) as i64;
+                (-args..locals)
+                    .filter_map(|depth| i8::try_from(depth).ok())
+                    .filter_map(|depth| {
+                        let value = ctx.frame_dig(depth).ok()?;
+                        Some(variable(depth.to_string(), &value, Expr::Frame(depth)))
+                    })
+                    .collect()
+            }
+            _ => bail!("Unknown variables reference"),
+        };
+        Ok(json!({ "variables": variables }))
+    }
+
+    fn set_variable(&mut self, arguments: &Value) -> Result<Value> {
+        let name = arguments["name"].as_str().unwrap_or_default();
+        let invalid = || format!("Invalid variable '{name}'");
+        let target = match arguments["variablesReference"].as_u64() {
+            Some(STACK_SCOPE) => Expr::Stack(name.parse().with_context(invalid)?),
+            Some(SCRATCH_SCOPE) => Expr::Scratch(name.parse().with_context(invalid)?),
+            Some(FRAME_SCOPE) => Expr::Frame(name.parse().with_context(invalid)?),
+            _ => bail!("Unknown variables reference"),
+        };
+        let value: Expr = arguments["value"].as_str().unwrap_or_default().parse()?;
+        let value = self.debugger.evaluate(&value)?;
+        self.debugger.set_value(&target, value.clone())?;
+        Ok(json!({ "value": value.to_string(), "type": value.type_name() }))
+    }
+
+    /// Evaluate an expression, or a condition to `true` or `false`
+    fn evaluate(&self, arguments: &Value) -> Result<Value> {
+        let expression = arguments["expression"].as_str().unwrap_or_default();
+        let result = match expression.parse::<Expr>() {
+            Ok(expr) => self.debugger.evaluate(&expr)?.to_string(),
+            Err(e) => match expression.parse::<Condition>() {
+                Ok(condition) => condition.holds(self.debugger.context())?.to_string(),
+                Err(_) => return Err(e.into()),
+            },
+        };
+        Ok(json!({ "result": result, "variablesReference": 0 }))
+    }
+}
+
+fn scope(name: &str, reference: u64) -> Value {
+    json!({ "name": name, "variablesReference": reference, "expensive": false })
+}
+
+fn variable(name: String, value: &StackValue, expr: Expr) -> Value {
+    json!({
+        "name": name,
+        "value": value.to_string(),
+        "type": value.type_name(),
+        "evaluateName": expr.to_string(),
+        "variablesReference": 0,
+    })
+}
@@ -0,0 +1,145 @@
+//! Disassemble command implementation
+
+use crate::assembler::{analysis::Analysis, disassembler::Disassembly};
+use crate::cli::{BytecodeFormat, DisassembleCommand, GlobalOptions, OutputFormat};
+use anyhow::{Context, Result, anyhow};
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64_STANDARD};
+use std::fs;
+use std::path::Path;
+
+/// Handle the disassemble command
+pub fn handle(cmd: DisassembleCommand, global: &GlobalOptions) -> Result<()> {
+    if !global.quiet && global.verbose {
+        println!("🔍 Disassembling bytecode...");
+        println!("Input: {}", cmd.input);
+        println!("Input format: {:?}", cmd.input_format);
+    }
+
+    let bytecode = read_bytecode(&cmd.input, &cmd.input_format)?;
+    let disassembly =
+        Disassembly::new(&bytecode).map_err(|e| anyhow!("Disassembly failed: {e}"))?;
+    let source = disassembly.to_source(cmd.comments);
+    let analysis = cmd
+        .analyze
+        .then(|| Analysis::new(&disassembly, bytecode.len()));
+
+    if let Some(output_path) = &cmd.output {
+        fs::write(output_path, &source)
+            .with_context(|| format!("Failed to write output: {output_path:?}"))?;
+        if !global.quiet {
+            println!(
+                "✅ Disassembled {} bytes to {output_path:?}",
+                bytecode.len()
+            );
+        }
+    }
+
+    match global.format {
+        OutputFormat::Text => {
+            if cmd.output.is_none() {
+                print!("{source}");
+            }
+            if let Some(analysis) = &analysis {
+                show_analysis(analysis);
+            }
+        }
+        OutputFormat::Json => {
+            let mut json = serde_json::json!({ "analysis": analysis });
+            if cmd.output.is_none() {
+                json["source"] = source.into();
+            }
+            println!("{}", serde_json::to_string_pretty(&json)?);
+        }
+    }
+
+    Ok(())
+}
+
+/// Read program bytes from a file or the argument itself
+///
+/// Binary input can only come from a file. When the format is auto-detected,
+/// text is decoded as hex or base64 and anything else, such as a `.tok` file
+/// written by `goal clerk compile`, is read as raw bytecode.
+fn read_bytecode(input: &str, format: &BytecodeFormat) -> Result<Vec<u8>> {
+    let path = Path::new(input);
+    let bytes = if path.exists() {
+        fs::read(path).with_context(|| format!("Failed to read file: {input}"))?
+    } else {
+        input.as_bytes().to_vec()
+    };
+
+    let text = std::str::from_utf8(&bytes).ok();
+    match (format, text) {
+        (BytecodeFormat::Binary, _) => Ok(bytes),
+        (BytecodeFormat::Hex, Some(text)) => decode_hex(text),
+        (BytecodeFormat::Base64, Some(text)) => decode_base64(text),
+        (BytecodeFormat::Hex | BytecodeFormat::Base64, None) => {
+            Err(anyhow!("Input is not text: {input}"))
+        }
+        (BytecodeFormat::Auto, Some(text))
+            if text
+                .bytes()
+                .all(|b| b.is_ascii_graphic() || b.is_ascii_whitespace()) =>
+        {
+            decode_hex(text)
+                .or_else(|_| decode_base64(text))
+                .with_context(|| format!("Input is neither hex nor base64: {input}"))
+        }
+        (BytecodeFormat::Auto, _) => Ok(bytes),
+    }
+}
+
+/// Decode hex, ignoring whitespace and a `0x` prefix
+fn decode_hex(text: &str) -> Result<Vec<u8>> {
+    let hex: String = text.split_whitespace().collect();
+    let hex = hex.strip_prefix("0x").unwrap_or(&hex);
+    hex::decode(hex).context("Invalid hex bytecode")
+}
+
+/// Decode base64, ignoring whitespace
+fn decode_base64(text: &str) -> Result<Vec<u8>> {
+    let base64: String = text.split_whitespace().collect();
+    BASE64_STANDARD
+        .decode(base64)
+        .context("Invalid base64 bytecode")
+}
+
+/// Print the program analysis
+fn show_analysis(analysis: &Analysis) {
+    println!("\n📊 Program Analysis:");
+    println!("  Version: {}", analysis.version);
+    println!(
+        "  Minimum version for its opcodes: {}",
+        analysis.min_version
+    );
+    println!("  Run mode: {}", analysis.mode);
+    println!("  Size: {} bytes", analysis.size);
+    println!("  Instructions: {}", analysis.instructions);
+    println!("  Static cost: {}", analysis.static_cost);
+
+    println!("\n🔢 Opcodes:");
+    for (name, count) in &analysis.opcodes {
+        println!("  {name:<20} {count}");
+    }
+
+    if !analysis.int_blocks.is_empty() || !analysis.byte_blocks.is_empty() {
+        println!("\n📦 Constant blocks:");
+        for block in &analysis.int_blocks {
+            println!("  intcblock {}", block.join(" "));
+        }
+        for block in &analysis.byte_blocks {
+            println!("  bytecblock {}", block.join(" "));
+        }
+    }
+
+    if !analysis.subroutines.is_empty() {
+        println!("\n📞 Subroutines:");
+        for subroutine in &analysis.subroutines {
+            let plural = if subroutine.calls == 1 { "" } else { "s" };
+            println!(
+                "  {} at {:04x} ({} call{plural})",
+                subroutine.label, subroutine.pc, subroutine.calls
+            );
+        }
+    }
+}
@@ -1,14 +1,18 @@
 //! Execute command implementation
 
-use crate::assembler::Assembler;
+use crate::assembler::{Assembler, source_map::SourceMap};
 #[cfg(feature = "tracing")]
 use crate::cli::TracingLevel;
-use crate::cli::{ExecuteCommand, ExecutionMode, GlobalOptions, InputType};
-use crate::state::MockLedger;
+use crate::cli::commands::assembly_failure;
+use crate::cli::{
+    ExecuteCommand, ExecutionMode, GlobalOptions, InputType, LedgerFixture, StepTraceFormat,
+};
+use crate::state::{MockLedger, OverlayLedger, StateChange};
 #[cfg(feature = "tracing")]
 use crate::tracing::{TraceLevel, TracingConfig};
-use crate::types::TealVersion;
-use crate::{ExecutionConfig, VirtualMachine};
+use crate::types::{StackValue, TealValue, TealVersion};
+use crate::vm::{Condition, Debugger, Expr, Location, StopReason, TraceStep};
+use crate::{ExecutionConfig, ExecutionOutcome, ExecutionResult, VirtualMachine};
 use anyhow::{Context, Result, anyhow};
 use std::fs;
 use std::path::Path;
@@ -34,9 +38,6 @@ pub fn handle(cmd: ExecuteCommand, global: &GlobalOptions) -> Result<()> {
         }
     }
 
-    // Determine input type and load bytecode
-    let bytecode = load_input(&cmd)?;
-
     // Create VM with specified version
     let version = cmd
         .version
@@ -45,6 +46,9 @@ pub fn handle(cmd: ExecuteCommand, global: &GlobalOptions) -> Result<()> {
         .context("Invalid TEAL version")?
         .unwrap_or(TealVersion::latest());
 
+    // Determine input type and load bytecode
+    let program = load_input(&cmd, version, global)?;
+
     let vm = VirtualMachine::with_version(version);
 
     // Configure execution
@@ -71,32 +75,101 @@ pub fn handle(cmd: ExecuteCommand, global: &GlobalOptions) -> Result<()> {
         .with_cost_budget(cmd.budget)
         .with_run_mode(run_mode);
 
+    let config = match cmd.step_trace {
+        Some(_) => config.with_step_trace(cmd.trace_limit.unwrap_or(usize::MAX)),
+        None => config,
+    };
+
     // Setup mock ledger
     let mut ledger = setup_ledger(&cmd)?;
 
     // Execute the program
     if cmd.step {
-        execute_with_stepping(&vm, &bytecode, config, &mut ledger, global)
+        execute_with_stepping(&vm, &program, config, &mut ledger, &cmd.breakpoints, global)
     } else {
-        execute_normal(&vm, &bytecode, config, &mut ledger, global)
+        execute_normal(&vm, &program, config, &mut ledger, &cmd, global)
+    }
+}
+
+/// A program to execute, with its source when known
+struct LoadedProgram {
+    bytecode: Vec<u8>,
+    source: Option<String>,
+    source_map: Option<SourceMap>,
+}
+
+impl LoadedProgram {
+    fn bytecode(bytecode: Vec<u8>) -> Self {
+        Self {
+            bytecode,
+            source: None,
+            source_map: None,
+        }
+    }
+
+    /// Describe `pc` with its source line when there is a source map
+    fn describe_pc(&self, pc: usize) -> String {
+        match self.source_line(pc) {
+            Some(line) => format!("pc {pc} ({line})"),
+            None => format!("pc {pc}"),
+        }
+    }
+
+    /// The source line of `pc`, as `line N: text`
+    fn source_line(&self, pc: usize) -> Option<String> {
+        let location = self.source_map.as_ref()?.location(pc)?;
+        let line = location.line + 1;
+        match self
+            .source
+            .as_ref()
+            .and_then(|s| s.lines().nth(location.line))
+        {
+            Some(text) => Some(format!("line {line}: {}", text.trim())),
+            None => Some(format!("line {line}")),
+        }
     }
 }
 
 /// Load input based on type
-fn load_input(cmd: &ExecuteCommand) -> Result<Vec<u8>> {
-    match cmd.input_type {
-        InputType::Auto => auto_detect_and_load(&cmd.input),
-        InputType::File => load_from_file(&cmd.input),
-        InputType::Bytecode => decode_bytecode(&cmd.input),
-        InputType::Inline => assemble_inline(&cmd.input),
+///
+/// TEAL source without a `#pragma version` is assembled for `version`.
+fn load_input(
+    cmd: &ExecuteCommand,
+    version: TealVersion,
+    global: &GlobalOptions,
+) -> Result<LoadedProgram> {
+    let mut program = match cmd.input_type {
+        InputType::Auto => auto_detect_and_load(&cmd.input, version, global),
+        InputType::File => load_from_file(&cmd.input, version, global),
+        InputType::Bytecode => decode_bytecode(&cmd.input).map(LoadedProgram::bytecode),
+        InputType::Inline => assemble_inline(&cmd.input, None, version, global),
+    }?;
+
+    if let Some(path) = &cmd.source_map {
+        let json = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read source map: {path:?}"))?;
+        let source_map = SourceMap::from_json(&json).map_err(|e| anyhow!("{e}"))?;
+        // Sources are named relative to the map
+        if program.source.is_none() {
+            program.source = source_map
+                .sources
+                .first()
+                .and_then(|name| fs::read_to_string(path.with_file_name(name)).ok());
+        }
+        program.source_map = Some(source_map);
     }
+    Ok(program)
 }
 
 /// Auto-detect input type and load accordingly
-fn auto_detect_and_load(input: &str) -> Result<Vec<u8>> {
+fn auto_detect_and_load(
+    input: &str,
+    version: TealVersion,
+    global: &GlobalOptions,
+) -> Result<LoadedProgram> {
     // Check if it's a file path
     if Path::new(input).exists() {
-        return load_from_file(input);
+        return load_from_file(input, version, global);
     }
 
     // Check if it looks like hex bytecode
@@ -106,26 +179,30 @@ fn auto_detect_and_load(input: &str) -> Result<Vec<u8>> {
         && input.len() > 10
     {
         if let Ok(bytecode) = decode_bytecode(input) {
-            return Ok(bytecode);
+            return Ok(LoadedProgram::bytecode(bytecode));
         }
     }
 
     // Treat as inline TEAL
-    assemble_inline(input)
+    assemble_inline(input, None, version, global)
 }
 
 /// Load bytecode from file
-fn load_from_file(path: &str) -> Result<Vec<u8>> {
+fn load_from_file(
+    path: &str,
+    version: TealVersion,
+    global: &GlobalOptions,
+) -> Result<LoadedProgram> {
     let content =
         fs::read_to_string(path).with_context(|| format!("Failed to read file: {path}"))?;
 
     // Check if file contains TEAL source or bytecode
     if content.trim_start().starts_with("#pragma") || content.contains("int ") {
         // TEAL source file
-        assemble_inline(&content)
+        assemble_inline(&content, Some(path), version, global)
     } else {
         // Assume bytecode file
-        decode_bytecode(&content)
+        decode_bytecode(&content).map(LoadedProgram::bytecode)
     }
 }
 
@@ -138,34 +215,46 @@ fn decode_bytecode(hex: &str) -> Result<Vec<u8>> {
     hex::decode(&hex).with_context(|| "Invalid hex bytecode")
 }
 
-/// Assemble inline TEAL source
-fn assemble_inline(source: &str) -> Result<Vec<u8>> {
-    let mut assembler = Assembler::new();
-    assembler
+/// Assemble inline TEAL source, read from `path` if given
+fn assemble_inline(
+    source: &str,
+    path: Option<&str>,
+    version: TealVersion,
+    global: &GlobalOptions,
+) -> Result<LoadedProgram> {
+    let mut assembler = Assembler::new().with_default_version(version.as_u8());
+    if let Some(path) = path {
+        assembler = assembler.with_source_name(path);
+    }
+    let bytecode = assembler
         .assemble(source)
-        .map_err(|e| anyhow!("Assembly failed: {}", e))
+        .map_err(|e| assembly_failure(e, source, global))?;
+    Ok(LoadedProgram {
+        bytecode,
+        source: Some(source.to_string()),
+        source_map: Some(assembler.source_map().clone()),
+    })
 }
 
-/// Setup mock ledger with optional data
+/// Setup mock ledger from the ledger fixture and transaction fields given
 fn setup_ledger(cmd: &ExecuteCommand) -> Result<MockLedger> {
-    let ledger = MockLedger::default();
+    let mut ledger = MockLedger::default();
 
-    // Load ledger data if provided
     if let Some(ledger_file) = &cmd.ledger {
-        let _content = fs::read_to_string(ledger_file)
-            .with_context(|| format!("Failed to read ledger file: {ledger_file:?}"))?;
-
-        // TODO: Implement JSON deserialization for ledger data
-        // For now, use default ledger
+        LedgerFixture::load(ledger_file)?.apply(&mut ledger)?;
     }
 
-    // Load transaction data if provided
+    // A transaction file holds the `txn` section of a fixture
     if let Some(txn_file) = &cmd.transaction {
-        let _content = fs::read_to_string(txn_file)
+        let content = fs::read_to_string(txn_file)
             .with_context(|| format!("Failed to read transaction file: {txn_file:?}"))?;
-
-        // TODO: Implement JSON deserialization for transaction data
-        // For now, use default transaction
+        let txn = serde_json::from_str(&content)
+            .with_context(|| format!("Invalid transaction file: {txn_file:?}"))?;
+        LedgerFixture {
+            txn,
+            ..Default::default()
+        }
+        .apply(&mut ledger)?;
     }
 
     Ok(ledger)
@@ -174,168 +263,546 @@ fn setup_ledger(cmd: &ExecuteCommand) -> Result<MockLedger> {
 /// Execute program normally
 fn execute_normal(
     vm: &VirtualMachine,
-    bytecode: &[u8],
+    program: &LoadedProgram,
     config: ExecutionConfig,
     ledger: &mut MockLedger,
+    cmd: &ExecuteCommand,
     global: &GlobalOptions,
 ) -> Result<()> {
     let start = std::time::Instant::now();
 
     let result = vm
-        .execute(bytecode, config.clone(), ledger)
-        .map_err(|e| anyhow!("Execution failed: {}", e))?;
+        .execute_detailed(&program.bytecode, config.clone(), ledger)
+        .map_err(|e| anyhow!("Execution failed: {e}"))?;
 
     let duration = start.elapsed();
 
+    if let Some(format) = cmd.step_trace {
+        let trace = format_step_trace(&result, program, format)?;
+        match &cmd.trace_file {
+            Some(path) => fs::write(path, trace)
+                .with_context(|| format!("Failed to write trace file: {path:?}"))?,
+            // Standard output holds only the result document in JSON mode
+            None if matches!(global.format, crate::cli::OutputFormat::Json) => {
+                eprint!("{trace}")
+            }
+            None => print!("{trace}"),
+        }
+    }
+
     if !global.quiet {
         match global.format {
-            crate::cli::OutputFormat::Text => {
-                println!("✅ Execution completed successfully");
-                println!("Result: {result}");
-
-                if global.verbose {
-                    println!("Duration: {duration:?}");
-                    println!("Cost budget: {}", config.cost_budget);
-                }
-            }
+            crate::cli::OutputFormat::Text => print_result_text(&result, program, duration, global),
             crate::cli::OutputFormat::Json => {
-                let output = serde_json::json!({
-                    "success": true,
-                    "result": result,
-                    "duration_ms": duration.as_millis(),
-                    "cost_budget": config.cost_budget
-                });
+                let mut output = result_json(&result, program);
+                output["duration_ms"] = serde_json::json!(duration.as_millis());
+                output["cost_budget"] = serde_json::json!(config.cost_budget);
                 println!("{}", serde_json::to_string_pretty(&output)?);
             }
         }
     }
 
-    Ok(())
+    match result.error() {
+        Some((pc, message)) => Err(anyhow!(
+            "Execution failed at {}: {message}",
+            program.describe_pc(pc)
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Print an execution result as human-readable text
+fn print_result_text(
+    result: &ExecutionResult,
+    program: &LoadedProgram,
+    duration: std::time::Duration,
+    global: &GlobalOptions,
+) {
+    match &result.outcome {
+        ExecutionOutcome::Approve | ExecutionOutcome::Reject => {
+            println!("✅ Execution completed successfully");
+            println!("Result: {}", result.approved());
+        }
+        ExecutionOutcome::Error { pc, message } => {
+            println!(
+                "❌ Execution failed at {}: {message}",
+                program.describe_pc(*pc)
+            );
+        }
+    }
+    println!(
+        "Cost: {} (remaining budget: {})",
+        result.cost, result.remaining_budget
+    );
+
+    if !result.logs.is_empty() {
+        println!("Logs:");
+        for (i, log) in result.logs.iter().enumerate() {
+            println!("  {i}: {}", StackValue::Bytes(log.clone()));
+        }
+    }
+
+    if global.verbose {
+        println!("Duration: {duration:?}");
+        let scratch: Vec<_> = result.used_scratch().collect();
+        if !scratch.is_empty() {
+            println!("Scratch:");
+            for (slot, value) in scratch {
+                println!("  {slot}: {value}");
+            }
+        }
+    }
+
+    if !result.inner_transactions.is_empty() {
+        println!("Inner transactions:");
+        for (i, txn) in result.inner_transactions.iter().enumerate() {
+            println!(
+                "  {i}: {} from {}",
+                txn.tx_type.as_str(),
+                hex::encode(&txn.sender)
+            );
+        }
+    }
+
+    if !result.state_changes.is_empty() {
+        println!("State changes:");
+        for change in &result.state_changes {
+            println!("  {}", describe_state_change(change));
+        }
+    }
+}
+
+/// Convert an execution result to JSON
+fn result_json(result: &ExecutionResult, program: &LoadedProgram) -> serde_json::Value {
+    let (outcome, error) = match &result.outcome {
+        ExecutionOutcome::Approve => ("approve", serde_json::Value::Null),
+        ExecutionOutcome::Reject => ("reject", serde_json::Value::Null),
+        ExecutionOutcome::Error { pc, message } => {
+            let mut error = serde_json::json!({ "pc": pc, "message": message });
+            if let Some(map) = &program.source_map
+                && let Some(location) = map.location(*pc)
+            {
+                error["line"] = serde_json::json!(location.line + 1);
+                error["column"] = serde_json::json!(location.column + 1);
+            }
+            ("error", error)
+        }
+    };
+
+    let scratch: serde_json::Map<String, serde_json::Value> = result
+        .used_scratch()
+        .map(|(slot, value)| (slot.to_string(), stack_value_json(value)))
+        .collect();
+
+    serde_json::json!({
+        "success": result.error().is_none(),
+        "result": result.approved(),
+        "outcome": outcome,
+        "error": error,
+        "cost": result.cost,
+        "remaining_budget": result.remaining_budget,
+        "logs": result.logs.iter().map(hex::encode).collect::<Vec<_>>(),
+        "stack": result.stack.iter().map(stack_value_json).collect::<Vec<_>>(),
+        "scratch": scratch,
+        "inner_transactions": result
+            .inner_transactions
+            .iter()
+            .map(|txn| serde_json::json!({
+                "type": txn.tx_type.as_str(),
+                "sender": hex::encode(&txn.sender),
+                "fee": txn.fee,
+                "group_index": txn.group_index,
+            }))
+            .collect::<Vec<_>>(),
+        "state_changes": result
+            .state_changes
+            .iter()
+            .map(describe_state_change)
+            .collect::<Vec<_>>(),
+        "eval_delta": result.eval_delta.to_json(),
+    })
+}
+
+/// Render the step trace of `result`
+///
+/// Text and NDJSON have one line per step, with text noting truncation on a
+/// final line. JSON is an object with the `steps` and whether the trace was
+/// `truncated`.
+fn format_step_trace(
+    result: &ExecutionResult,
+    program: &LoadedProgram,
+    format: StepTraceFormat,
+) -> Result<String> {
+    let mut output = String::new();
+    match format {
+        StepTraceFormat::Text => {
+            for step in &result.trace {
+                output.push_str(&format!("{step}\n"));
+            }
+            if result.trace_truncated {
+                output.push_str(&format!(
+                    "... trace truncated after {} steps\n",
+                    result.trace.len()
+                ));
+            }
+        }
+        StepTraceFormat::Json => {
+            let steps: Vec<_> = result
+                .trace
+                .iter()
+                .map(|step| trace_step_json(step, program))
+                .collect();
+            let trace = serde_json::json!({
+                "steps": steps,
+                "truncated": result.trace_truncated,
+            });
+            output = serde_json::to_string_pretty(&trace)? + "\n";
+        }
+        StepTraceFormat::Ndjson => {
+            for step in &result.trace {
+                output.push_str(&format!("{}\n", trace_step_json(step, program)));
+            }
+        }
+    }
+    Ok(output)
+}
+
+/// Convert a trace step to JSON, with its source line when known
+fn trace_step_json(step: &TraceStep, program: &LoadedProgram) -> serde_json::Value {
+    let mut json = serde_json::json!({
+        "pc": step.pc,
+        "opcode": step.opcode,
+        "immediates": step.immediates,
+        "cost": step.cost,
+        "popped": step.popped.iter().map(stack_value_json).collect::<Vec<_>>(),
+        "pushed": step.pushed.iter().map(stack_value_json).collect::<Vec<_>>(),
+        "scratch_writes": step
+            .scratch_writes
+            .iter()
+            .map(|write| serde_json::json!({
+                "slot": write.slot,
+                "value": stack_value_json(&write.value),
+            }))
+            .collect::<Vec<_>>(),
+        "state_writes": step
+            .state_writes
+            .iter()
+            .map(state_change_json)
+            .collect::<Vec<_>>(),
+    });
+    if let Some(map) = &program.source_map
+        && let Some(location) = map.location(step.pc)
+    {
+        json["line"] = serde_json::json!(location.line + 1);
+    }
+    json
+}
+
+/// Convert a stack value to JSON
+fn stack_value_json(value: &StackValue) -> serde_json::Value {
+    match value {
+        StackValue::Uint(n) => serde_json::json!({ "type": "uint", "value": n }),
+        StackValue::Bytes(b) => serde_json::json!({ "type": "bytes", "value": hex::encode(b) }),
+    }
+}
+
+/// Convert a state change to JSON, with keys and addresses in hex
+///
+/// Every change has a `kind`; writes carry the new `value` and removals set
+/// `deleted`.
+fn state_change_json(change: &StateChange) -> serde_json::Value {
+    use serde_json::json;
+
+    let value = |value: &TealValue| stack_value_json(&value.to_stack_value());
+    match change {
+        StateChange::GlobalPut {
+            app_id,
+            key,
+            value: v,
+        } => json!({
+            "kind": "global",
+            "app_id": app_id,
+            "key": hex::encode(key),
+            "value": value(v),
+        }),
+        StateChange::GlobalDel { app_id, key } => json!({
+            "kind": "global",
+            "app_id": app_id,
+            "key": hex::encode(key),
+            "deleted": true,
+        }),
+        StateChange::LocalPut {
+            address,
+            app_id,
+            key,
+            value: v,
+        } => json!({
+            "kind": "local",
+            "app_id": app_id,
+            "account": hex::encode(address),
+            "key": hex::encode(key),
+            "value": value(v),
+        }),
+        StateChange::LocalDel {
+            address,
+            app_id,
+            key,
+        } => json!({
+            "kind": "local",
+            "app_id": app_id,
+            "account": hex::encode(address),
+            "key": hex::encode(key),
+            "deleted": true,
+        }),
+        StateChange::BoxPut {
+            app_id,
+            name,
+            contents,
+        } => json!({
+            "kind": "box",
+            "app_id": app_id,
+            "key": hex::encode(name),
+            "value": stack_value_json(&StackValue::Bytes(contents.clone())),
+        }),
+        StateChange::BoxDel { app_id, name } => json!({
+            "kind": "box",
+            "app_id": app_id,
+            "key": hex::encode(name),
+            "deleted": true,
+        }),
+        StateChange::Balance {
+            address,
+            previous,
+            amount,
+        } => json!({
+            "kind": "balance",
+            "account": hex::encode(address),
+            "value": stack_value_json(&StackValue::Uint(*amount)),
+            "previous": previous,
+        }),
+        StateChange::AssetHoldingPut {
+            address,
+            asset_id,
+            holding,
+        } => json!({
+            "kind": "asset_holding",
+            "asset_id": asset_id,
+            "account": hex::encode(address),
+            "value": stack_value_json(&StackValue::Uint(holding.amount)),
+            "frozen": holding.frozen,
+        }),
+        StateChange::AssetHoldingDel { address, asset_id } => json!({
+            "kind": "asset_holding",
+            "asset_id": asset_id,
+            "account": hex::encode(address),
+            "deleted": true,
+        }),
+        StateChange::AssetParamsPut { asset_id, .. } => {
+            json!({ "kind": "asset_params", "asset_id": asset_id })
+        }
+        StateChange::AssetParamsDel { asset_id } => {
+            json!({ "kind": "asset_params", "asset_id": asset_id, "deleted": true })
+        }
+        StateChange::AppParamsPut { app_id, .. } => {
+            json!({ "kind": "app_params", "app_id": app_id })
+        }
+        StateChange::AppParamsDel { app_id } => {
+            json!({ "kind": "app_params", "app_id": app_id, "deleted": true })
+        }
+        StateChange::AppOptIn { address, app_id } => json!({
+            "kind": "opt_in",
+            "app_id": app_id,
+            "account": hex::encode(address),
+        }),
+        StateChange::AppOptOut { address, app_id } => json!({
+            "kind": "opt_in",
+            "app_id": app_id,
+            "account": hex::encode(address),
+            "deleted": true,
+        }),
+    }
+}
+
+/// Quoted text for printable keys, hex otherwise
+fn describe_key(key: &[u8]) -> String {
+    match std::str::from_utf8(key) {
+        Ok(text) if !text.chars().any(char::is_control) => format!("{text:?}"),
+        _ => format!("0x{}", hex::encode(key)),
+    }
 }
 
-/// Execute program with step-by-step debugging
+/// One-line description of a state change
+fn describe_state_change(change: &StateChange) -> String {
+    match change {
+        StateChange::GlobalPut { app_id, key, value } => {
+            format!("app {app_id} global {} = {value}", describe_key(key))
+        }
+        StateChange::GlobalDel { app_id, key } => {
+            format!("app {app_id} global {} deleted", describe_key(key))
+        }
+        StateChange::LocalPut {
+            address,
+            app_id,
+            key,
+            value,
+        } => format!(
+            "app {app_id} local {} of {} = {value}",
+            describe_key(key),
+            hex::encode(address)
+        ),
+        StateChange::LocalDel {
+            address,
+            app_id,
+            key,
+        } => format!(
+            "app {app_id} local {} of {} deleted",
+            describe_key(key),
+            hex::encode(address)
+        ),
+        StateChange::BoxPut {
+            app_id,
+            name,
+            contents,
+        } => format!(
+            "app {app_id} box {} = 0x{}",
+            hex::encode(name),
+            hex::encode(contents)
+        ),
+        StateChange::BoxDel { app_id, name } => {
+            format!("app {app_id} box {} deleted", hex::encode(name))
+        }
+        StateChange::Balance {
+            address,
+            previous,
+            amount,
+        } => format!(
+            "balance of {} = {amount} (was {previous})",
+            hex::encode(address)
+        ),
+        StateChange::AssetHoldingPut {
+            address,
+            asset_id,
+            holding,
+        } => format!(
+            "asset {asset_id} holding of {} = {} (frozen: {})",
+            hex::encode(address),
+            holding.amount,
+            holding.frozen
+        ),
+        StateChange::AssetHoldingDel { address, asset_id } => format!(
+            "asset {asset_id} holding of {} removed",
+            hex::encode(address)
+        ),
+        StateChange::AssetParamsPut { asset_id, .. } => format!("asset {asset_id} configured"),
+        StateChange::AssetParamsDel { asset_id } => format!("asset {asset_id} destroyed"),
+        StateChange::AppParamsPut { app_id, .. } => format!("app {app_id} created or updated"),
+        StateChange::AppParamsDel { app_id } => format!("app {app_id} deleted"),
+        StateChange::AppOptIn { address, app_id } => {
+            format!("{} opted in to app {app_id}", hex::encode(address))
+        }
+        StateChange::AppOptOut { address, app_id } => {
+            format!("{} opted out of app {app_id}", hex::encode(address))
+        }
+    }
+}
+
+/// Execute program under the interactive debugger
 fn execute_with_stepping(
     vm: &VirtualMachine,
-    bytecode: &[u8],
+    program: &LoadedProgram,
     config: ExecutionConfig,
     ledger: &mut MockLedger,
+    breakpoints: &[String],
     global: &GlobalOptions,
 ) -> Result<()> {
     use std::io::{self, Write};
 
+    let start = std::time::Instant::now();
+    let mut overlay = OverlayLedger::new(ledger);
+    let mut debugger = Debugger::new(vm, &program.bytecode, config, &mut overlay)
+        .map_err(|e| anyhow!("Failed to create evaluation context: {e}"))?;
+    if let Some(source_map) = &program.source_map {
+        debugger = debugger.with_source_map(source_map.clone());
+    }
+    for breakpoint in breakpoints {
+        let (location, condition) = parse_breakpoint(breakpoint)?;
+        debugger
+            .add_breakpoint(location, condition)
+            .with_context(|| format!("Invalid breakpoint: {breakpoint}"))?;
+    }
+
     if !global.quiet {
         println!("🔍 Step-by-step execution mode");
-        println!("Commands: [Enter] step, 'c' continue, 'q' quit, 'h' help");
+        println!("Commands: [Enter] step, 'n' next, 'c' continue, 'b' break, 'q' quit, 'h' help");
         println!("{}", "─".repeat(60));
+        show_stop(vm, &debugger, program);
     }
 
-    let start = std::time::Instant::now();
-
-    // Create evaluation context for stepping
-    let mut eval_ctx = vm
-        .create_eval_context(bytecode, config.clone(), ledger)
-        .map_err(|e| anyhow::anyhow!("Failed to create evaluation context: {}", e))?;
-
-    let mut step_count = 0;
-    let mut continue_mode = false;
-
-    while !eval_ctx.is_finished() {
-        if !continue_mode && !global.quiet {
-            // Display current state
-            let opcode_info = eval_ctx
-                .current_opcode_spec(vm)
-                .map(|spec| format!("{} (cost: {})", spec.name, spec.cost))
-                .unwrap_or_else(|_| "Invalid opcode".to_string());
-
-            println!(
-                "Step {}: PC={:04} | {}",
-                step_count,
-                eval_ctx.pc(),
-                opcode_info
-            );
-
-            // Display stack
-            let stack = eval_ctx.stack();
-            if stack.is_empty() {
-                println!("Stack: (empty)");
-            } else {
-                println!(
-                    "Stack: [{}]",
-                    stack
-                        .iter()
-                        .enumerate()
-                        .map(|(i, val)| format!("{i}: {val:?}"))
-                        .collect::<Vec<_>>()
-                        .join(", ")
-                );
-            }
-
+    while !debugger.is_finished() {
+        let stop = if global.quiet {
+            debugger.resume()
+        } else {
             print!("vm> ");
-            io::stdout().flush().unwrap();
-
-            // Read user input
-            let mut input = String::new();
-            io::stdin().read_line(&mut input).unwrap();
-            let input = input.trim();
-
-            match input {
+            io::stdout().flush()?;
+            let mut line = String::new();
+            // Without more input, run to the next breakpoint
+            let input = match io::stdin().read_line(&mut line)? {
+                0 => "c",
+                _ => line.trim(),
+            };
+            let (command, args) = input
+                .split_once(char::is_whitespace)
+                .map_or((input, ""), |(command, args)| (command, args.trim()));
+
+            match command {
+                "" | "s" | "step" => debugger.step(),
+                "n" | "next" => debugger.step_over(),
+                "o" | "out" => debugger.step_out(),
+                "c" | "continue" => debugger.resume(),
                 "q" | "quit" => {
                     println!("Execution interrupted by user");
                     return Ok(());
                 }
-                "c" | "continue" => {
-                    continue_mode = true;
-                    println!("Continuing execution...");
-                }
                 "h" | "help" => {
-                    println!("Commands:");
-                    println!("  [Enter] - Execute next instruction");
-                    println!("  c       - Continue execution without stepping");
-                    println!("  q       - Quit execution");
-                    println!("  h       - Show this help");
+                    print_debugger_help();
                     continue;
                 }
-                "" => {
-                    // Step (default action)
-                }
                 _ => {
-                    println!("Unknown command '{input}'. Type 'h' for help.");
+                    if let Err(e) = debugger_command(&mut debugger, command, args) {
+                        println!("❌ {e}");
+                    }
                     continue;
                 }
             }
-        }
+        };
 
-        // Execute one step
-        eval_ctx
-            .step(vm, &config)
-            .map_err(|e| anyhow::anyhow!("Execution failed at step {}: {}", step_count, e))?;
-
-        step_count += 1;
-    }
-
-    // Extract final result
-    let result = if eval_ctx.is_finished() {
-        // Check final result
-        let stack = eval_ctx.stack();
-        if stack.is_empty() {
-            return Err(anyhow::anyhow!(
-                "Program ended with 0 values on stack, expected 1"
-            ));
+        if global.quiet {
+            continue;
         }
-        if stack.len() > 1 {
-            return Err(anyhow::anyhow!(
-                "Program ended with {} values on stack, expected 1",
-                stack.len()
-            ));
+        match stop {
+            StopReason::Breakpoint(id) => println!("⏸  Breakpoint #{id}"),
+            StopReason::Step => {}
+            StopReason::Finished | StopReason::Error { .. } => break,
         }
-        stack[0]
-            .as_bool()
-            .map_err(|e| anyhow::anyhow!("Invalid final result: {}", e))?
-    } else {
-        return Err(anyhow::anyhow!("Program execution incomplete"));
-    };
+        show_stop(vm, &debugger, program);
+    }
 
+    if let Some((pc, message)) = debugger.error() {
+        return Err(anyhow!(
+            "Execution failed at step {}, {}: {message}",
+            debugger.steps(),
+            program.describe_pc(pc)
+        ));
+    }
+    let result = debugger
+        .result()
+        .map_err(|e| anyhow!("Invalid final result: {e}"))?;
+    let steps = debugger.steps();
     let duration = start.elapsed();
+    drop(debugger);
+    if result {
+        overlay.commit()?;
+    }
 
     if !global.quiet {
         println!("{}", "─".repeat(60));
@@ -343,14 +810,14 @@ fn execute_with_stepping(
             crate::cli::OutputFormat::Text => {
                 println!("✅ Execution completed successfully");
                 println!("Result: {result}");
-                println!("Steps: {step_count}");
+                println!("Steps: {steps}");
                 println!("Duration: {duration:?}");
             }
             crate::cli::OutputFormat::Json => {
                 let output = serde_json::json!({
                     "success": true,
                     "result": result,
-                    "steps": step_count,
+                    "steps": steps,
                     "duration_ms": duration.as_millis()
                 });
                 println!("{}", serde_json::to_string_pretty(&output)?);
@@ -361,6 +828,165 @@ fn execute_with_stepping(
     Ok(())
 }
 
+/// Run a debugger command that inspects or changes state without executing
+fn debugger_command(debugger: &mut Debugger, command: &str, args: &str) -> Result<()> {
+    match command {
+        "b" | "break" => {
+            let (location, condition) = match args {
+                "" => (Some(Location::Pc(debugger.context().pc())), None),
+                args => parse_breakpoint(args)?,
+            };
+            let id = debugger.add_breakpoint(location, condition)?;
+            if let Some(breakpoint) = debugger.breakpoints().iter().find(|b| b.id == id) {
+                println!("Breakpoint {breakpoint}");
+            }
+        }
+        "d" | "delete" => {
+            let id = args.parse().context("Usage: delete ID")?;
+            if !debugger.remove_breakpoint(id) {
+                return Err(anyhow!("No breakpoint #{id}"));
+            }
+        }
+        "w" | "watch" => {
+            let expr: Expr = args.parse()?;
+            println!("{expr} = {}", describe_value(debugger.evaluate(&expr)));
+            debugger.add_watch(expr);
+        }
+        "unwatch" => {
+            let index: usize = args.parse().context("Usage: unwatch N")?;
+            index
+                .checked_sub(1)
+                .and_then(|index| debugger.remove_watch(index))
+                .ok_or_else(|| anyhow!("No watch {index}"))?;
+        }
+        "p" | "print" => {
+            let expr: Expr = args.parse()?;
+            println!("{expr} = {}", describe_value(debugger.evaluate(&expr)));
+        }
+        "set" => {
+            let (target, value) = args
+                .split_once('=')
+                .ok_or_else(|| anyhow!("Usage: set TARGET = VALUE"))?;
+            let target: Expr = target.parse()?;
+            let value = debugger.evaluate(&value.parse()?)?;
+            debugger.set_value(&target, value)?;
+            println!("{target} = {}", describe_value(debugger.evaluate(&target)));
+        }
+        "i" | "info" => {
+            if debugger.breakpoints().is_empty() {
+                println!("No breakpoints");
+            }
+            for breakpoint in debugger.breakpoints() {
+                println!("Breakpoint {breakpoint} (hits: {})", breakpoint.hits);
+            }
+            for (index, (expr, value)) in debugger.watches().into_iter().enumerate() {
+                println!("Watch {}: {expr} = {}", index + 1, describe_value(value));
+            }
+        }
+        "stack" => {
+            for (index, value) in debugger.context().stack().iter().enumerate() {
+                println!("  {index}: {value}");
+            }
+        }
+        "scratch" => {
+            for (index, value) in debugger.context().scratch().iter().enumerate() {
+                if *value != StackValue::Uint(0) {
+                    println!("  {index}: {value}");
+                }
+            }
+        }
+        _ => {
+            return Err(anyhow!("Unknown command '{command}'. Type 'h' for help."));
+        }
+    }
+    Ok(())
+}
+
+/// Parse `[LOCATION] [if CONDITION]`
+fn parse_breakpoint(text: &str) -> Result<(Option<Location>, Option<Condition>)> {
+    let text = text.trim();
+    let (location, condition) = match text.strip_prefix("if ") {
+        Some(condition) => ("", Some(condition)),
+        None => match text.split_once(" if ") {
+            Some((location, condition)) => (location, Some(condition)),
+            None => (text, None),
+        },
+    };
+    let location = match location.trim() {
+        "" => None,
+        location => Some(location.parse()?),
+    };
+    let condition = condition.map(str::parse).transpose()?;
+    Ok((location, condition))
+}
+
+/// Format a value read by the debugger, or why it could not be read
+fn describe_value(value: crate::error::AvmResult<StackValue>) -> String {
+    match value {
+        Ok(value) => value.to_string(),
+        Err(e) => format!("<{e}>"),
+    }
+}
+
+/// Show the next instruction, the stack and the watches
+fn show_stop(vm: &VirtualMachine, debugger: &Debugger, program: &LoadedProgram) {
+    if debugger.is_finished() {
+        return;
+    }
+    let ctx = debugger.context();
+    let pc = ctx.pc();
+    let instruction = match (
+        debugger.disassembly().instruction_at(pc),
+        ctx.current_opcode_spec(vm),
+    ) {
+        (Some(instruction), Ok(spec)) => format!("{} (cost: {})", instruction.text, spec.cost),
+        _ => "Invalid opcode".to_string(),
+    };
+    println!("Step {}: PC={pc:04} | {instruction}", debugger.steps());
+    if let Some(line) = program.source_line(pc) {
+        println!("  {line}");
+    }
+
+    let stack = ctx.stack();
+    if stack.is_empty() {
+        println!("Stack: (empty)");
+    } else {
+        println!(
+            "Stack: [{}]",
+            stack
+                .iter()
+                .enumerate()
+                .map(|(i, val)| format!("{i}: {val:?}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+    for (expr, value) in debugger.watches() {
+        println!("  {expr} = {}", describe_value(value));
+    }
+}
+
+/// Print the debugger commands
+fn print_debugger_help() {
+    println!("Commands:");
+    println!("  [Enter], s      - Execute next instruction");
+    println!("  n               - Execute next instruction, stepping over callsub");
+    println!("  o               - Run until the current subroutine returns");
+    println!("  c               - Continue to the next breakpoint");
+    println!("  b [LOC] [if C]  - Break at a pc, label or 'line N', or when C holds");
+    println!("  d ID            - Delete a breakpoint");
+    println!("  w EXPR          - Show EXPR at every stop; 'unwatch N' removes it");
+    println!("  p EXPR          - Print EXPR");
+    println!("  set EXPR = EXPR - Change a stack value, scratch slot or frame variable");
+    println!("  i               - List breakpoints and watches");
+    println!("  stack, scratch  - Show the stack or the used scratch slots");
+    println!("  q               - Quit execution");
+    println!("  h               - Show this help");
+    println!("Expressions: pc, cost, height, stack[N] (negative from the top),");
+    println!("scratch[N], frame[N], integers and byte constants such as \"abc\"");
+    println!("Conditions compare two expressions with ==, !=, <, <=, > or >=");
+}
+
 /// Build tracing configuration from CLI options
 #[cfg(feature = "tracing")]
 fn build_tracing_config(cmd: &ExecuteCommand) -> Result<TracingConfig> {
@@ -1,11 +1,47 @@
 //! Command implementations for the CLI
 
 pub mod assemble;
+pub mod dap;
+pub mod disassemble;
 pub mod execute;
 pub mod repl;
 pub mod validate;
 
 // Re-export command handlers for easier access
 pub use assemble::handle as assemble_handler;
+pub use dap::handle as dap_handler;
+pub use disassemble::handle as disassemble_handler;
 pub use execute::handle as execute_handler;
+pub use repl::handle as repl_handler;
 pub use validate::handle as validate_handler;
+
+use crate::cli::{GlobalOptions, OutputFormat};
+use crate::error::AvmError;
+
+/// Report an assembly error, rendering its diagnostics against `source`
+pub(crate) fn assembly_failure(
+    error: AvmError,
+    source: &str,
+    global: &GlobalOptions,
+) -> anyhow::Error {
+    let AvmError::AssemblyDiagnostics(diagnostics) = error else {
+        return anyhow::anyhow!("Assembly failed: {error}");
+    };
+
+    match global.format {
+        OutputFormat::Text => {
+            for diagnostic in &diagnostics {
+                eprintln!("{}", diagnostic.render(source));
+            }
+        }
+        OutputFormat::Json => {
+            let json = serde_json::json!({ "diagnostics": diagnostics });
+            match serde_json::to_string_pretty(&json) {
+                Ok(json) => println!("{json}"),
+                Err(e) => return e.into(),
+            }
+        }
+    }
+    let plural = if diagnostics.len() == 1 { "" } else { "s" };
+    anyhow::anyhow!("Assembly failed with {} error{plural}", diagnostics.len())
+}
@@ -1,41 +1,445 @@
 //! REPL command implementation
 
-use crate::cli::{GlobalOptions, ReplCommand};
-use anyhow::Result;
+use crate::assembler::Assembler;
+use crate::cli::commands::assembly_failure;
+use crate::cli::fixture::LedgerField;
+use crate::cli::{ExecutionMode, GlobalOptions, ReplCommand};
+use crate::opcodes::FieldGroup;
+use crate::opcodes::transaction::{parse_global_field, parse_txn_field};
+use crate::state::MockLedger;
+use crate::types::{RunMode, StackValue, TealValue, TealVersion};
+use crate::vm::{EvalContext, MIN_APP_PROGRAM_VERSION};
+use crate::{ExecutionConfig, VirtualMachine};
+use anyhow::{Context, Result, anyhow};
+use rustyline::DefaultEditor;
+use rustyline::error::ReadlineError;
+use std::fs;
+use std::path::PathBuf;
+
+/// Opcodes taking labels, which start a block ended by an empty line
+const BRANCH_OPCODES: &[&str] = &["b", "bz", "bnz", "callsub", "switch", "match"];
+
+/// Reason to leave an evaluation context
+enum Exit {
+    Quit,
+    Reset,
+    Version(TealVersion),
+    Mode(RunMode),
+    /// Set a transaction field from `Field=value`
+    Txn(String),
+    /// Set a global field from `Field=value`
+    Global(String),
+}
+
+/// Machine state kept when the evaluation context is rebuilt
+#[derive(Default)]
+struct Carried {
+    stack: Vec<StackValue>,
+    scratch: Vec<StackValue>,
+    cost: u64,
+}
+
+impl Carried {
+    fn save(ctx: &EvalContext) -> Self {
+        Self {
+            stack: ctx.stack().to_vec(),
+            scratch: ctx.scratch().to_vec(),
+            cost: ctx.cost(),
+        }
+    }
+
+    fn restore(self, ctx: &mut EvalContext) -> Result<()> {
+        for value in self.stack {
+            ctx.push(value)?;
+        }
+        for (slot, value) in self.scratch.into_iter().enumerate() {
+            ctx.set_scratch(slot as u8, value)?;
+        }
+        ctx.add_cost(self.cost)?;
+        Ok(())
+    }
+}
 
 /// Handle the REPL command
 pub fn handle(cmd: ReplCommand, global: &GlobalOptions) -> Result<()> {
+    let mut version = TealVersion::from_u8(cmd.version).context("Invalid TEAL version")?;
+    let mut run_mode = match cmd.mode {
+        ExecutionMode::Signature => RunMode::Signature,
+        ExecutionMode::Application => RunMode::Application,
+    };
+
     if !global.quiet {
         println!("🔬 Rust AVM Interactive REPL");
-        println!("TEAL Version: {}", cmd.version);
-        println!("Mode: {:?}", cmd.mode);
-        println!("Type 'help' for commands, 'exit' to quit\n");
+        println!("TEAL Version: {}", version.as_u8());
+        println!("Mode: {run_mode:?}");
+        println!("Type ':help' for commands, ':quit' or Ctrl-D to quit\n");
     }
 
-    // Load initial script if provided
-    if let Some(load_path) = &cmd.load {
-        if !global.quiet {
-            println!("📂 Loading initial script: {load_path:?}");
+    let mut editor = DefaultEditor::new()?;
+    let history = history_path();
+    if let Some(path) = &history {
+        // A missing history file just means a first session
+        let _ = editor.load_history(path);
+    }
+
+    let mut ledger = MockLedger::default();
+    let mut carried = Carried::default();
+    let mut pending = cmd.load.map(|path| format!(":load {}", path.display()));
+
+    loop {
+        let vm = VirtualMachine::with_version(version);
+        let config = ExecutionConfig::new(version).with_run_mode(run_mode);
+        let header = [version.as_u8()];
+        let mut ctx = vm
+            .create_eval_context(&header, config.clone(), &mut ledger)
+            .map_err(|e| anyhow!("Failed to create evaluation context: {e}"))?;
+        std::mem::take(&mut carried).restore(&mut ctx)?;
+
+        let mut repl = Repl {
+            vm: &vm,
+            config: &config,
+            global,
+            editor: &mut editor,
+            pending: pending.take(),
+        };
+        let exit = repl.run(&mut ctx)?;
+        carried = Carried::save(&ctx);
+        drop(ctx);
+
+        match exit {
+            Exit::Quit => break,
+            Exit::Reset => {
+                ledger = MockLedger::default();
+                carried = Carried::default();
+                println!("Session reset");
+            }
+            Exit::Version(new_version) => {
+                if run_mode == RunMode::Application && new_version < MIN_APP_PROGRAM_VERSION {
+                    println!(
+                        "❌ Application mode needs version {} or later",
+                        MIN_APP_PROGRAM_VERSION.as_u8()
+                    );
+                    continue;
+                }
+                version = new_version;
+                carried = Carried::default();
+                println!("Version {}, stack and scratch cleared", version.as_u8());
+            }
+            Exit::Mode(new_mode) => {
+                if new_mode == RunMode::Application && version < MIN_APP_PROGRAM_VERSION {
+                    println!(
+                        "❌ Application mode needs version {} or later",
+                        MIN_APP_PROGRAM_VERSION.as_u8()
+                    );
+                    continue;
+                }
+                run_mode = new_mode;
+                carried = Carried::default();
+                println!("Mode {run_mode:?}, stack and scratch cleared");
+            }
+            Exit::Txn(assignment) => {
+                if let Err(e) = set_txn_field(&mut ledger, &assignment) {
+                    println!("❌ {e}");
+                }
+            }
+            Exit::Global(assignment) => {
+                if let Err(e) = set_global_field(&mut ledger, &assignment) {
+                    println!("❌ {e}");
+                }
+            }
         }
-        // TODO: Load and execute initial script
     }
 
-    // TODO: Implement actual REPL loop
-    // This would require:
-    // 1. Reading input line by line
-    // 2. Parsing TEAL commands/expressions
-    // 3. Maintaining execution state between commands
-    // 4. Providing interactive help and debugging features
+    if let Some(path) = &history
+        && let Err(e) = editor.save_history(path)
+    {
+        eprintln!("⚠️  Failed to save history to {path:?}: {e}");
+    }
+    Ok(())
+}
+
+/// File the line history is kept in between sessions
+fn history_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".avm_rs_history"))
+}
+
+/// Reads lines and evaluates them against one evaluation context
+struct Repl<'r> {
+    vm: &'r VirtualMachine,
+    config: &'r ExecutionConfig,
+    global: &'r GlobalOptions,
+    editor: &'r mut DefaultEditor,
+    /// Line to handle before reading input
+    pending: Option<String>,
+}
+
+impl Repl<'_> {
+    /// Handle lines until one needs a new evaluation context
+    fn run(&mut self, ctx: &mut EvalContext) -> Result<Exit> {
+        let mut block: Vec<String> = Vec::new();
+        loop {
+            let line = match self.pending.take() {
+                Some(line) => line,
+                None => {
+                    let prompt = if block.is_empty() { "avm> " } else { "...> " };
+                    let line = match self.editor.readline(prompt) {
+                        Ok(line) => line,
+                        Err(ReadlineError::Interrupted) => {
+                            block.clear();
+                            continue;
+                        }
+                        Err(ReadlineError::Eof) => return Ok(Exit::Quit),
+                        Err(e) => return Err(e.into()),
+                    };
+                    if !line.trim().is_empty() {
+                        self.editor.add_history_entry(line.as_str())?;
+                    }
+                    line
+                }
+            };
 
-    println!("🚧 Interactive REPL is not yet implemented.");
-    println!("This feature will provide:");
-    println!("  • Line-by-line TEAL execution");
-    println!("  • Stack inspection");
-    println!("  • Variable tracking");
-    println!("  • Step-by-step debugging");
-    println!("  • History and completion");
-    println!("\nFor now, use the execute command with inline TEAL:");
-    println!("  avm-rs execute 'int 1; int 2; +; return'");
+            // Lines of a block are evaluated together once it ends
+            if !block.is_empty() {
+                if line.trim().is_empty() {
+                    self.eval(ctx, &block.join("\n"));
+                    block.clear();
+                } else {
+                    block.push(line);
+                }
+                continue;
+            }
+
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(command) = line.strip_prefix(':') {
+                if let Some(exit) = self.meta(ctx, command)? {
+                    return Ok(exit);
+                }
+            } else if starts_block(line) {
+                block.push(line.to_string());
+            } else {
+                self.eval(ctx, line);
+            }
+        }
+    }
+
+    /// Assemble and run `source`, then show the stack
+    fn eval(&self, ctx: &mut EvalContext, source: &str) {
+        let bytecode = match Assembler::new()
+            .with_default_version(ctx.version().as_u8())
+            .with_source_name("<repl>")
+            .assemble(source)
+        {
+            Ok(bytecode) => bytecode,
+            Err(e) => {
+                println!("❌ {}", assembly_failure(e, source, self.global));
+                return;
+            }
+        };
+        // The header is one byte, as versions are below 128
+        if bytecode[0] != ctx.version().as_u8() {
+            println!(
+                "❌ Program is for version {0}, switch with ':version {0}'",
+                bytecode[0]
+            );
+            return;
+        }
+        if let Err(e) = ctx.load_program(bytecode) {
+            println!("❌ {e}");
+            return;
+        }
+
+        while !ctx.is_finished() {
+            let pc = ctx.pc();
+            if let Err(e) = ctx.step(self.vm, self.config) {
+                println!("❌ {e} (at pc {pc})");
+                break;
+            }
+        }
+        show_stack(ctx);
+    }
+
+    /// Run a meta-command, returning how to leave the context if it needs a new one
+    fn meta(&mut self, ctx: &mut EvalContext, command: &str) -> Result<Option<Exit>> {
+        let (name, argument) = match command.split_once(char::is_whitespace) {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (command, ""),
+        };
+
+        match (name, argument) {
+            ("q" | "quit" | "exit", _) => return Ok(Some(Exit::Quit)),
+            ("h" | "help", _) => show_help(),
+            ("stack", _) => show_stack(ctx),
+            ("scratch", _) => show_scratch(ctx),
+            ("cost", _) => println!(
+                "Cost: {} of {} (remaining: {})",
+                ctx.cost(),
+                ctx.cost_budget(),
+                ctx.cost_budget().saturating_sub(ctx.cost())
+            ),
+            ("reset", _) => return Ok(Some(Exit::Reset)),
+            ("history", _) => {
+                for (i, line) in self.editor.history().iter().enumerate() {
+                    println!("{:4}  {line}", i + 1);
+                }
+            }
+            ("load", "") => println!("Usage: :load <file.teal>"),
+            ("load", path) => match fs::read_to_string(path) {
+                Ok(source) => self.eval(ctx, &source),
+                Err(e) => println!("❌ Failed to read {path}: {e}"),
+            },
+            ("txn", "") => show_txn(ctx)?,
+            ("txn", assignment) => return Ok(Some(Exit::Txn(assignment.to_string()))),
+            ("global", "") => show_globals(ctx),
+            ("global", assignment) => return Ok(Some(Exit::Global(assignment.to_string()))),
+            ("version", "") => println!("Version: {}", ctx.version().as_u8()),
+            ("version", version) => {
+                match version
+                    .parse::<u8>()
+                    .map_err(|e| anyhow!(e))
+                    .and_then(|v| TealVersion::from_u8(v).map_err(|e| anyhow!(e)))
+                {
+                    Ok(version) => return Ok(Some(Exit::Version(version))),
+                    Err(e) => println!("❌ Invalid version '{version}': {e}"),
+                }
+            }
+            ("mode", "") => println!("Mode: {:?}", ctx.run_mode()),
+            ("mode", "app" | "application") => return Ok(Some(Exit::Mode(RunMode::Application))),
+            ("mode", "sig" | "signature") => return Ok(Some(Exit::Mode(RunMode::Signature))),
+            ("mode", mode) => println!("❌ Unknown mode '{mode}', expected app or sig"),
+            _ => println!("Unknown command ':{name}'. Type ':help' for help."),
+        }
+        Ok(None)
+    }
+}
+
+/// Whether `line` defines a label or branches to one, so starts a block
+fn starts_block(line: &str) -> bool {
+    let code = line.split("//").next().unwrap_or_default();
+    match code.split_whitespace().next() {
+        Some(word) => word.ends_with(':') || BRANCH_OPCODES.contains(&word),
+        None => false,
+    }
+}
+
+/// Print the meta-commands
+fn show_help() {
+    println!("Enter TEAL to run it. A line defining a label or branching to one");
+    println!("starts a block, which runs when ended with an empty line.");
+    println!("Commands:");
+    println!("  :stack              Show the stack");
+    println!("  :scratch            Show the scratch slots in use");
+    println!("  :cost               Show the cost so far and the budget");
+    println!("  :reset              Clear the stack, scratch, cost and ledger");
+    println!("  :load <file>        Run a TEAL file");
+    println!("  :txn [Field=value]  Show the transaction or set one of its fields");
+    println!("  :global [Field=value] Show the global fields or set one");
+    println!("  :version [N]        Show or change the TEAL version");
+    println!("  :mode [app|sig]     Show or change the run mode");
+    println!("  :history            Show the line history");
+    println!("  :quit               Leave the REPL");
+}
+
+/// Print the stack, bottom first
+fn show_stack(ctx: &EvalContext) {
+    let stack = ctx.stack();
+    if stack.is_empty() {
+        println!("Stack: (empty)");
+    } else {
+        println!(
+            "Stack: [{}]",
+            stack
+                .iter()
+                .enumerate()
+                .map(|(i, value)| format!("{i}: {value}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+}
+
+/// Print the scratch slots holding something other than zero
+fn show_scratch(ctx: &EvalContext) {
+    let mut used = ctx
+        .scratch()
+        .iter()
+        .enumerate()
+        .filter(|(_, value)| **value != StackValue::Uint(0))
+        .peekable();
+    if used.peek().is_none() {
+        println!("Scratch: (empty)");
+    }
+    for (slot, value) in used {
+        println!("  {slot}: {value}");
+    }
+}
+
+/// Print the fields of the current transaction that are set
+fn show_txn(ctx: &EvalContext) -> Result<()> {
+    let txn = ctx.ledger().current_transaction()?;
+    for name in FieldGroup::Txn.names() {
+        let Some(field) = FieldGroup::Txn
+            .value(name)
+            .and_then(|id| parse_txn_field(id).ok())
+        else {
+            continue;
+        };
+        let elements: Vec<String> = (0..)
+            .map_while(|i| txn.array_field(field, i).ok())
+            .map(|value| value.to_string())
+            .collect();
+        if !elements.is_empty() {
+            println!("  {name} = [{}]", elements.join(", "));
+            continue;
+        }
+        match txn.field(field) {
+            TealValue::Uint(0) => {}
+            TealValue::Bytes(bytes) if bytes.is_empty() => {}
+            value => println!("  {name} = {value}"),
+        }
+    }
+    Ok(())
+}
+
+/// Print every global field the ledger provides
+fn show_globals(ctx: &EvalContext) {
+    for name in FieldGroup::Global.names() {
+        let value = FieldGroup::Global
+            .value(name)
+            .and_then(|id| parse_global_field(id).ok())
+            .and_then(|field| ctx.ledger().get_global_field(field).ok());
+        if let Some(value) = value {
+            println!("  {name} = {value}");
+        }
+    }
+}
+
+/// Split `Field=value`
+fn split_assignment(assignment: &str) -> Result<(&str, &str)> {
+    assignment
+        .split_once('=')
+        .map(|(name, value)| (name.trim(), value.trim()))
+        .ok_or_else(|| anyhow!("Expected Field=value, got '{assignment}'"))
+}
+
+/// Set a field of the current transaction, appending to array fields
+fn set_txn_field(ledger: &mut MockLedger, assignment: &str) -> Result<()> {
+    let (name, text) = split_assignment(assignment)?;
+    set_field(ledger, LedgerField::txn(name)?, text)
+}
+
+/// Set a global field the mock ledger keeps
+fn set_global_field(ledger: &mut MockLedger, assignment: &str) -> Result<()> {
+    let (name, text) = split_assignment(assignment)?;
+    set_field(ledger, LedgerField::global(name)?, text)
+}
 
+/// Set `field` to the constant `text`
+fn set_field(ledger: &mut MockLedger, field: LedgerField, text: &str) -> Result<()> {
+    let value = Assembler::new().parse_constant(text, field.is_bytes(ledger)?)?;
+    field.set(ledger, &value)?;
+    println!("{} = {value}", field.name);
     Ok(())
 }
@@ -1,7 +1,9 @@
 //! Validate command implementation
 
 use crate::assembler::Assembler;
+use crate::cli::commands::assembly_failure;
 use crate::cli::{ExecutionMode, GlobalOptions, ValidateCommand};
+use crate::opcodes::opcode_by_name;
 use anyhow::{Context, Result};
 use std::fs;
 use std::path::Path;
@@ -105,10 +107,10 @@ fn validate_file(
         .with_context(|| format!("Failed to read file: {file_path:?}"))?;
 
     // Parse and validate syntax
-    let mut assembler = Assembler::new();
+    let mut assembler = Assembler::new().with_source_name(file_path.display().to_string());
     let _bytecode = assembler
         .assemble(&source)
-        .map_err(|e| anyhow::anyhow!("Assembly failed: {}", e))?;
+        .map_err(|e| assembly_failure(e, &source, global))?;
 
     // Additional validation checks
     let mut warnings = 0;
@@ -148,6 +150,22 @@ fn check_version_compatibility(source: &str, target_version: u8) -> Result<usize
         eprintln!("Warning: No version pragma found, assuming latest version");
     }
 
+    for (line_num, line) in source.lines().enumerate() {
+        let Some(mnemonic) = line.split_whitespace().next() else {
+            continue;
+        };
+        if let Some(spec) = opcode_by_name(mnemonic) {
+            if spec.min_version > target_version {
+                warnings += 1;
+                eprintln!(
+                    "Warning: Line {}: {mnemonic} opcode was introduced in v{}",
+                    line_num + 1,
+                    spec.min_version
+                );
+            }
+        }
+    }
+
     Ok(warnings)
 }
 
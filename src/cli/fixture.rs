@@ -0,0 +1,236 @@
+//! Ledger fixtures: JSON describing the mock ledger a program runs against
+//!
+//! ```json
+//! {
+//!   "txn": { "Fee": 2000, "ApplicationArgs": ["hello", "0x01"] },
+//!   "global": { "Round": 1234, "CurrentApplicationID": 5 },
+//!   "args": ["base64 AQI="],
+//!   "balances": { "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAY5HFKQ": 5000000 },
+//!   "global_state": { "5": { "counter": 3, "owner": "\"alice\"" } },
+//!   "boxes": { "5": { "scores": "0x0001" } }
+//! }
+//! ```
+//!
+//! Every section is optional. Fields are named as in TEAL, and array fields
+//! take a list that is appended to the current transaction's. Strings holding
+//! bytes are read as the argument of `byte` or `addr`, so `0x0102`, `"text"`,
+//! `base64 AQI=` and addresses all work, and anything else is taken as its
+//! UTF-8 text.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow, bail};
+use serde::Deserialize;
+
+use crate::assembler::Assembler;
+use crate::opcodes::FieldGroup;
+use crate::opcodes::inner_transaction::InnerTransactionField;
+use crate::opcodes::transaction::{parse_global_field, parse_txn_field};
+use crate::state::{LedgerAccess, MockLedger};
+use crate::types::{GlobalField, StackValue, TealValue, TxnField};
+
+/// Mock ledger contents read from JSON
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LedgerFixture {
+    /// Fields of the current transaction
+    pub txn: BTreeMap<String, FixtureValue>,
+    /// Global fields
+    pub global: BTreeMap<String, FixtureValue>,
+    /// Logic signature arguments, read by `arg`
+    pub args: Vec<String>,
+    /// Balance of each address
+    pub balances: BTreeMap<String, u64>,
+    /// Global state of each application, by key
+    pub global_state: BTreeMap<u64, BTreeMap<String, FixtureValue>>,
+    /// Box contents of each application, by box name
+    pub boxes: BTreeMap<u64, BTreeMap<String, String>>,
+}
+
+/// A value in a fixture
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum FixtureValue {
+    Uint(u64),
+    Text(String),
+    List(Vec<FixtureValue>),
+}
+
+impl FixtureValue {
+    /// Convert to an integer, or to bytes if `bytes`
+    fn to_stack_value(&self, bytes: bool) -> Result<StackValue> {
+        match (self, bytes) {
+            (Self::Uint(value), false) => Ok(StackValue::Uint(*value)),
+            (Self::Text(text), false) => Ok(Assembler::new().parse_constant(text, false)?),
+            (Self::Text(text), true) => Ok(StackValue::Bytes(parse_bytes(text))),
+            (Self::Uint(value), true) => bail!("expected bytes, got {value}"),
+            (Self::List(_), _) => bail!("unexpected list"),
+        }
+    }
+
+    /// Convert to an integer, or bytes if text
+    fn to_teal_value(&self) -> Result<TealValue> {
+        match self {
+            Self::Text(text) => Ok(TealValue::Bytes(parse_bytes(text))),
+            value => Ok(TealValue::from_stack_value(&value.to_stack_value(false)?)),
+        }
+    }
+}
+
+/// Bytes written as the argument of `byte` or `addr`, or as plain text
+fn parse_bytes(text: &str) -> Vec<u8> {
+    match Assembler::new().parse_constant(text, true) {
+        Ok(StackValue::Bytes(bytes)) => bytes,
+        _ => text.as_bytes().to_vec(),
+    }
+}
+
+impl LedgerFixture {
+    /// Read a fixture from a JSON file
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read ledger file: {path:?}"))?;
+        Self::from_json(&json).with_context(|| format!("Invalid ledger file: {path:?}"))
+    }
+
+    /// Parse a fixture from JSON
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Fill in `ledger`
+    pub fn apply(&self, ledger: &mut MockLedger) -> Result<()> {
+        for (name, value) in &self.txn {
+            let field = LedgerField::txn(name)?;
+            let values = match value {
+                FixtureValue::List(values) => values.as_slice(),
+                value => std::slice::from_ref(value),
+            };
+            for value in values {
+                let value = value
+                    .to_stack_value(field.is_bytes(ledger)?)
+                    .with_context(|| format!("Invalid value for {name}"))?;
+                field.set(ledger, &value)?;
+            }
+        }
+        for (name, value) in &self.global {
+            let field = LedgerField::global(name)?;
+            let value = value
+                .to_stack_value(field.is_bytes(ledger)?)
+                .with_context(|| format!("Invalid value for {name}"))?;
+            field.set(ledger, &value)?;
+        }
+        if !self.args.is_empty() {
+            ledger.set_program_args(self.args.iter().map(|arg| parse_bytes(arg)).collect());
+        }
+        for (address, balance) in &self.balances {
+            ledger.set_balance(parse_bytes(address), *balance);
+        }
+        for (app_id, state) in &self.global_state {
+            for (key, value) in state {
+                let value = value
+                    .to_teal_value()
+                    .with_context(|| format!("Invalid global state {key} of app {app_id}"))?;
+                ledger.set_global_state(*app_id, parse_bytes(key), value);
+            }
+        }
+        for (app_id, boxes) in &self.boxes {
+            for (name, contents) in boxes {
+                ledger.set_box(*app_id, parse_bytes(name), parse_bytes(contents));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A transaction or global field the mock ledger can set
+pub(crate) struct LedgerField {
+    pub name: String,
+    kind: FieldKind,
+}
+
+enum FieldKind {
+    Txn(u8, TxnField),
+    Global(GlobalField),
+}
+
+impl LedgerField {
+    /// The transaction field called `name`
+    pub fn txn(name: &str) -> Result<Self> {
+        let id = FieldGroup::Txn
+            .value(name)
+            .ok_or_else(|| anyhow!("Unknown transaction field '{name}'"))?;
+        Ok(Self {
+            name: name.to_string(),
+            kind: FieldKind::Txn(id, parse_txn_field(id)?),
+        })
+    }
+
+    /// The global field called `name`
+    pub fn global(name: &str) -> Result<Self> {
+        let id = FieldGroup::Global
+            .value(name)
+            .ok_or_else(|| anyhow!("Unknown global field '{name}'"))?;
+        Ok(Self {
+            name: name.to_string(),
+            kind: FieldKind::Global(parse_global_field(id)?),
+        })
+    }
+
+    /// Whether the field holds bytes
+    pub fn is_bytes(&self, ledger: &MockLedger) -> Result<bool> {
+        let value = match &self.kind {
+            FieldKind::Txn(_, field) => ledger.current_transaction()?.field(*field),
+            FieldKind::Global(field) => ledger.get_global_field(*field)?,
+        };
+        Ok(matches!(value, TealValue::Bytes(_)))
+    }
+
+    /// Set the field, appending to array fields
+    pub fn set(&self, ledger: &mut MockLedger, value: &StackValue) -> Result<()> {
+        let name = &self.name;
+        match self.kind {
+            FieldKind::Txn(id, field) => {
+                let txn = ledger
+                    .current_transaction_mut()
+                    .ok_or_else(|| anyhow!("There is no current transaction"))?;
+                match InnerTransactionField::from_id(id) {
+                    Ok(field) => field.apply(txn, value)?,
+                    Err(_) => match field {
+                        TxnField::FirstValid => txn.first_valid = value.as_uint()?,
+                        TxnField::FirstValidTime => txn.first_valid_time = value.as_uint()?,
+                        TxnField::LastValid => txn.last_valid = value.as_uint()?,
+                        TxnField::Lease => txn.lease = value.as_bytes()?.to_vec(),
+                        _ => bail!("{name} cannot be set"),
+                    },
+                }
+            }
+            FieldKind::Global(field) => match field {
+                GlobalField::Round => ledger.set_current_round(value.as_uint()?),
+                GlobalField::LatestTimestamp => ledger.set_latest_timestamp(value.as_uint()?),
+                GlobalField::CurrentApplicationID => {
+                    ledger.set_current_application_id(value.as_uint()?)
+                }
+                GlobalField::OpcodeBudget => ledger.set_opcode_budget(value.as_uint()?),
+                GlobalField::CallerApplicationID => {
+                    ledger.set_caller_application_id(Some(value.as_uint()?))
+                }
+                GlobalField::CreatorAddress => {
+                    ledger.set_creator_address(value.as_bytes()?.to_vec())
+                }
+                GlobalField::CurrentApplicationAddress => {
+                    ledger.set_current_application_address(value.as_bytes()?.to_vec())
+                }
+                GlobalField::CallerApplicationAddress => {
+                    ledger.set_caller_application_address(Some(value.as_bytes()?.to_vec()))
+                }
+                GlobalField::GroupID => ledger.set_group_id(value.as_bytes()?.to_vec()),
+                GlobalField::GenesisHash => ledger.set_genesis_hash(value.as_bytes()?.to_vec()),
+                _ => bail!("{name} cannot be set"),
+            },
+        }
+        Ok(())
+    }
+}
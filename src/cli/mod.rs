@@ -4,7 +4,9 @@ use clap::{Command, Parser, Subcommand};
 use std::path::PathBuf;
 
 mod commands;
+mod fixture;
 pub use commands::*;
+pub use fixture::{FixtureValue, LedgerFixture};
 
 /// Rust AVM - Algorand Virtual Machine implementation in Rust
 #[derive(Parser)]
@@ -38,6 +40,7 @@ pub struct GlobalOptions {
     /// Output format (text, json)
     #[arg(
         long = "output-style",
+        alias = "format",
         value_enum,
         default_value = "text",
         global = true
@@ -86,9 +89,19 @@ pub enum Commands {
     #[command(aliases = ["asm", "compile"])]
     Assemble(AssembleCommand),
 
+    /// Disassemble bytecode to TEAL source
+    #[command(alias = "disasm")]
+    Disassemble(DisassembleCommand),
+
     /// Validate TEAL programs
     #[command(alias = "check")]
     Validate(ValidateCommand),
+
+    /// Start an interactive TEAL session
+    Repl(ReplCommand),
+
+    /// Serve the Debug Adapter Protocol over stdio for editor debugging
+    Dap(DapCommand),
 }
 
 /// Execute command for running TEAL programs
@@ -118,11 +131,20 @@ pub struct ExecuteCommand {
     #[arg(short = 's', long = "step")]
     pub step: bool,
 
-    /// Mock ledger data from JSON file
+    /// Breakpoint for --step, as `LOCATION`, `LOCATION if CONDITION` or
+    /// `if CONDITION`; LOCATION is a pc, a label or `line N`
+    #[arg(long = "break", value_name = "BREAKPOINT", requires = "step")]
+    pub breakpoints: Vec<String>,
+
+    /// Source map of bytecode input, to report errors by source line
+    #[arg(long = "source-map", value_name = "FILE")]
+    pub source_map: Option<PathBuf>,
+
+    /// Mock ledger fixture from JSON file
     #[arg(short = 'l', long = "ledger")]
     pub ledger: Option<PathBuf>,
 
-    /// Transaction data from JSON file
+    /// Transaction fields from JSON file, as in a ledger fixture's `txn`
     #[arg(short = 'x', long = "txn")]
     pub transaction: Option<PathBuf>,
 
@@ -130,6 +152,24 @@ pub struct ExecuteCommand {
     #[arg(short = 'a', long = "arg")]
     pub args: Vec<String>,
 
+    /// Record each executed step and write the trace in this format
+    #[arg(
+        long = "step-trace",
+        value_enum,
+        value_name = "FORMAT",
+        conflicts_with = "step"
+    )]
+    pub step_trace: Option<StepTraceFormat>,
+
+    /// Write the step trace to this file instead of standard output, or
+    /// standard error with `--format json`
+    #[arg(long = "trace-file", value_name = "FILE", requires = "step_trace")]
+    pub trace_file: Option<PathBuf>,
+
+    /// Record at most this many steps in the step trace
+    #[arg(long = "trace-limit", value_name = "STEPS", requires = "step_trace")]
+    pub trace_limit: Option<usize>,
+
     /// Tracing level (trace, debug, info, warn, error) - enables tracing when specified
     #[cfg(feature = "tracing")]
     #[arg(long = "trace-level", value_enum)]
@@ -161,13 +201,17 @@ pub struct AssembleCommand {
     #[arg(short = 'f', long = "output-format", value_enum, default_value = "hex")]
     pub output_format: BytecodeFormat,
 
-    /// Optimize the bytecode
+    /// Choose constant blocks for the smallest bytecode
     #[arg(long = "optimize")]
     pub optimize: bool,
 
     /// Show assembly statistics
     #[arg(long = "stats")]
     pub stats: bool,
+
+    /// Write a source map relating pcs to source lines
+    #[arg(long = "source-map", value_name = "FILE")]
+    pub source_map: Option<PathBuf>,
 }
 
 /// Disassemble command for converting bytecode to TEAL
@@ -278,6 +322,12 @@ pub struct ReplCommand {
     pub load: Option<PathBuf>,
 }
 
+/// DAP command for debugging from an editor
+///
+/// The program and its ledger are given by the client's `launch` request.
+#[derive(Parser)]
+pub struct DapCommand {}
+
 /// Input type enumeration
 #[derive(clap::ValueEnum, Clone, Debug)]
 pub enum InputType {
@@ -300,6 +350,17 @@ pub enum ExecutionMode {
     Application,
 }
 
+/// Step trace formats
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum StepTraceFormat {
+    /// One line per step, as `PC:0004 + (cost: 1)`
+    Text,
+    /// A single JSON document
+    Json,
+    /// One JSON record per line
+    Ndjson,
+}
+
 /// Bytecode format enumeration
 #[derive(clap::ValueEnum, Clone, Debug)]
 pub enum BytecodeFormat {
@@ -337,7 +398,10 @@ pub fn run() -> anyhow::Result<()> {
     match cli.command {
         Commands::Execute(cmd) => commands::execute::handle(cmd, &cli.global),
         Commands::Assemble(cmd) => commands::assemble::handle(cmd, &cli.global),
+        Commands::Disassemble(cmd) => commands::disassemble::handle(cmd, &cli.global),
         Commands::Validate(cmd) => commands::validate::handle(cmd, &cli.global),
+        Commands::Repl(cmd) => commands::repl::handle(cmd, &cli.global),
+        Commands::Dap(cmd) => commands::dap::handle(cmd, &cli.global),
     }
 }
 
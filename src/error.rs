@@ -2,6 +2,8 @@
 
 use thiserror::Error;
 
+use crate::assembler::diagnostics::Diagnostic;
+
 /// Result type for AVM operations
 pub type AvmResult<T> = Result<T, AvmError>;
 
@@ -83,6 +85,9 @@ pub enum AvmError {
     #[error("Assembly error: {0}")]
     AssemblyError(String),
 
+    #[error("Assembly failed:\n{}", join_diagnostics(.0))]
+    AssemblyDiagnostics(Vec<Diagnostic>),
+
     #[error("Parse error: {0}")]
     ParseError(String),
 }
@@ -125,3 +130,9 @@ impl AvmError {
         Self::ParseError(msg.into())
     }
 }
+
+/// One diagnostic per line
+fn join_diagnostics(diagnostics: &[Diagnostic]) -> String {
+    let lines: Vec<String> = diagnostics.iter().map(ToString::to_string).collect();
+    lines.join("\n")
+}
@@ -19,4 +19,7 @@ pub mod vm;
 // Re-export main types
 pub use error::{AvmError, AvmResult};
 pub use types::{StackValue, TealValue, TealVersion};
-pub use vm::{EvalContext, ExecutionConfig, VirtualMachine, VirtualMachineBuilder};
+pub use vm::{
+    EvalContext, ExecutionConfig, ExecutionOutcome, ExecutionResult, VirtualMachine,
+    VirtualMachineBuilder,
+};
@@ -1,232 +1,224 @@
 //! Box storage opcodes for persistent key-value storage in smart contracts
+//!
+//! Boxes are scoped to the currently executing application and are stored
+//! through [`LedgerAccess`](crate::state::LedgerAccess).
 
 use crate::error::{AvmError, AvmResult};
+use crate::state::AppId;
 use crate::types::StackValue;
 use crate::vm::EvalContext;
 
-/// Create a new box with the given name and size
-pub fn op_box_create(ctx: &mut EvalContext) -> AvmResult<()> {
-    ctx.advance_pc(1)?;
-    let size = ctx.pop()?;
-    let name = ctx.pop()?;
+/// Maximum length of a box name in bytes
+pub const MAX_BOX_NAME_LEN: usize = 64;
 
-    let name_bytes = name.as_bytes()?;
-    let size_value = size.as_uint()?;
+/// Maximum size of a box in bytes
+pub const MAX_BOX_SIZE: u64 = 32768;
 
-    // Box names are limited to 64 bytes
-    if name_bytes.len() > 64 {
-        return Err(AvmError::invalid_program("Box name exceeds 64 bytes"));
+/// Check that a box name is between 1 and 64 bytes
+fn validate_box_name(name: &[u8]) -> AvmResult<()> {
+    if name.is_empty() {
+        return Err(AvmError::invalid_program(
+            "Box names may not be zero length",
+        ));
     }
+    if name.len() > MAX_BOX_NAME_LEN {
+        return Err(AvmError::invalid_program(format!(
+            "Box name exceeds {MAX_BOX_NAME_LEN} bytes"
+        )));
+    }
+    Ok(())
+}
 
-    // Box size is limited to 32,768 bytes
-    if size_value > 32768 {
-        return Err(AvmError::invalid_program("Box size exceeds 32,768 bytes"));
+/// Check that a box size does not exceed the protocol limit
+fn validate_box_size(size: u64) -> AvmResult<()> {
+    if size > MAX_BOX_SIZE {
+        return Err(AvmError::invalid_program(format!(
+            "Box size {size} exceeds {MAX_BOX_SIZE} bytes"
+        )));
     }
+    Ok(())
+}
+
+/// Pop a box name from the stack and resolve the application it belongs to
+fn pop_box_name(ctx: &mut EvalContext) -> AvmResult<(AppId, Vec<u8>)> {
+    let name = ctx.pop()?.as_bytes()?.to_vec();
+    validate_box_name(&name)?;
+    let app_id = ctx.ledger().current_application_id()?;
+    Ok((app_id, name))
+}
+
+/// Get the length of an existing box, failing if it does not exist
+fn existing_box_len(ctx: &EvalContext, app_id: AppId, name: &[u8]) -> AvmResult<u64> {
+    ctx.ledger()
+        .box_len(app_id, name)?
+        .ok_or_else(|| AvmError::state_error(format!("no such box {}", hex::encode(name))))
+}
 
-    // In a real implementation, this would:
-    // 1. Check if box already exists
-    // 2. Create the box in persistent storage
-    // 3. Return 1 if created, 0 if already exists
+/// Create a new box with the given name and size
+///
+/// Pushes 1 if the box was created and 0 if a box of the same size already
+/// exists. Fails if an existing box has a different size.
+pub fn op_box_create(ctx: &mut EvalContext) -> AvmResult<()> {
+    ctx.advance_pc(1)?;
+    let size = ctx.pop()?.as_uint()?;
+    let (app_id, name) = pop_box_name(ctx)?;
+    validate_box_size(size)?;
+
+    if let Some(existing) = ctx.ledger().box_len(app_id, &name)? {
+        if existing != size {
+            return Err(AvmError::state_error(format!(
+                "box size mismatch: {existing} != {size}"
+            )));
+        }
+        ctx.push(StackValue::Uint(0))?;
+        return Ok(());
+    }
 
-    // For now, always return success (1)
-    ctx.push(StackValue::Uint(1))?;
+    let created = ctx.ledger_mut().box_create(app_id, &name, size)?;
+    ctx.push(StackValue::Uint(created as u64))?;
     Ok(())
 }
 
 /// Extract bytes from a box
 pub fn op_box_extract(ctx: &mut EvalContext) -> AvmResult<()> {
     ctx.advance_pc(1)?;
-    let length = ctx.pop()?;
-    let start = ctx.pop()?;
-    let name = ctx.pop()?;
-
-    let name_bytes = name.as_bytes()?;
-    let _start_idx = start.as_uint()? as usize;
-    let len = length.as_uint()? as usize;
-
-    // Box names are limited to 64 bytes
-    if name_bytes.len() > 64 {
-        return Err(AvmError::invalid_program("Box name exceeds 64 bytes"));
-    }
-
-    // In a real implementation, this would:
-    // 1. Look up the box by name
-    // 2. Extract the specified range of bytes
-    // 3. Return the extracted bytes
+    let length = ctx.pop()?.as_uint()?;
+    let start = ctx.pop()?.as_uint()?;
+    let (app_id, name) = pop_box_name(ctx)?;
 
-    // For now, return empty bytes as placeholder
-    ctx.push(StackValue::Bytes(vec![0u8; len]))?;
+    let contents = ctx.ledger().box_read(app_id, &name, start, length)?;
+    ctx.push(StackValue::Bytes(contents))?;
     Ok(())
 }
 
-/// Replace bytes in a box
+/// Replace bytes in a box starting at the given offset
 pub fn op_box_replace(ctx: &mut EvalContext) -> AvmResult<()> {
     ctx.advance_pc(1)?;
-    let replacement = ctx.pop()?;
-    let start = ctx.pop()?;
-    let name = ctx.pop()?;
-
-    let name_bytes = name.as_bytes()?;
-    let _start_idx = start.as_uint()? as usize;
-    let _repl_bytes = replacement.as_bytes()?;
-
-    // Box names are limited to 64 bytes
-    if name_bytes.len() > 64 {
-        return Err(AvmError::invalid_program("Box name exceeds 64 bytes"));
-    }
-
-    // In a real implementation, this would:
-    // 1. Look up the box by name
-    // 2. Replace the bytes at the specified offset
-    // 3. Validate that the replacement fits within the box
+    let replacement = ctx.pop()?.as_bytes()?.to_vec();
+    let start = ctx.pop()?.as_uint()?;
+    let (app_id, name) = pop_box_name(ctx)?;
 
+    ctx.ledger_mut()
+        .box_write(app_id, &name, start, &replacement)?;
     Ok(())
 }
 
-/// Delete a box
+/// Delete a box, pushing 1 if it existed and 0 otherwise
 pub fn op_box_del(ctx: &mut EvalContext) -> AvmResult<()> {
     ctx.advance_pc(1)?;
-    let name = ctx.pop()?;
+    let (app_id, name) = pop_box_name(ctx)?;
 
-    let name_bytes = name.as_bytes()?;
-
-    // Box names are limited to 64 bytes
-    if name_bytes.len() > 64 {
-        return Err(AvmError::invalid_program("Box name exceeds 64 bytes"));
-    }
-
-    // In a real implementation, this would:
-    // 1. Look up the box by name
-    // 2. Delete the box from storage
-    // 3. Return 1 if deleted, 0 if not found
-
-    // For now, always return success (1)
-    ctx.push(StackValue::Uint(1))?;
+    let deleted = ctx.ledger_mut().box_delete(app_id, &name)?;
+    ctx.push(StackValue::Uint(deleted as u64))?;
     Ok(())
 }
 
-/// Get the length of a box
+/// Get the length of a box and whether it exists
 pub fn op_box_len(ctx: &mut EvalContext) -> AvmResult<()> {
     ctx.advance_pc(1)?;
-    let name = ctx.pop()?;
-
-    let name_bytes = name.as_bytes()?;
-
-    // Box names are limited to 64 bytes
-    if name_bytes.len() > 64 {
-        return Err(AvmError::invalid_program("Box name exceeds 64 bytes"));
+    let (app_id, name) = pop_box_name(ctx)?;
+
+    match ctx.ledger().box_len(app_id, &name)? {
+        Some(len) => {
+            ctx.push(StackValue::Uint(len))?;
+            ctx.push(StackValue::Uint(1))?;
+        }
+        None => {
+            ctx.push(StackValue::Uint(0))?;
+            ctx.push(StackValue::Uint(0))?;
+        }
     }
-
-    // In a real implementation, this would:
-    // 1. Look up the box by name
-    // 2. Return the box length and existence flag
-
-    // For now, return length=0 and exists=1
-    ctx.push(StackValue::Uint(0))?; // length
-    ctx.push(StackValue::Uint(1))?; // exists
     Ok(())
 }
 
-/// Get the entire contents of a box
+/// Get the entire contents of a box and whether it exists
 pub fn op_box_get(ctx: &mut EvalContext) -> AvmResult<()> {
     ctx.advance_pc(1)?;
-    let name = ctx.pop()?;
-
-    let name_bytes = name.as_bytes()?;
-
-    // Box names are limited to 64 bytes
-    if name_bytes.len() > 64 {
-        return Err(AvmError::invalid_program("Box name exceeds 64 bytes"));
+    let (app_id, name) = pop_box_name(ctx)?;
+
+    match ctx.ledger().box_len(app_id, &name)? {
+        Some(len) => {
+            let contents = ctx.ledger().box_read(app_id, &name, 0, len)?;
+            ctx.push(StackValue::Bytes(contents))?;
+            ctx.push(StackValue::Uint(1))?;
+        }
+        None => {
+            ctx.push(StackValue::Bytes(Vec::new()))?;
+            ctx.push(StackValue::Uint(0))?;
+        }
     }
-
-    // In a real implementation, this would:
-    // 1. Look up the box by name
-    // 2. Return the entire box contents and existence flag
-
-    // For now, return empty contents and exists=1
-    ctx.push(StackValue::Bytes(Vec::new()))?; // contents
-    ctx.push(StackValue::Uint(1))?; // exists
     Ok(())
 }
 
-/// Put bytes into a box (overwrite entire contents)
+/// Put bytes into a box, creating it if it does not exist
+///
+/// Fails if the box exists with a size different from the new contents.
 pub fn op_box_put(ctx: &mut EvalContext) -> AvmResult<()> {
     ctx.advance_pc(1)?;
-    let value = ctx.pop()?;
-    let name = ctx.pop()?;
-
-    let name_bytes = name.as_bytes()?;
-    let value_bytes = value.as_bytes()?;
-
-    // Box names are limited to 64 bytes
-    if name_bytes.len() > 64 {
-        return Err(AvmError::invalid_program("Box name exceeds 64 bytes"));
-    }
-
-    // Box contents are limited to 32,768 bytes
-    if value_bytes.len() > 32768 {
-        return Err(AvmError::invalid_program("Box value exceeds 32,768 bytes"));
+    let value = ctx.pop()?.as_bytes()?.to_vec();
+    let (app_id, name) = pop_box_name(ctx)?;
+    let size = value.len() as u64;
+
+    match ctx.ledger().box_len(app_id, &name)? {
+        Some(existing) if existing != size => {
+            return Err(AvmError::state_error(format!(
+                "box_put wrong size: {existing} != {size}"
+            )));
+        }
+        Some(_) => {}
+        None => {
+            validate_box_size(size)?;
+            ctx.ledger_mut().box_create(app_id, &name, size)?;
+        }
     }
 
-    // In a real implementation, this would:
-    // 1. Look up the box by name (must exist)
-    // 2. Replace the entire contents with the new value
-    // 3. Validate that the new value fits in the box size
-
+    ctx.ledger_mut().box_write(app_id, &name, 0, &value)?;
     Ok(())
 }
 
-/// Splice bytes into a box (insert/replace with size change)
+/// Splice bytes into a box
+///
+/// Removes `length` bytes at `start`, inserts the replacement, then truncates
+/// or zero-pads the result so the box keeps its original size.
 pub fn op_box_splice(ctx: &mut EvalContext) -> AvmResult<()> {
     ctx.advance_pc(1)?;
-    let replacement = ctx.pop()?;
-    let length = ctx.pop()?;
-    let start = ctx.pop()?;
-    let name = ctx.pop()?;
-
-    let name_bytes = name.as_bytes()?;
-    let _start_idx = start.as_uint()? as usize;
-    let _len = length.as_uint()? as usize;
-    let _repl_bytes = replacement.as_bytes()?;
-
-    // Box names are limited to 64 bytes
-    if name_bytes.len() > 64 {
-        return Err(AvmError::invalid_program("Box name exceeds 64 bytes"));
+    let replacement = ctx.pop()?.as_bytes()?.to_vec();
+    let length = ctx.pop()?.as_uint()?;
+    let start = ctx.pop()?.as_uint()?;
+    let (app_id, name) = pop_box_name(ctx)?;
+
+    let size = existing_box_len(ctx, app_id, &name)?;
+    if start > size {
+        return Err(AvmError::state_error(format!(
+            "box_splice start {start} beyond box length {size}"
+        )));
+    }
+    if start.checked_add(length).is_none_or(|end| end > size) {
+        return Err(AvmError::state_error(format!(
+            "box_splice range {start}+{length} beyond box length {size}"
+        )));
     }
 
-    // In a real implementation, this would:
-    // 1. Look up the box by name
-    // 2. Remove 'length' bytes starting at 'start'
-    // 3. Insert the replacement bytes at 'start'
-    // 4. Validate the resulting box size doesn't exceed limits
+    let contents = ctx.ledger().box_read(app_id, &name, 0, size)?;
+    let (start, end) = (start as usize, (start + length) as usize);
+    let mut spliced = Vec::with_capacity(contents.len() + replacement.len());
+    spliced.extend_from_slice(&contents[..start]);
+    spliced.extend_from_slice(&replacement);
+    spliced.extend_from_slice(&contents[end..]);
+    spliced.resize(size as usize, 0);
 
+    ctx.ledger_mut().box_write(app_id, &name, 0, &spliced)?;
     Ok(())
 }
 
-/// Resize a box
+/// Resize a box, truncating or zero-padding its contents
 pub fn op_box_resize(ctx: &mut EvalContext) -> AvmResult<()> {
     ctx.advance_pc(1)?;
-    let new_size = ctx.pop()?;
-    let name = ctx.pop()?;
-
-    let name_bytes = name.as_bytes()?;
-    let size_value = new_size.as_uint()?;
-
-    // Box names are limited to 64 bytes
-    if name_bytes.len() > 64 {
-        return Err(AvmError::invalid_program("Box name exceeds 64 bytes"));
-    }
-
-    // Box size is limited to 32,768 bytes
-    if size_value > 32768 {
-        return Err(AvmError::invalid_program("Box size exceeds 32,768 bytes"));
-    }
-
-    // In a real implementation, this would:
-    // 1. Look up the box by name
-    // 2. Resize the box to the new size
-    // 3. If shrinking, truncate contents
-    // 4. If growing, pad with zeros
+    let size = ctx.pop()?.as_uint()?;
+    let (app_id, name) = pop_box_name(ctx)?;
+    validate_box_size(size)?;
 
+    existing_box_len(ctx, app_id, &name)?;
+    ctx.ledger_mut().box_resize(app_id, &name, size)?;
     Ok(())
 }
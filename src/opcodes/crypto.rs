@@ -164,115 +164,154 @@ pub fn op_ed25519verify_bare(ctx: &mut EvalContext) -> AvmResult<()> {
     Ok(())
 }
 
-/// ECDSA signature verification (secp256k1)
+/// Curve selected by the immediate of the `ecdsa_*` opcodes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EcdsaCurve {
+    Secp256k1,
+    Secp256r1,
+}
+
+/// Read the curve immediate of an `ecdsa_*` opcode, leaving the pc after it
+fn read_ecdsa_curve(ctx: &mut EvalContext) -> AvmResult<EcdsaCurve> {
+    ctx.advance_pc(1)?;
+    let curve = match ctx.read_bytes(1)?[0] {
+        0 => EcdsaCurve::Secp256k1,
+        1 => EcdsaCurve::Secp256r1,
+        other => {
+            return Err(AvmError::invalid_program(format!(
+                "Invalid ECDSA curve: {other}"
+            )));
+        }
+    };
+    ctx.advance_pc(1)?;
+    Ok(curve)
+}
+
+/// Split an uncompressed SEC1 point into its X and Y coordinates
+fn push_point_coordinates(ctx: &mut EvalContext, uncompressed: &[u8]) -> AvmResult<()> {
+    ctx.push(StackValue::Bytes(uncompressed[1..33].to_vec()))?;
+    ctx.push(StackValue::Bytes(uncompressed[33..65].to_vec()))?;
+    Ok(())
+}
+
+/// ECDSA verification of signature (B, C) of data A by public key (D, E)
 pub fn op_ecdsa_verify(ctx: &mut EvalContext) -> AvmResult<()> {
-    use secp256k1::{Message, PublicKey, Secp256k1, ecdsa::Signature};
+    let curve = read_ecdsa_curve(ctx)?;
 
-    let public_key = ctx.pop()?;
-    let signature = ctx.pop()?;
+    let y = ctx.pop()?;
+    let x = ctx.pop()?;
+    let s = ctx.pop()?;
+    let r = ctx.pop()?;
     let data = ctx.pop()?;
-    let recovery_id = ctx.pop()?;
 
-    let pub_key_bytes = public_key.as_bytes()?;
-    let sig_bytes = signature.as_bytes()?;
     let data_bytes = data.as_bytes()?;
-    let _recovery_id = recovery_id.as_uint()?; // Currently unused in verification
-
-    // Verify the signature
-    let verification_result = match (
-        PublicKey::from_slice(pub_key_bytes),
-        Signature::from_compact(sig_bytes),
-        Message::from_digest_slice(data_bytes),
-    ) {
-        (Ok(pubkey), Ok(sig), Ok(msg)) => {
-            let secp = Secp256k1::verification_only();
-            match secp.verify_ecdsa(&msg, &sig, &pubkey) {
-                Ok(()) => 1, // Verification successful
-                Err(_) => 0, // Verification failed
+    let public_key = [&[0x04], x.as_bytes()?, y.as_bytes()?].concat();
+    let signature = [r.as_bytes()?, s.as_bytes()?].concat();
+
+    let verified = match curve {
+        EcdsaCurve::Secp256k1 => {
+            use secp256k1::{Message, PublicKey, SECP256K1, ecdsa::Signature};
+
+            match (
+                PublicKey::from_slice(&public_key),
+                Signature::from_compact(&signature),
+                Message::from_digest_slice(data_bytes),
+            ) {
+                (Ok(key), Ok(sig), Ok(msg)) => SECP256K1.verify_ecdsa(&msg, &sig, &key).is_ok(),
+                _ => false,
+            }
+        }
+        EcdsaCurve::Secp256r1 => {
+            use p256::ecdsa::{Signature, VerifyingKey, signature::hazmat::PrehashVerifier};
+
+            match (
+                VerifyingKey::from_sec1_bytes(&public_key),
+                Signature::from_slice(&signature),
+            ) {
+                (Ok(key), Ok(sig)) => key.verify_prehash(data_bytes, &sig).is_ok(),
+                _ => false,
             }
         }
-        _ => 0, // Invalid input format
     };
 
-    ctx.push(StackValue::Uint(verification_result))?;
-    ctx.advance_pc(1)?;
+    ctx.push(StackValue::Uint(verified as u64))?;
     Ok(())
 }
 
-/// ECDSA public key decompression
+/// Decompress a 33 byte public key into its X and Y coordinates
 pub fn op_ecdsa_pk_decompress(ctx: &mut EvalContext) -> AvmResult<()> {
-    use secp256k1::{PublicKey, Secp256k1};
-
-    let compressed_key = ctx.pop()?;
-    let key_bytes = compressed_key.as_bytes()?;
-
-    // Decompress the public key
-    let result = match PublicKey::from_slice(key_bytes) {
-        Ok(pubkey) => {
-            let _secp = Secp256k1::verification_only();
-            // Convert to uncompressed format (64 bytes without prefix)
-            let serialized = pubkey.serialize_uncompressed();
-            // Remove the 0x04 prefix byte to get just the X,Y coordinates
-            serialized[1..].to_vec()
-        }
-        Err(_) => {
-            // Return 64 bytes of zeros for invalid key (test compatibility)
-            vec![0u8; 64]
+    let curve = read_ecdsa_curve(ctx)?;
+
+    let compressed = ctx.pop()?;
+    let key_bytes = compressed.as_bytes()?;
+    if key_bytes.len() != 33 {
+        return Err(AvmError::InvalidByteArrayLength {
+            expected: 33,
+            actual: key_bytes.len(),
+        });
+    }
+
+    let uncompressed = match curve {
+        EcdsaCurve::Secp256k1 => secp256k1::PublicKey::from_slice(key_bytes)
+            .map(|key| key.serialize_uncompressed().to_vec())
+            .map_err(|e| AvmError::crypto_error(format!("Invalid public key: {e}")))?,
+        EcdsaCurve::Secp256r1 => {
+            use p256::elliptic_curve::sec1::ToEncodedPoint;
+
+            p256::PublicKey::from_sec1_bytes(key_bytes)
+                .map(|key| key.to_encoded_point(false).as_bytes().to_vec())
+                .map_err(|e| AvmError::crypto_error(format!("Invalid public key: {e}")))?
         }
     };
 
-    ctx.push(StackValue::Bytes(result))?;
-    ctx.advance_pc(1)?;
-    Ok(())
+    push_point_coordinates(ctx, &uncompressed)
 }
 
-/// ECDSA public key recovery
+/// Recover the public key of signature (C, D) of data A with recovery id B
 pub fn op_ecdsa_pk_recover(ctx: &mut EvalContext) -> AvmResult<()> {
     use secp256k1::{
-        Message, Secp256k1,
+        Message, SECP256K1,
         ecdsa::{RecoverableSignature, RecoveryId},
     };
 
-    let recovery_id = ctx.pop()?;
-    let signature = ctx.pop()?;
+    let curve = read_ecdsa_curve(ctx)?;
+    if curve != EcdsaCurve::Secp256k1 {
+        return Err(AvmError::crypto_error(
+            "ecdsa_pk_recover only supports Secp256k1",
+        ));
+    }
+
+    let s = ctx.pop()?;
+    let r = ctx.pop()?;
+    let recovery_id = ctx.pop()?.as_uint()?;
     let data = ctx.pop()?;
 
-    let recovery_id_value = recovery_id.as_uint()? as i32;
-    let sig_bytes = signature.as_bytes()?;
-    let data_bytes = data.as_bytes()?;
+    let signature = [r.as_bytes()?, s.as_bytes()?].concat();
+    let recovery_id = i32::try_from(recovery_id)
+        .ok()
+        .and_then(|id| RecoveryId::from_i32(id).ok())
+        .ok_or_else(|| AvmError::crypto_error(format!("Invalid recovery id: {recovery_id}")))?;
+    let message = Message::from_digest_slice(data.as_bytes()?)
+        .map_err(|e| AvmError::crypto_error(format!("Invalid message: {e}")))?;
 
-    // Recover the public key
-    let result = match (
-        RecoveryId::from_i32(recovery_id_value),
-        Message::from_digest_slice(data_bytes),
-        sig_bytes.len() == 64, // Signature should be 64 bytes (r + s)
-    ) {
-        (Ok(recovery_id), Ok(msg), true) => {
-            match RecoverableSignature::from_compact(sig_bytes, recovery_id) {
-                Ok(recoverable_sig) => {
-                    let secp = Secp256k1::new();
-                    match secp.recover_ecdsa(&msg, &recoverable_sig) {
-                        Ok(pubkey) => {
-                            // Return uncompressed public key (64 bytes without prefix)
-                            let serialized = pubkey.serialize_uncompressed();
-                            serialized[1..].to_vec()
-                        }
-                        Err(_) => vec![0u8; 64], // Recovery failed - return 64 zeros
-                    }
-                }
-                Err(_) => vec![0u8; 64], // Invalid signature format - return 64 zeros
-            }
-        }
-        _ => vec![0u8; 64], // Invalid input parameters - return 64 zeros
-    };
+    let public_key = RecoverableSignature::from_compact(&signature, recovery_id)
+        .and_then(|sig| SECP256K1.recover_ecdsa(&message, &sig))
+        .map_err(|e| AvmError::crypto_error(format!("Public key recovery failed: {e}")))?;
 
-    ctx.push(StackValue::Bytes(result))?;
-    ctx.advance_pc(1)?;
-    Ok(())
+    push_point_coordinates(ctx, &public_key.serialize_uncompressed())
 }
 
 /// VRF verification
 pub fn op_vrf_verify(ctx: &mut EvalContext) -> AvmResult<()> {
+    ctx.advance_pc(1)?;
+    let standard = ctx.read_bytes(1)?[0];
+    ctx.advance_pc(1)?;
+    if standard != 0 {
+        return Err(AvmError::invalid_program(format!(
+            "Invalid VRF standard: {standard}"
+        )));
+    }
+
     let public_key = ctx.pop()?;
     let proof = ctx.pop()?;
     let data = ctx.pop()?;
@@ -285,32 +324,31 @@ pub fn op_vrf_verify(ctx: &mut EvalContext) -> AvmResult<()> {
     // Placeholder implementation
     ctx.push(StackValue::Bytes(vec![0u8; 64]))?; // VRF output
     ctx.push(StackValue::Uint(0))?; // Verification result
-    ctx.advance_pc(1)?;
     Ok(())
 }
 
 /// Advanced cryptographic hash function (MiMC)
 pub fn op_mimc(ctx: &mut EvalContext) -> AvmResult<()> {
     ctx.advance_pc(1)?;
-    let round_count = ctx.read_bytes(1)?[0] as usize;
+    let config = ctx.read_bytes(1)?[0];
     ctx.advance_pc(1)?;
 
-    let message = ctx.pop()?;
-    let key = ctx.pop()?;
-
-    let _message_bytes = message.as_bytes()?;
-    let _key_bytes = key.as_bytes()?;
+    // 0 - BN254Mp110, 1 - BLS12_381Mp111
+    if config > 1 {
+        return Err(AvmError::invalid_program(format!(
+            "Invalid MiMC configuration: {config}"
+        )));
+    }
 
-    // Validate round count
-    if round_count == 0 || round_count > 255 {
-        return Err(AvmError::invalid_program("Invalid MiMC round count"));
+    let message = ctx.pop()?;
+    let message_bytes = message.as_bytes()?;
+    if message_bytes.is_empty() || message_bytes.len() % 32 != 0 {
+        return Err(AvmError::invalid_program(
+            "MiMC input must be a non-empty multiple of 32 bytes",
+        ));
     }
 
-    // TODO: Implement MiMC hash function with configurable rounds
-    // Real implementation would:
-    // 1. Implement the MiMC hash function
-    // 2. Use the specified number of rounds
-    // 3. Apply the key and message according to MiMC specification
+    // TODO: Implement the MiMC hash function for the selected configuration
     ctx.push(StackValue::Bytes(vec![0u8; 32]))?;
     Ok(())
 }
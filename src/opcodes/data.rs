@@ -9,11 +9,8 @@ use crate::vm::EvalContext;
 pub fn op_pushint(ctx: &mut EvalContext) -> AvmResult<()> {
     // Advance past the opcode first
     ctx.advance_pc(1)?;
-    // Read 8 bytes for the integer value
-    let bytes = ctx.read_bytes(8)?.to_vec();
-    ctx.advance_pc(8)?;
+    let value = read_varuint_from_context(ctx)?;
 
-    let value = u64::from_be_bytes(bytes.try_into().unwrap());
     ctx.push(StackValue::Uint(value))?;
     Ok(())
 }
@@ -22,13 +19,7 @@ pub fn op_pushint(ctx: &mut EvalContext) -> AvmResult<()> {
 pub fn op_pushbytes(ctx: &mut EvalContext) -> AvmResult<()> {
     // Advance past the opcode first
     ctx.advance_pc(1)?;
-    // Read the length byte
-    let length = ctx.read_bytes(1)?[0] as usize;
-    ctx.advance_pc(1)?;
-
-    // Read the bytes
-    let bytes = ctx.read_bytes(length)?.to_vec();
-    ctx.advance_pc(length)?;
+    let bytes = read_bytes_immediate(ctx)?;
 
     ctx.push(StackValue::Bytes(bytes))?;
     Ok(())
@@ -38,16 +29,10 @@ pub fn op_pushbytes(ctx: &mut EvalContext) -> AvmResult<()> {
 pub fn op_pushints(ctx: &mut EvalContext) -> AvmResult<()> {
     // Advance past the opcode first
     ctx.advance_pc(1)?;
-    // Read count
-    let count = ctx.read_bytes(1)?[0] as usize;
-    ctx.advance_pc(1)?;
+    let count = read_varuint_from_context(ctx)?;
 
-    // Read each integer
     for _ in 0..count {
-        let bytes = ctx.read_bytes(8)?.to_vec();
-        ctx.advance_pc(8)?;
-
-        let value = u64::from_be_bytes(bytes.try_into().unwrap());
+        let value = read_varuint_from_context(ctx)?;
         ctx.push(StackValue::Uint(value))?;
     }
 
@@ -58,24 +43,24 @@ pub fn op_pushints(ctx: &mut EvalContext) -> AvmResult<()> {
 pub fn op_pushbytess(ctx: &mut EvalContext) -> AvmResult<()> {
     // Advance past the opcode first
     ctx.advance_pc(1)?;
-    // Read count
-    let count = ctx.read_bytes(1)?[0] as usize;
-    ctx.advance_pc(1)?;
+    let count = read_varuint_from_context(ctx)?;
 
-    // Read each byte array
     for _ in 0..count {
-        let length = ctx.read_bytes(1)?[0] as usize;
-        ctx.advance_pc(1)?;
-
-        let bytes = ctx.read_bytes(length)?.to_vec();
-        ctx.advance_pc(length)?;
-
+        let bytes = read_bytes_immediate(ctx)?;
         ctx.push(StackValue::Bytes(bytes))?;
     }
 
     Ok(())
 }
 
+/// Read a varuint length followed by that many bytes
+fn read_bytes_immediate(ctx: &mut EvalContext) -> AvmResult<Vec<u8>> {
+    let length = read_varuint_from_context(ctx)? as usize;
+    let bytes = ctx.read_bytes(length)?.to_vec();
+    ctx.advance_pc(length)?;
+    Ok(bytes)
+}
+
 /// Integer constant block
 pub fn op_intcblock(ctx: &mut EvalContext) -> AvmResult<()> {
     // Advance past the opcode first
@@ -152,10 +137,7 @@ pub fn op_bytecblock(ctx: &mut EvalContext) -> AvmResult<()> {
     // Read each byte constant (length-prefixed)
     let mut constants = Vec::with_capacity(count);
     for _ in 0..count {
-        let length = read_varuint_from_context(ctx)? as usize;
-        let bytes = ctx.read_bytes(length)?.to_vec();
-        ctx.advance_pc(length)?;
-        constants.push(bytes);
+        constants.push(read_bytes_immediate(ctx)?);
     }
 
     // Store constants in context
@@ -0,0 +1,251 @@
+//! Named values of field immediates
+
+/// A family of named values taken by a field immediate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FieldGroup {
+    /// Transaction fields of `txn`, `gtxn`, `itxn_field` and friends
+    Txn,
+    /// Fields of `global`
+    Global,
+    /// Fields of `asset_holding_get`
+    AssetHolding,
+    /// Fields of `asset_params_get`
+    AssetParams,
+    /// Fields of `app_params_get`
+    AppParams,
+    /// Fields of `acct_params_get`
+    AcctParams,
+    /// Fields of `block`
+    Block,
+    /// Encodings of `base64_decode`
+    Base64Encoding,
+    /// Return types of `json_ref`
+    JsonRefType,
+    /// Curves of the `ecdsa_*` opcodes
+    EcdsaCurve,
+    /// Standards of `vrf_verify`
+    VrfStandard,
+    /// Curve groups of the `ec_*` opcodes
+    EcGroup,
+    /// Configurations of `mimc`
+    MimcConfig,
+}
+
+const TXN_FIELDS: &[&str] = &[
+    "Sender",
+    "Fee",
+    "FirstValid",
+    "FirstValidTime",
+    "LastValid",
+    "Note",
+    "Lease",
+    "Receiver",
+    "Amount",
+    "CloseRemainderTo",
+    "VotePK",
+    "SelectionPK",
+    "VoteFirst",
+    "VoteLast",
+    "VoteKeyDilution",
+    "Type",
+    "TypeEnum",
+    "XferAsset",
+    "AssetAmount",
+    "AssetSender",
+    "AssetReceiver",
+    "AssetCloseTo",
+    "GroupIndex",
+    "TxID",
+    "ApplicationID",
+    "OnCompletion",
+    "ApplicationArgs",
+    "NumAppArgs",
+    "Accounts",
+    "NumAccounts",
+    "ApprovalProgram",
+    "ClearStateProgram",
+    "RekeyTo",
+    "ConfigAsset",
+    "ConfigAssetTotal",
+    "ConfigAssetDecimals",
+    "ConfigAssetDefaultFrozen",
+    "ConfigAssetUnitName",
+    "ConfigAssetName",
+    "ConfigAssetURL",
+    "ConfigAssetMetadataHash",
+    "ConfigAssetManager",
+    "ConfigAssetReserve",
+    "ConfigAssetFreeze",
+    "ConfigAssetClawback",
+    "FreezeAsset",
+    "FreezeAssetAccount",
+    "FreezeAssetFrozen",
+    "Assets",
+    "NumAssets",
+    "Applications",
+    "NumApplications",
+    "GlobalNumUint",
+    "GlobalNumByteSlice",
+    "LocalNumUint",
+    "LocalNumByteSlice",
+    "ExtraProgramPages",
+    "Nonparticipation",
+    "Logs",
+    "NumLogs",
+    "CreatedAssetID",
+    "CreatedApplicationID",
+    "LastLog",
+    "StateProofPK",
+    "ApprovalProgramPages",
+    "NumApprovalProgramPages",
+    "ClearStateProgramPages",
+    "NumClearStateProgramPages",
+];
+
+const GLOBAL_FIELDS: &[&str] = &[
+    "MinTxnFee",
+    "MinBalance",
+    "MaxTxnLife",
+    "ZeroAddress",
+    "GroupSize",
+    "LogicSigVersion",
+    "Round",
+    "LatestTimestamp",
+    "CurrentApplicationID",
+    "CreatorAddress",
+    "CurrentApplicationAddress",
+    "GroupID",
+    "OpcodeBudget",
+    "CallerApplicationID",
+    "CallerApplicationAddress",
+    "AssetCreateMinBalance",
+    "AssetOptInMinBalance",
+    "GenesisHash",
+    "PayoutsEnabled",
+    "PayoutsGoOnlineFee",
+    "PayoutsPercent",
+    "PayoutsMinBalance",
+    "PayoutsMaxBalance",
+];
+
+const ASSET_HOLDING_FIELDS: &[&str] = &["AssetBalance", "AssetFrozen"];
+
+const ASSET_PARAMS_FIELDS: &[&str] = &[
+    "AssetTotal",
+    "AssetDecimals",
+    "AssetDefaultFrozen",
+    "AssetUnitName",
+    "AssetName",
+    "AssetURL",
+    "AssetMetadataHash",
+    "AssetManager",
+    "AssetReserve",
+    "AssetFreeze",
+    "AssetClawback",
+    "AssetCreator",
+];
+
+const APP_PARAMS_FIELDS: &[&str] = &[
+    "AppApprovalProgram",
+    "AppClearStateProgram",
+    "AppGlobalNumUint",
+    "AppGlobalNumByteSlice",
+    "AppLocalNumUint",
+    "AppLocalNumByteSlice",
+    "AppExtraProgramPages",
+    "AppCreator",
+    "AppAddress",
+];
+
+const ACCT_PARAMS_FIELDS: &[&str] = &[
+    "AcctBalance",
+    "AcctMinBalance",
+    "AcctAuthAddr",
+    "AcctTotalNumUint",
+    "AcctTotalNumByteSlice",
+    "AcctTotalExtraAppPages",
+    "AcctTotalAppsCreated",
+    "AcctTotalAppsOptedIn",
+    "AcctTotalAssetsCreated",
+    "AcctTotalAssets",
+    "AcctTotalBoxes",
+    "AcctTotalBoxBytes",
+];
+
+const BLOCK_FIELDS: &[&str] = &[
+    "BlkSeed",
+    "BlkTimestamp",
+    "BlkProposer",
+    "BlkFeesCollected",
+    "BlkBonus",
+    "BlkBranch",
+    "BlkFeeSink",
+    "BlkProtocol",
+    "BlkTxnCounter",
+    "BlkProposerPayout",
+];
+
+const BASE64_ENCODINGS: &[&str] = &["URLEncoding", "StdEncoding"];
+
+const JSON_REF_TYPES: &[&str] = &["JSONString", "JSONUint64", "JSONObject"];
+
+const ECDSA_CURVES: &[&str] = &["Secp256k1", "Secp256r1"];
+
+const VRF_STANDARDS: &[&str] = &["VrfAlgorand"];
+
+const EC_GROUPS: &[&str] = &["BN254g1", "BN254g2", "BLS12_381g1", "BLS12_381g2"];
+
+const MIMC_CONFIGS: &[&str] = &["BN254Mp110", "BLS12_381Mp111"];
+
+impl FieldGroup {
+    /// Field names, indexed by their encoded value
+    pub fn names(self) -> &'static [&'static str] {
+        match self {
+            Self::Txn => TXN_FIELDS,
+            Self::Global => GLOBAL_FIELDS,
+            Self::AssetHolding => ASSET_HOLDING_FIELDS,
+            Self::AssetParams => ASSET_PARAMS_FIELDS,
+            Self::AppParams => APP_PARAMS_FIELDS,
+            Self::AcctParams => ACCT_PARAMS_FIELDS,
+            Self::Block => BLOCK_FIELDS,
+            Self::Base64Encoding => BASE64_ENCODINGS,
+            Self::JsonRefType => JSON_REF_TYPES,
+            Self::EcdsaCurve => ECDSA_CURVES,
+            Self::VrfStandard => VRF_STANDARDS,
+            Self::EcGroup => EC_GROUPS,
+            Self::MimcConfig => MIMC_CONFIGS,
+        }
+    }
+
+    /// Encoded value of a field name
+    pub fn value(self, name: &str) -> Option<u8> {
+        self.names()
+            .iter()
+            .position(|field| *field == name)
+            .map(|index| index as u8)
+    }
+
+    /// Name of an encoded field value
+    pub fn field_name(self, value: u8) -> Option<&'static str> {
+        self.names().get(value as usize).copied()
+    }
+
+    /// Description used in error messages
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Txn => "transaction field",
+            Self::Global => "global field",
+            Self::AssetHolding => "asset holding field",
+            Self::AssetParams => "asset params field",
+            Self::AppParams => "app params field",
+            Self::AcctParams => "account params field",
+            Self::Block => "block field",
+            Self::Base64Encoding => "base64 encoding",
+            Self::JsonRefType => "json_ref type",
+            Self::EcdsaCurve => "ECDSA curve",
+            Self::VrfStandard => "VRF standard",
+            Self::EcGroup => "elliptic curve group",
+            Self::MimcConfig => "MiMC configuration",
+        }
+    }
+}
@@ -73,51 +73,34 @@ pub fn op_switch(ctx: &mut EvalContext) -> AvmResult<()> {
     Ok(())
 }
 
-/// Match statement - like switch but matches specific values
+/// Match statement - branch to the target whose case value equals the top value
 pub fn op_match(ctx: &mut EvalContext) -> AvmResult<()> {
     ctx.advance_pc(1)?; // advance past opcode
 
-    // Read number of match cases
-    let num_cases = ctx.read_bytes(1)?[0] as usize;
+    // Read number of targets
+    let num_targets = ctx.read_bytes(1)?[0] as usize;
     ctx.advance_pc(1)?; // advance past count
 
-    // Read all match values and targets
-    let mut cases = Vec::new();
-    for _ in 0..num_cases {
-        // Read match value (8 bytes for u64)
-        let value_bytes = ctx.read_bytes(8)?;
-        let match_value = u64::from_be_bytes([
-            value_bytes[0],
-            value_bytes[1],
-            value_bytes[2],
-            value_bytes[3],
-            value_bytes[4],
-            value_bytes[5],
-            value_bytes[6],
-            value_bytes[7],
-        ]);
-        ctx.advance_pc(8)?;
-
-        // Read target offset (2 bytes)
+    // Read all target offsets
+    let mut targets = Vec::with_capacity(num_targets);
+    for _ in 0..num_targets {
         let target_bytes = ctx.read_bytes(2)?;
-        let target = i16::from_be_bytes([target_bytes[0], target_bytes[1]]);
+        targets.push(i16::from_be_bytes([target_bytes[0], target_bytes[1]]));
         ctx.advance_pc(2)?;
-
-        cases.push((match_value, target));
     }
 
-    // Pop the value to match
-    let match_value = ctx.pop()?;
-    let value = match_value.as_uint()?;
+    // Pop the value to match, then one case value per target
+    let value = ctx.pop()?;
+    let mut cases = Vec::with_capacity(num_targets);
+    for _ in 0..num_targets {
+        cases.push(ctx.pop()?);
+    }
+    cases.reverse();
 
-    // Find matching case and jump
-    for (case_value, target) in cases {
-        if value == case_value {
-            ctx.branch(target)?;
-            return Ok(());
-        }
+    // Jump to the first matching case or fall through
+    if let Some(index) = cases.iter().position(|case| *case == value) {
+        ctx.branch(targets[index])?;
     }
 
-    // No match found, fall through
     Ok(())
 }
@@ -1,21 +1,38 @@
 //! Inner transaction opcodes for creating and submitting transactions from smart contracts
+//!
+//! Inner transactions are assembled in the [`EvalContext`] between `itxn_begin` and
+//! `itxn_submit`. Submitting a group validates it, applies its effects through the
+//! ledger (running the approval program of any called application) and keeps the
+//! submitted group so that `itxn` and `gitxn` can read back what happened.
 
+use super::transaction::parse_txn_field;
 use crate::error::{AvmError, AvmResult};
-use crate::types::StackValue;
-use crate::vm::EvalContext;
-
-/// Inner transaction field types
-#[derive(Debug, Clone)]
+use crate::state::{
+    Address, AppId, AppParams, ContextLedger, EvalDelta, LedgerAccess, MicroAlgos, OnCompletion,
+    OverlayLedger, RecordingLedger, Transaction, TransactionType, apply,
+};
+use crate::types::{GlobalField, StackValue, TealValue, TealVersion};
+use crate::vm::{
+    EvalContext, ExecutionConfig, MAX_INNER_CALL_DEPTH, MAX_INNER_TRANSACTIONS, VirtualMachine,
+};
+
+/// Maximum length of an inner transaction note
+const MAX_NOTE_LEN: usize = 1024;
+
+/// Maximum number of application arguments
+const MAX_APP_ARGS: usize = 16;
+
+/// Maximum number of extra program pages
+const MAX_EXTRA_PROGRAM_PAGES: u64 = 3;
+
+/// Inner transaction fields that can be set with `itxn_field`
+///
+/// Field IDs are the same as those used by `txn`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum InnerTransactionField {
-    Type,
-    TypeEnum,
     Sender,
     Fee,
-    FirstValid,
-    FirstValidTime,
-    LastValid,
     Note,
-    Lease,
     Receiver,
     Amount,
     CloseRemainderTo,
@@ -24,31 +41,35 @@ pub enum InnerTransactionField {
     VoteFirst,
     VoteLast,
     VoteKeyDilution,
-    StateProofPK,
+    Type,
+    TypeEnum,
+    XferAsset,
     AssetAmount,
     AssetSender,
     AssetReceiver,
     AssetCloseTo,
-    XferAsset,
-    AssetFrozen,
-    FreezeAsset,
-    FreezeAssetAccount,
+    ApplicationID,
+    OnCompletion,
+    ApplicationArgs,
+    Accounts,
+    ApprovalProgram,
+    ClearStateProgram,
+    RekeyTo,
     ConfigAsset,
     ConfigAssetTotal,
     ConfigAssetDecimals,
     ConfigAssetDefaultFrozen,
-    ConfigAssetName,
     ConfigAssetUnitName,
+    ConfigAssetName,
     ConfigAssetURL,
     ConfigAssetMetadataHash,
     ConfigAssetManager,
     ConfigAssetReserve,
     ConfigAssetFreeze,
     ConfigAssetClawback,
-    ApplicationID,
-    OnCompletion,
-    ApplicationArgs,
-    Accounts,
+    FreezeAsset,
+    FreezeAssetAccount,
+    FreezeAssetFrozen,
     Assets,
     Applications,
     GlobalNumUint,
@@ -56,105 +77,290 @@ pub enum InnerTransactionField {
     LocalNumUint,
     LocalNumByteSlice,
     ExtraProgramPages,
-    ApprovalProgram,
-    ClearStateProgram,
-    CreatedAssetID,
-    CreatedApplicationID,
-    LastLog,
-    NumLogs,
+    Nonparticipation,
+    StateProofPK,
+    ApprovalProgramPages,
+    ClearStateProgramPages,
 }
 
 impl InnerTransactionField {
     /// Convert field ID to field type
     pub fn from_id(id: u8) -> AvmResult<Self> {
         match id {
-            0 => Ok(Self::Type),
-            1 => Ok(Self::TypeEnum),
-            2 => Ok(Self::Sender),
-            3 => Ok(Self::Fee),
-            4 => Ok(Self::FirstValid),
-            5 => Ok(Self::FirstValidTime),
-            6 => Ok(Self::LastValid),
-            7 => Ok(Self::Note),
-            8 => Ok(Self::Lease),
-            9 => Ok(Self::Receiver),
-            10 => Ok(Self::Amount),
-            11 => Ok(Self::CloseRemainderTo),
-            12 => Ok(Self::VotePK),
-            13 => Ok(Self::SelectionPK),
-            14 => Ok(Self::VoteFirst),
-            15 => Ok(Self::VoteLast),
-            16 => Ok(Self::VoteKeyDilution),
-            17 => Ok(Self::StateProofPK),
+            0 => Ok(Self::Sender),
+            1 => Ok(Self::Fee),
+            5 => Ok(Self::Note),
+            7 => Ok(Self::Receiver),
+            8 => Ok(Self::Amount),
+            9 => Ok(Self::CloseRemainderTo),
+            10 => Ok(Self::VotePK),
+            11 => Ok(Self::SelectionPK),
+            12 => Ok(Self::VoteFirst),
+            13 => Ok(Self::VoteLast),
+            14 => Ok(Self::VoteKeyDilution),
+            15 => Ok(Self::Type),
+            16 => Ok(Self::TypeEnum),
+            17 => Ok(Self::XferAsset),
             18 => Ok(Self::AssetAmount),
             19 => Ok(Self::AssetSender),
             20 => Ok(Self::AssetReceiver),
             21 => Ok(Self::AssetCloseTo),
-            22 => Ok(Self::XferAsset),
-            23 => Ok(Self::AssetFrozen),
-            24 => Ok(Self::FreezeAsset),
-            25 => Ok(Self::FreezeAssetAccount),
-            26 => Ok(Self::ConfigAsset),
-            27 => Ok(Self::ConfigAssetTotal),
-            28 => Ok(Self::ConfigAssetDecimals),
-            29 => Ok(Self::ConfigAssetDefaultFrozen),
-            30 => Ok(Self::ConfigAssetName),
-            31 => Ok(Self::ConfigAssetUnitName),
-            32 => Ok(Self::ConfigAssetURL),
-            33 => Ok(Self::ConfigAssetMetadataHash),
-            34 => Ok(Self::ConfigAssetManager),
-            35 => Ok(Self::ConfigAssetReserve),
-            36 => Ok(Self::ConfigAssetFreeze),
-            37 => Ok(Self::ConfigAssetClawback),
-            38 => Ok(Self::ApplicationID),
-            39 => Ok(Self::OnCompletion),
-            40 => Ok(Self::ApplicationArgs),
-            41 => Ok(Self::Accounts),
-            42 => Ok(Self::Assets),
-            43 => Ok(Self::Applications),
-            44 => Ok(Self::GlobalNumUint),
-            45 => Ok(Self::GlobalNumByteSlice),
-            46 => Ok(Self::LocalNumUint),
-            47 => Ok(Self::LocalNumByteSlice),
-            48 => Ok(Self::ExtraProgramPages),
-            49 => Ok(Self::ApprovalProgram),
-            50 => Ok(Self::ClearStateProgram),
-            51 => Ok(Self::CreatedAssetID),
-            52 => Ok(Self::CreatedApplicationID),
-            53 => Ok(Self::LastLog),
-            54 => Ok(Self::NumLogs),
+            24 => Ok(Self::ApplicationID),
+            25 => Ok(Self::OnCompletion),
+            26 => Ok(Self::ApplicationArgs),
+            28 => Ok(Self::Accounts),
+            30 => Ok(Self::ApprovalProgram),
+            31 => Ok(Self::ClearStateProgram),
+            32 => Ok(Self::RekeyTo),
+            33 => Ok(Self::ConfigAsset),
+            34 => Ok(Self::ConfigAssetTotal),
+            35 => Ok(Self::ConfigAssetDecimals),
+            36 => Ok(Self::ConfigAssetDefaultFrozen),
+            37 => Ok(Self::ConfigAssetUnitName),
+            38 => Ok(Self::ConfigAssetName),
+            39 => Ok(Self::ConfigAssetURL),
+            40 => Ok(Self::ConfigAssetMetadataHash),
+            41 => Ok(Self::ConfigAssetManager),
+            42 => Ok(Self::ConfigAssetReserve),
+            43 => Ok(Self::ConfigAssetFreeze),
+            44 => Ok(Self::ConfigAssetClawback),
+            45 => Ok(Self::FreezeAsset),
+            46 => Ok(Self::FreezeAssetAccount),
+            47 => Ok(Self::FreezeAssetFrozen),
+            48 => Ok(Self::Assets),
+            50 => Ok(Self::Applications),
+            52 => Ok(Self::GlobalNumUint),
+            53 => Ok(Self::GlobalNumByteSlice),
+            54 => Ok(Self::LocalNumUint),
+            55 => Ok(Self::LocalNumByteSlice),
+            56 => Ok(Self::ExtraProgramPages),
+            57 => Ok(Self::Nonparticipation),
+            63 => Ok(Self::StateProofPK),
+            64 => Ok(Self::ApprovalProgramPages),
+            66 => Ok(Self::ClearStateProgramPages),
             _ => Err(AvmError::invalid_program(format!(
                 "Invalid inner transaction field: {id}"
             ))),
         }
     }
+
+    /// Set this field on a transaction, appending for array fields
+    pub fn apply(self, txn: &mut Transaction, value: &StackValue) -> AvmResult<()> {
+        match self {
+            Self::Sender => txn.sender = address(value)?,
+            Self::Fee => txn.fee = value.as_uint()?,
+            Self::Note => txn.note = bounded_bytes(value, MAX_NOTE_LEN, "Note")?,
+            Self::Receiver => txn.receiver = Some(address(value)?),
+            Self::Amount => txn.amount = Some(value.as_uint()?),
+            Self::CloseRemainderTo => txn.close_remainder_to = Some(address(value)?),
+            Self::VotePK => txn.vote_pk = Some(sized_bytes(value, 32, "VotePK")?),
+            Self::SelectionPK => txn.selection_pk = Some(sized_bytes(value, 32, "SelectionPK")?),
+            Self::StateProofPK => {
+                txn.state_proof_pk = Some(sized_bytes(value, 64, "StateProofPK")?)
+            }
+            Self::VoteFirst => txn.vote_first = Some(value.as_uint()?),
+            Self::VoteLast => txn.vote_last = Some(value.as_uint()?),
+            Self::VoteKeyDilution => txn.vote_key_dilution = Some(value.as_uint()?),
+            Self::Type => {
+                let tx_type =
+                    TransactionType::from_type_str(value.as_bytes()?).ok_or_else(|| {
+                        AvmError::invalid_program(format!(
+                            "unknown transaction type {}",
+                            String::from_utf8_lossy(value.as_bytes().unwrap_or_default())
+                        ))
+                    })?;
+                set_type(txn, tx_type);
+            }
+            Self::TypeEnum => {
+                let type_enum = value.as_uint()?;
+                let tx_type = TransactionType::from_type_enum(type_enum).ok_or_else(|| {
+                    AvmError::invalid_program(format!("unknown transaction type {type_enum}"))
+                })?;
+                set_type(txn, tx_type);
+            }
+            Self::XferAsset => txn.xfer_asset = Some(value.as_uint()?),
+            Self::AssetAmount => txn.asset_amount = Some(value.as_uint()?),
+            Self::AssetSender => txn.asset_sender = Some(address(value)?),
+            Self::AssetReceiver => txn.asset_receiver = Some(address(value)?),
+            Self::AssetCloseTo => txn.asset_close_to = Some(address(value)?),
+            Self::ApplicationID => txn.application_id = Some(value.as_uint()?),
+            Self::OnCompletion => {
+                let on_completion = value.as_uint()?;
+                if on_completion > OnCompletion::DeleteApplication as u64 {
+                    return Err(AvmError::invalid_program(format!(
+                        "invalid OnCompletion value {on_completion}"
+                    )));
+                }
+                txn.on_completion = Some(on_completion);
+            }
+            Self::ApplicationArgs => {
+                if txn.application_args.len() >= MAX_APP_ARGS {
+                    return Err(AvmError::invalid_program(format!(
+                        "too many application args (max {MAX_APP_ARGS})"
+                    )));
+                }
+                txn.application_args.push(value.as_bytes()?.to_vec());
+            }
+            Self::Accounts => txn.accounts.push(address(value)?),
+            Self::Assets => txn.assets.push(value.as_uint()?),
+            Self::Applications => txn.applications.push(value.as_uint()?),
+            Self::ApprovalProgram => txn.approval_program = Some(value.as_bytes()?.to_vec()),
+            Self::ClearStateProgram => txn.clear_state_program = Some(value.as_bytes()?.to_vec()),
+            Self::ApprovalProgramPages => {
+                let page = value.as_bytes()?.to_vec();
+                txn.approval_program
+                    .get_or_insert_with(Vec::new)
+                    .extend_from_slice(&page);
+                txn.approval_program_pages.push(page);
+            }
+            Self::ClearStateProgramPages => {
+                let page = value.as_bytes()?.to_vec();
+                txn.clear_state_program
+                    .get_or_insert_with(Vec::new)
+                    .extend_from_slice(&page);
+                txn.clear_state_program_pages.push(page);
+            }
+            Self::RekeyTo => txn.rekey_to = Some(address(value)?),
+            Self::ConfigAsset => txn.config_asset = Some(value.as_uint()?),
+            Self::ConfigAssetTotal => txn.config_asset_total = Some(value.as_uint()?),
+            Self::ConfigAssetDecimals => {
+                let decimals = value.as_uint()?;
+                if decimals > 19 {
+                    return Err(AvmError::invalid_program(format!(
+                        "ConfigAssetDecimals {decimals} exceeds 19"
+                    )));
+                }
+                txn.config_asset_decimals = Some(decimals as u8);
+            }
+            Self::ConfigAssetDefaultFrozen => {
+                txn.config_asset_default_frozen = Some(boolean(value)?)
+            }
+            Self::ConfigAssetUnitName => {
+                txn.config_asset_unit_name = Some(bounded_string(value, 8, "ConfigAssetUnitName")?)
+            }
+            Self::ConfigAssetName => {
+                txn.config_asset_name = Some(bounded_string(value, 32, "ConfigAssetName")?)
+            }
+            Self::ConfigAssetURL => {
+                txn.config_asset_url = Some(bounded_string(value, 96, "ConfigAssetURL")?)
+            }
+            Self::ConfigAssetMetadataHash => {
+                txn.config_asset_metadata_hash =
+                    Some(sized_bytes(value, 32, "ConfigAssetMetadataHash")?)
+            }
+            Self::ConfigAssetManager => txn.config_asset_manager = Some(address(value)?),
+            Self::ConfigAssetReserve => txn.config_asset_reserve = Some(address(value)?),
+            Self::ConfigAssetFreeze => txn.config_asset_freeze = Some(address(value)?),
+            Self::ConfigAssetClawback => txn.config_asset_clawback = Some(address(value)?),
+            Self::FreezeAsset => txn.freeze_asset = Some(value.as_uint()?),
+            Self::FreezeAssetAccount => txn.freeze_asset_account = Some(address(value)?),
+            Self::FreezeAssetFrozen => txn.freeze_asset_frozen = Some(boolean(value)?),
+            Self::GlobalNumUint => txn.global_num_uint = Some(value.as_uint()?),
+            Self::GlobalNumByteSlice => txn.global_num_byte_slice = Some(value.as_uint()?),
+            Self::LocalNumUint => txn.local_num_uint = Some(value.as_uint()?),
+            Self::LocalNumByteSlice => txn.local_num_byte_slice = Some(value.as_uint()?),
+            Self::ExtraProgramPages => {
+                let pages = value.as_uint()?;
+                if pages > MAX_EXTRA_PROGRAM_PAGES {
+                    return Err(AvmError::invalid_program(format!(
+                        "ExtraProgramPages {pages} exceeds {MAX_EXTRA_PROGRAM_PAGES}"
+                    )));
+                }
+                txn.extra_program_pages = Some(pages as u32);
+            }
+            Self::Nonparticipation => txn.nonparticipation = Some(boolean(value)?),
+        }
+        Ok(())
+    }
 }
 
-/// Log an event (limited to application mode)
-pub fn op_log(ctx: &mut EvalContext) -> AvmResult<()> {
-    ctx.advance_pc(1)?;
-    let data = ctx.pop()?;
-    let log_data = data.as_bytes()?;
+fn set_type(txn: &mut Transaction, tx_type: TransactionType) {
+    txn.type_enum = tx_type.type_enum();
+    txn.tx_type = tx_type;
+}
+
+fn address(value: &StackValue) -> AvmResult<Address> {
+    sized_bytes(value, 32, "address")
+}
 
-    // Log limit is 1024 bytes
-    if log_data.len() > 1024 {
-        return Err(AvmError::invalid_program("Log data exceeds 1024 bytes"));
+fn sized_bytes(value: &StackValue, len: usize, name: &str) -> AvmResult<Vec<u8>> {
+    let bytes = value.as_bytes()?;
+    if bytes.len() != len {
+        return Err(AvmError::invalid_program(format!(
+            "{name} must be {len} bytes, got {}",
+            bytes.len()
+        )));
     }
+    Ok(bytes.to_vec())
+}
 
-    // In a real implementation, this would add to the transaction's log array
-    // For now, we'll just validate the operation
+fn bounded_bytes(value: &StackValue, max: usize, name: &str) -> AvmResult<Vec<u8>> {
+    let bytes = value.as_bytes()?;
+    if bytes.len() > max {
+        return Err(AvmError::invalid_program(format!(
+            "{name} may not exceed {max} bytes"
+        )));
+    }
+    Ok(bytes.to_vec())
+}
 
-    Ok(())
+fn bounded_string(value: &StackValue, max: usize, name: &str) -> AvmResult<String> {
+    let bytes = bounded_bytes(value, max, name)?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn boolean(value: &StackValue) -> AvmResult<bool> {
+    match value.as_uint()? {
+        0 => Ok(false),
+        1 => Ok(true),
+        other => Err(AvmError::invalid_program(format!(
+            "boolean field must be 0 or 1, got {other}"
+        ))),
+    }
+}
+
+/// Minimum fee per transaction, as reported by the ledger
+fn min_txn_fee(ctx: &EvalContext) -> AvmResult<MicroAlgos> {
+    match ctx.ledger().get_global_field(GlobalField::MinTxnFee)? {
+        TealValue::Uint(fee) => Ok(fee),
+        TealValue::Bytes(_) => Err(AvmError::ledger_error("MinTxnFee is not an integer")),
+    }
+}
+
+/// Fee credit available for pooling, initialised from the surplus fees of the outer group
+fn available_fee_credit(ctx: &EvalContext) -> AvmResult<MicroAlgos> {
+    if let Some(credit) = ctx.fee_credit() {
+        return Ok(credit);
+    }
+    let min_fee = min_txn_fee(ctx)?;
+    let group = ctx.ledger().transaction_group()?;
+    let paid: u64 = group.iter().map(|txn| txn.fee).sum();
+    Ok(paid.saturating_sub(min_fee * group.len() as u64))
+}
+
+/// Build an inner transaction with the defaults set by `itxn_begin` and `itxn_next`
+fn default_inner_transaction(ctx: &EvalContext) -> AvmResult<Transaction> {
+    let mut txn = Transaction::new();
+    txn.sender = ctx.ledger().current_application_address()?;
+    txn.fee = min_txn_fee(ctx)?.saturating_sub(available_fee_credit(ctx)?);
+    txn.first_valid = ctx.ledger().current_round()?;
+    txn.last_valid = txn.first_valid + 1000;
+    txn.tx_id = Vec::new();
+    Ok(txn)
+}
+
+/// Append the top of the stack to the application call's logs
+pub fn op_log(ctx: &mut EvalContext) -> AvmResult<()> {
+    ctx.advance_pc(1)?;
+    let data = ctx.pop()?.as_bytes()?.to_vec();
+    ctx.append_log(data)
 }
 
 /// Begin construction of an inner transaction
 pub fn op_itxn_begin(ctx: &mut EvalContext) -> AvmResult<()> {
     ctx.advance_pc(1)?;
-
-    // Initialize inner transaction construction
-    // In a real implementation, this would set up a new transaction context
-
-    Ok(())
+    let txn = default_inner_transaction(ctx)?;
+    ctx.begin_inner_group(txn)
 }
 
 /// Set field for current inner transaction
@@ -164,29 +370,266 @@ pub fn op_itxn_field(ctx: &mut EvalContext) -> AvmResult<()> {
     ctx.advance_pc(1)?;
 
     let value = ctx.pop()?;
-    let _field = InnerTransactionField::from_id(field_id)?;
+    let field = InnerTransactionField::from_id(field_id)?;
+    field.apply(ctx.current_inner_transaction_mut()?, &value)
+}
+
+/// Submit the current inner transaction group
+pub fn op_itxn_submit(ctx: &mut EvalContext) -> AvmResult<()> {
+    ctx.advance_pc(1)?;
+    let mut group = ctx.take_inner_group()?;
+
+    let issued = ctx.inner_transaction_count() + group.len();
+    if issued > MAX_INNER_TRANSACTIONS {
+        return Err(AvmError::invalid_program(format!(
+            "too many inner transactions (max {MAX_INNER_TRANSACTIONS})"
+        )));
+    }
+    ctx.set_inner_transaction_count(issued);
+
+    let app_address = ctx.ledger().current_application_address()?;
+    for txn in &group {
+        check_inner_type(ctx.version(), &txn.tx_type)?;
+        check_authorized_sender(ctx.ledger(), &txn.sender, &app_address)?;
+    }
+
+    // Fee pooling: surplus from earlier transactions may cover later ones
+    let min_fee = min_txn_fee(ctx)?;
+    let paid: u64 = group.iter().map(|txn| txn.fee).sum();
+    let required = min_fee * group.len() as u64;
+    let pooled = available_fee_credit(ctx)? + paid;
+    if pooled < required {
+        return Err(AvmError::invalid_program(format!(
+            "fee too small: inner group pays {pooled} of {required} required"
+        )));
+    }
+    ctx.set_fee_credit(pooled - required);
+
+    let mut deltas = Vec::with_capacity(group.len());
+    for index in 0..group.len() {
+        let mut txn = group[index].clone();
+        deltas.push(apply_inner_transaction(ctx, &group, &mut txn)?);
+        group[index] = txn;
+    }
 
-    // In a real implementation, this would set the field value in the current inner transaction
-    match value {
-        StackValue::Uint(_) => {
-            // Handle uint fields
+    ctx.record_inner_group(group, deltas);
+    Ok(())
+}
+
+/// Check that a transaction type may be issued as an inner transaction
+fn check_inner_type(version: TealVersion, tx_type: &TransactionType) -> AvmResult<()> {
+    let allowed = match tx_type {
+        TransactionType::Payment
+        | TransactionType::AssetConfig
+        | TransactionType::AssetTransfer
+        | TransactionType::AssetFreeze => true,
+        TransactionType::ApplicationCall | TransactionType::KeyRegistration => {
+            version >= TealVersion::V6
         }
-        StackValue::Bytes(_) => {
-            // Handle byte fields
+        TransactionType::StateProof => false,
+    };
+    if !allowed {
+        return Err(AvmError::invalid_program(format!(
+            "{} is not allowed as an inner transaction in v{}",
+            tx_type.as_str(),
+            version.as_u8()
+        )));
+    }
+    Ok(())
+}
+
+/// Check that the application may sign for the sender
+///
+/// The sender must be the application account or an account rekeyed to it.
+fn check_authorized_sender(
+    ledger: &dyn LedgerAccess,
+    sender: &Address,
+    app_address: &Address,
+) -> AvmResult<()> {
+    if sender == app_address {
+        return Ok(());
+    }
+    let auth_addr = ledger
+        .account_params(sender)?
+        .and_then(|params| params.auth_addr);
+    if auth_addr.as_ref() == Some(app_address) {
+        return Ok(());
+    }
+    Err(AvmError::invalid_program(format!(
+        "unauthorized inner transaction sender {}",
+        hex::encode(sender)
+    )))
+}
+
+/// Apply the effects of one inner transaction through the ledger
+///
+/// Returns the delta of the application called, which is empty for other
+/// transaction types.
+fn apply_inner_transaction(
+    ctx: &mut EvalContext,
+    group: &[Transaction],
+    txn: &mut Transaction,
+) -> AvmResult<EvalDelta> {
+    apply::charge_fee(ctx.ledger_mut(), txn)?;
+    let delta = if txn.tx_type == TransactionType::ApplicationCall {
+        apply_app_call(ctx, group, txn)?
+    } else {
+        apply::apply_effects(ctx.ledger_mut(), txn)?;
+        EvalDelta::default()
+    };
+    apply::check_min_balance(ctx.ledger(), &txn.sender)?;
+    Ok(delta)
+}
+
+fn apply_app_call(
+    ctx: &mut EvalContext,
+    group: &[Transaction],
+    txn: &mut Transaction,
+) -> AvmResult<EvalDelta> {
+    let on_completion = OnCompletion::from_u64(txn.on_completion.unwrap_or(0))?;
+    let (app_id, params) = apply::resolve_application(ctx.ledger_mut(), txn)?;
+
+    let delta = if on_completion == OnCompletion::ClearState {
+        // The clear state program cannot prevent opting out
+        match run_inner_app(
+            ctx,
+            group,
+            txn,
+            app_id,
+            &params,
+            &params.clear_state_program,
+        )? {
+            Ok((true, delta)) => delta,
+            Ok((false, _)) | Err(_) => EvalDelta::default(),
+        }
+    } else {
+        let (approved, delta) =
+            run_inner_app(ctx, group, txn, app_id, &params, &params.approval_program)??;
+        if !approved {
+            return Err(AvmError::execution_halted(format!(
+                "inner application call to {app_id} rejected"
+            )));
         }
+        delta
+    };
+
+    apply::complete_app_call(ctx.ledger_mut(), txn, app_id, params, on_completion)?;
+    Ok(delta)
+}
+
+/// Run a program for an inner application call, returning whether it approved
+/// and what it changed
+///
+/// The program's logs are recorded on the transaction. Failures of the
+/// program itself are returned in the inner result, so a clear state call
+/// can ignore them while still failing on anything else.
+fn run_inner_app(
+    ctx: &mut EvalContext,
+    group: &[Transaction],
+    txn: &mut Transaction,
+    app_id: AppId,
+    params: &AppParams,
+    program: &[u8],
+) -> AvmResult<AvmResult<(bool, EvalDelta)>> {
+    let depth = ctx.inner_call_depth() + 1;
+    if depth > MAX_INNER_CALL_DEPTH {
+        return Err(AvmError::invalid_program(format!(
+            "inner application calls nested deeper than {MAX_INNER_CALL_DEPTH}"
+        )));
+    }
+    if program.is_empty() {
+        return Ok(Ok((true, EvalDelta::default())));
     }
 
-    Ok(())
+    let caller_app_id = ctx.ledger().current_application_id()?;
+    let caller_app_address = ctx.ledger().current_application_address()?;
+    let issued = ctx.inner_transaction_count();
+    let group_index = txn.group_index as usize;
+
+    let mut view_group = group.to_vec();
+    view_group[group_index] = txn.clone();
+
+    // The callee runs at its own header version, whatever the caller's is
+    let version = TealVersion::latest();
+    let vm = VirtualMachine::with_version(version);
+    let config = ExecutionConfig::application(version).with_group(group_index, group.len());
+    // The callee's writes are kept apart so a rejected clear state program
+    // leaves no trace
+    let mut overlay = OverlayLedger::new(ctx.ledger_mut());
+    let mut recorder = RecordingLedger::new(&mut overlay);
+    let mut ledger = ContextLedger::new(&mut recorder, view_group, group_index)?
+        .with_application(app_id, params.creator.clone())
+        .with_caller(caller_app_id, caller_app_address)
+        .with_group_id(vec![0; 32]);
+
+    let outcome = vm
+        .create_eval_context(program, config.clone(), &mut ledger)
+        .and_then(|mut inner| {
+            inner.set_inner_call_depth(depth);
+            inner.set_inner_transaction_count(issued);
+            let approved = vm.run(&mut inner, &config)?;
+            Ok((
+                approved,
+                inner.inner_transaction_count(),
+                inner.logs().to_vec(),
+                inner.inner_transaction_deltas(),
+            ))
+        });
+    drop(ledger);
+    let (approved, issued, logs, inner_txns) = match outcome {
+        Ok(outcome) => outcome,
+        Err(e) => return Ok(Err(e)),
+    };
+    txn.logs = logs;
+
+    let delta = EvalDelta::from_changes(app_id, recorder.changes(), txn.logs.clone(), inner_txns);
+    drop(recorder);
+    if approved {
+        overlay.commit()?;
+    }
+
+    ctx.set_inner_transaction_count(issued);
+    Ok(Ok((approved, delta)))
 }
 
-/// Submit current inner transaction
-pub fn op_itxn_submit(ctx: &mut EvalContext) -> AvmResult<()> {
-    ctx.advance_pc(1)?;
+/// Get a transaction from the most recently submitted inner group
+fn submitted_inner_transaction<'c>(
+    ctx: &'c EvalContext,
+    index: Option<usize>,
+) -> AvmResult<&'c Transaction> {
+    let group = ctx.last_inner_group();
+    let txn = match index {
+        Some(index) => group.get(index),
+        None => group.last(),
+    };
+    txn.ok_or_else(|| {
+        AvmError::invalid_program(match index {
+            Some(index) => format!(
+                "inner transaction {index} out of range (group size {})",
+                group.len()
+            ),
+            None => "no inner transaction has been submitted".to_string(),
+        })
+    })
+}
 
-    // In a real implementation, this would validate and submit the inner transaction
-    // and add it to the transaction group
+/// Read a scalar field from a submitted inner transaction
+fn inner_field(ctx: &EvalContext, index: Option<usize>, field_id: u8) -> AvmResult<StackValue> {
+    let field = parse_txn_field(field_id)?;
+    let txn = submitted_inner_transaction(ctx, index)?;
+    Ok(txn.field(field).to_stack_value())
+}
 
-    Ok(())
+/// Read an element of an array field from a submitted inner transaction
+fn inner_array_field(
+    ctx: &EvalContext,
+    index: Option<usize>,
+    field_id: u8,
+    array_index: usize,
+) -> AvmResult<StackValue> {
+    let field = parse_txn_field(field_id)?;
+    let txn = submitted_inner_transaction(ctx, index)?;
+    Ok(txn.array_field(field, array_index)?.to_stack_value())
 }
 
 /// Access field from last submitted inner transaction
@@ -195,23 +638,8 @@ pub fn op_itxn(ctx: &mut EvalContext) -> AvmResult<()> {
     let field_id = ctx.read_bytes(1)?[0];
     ctx.advance_pc(1)?;
 
-    let _field = InnerTransactionField::from_id(field_id)?;
-
-    // In a real implementation, this would get the field from the last inner transaction
-    // For now, return placeholder values
-    match field_id {
-        // Uint fields
-        1 | 3 | 4 | 5 | 6 | 10 | 14 | 15 | 16 | 18 | 23 | 27 | 28 | 29 | 38 | 39 | 44 | 45 | 46
-        | 47 | 48 | 51 | 52 | 54 => {
-            ctx.push(StackValue::Uint(0))?;
-        }
-        // Bytes fields
-        _ => {
-            ctx.push(StackValue::Bytes(Vec::new()))?;
-        }
-    }
-
-    Ok(())
+    let value = inner_field(ctx, None, field_id)?;
+    ctx.push(value)
 }
 
 /// Access array field from last submitted inner transaction
@@ -219,87 +647,44 @@ pub fn op_itxna(ctx: &mut EvalContext) -> AvmResult<()> {
     ctx.advance_pc(1)?;
     let field_id = ctx.read_bytes(1)?[0];
     ctx.advance_pc(1)?;
-    let _index = ctx.read_bytes(1)?[0] as usize;
+    let index = ctx.read_bytes(1)?[0] as usize;
     ctx.advance_pc(1)?;
 
-    let _field = InnerTransactionField::from_id(field_id)?;
-
-    // In a real implementation, this would get the indexed field from the last inner transaction
-    // For now, return placeholder values
-    match field_id {
-        40..=43 => {
-            // Array fields
-            ctx.push(StackValue::Bytes(Vec::new()))?;
-        }
-        _ => {
-            return Err(AvmError::invalid_program("Field is not an array"));
-        }
-    }
-
-    Ok(())
+    let value = inner_array_field(ctx, None, field_id, index)?;
+    ctx.push(value)
 }
 
 /// Begin construction of next inner transaction in group
 pub fn op_itxn_next(ctx: &mut EvalContext) -> AvmResult<()> {
     ctx.advance_pc(1)?;
-
-    // In a real implementation, this would start construction of the next inner transaction
-
-    Ok(())
+    let txn = default_inner_transaction(ctx)?;
+    ctx.push_inner_transaction(txn)
 }
 
 /// Access field from specific inner transaction in group
 pub fn op_gitxn(ctx: &mut EvalContext) -> AvmResult<()> {
     ctx.advance_pc(1)?;
-    let _group_index = ctx.read_bytes(1)?[0] as usize;
+    let group_index = ctx.read_bytes(1)?[0] as usize;
     ctx.advance_pc(1)?;
     let field_id = ctx.read_bytes(1)?[0];
     ctx.advance_pc(1)?;
 
-    let _field = InnerTransactionField::from_id(field_id)?;
-
-    // In a real implementation, this would get the field from the specified inner transaction
-    // For now, return placeholder values
-    match field_id {
-        // Uint fields
-        1 | 3 | 4 | 5 | 6 | 10 | 14 | 15 | 16 | 18 | 23 | 27 | 28 | 29 | 38 | 39 | 44 | 45 | 46
-        | 47 | 48 | 51 | 52 | 54 => {
-            ctx.push(StackValue::Uint(0))?;
-        }
-        // Bytes fields
-        _ => {
-            ctx.push(StackValue::Bytes(Vec::new()))?;
-        }
-    }
-
-    Ok(())
+    let value = inner_field(ctx, Some(group_index), field_id)?;
+    ctx.push(value)
 }
 
 /// Access array field from specific inner transaction in group
 pub fn op_gitxna(ctx: &mut EvalContext) -> AvmResult<()> {
     ctx.advance_pc(1)?;
-    let _group_index = ctx.read_bytes(1)?[0] as usize;
+    let group_index = ctx.read_bytes(1)?[0] as usize;
     ctx.advance_pc(1)?;
     let field_id = ctx.read_bytes(1)?[0];
     ctx.advance_pc(1)?;
-    let _index = ctx.read_bytes(1)?[0] as usize;
+    let index = ctx.read_bytes(1)?[0] as usize;
     ctx.advance_pc(1)?;
 
-    let _field = InnerTransactionField::from_id(field_id)?;
-
-    // In a real implementation, this would get the indexed field from the specified inner transaction
-    // For now, return placeholder values
-    match field_id {
-        40..=43 => {
-            // Array fields
-            ctx.push(StackValue::Bytes(Vec::new()))?;
-        }
-        _ => {
-            return Err(AvmError::invalid_program("Field is not an array"));
-        }
-    }
-
-    Ok(())
+    let value = inner_array_field(ctx, Some(group_index), field_id, index)?;
+    ctx.push(value)
 }
 
 /// Access array field from last submitted inner transaction (stack index)
@@ -308,50 +693,20 @@ pub fn op_itxnas(ctx: &mut EvalContext) -> AvmResult<()> {
     let field_id = ctx.read_bytes(1)?[0];
     ctx.advance_pc(1)?;
 
-    let index = ctx.pop()?;
-    let _idx = index.as_uint()? as usize;
-
-    let _field = InnerTransactionField::from_id(field_id)?;
-
-    // In a real implementation, this would get the indexed field from the last inner transaction
-    // For now, return placeholder values
-    match field_id {
-        40..=43 => {
-            // Array fields
-            ctx.push(StackValue::Bytes(Vec::new()))?;
-        }
-        _ => {
-            return Err(AvmError::invalid_program("Field is not an array"));
-        }
-    }
-
-    Ok(())
+    let index = ctx.pop()?.as_uint()? as usize;
+    let value = inner_array_field(ctx, None, field_id, index)?;
+    ctx.push(value)
 }
 
 /// Access array field from specific inner transaction in group (stack index)
 pub fn op_gitxnas(ctx: &mut EvalContext) -> AvmResult<()> {
     ctx.advance_pc(1)?;
-    let _group_index = ctx.read_bytes(1)?[0] as usize;
+    let group_index = ctx.read_bytes(1)?[0] as usize;
     ctx.advance_pc(1)?;
     let field_id = ctx.read_bytes(1)?[0];
     ctx.advance_pc(1)?;
 
-    let index = ctx.pop()?;
-    let _idx = index.as_uint()? as usize;
-
-    let _field = InnerTransactionField::from_id(field_id)?;
-
-    // In a real implementation, this would get the indexed field from the specified inner transaction
-    // For now, return placeholder values
-    match field_id {
-        40..=43 => {
-            // Array fields
-            ctx.push(StackValue::Bytes(Vec::new()))?;
-        }
-        _ => {
-            return Err(AvmError::invalid_program("Field is not an array"));
-        }
-    }
-
-    Ok(())
+    let index = ctx.pop()?.as_uint()? as usize;
+    let value = inner_array_field(ctx, Some(group_index), field_id, index)?;
+    ctx.push(value)
 }
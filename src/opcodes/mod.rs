@@ -4,6 +4,7 @@ use crate::error::AvmResult;
 use crate::types::RunMode;
 use crate::vm::EvalContext;
 use std::collections::HashSet;
+use std::sync::OnceLock;
 
 pub mod argument;
 pub mod arithmetic;
@@ -13,6 +14,7 @@ pub mod constant;
 pub mod crypto;
 pub mod data;
 pub mod elliptic_curve;
+pub mod fields;
 pub mod flow;
 pub mod function;
 pub mod inner_transaction;
@@ -28,6 +30,7 @@ pub use constant::*;
 pub use crypto::*;
 pub use data::*;
 pub use elliptic_curve::*;
+pub use fields::FieldGroup;
 pub use flow::*;
 pub use function::*;
 pub use inner_transaction::*;
@@ -38,6 +41,187 @@ pub use transaction::*;
 /// Opcode execution function type
 pub type OpcodeExecutor = fn(&mut EvalContext) -> AvmResult<()>;
 
+/// Encoding of an immediate argument
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImmediateKind {
+    /// A single unsigned byte
+    Uint8,
+    /// A single signed byte
+    Int8,
+    /// A varuint-encoded integer
+    Varuint,
+    /// A varuint length followed by that many bytes
+    Bytes,
+    /// A two byte signed branch offset
+    Label,
+    /// A one byte count followed by that many branch offsets
+    Labels,
+    /// A varuint count followed by that many varuints
+    Varuints,
+    /// A varuint count followed by that many length-prefixed byte strings
+    BytesList,
+    /// A single byte naming a field of the given group
+    Field(FieldGroup),
+}
+
+/// An immediate argument of an opcode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Immediate {
+    /// Name used in the opcode documentation
+    pub name: &'static str,
+    /// How the argument is encoded
+    pub kind: ImmediateKind,
+}
+
+impl Immediate {
+    /// A single unsigned byte
+    pub const fn uint8(name: &'static str) -> Self {
+        Self {
+            name,
+            kind: ImmediateKind::Uint8,
+        }
+    }
+
+    /// A single signed byte
+    pub const fn int8(name: &'static str) -> Self {
+        Self {
+            name,
+            kind: ImmediateKind::Int8,
+        }
+    }
+
+    /// A varuint-encoded integer
+    pub const fn varuint(name: &'static str) -> Self {
+        Self {
+            name,
+            kind: ImmediateKind::Varuint,
+        }
+    }
+
+    /// A length-prefixed byte string
+    pub const fn bytes(name: &'static str) -> Self {
+        Self {
+            name,
+            kind: ImmediateKind::Bytes,
+        }
+    }
+
+    /// A branch target
+    pub const fn label(name: &'static str) -> Self {
+        Self {
+            name,
+            kind: ImmediateKind::Label,
+        }
+    }
+
+    /// A list of branch targets
+    pub const fn labels(name: &'static str) -> Self {
+        Self {
+            name,
+            kind: ImmediateKind::Labels,
+        }
+    }
+
+    /// A list of varuint-encoded integers
+    pub const fn varuints(name: &'static str) -> Self {
+        Self {
+            name,
+            kind: ImmediateKind::Varuints,
+        }
+    }
+
+    /// A list of byte strings
+    pub const fn bytes_list(name: &'static str) -> Self {
+        Self {
+            name,
+            kind: ImmediateKind::BytesList,
+        }
+    }
+
+    /// A named field of the given group
+    pub const fn field(name: &'static str, group: FieldGroup) -> Self {
+        Self {
+            name,
+            kind: ImmediateKind::Field(group),
+        }
+    }
+
+    /// Encoded size in bytes, if it does not depend on the value
+    pub fn fixed_size(&self) -> Option<usize> {
+        match self.kind {
+            ImmediateKind::Uint8 | ImmediateKind::Int8 | ImmediateKind::Field(_) => Some(1),
+            ImmediateKind::Label => Some(2),
+            _ => None,
+        }
+    }
+}
+
+/// Type of a stack value in an opcode signature
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StackType {
+    /// Either an integer or a byte array
+    Any,
+    /// A 64-bit unsigned integer
+    Uint64,
+    /// A byte array
+    Bytes,
+}
+
+impl StackType {
+    /// Parse the single letter code used in opcode signatures
+    fn from_code(code: char) -> Self {
+        match code {
+            'a' => Self::Any,
+            'i' => Self::Uint64,
+            'b' => Self::Bytes,
+            _ => panic!("unknown stack type code '{code}'"),
+        }
+    }
+
+    /// Name used in the opcode documentation
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Any => "any",
+            Self::Uint64 => "uint64",
+            Self::Bytes => "[]byte",
+        }
+    }
+}
+
+/// Documentation group of an opcode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum OpGroup {
+    #[default]
+    Arithmetic,
+    ByteArrayManipulation,
+    ByteArrayArithmetic,
+    ByteArrayLogic,
+    Cryptography,
+    LoadingValues,
+    FlowControl,
+    StateAccess,
+    BoxAccess,
+    InnerTransactions,
+}
+
+impl OpGroup {
+    /// Group title as used in the AVM opcode documentation
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Arithmetic => "Arithmetic",
+            Self::ByteArrayManipulation => "Byte Array Manipulation",
+            Self::ByteArrayArithmetic => "Byte Array Arithmetic",
+            Self::ByteArrayLogic => "Byte Array Logic",
+            Self::Cryptography => "Cryptography",
+            Self::LoadingValues => "Loading Values",
+            Self::FlowControl => "Flow Control",
+            Self::StateAccess => "State Access",
+            Self::BoxAccess => "Box Access",
+            Self::InnerTransactions => "Inner Transactions",
+        }
+    }
+}
+
 /// Opcode specification
 #[derive(Debug, Clone)]
 pub struct OpSpec {
@@ -53,10 +237,18 @@ pub struct OpSpec {
     pub min_version: u8,
     /// Execution cost
     pub cost: u64,
-    /// Size in bytes (including immediate values)
+    /// Size in bytes including immediate values, or 1 when their length varies
     pub size: usize,
     /// Human-readable description
     pub description: String,
+    /// Immediate arguments following the opcode byte, in encoding order
+    pub immediates: Vec<Immediate>,
+    /// Types of the values popped from the stack, deepest first
+    pub stack_args: Vec<StackType>,
+    /// Types of the values pushed on the stack, deepest first
+    pub stack_returns: Vec<StackType>,
+    /// Documentation group
+    pub group: OpGroup,
 }
 
 impl OpSpec {
@@ -81,6 +273,10 @@ impl OpSpec {
             cost,
             size,
             description: description.into(),
+            immediates: Vec::new(),
+            stack_args: Vec::new(),
+            stack_returns: Vec::new(),
+            group: OpGroup::default(),
         }
     }
 
@@ -127,13 +323,42 @@ impl OpSpec {
             description,
         )
     }
+
+    /// Attach the immediate arguments read after the opcode byte
+    pub fn with_immediates(mut self, immediates: &[Immediate]) -> Self {
+        self.immediates = immediates.to_vec();
+        self
+    }
+
+    /// Attach the stack signature, written as argument and return type
+    /// codes separated by a colon: `a` for any, `i` for uint64 and `b` for
+    /// bytes, e.g. `"ii:i"` for `+`
+    ///
+    /// Opcodes whose stack effect depends on their immediates record the
+    /// effect of the smallest immediate values.
+    pub fn with_signature(mut self, signature: &str) -> Self {
+        let (args, returns) = signature
+            .split_once(':')
+            .unwrap_or_else(|| panic!("invalid stack signature '{signature}'"));
+        self.stack_args = args.chars().map(StackType::from_code).collect();
+        self.stack_returns = returns.chars().map(StackType::from_code).collect();
+        self
+    }
+
+    /// Set the documentation group
+    pub fn in_group(mut self, group: OpGroup) -> Self {
+        self.group = group;
+        self
+    }
 }
 
 /// Get all standard opcode specifications
 pub fn get_standard_opcodes() -> Vec<OpSpec> {
     vec![
         // Error
-        OpSpec::both_modes(OP_ERR, "err", op_err, 1, 1, 1, "Error. Panic immediately."),
+        OpSpec::both_modes(OP_ERR, "err", op_err, 1, 1, 1, "Error. Panic immediately.")
+            .with_signature(":")
+            .in_group(OpGroup::FlowControl),
         // Arithmetic
         OpSpec::both_modes(
             OP_PLUS,
@@ -143,7 +368,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "A plus B. A and B are zero-terminated big-endian integers.",
-        ),
+        )
+        .with_signature("ii:i")
+        .in_group(OpGroup::Arithmetic),
         OpSpec::both_modes(
             OP_MINUS,
             "-",
@@ -152,7 +379,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "A minus B. A and B are zero-terminated big-endian integers.",
-        ),
+        )
+        .with_signature("ii:i")
+        .in_group(OpGroup::Arithmetic),
         OpSpec::both_modes(
             OP_DIV,
             "/",
@@ -161,7 +390,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "A divided by B. A and B are zero-terminated big-endian integers. Fail if B is zero.",
-        ),
+        )
+        .with_signature("ii:i")
+        .in_group(OpGroup::Arithmetic),
         OpSpec::both_modes(
             OP_MUL,
             "*",
@@ -170,7 +401,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "A times B. A and B are zero-terminated big-endian integers.",
-        ),
+        )
+        .with_signature("ii:i")
+        .in_group(OpGroup::Arithmetic),
         OpSpec::both_modes(
             OP_LT,
             "<",
@@ -179,7 +412,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "A less than B. A and B are zero-terminated big-endian integers.",
-        ),
+        )
+        .with_signature("ii:i")
+        .in_group(OpGroup::Arithmetic),
         OpSpec::both_modes(
             OP_GT,
             ">",
@@ -188,7 +423,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "A greater than B. A and B are zero-terminated big-endian integers.",
-        ),
+        )
+        .with_signature("ii:i")
+        .in_group(OpGroup::Arithmetic),
         OpSpec::both_modes(
             OP_LE,
             "<=",
@@ -197,7 +434,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "A less than or equal to B. A and B are zero-terminated big-endian integers.",
-        ),
+        )
+        .with_signature("ii:i")
+        .in_group(OpGroup::Arithmetic),
         OpSpec::both_modes(
             OP_GE,
             ">=",
@@ -206,7 +445,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "A greater than or equal to B. A and B are zero-terminated big-endian integers.",
-        ),
+        )
+        .with_signature("ii:i")
+        .in_group(OpGroup::Arithmetic),
         OpSpec::both_modes(
             OP_AND,
             "&&",
@@ -215,7 +456,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "A is not zero and B is not zero.",
-        ),
+        )
+        .with_signature("ii:i")
+        .in_group(OpGroup::Arithmetic),
         OpSpec::both_modes(
             OP_OR,
             "||",
@@ -224,10 +467,18 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "A is not zero or B is not zero.",
-        ),
-        OpSpec::both_modes(OP_EQ, "==", op_eq, 1, 1, 1, "A is equal to B."),
-        OpSpec::both_modes(OP_NE, "!=", op_ne, 1, 1, 1, "A is not equal to B."),
-        OpSpec::both_modes(OP_NOT, "!", op_not, 1, 1, 1, "A is zero."),
+        )
+        .with_signature("ii:i")
+        .in_group(OpGroup::Arithmetic),
+        OpSpec::both_modes(OP_EQ, "==", op_eq, 1, 1, 1, "A is equal to B.")
+            .with_signature("aa:i")
+            .in_group(OpGroup::Arithmetic),
+        OpSpec::both_modes(OP_NE, "!=", op_ne, 1, 1, 1, "A is not equal to B.")
+            .with_signature("aa:i")
+            .in_group(OpGroup::Arithmetic),
+        OpSpec::both_modes(OP_NOT, "!", op_not, 1, 1, 1, "A is zero.")
+            .with_signature("i:i")
+            .in_group(OpGroup::Arithmetic),
         OpSpec::both_modes(
             OP_MOD,
             "%",
@@ -236,7 +487,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "A modulo B. A and B are zero-terminated big-endian integers. Fail if B is zero.",
-        ),
+        )
+        .with_signature("ii:i")
+        .in_group(OpGroup::Arithmetic),
         OpSpec::both_modes(
             OP_BITWISE_OR,
             "|",
@@ -245,7 +498,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "A bitwise-or B. A and B are zero-terminated big-endian integers.",
-        ),
+        )
+        .with_signature("ii:i")
+        .in_group(OpGroup::Arithmetic),
         OpSpec::both_modes(
             OP_BITWISE_AND,
             "&",
@@ -254,7 +509,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "A bitwise-and B. A and B are zero-terminated big-endian integers.",
-        ),
+        )
+        .with_signature("ii:i")
+        .in_group(OpGroup::Arithmetic),
         OpSpec::both_modes(
             OP_BITWISE_XOR,
             "^",
@@ -263,7 +520,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "A bitwise-xor B. A and B are zero-terminated big-endian integers.",
-        ),
+        )
+        .with_signature("ii:i")
+        .in_group(OpGroup::Arithmetic),
         OpSpec::both_modes(
             OP_BITWISE_NOT,
             "~",
@@ -272,7 +531,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "bitwise invert value A.",
-        ),
+        )
+        .with_signature("i:i")
+        .in_group(OpGroup::Arithmetic),
         // Advanced arithmetic operations
         OpSpec::both_modes(
             OP_MULW,
@@ -282,7 +543,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             10,
             1,
             "Multiply with overflow - returns low and high words.",
-        ),
+        )
+        .with_signature("ii:ii")
+        .in_group(OpGroup::Arithmetic),
         OpSpec::both_modes(
             OP_ADDW,
             "addw",
@@ -291,7 +554,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             10,
             1,
             "Add with overflow - returns low and high words.",
-        ),
+        )
+        .with_signature("ii:ii")
+        .in_group(OpGroup::Arithmetic),
         OpSpec::both_modes(
             OP_DIVMODW,
             "divmodw",
@@ -300,12 +565,24 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             20,
             1,
             "Division with remainder - returns quotient high, low, remainder.",
-        ),
-        OpSpec::both_modes(OP_SHL, "shl", op_shl, 4, 1, 1, "Shift left."),
-        OpSpec::both_modes(OP_SHR, "shr", op_shr, 4, 1, 1, "Shift right."),
-        OpSpec::both_modes(OP_SQRT, "sqrt", op_sqrt, 4, 4, 1, "Square root."),
-        OpSpec::both_modes(OP_BITLEN, "bitlen", op_bitlen, 4, 1, 1, "Bit length."),
-        OpSpec::both_modes(OP_EXP, "exp", op_exp, 4, 1, 1, "Exponentiation."),
+        )
+        .with_signature("iiii:iiii")
+        .in_group(OpGroup::Arithmetic),
+        OpSpec::both_modes(OP_SHL, "shl", op_shl, 4, 1, 1, "Shift left.")
+            .with_signature("ii:i")
+            .in_group(OpGroup::Arithmetic),
+        OpSpec::both_modes(OP_SHR, "shr", op_shr, 4, 1, 1, "Shift right.")
+            .with_signature("ii:i")
+            .in_group(OpGroup::Arithmetic),
+        OpSpec::both_modes(OP_SQRT, "sqrt", op_sqrt, 4, 4, 1, "Square root.")
+            .with_signature("i:i")
+            .in_group(OpGroup::Arithmetic),
+        OpSpec::both_modes(OP_BITLEN, "bitlen", op_bitlen, 4, 1, 1, "Bit length.")
+            .with_signature("a:i")
+            .in_group(OpGroup::Arithmetic),
+        OpSpec::both_modes(OP_EXP, "exp", op_exp, 4, 1, 1, "Exponentiation.")
+            .with_signature("ii:i")
+            .in_group(OpGroup::Arithmetic),
         OpSpec::both_modes(
             OP_EXPW,
             "expw",
@@ -314,8 +591,12 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             10,
             1,
             "Exponentiation with overflow.",
-        ),
-        OpSpec::both_modes(OP_BSQRT, "bsqrt", op_bsqrt, 6, 40, 1, "Byte square root."),
+        )
+        .with_signature("ii:ii")
+        .in_group(OpGroup::Arithmetic),
+        OpSpec::both_modes(OP_BSQRT, "bsqrt", op_bsqrt, 6, 40, 1, "Byte square root.")
+            .with_signature("b:b")
+            .in_group(OpGroup::ByteArrayArithmetic),
         OpSpec::both_modes(
             OP_DIVW,
             "divw",
@@ -324,7 +605,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             20,
             1,
             "Division with overflow.",
-        ),
+        )
+        .with_signature("iii:i")
+        .in_group(OpGroup::Arithmetic),
         // Stack manipulation
         OpSpec::both_modes(
             OP_POP,
@@ -334,25 +617,33 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Remove the top stack value.",
-        ),
+        )
+        .with_signature("a:")
+        .in_group(OpGroup::FlowControl),
         OpSpec::both_modes(
             OP_POPN,
             "popn",
             op_popn,
-            1,
+            8,
             1,
             2,
             "Remove N values from stack.",
-        ),
+        )
+        .with_immediates(&[Immediate::uint8("n")])
+        .with_signature(":")
+        .in_group(OpGroup::FlowControl),
         OpSpec::both_modes(
             OP_DUPN,
             "dupn",
             op_dupn,
-            1,
+            8,
             1,
             2,
             "Duplicate Nth value from top.",
-        ),
+        )
+        .with_immediates(&[Immediate::uint8("n")])
+        .with_signature("a:a")
+        .in_group(OpGroup::FlowControl),
         OpSpec::both_modes(
             OP_DUP,
             "dup",
@@ -361,7 +652,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Duplicate the top stack value.",
-        ),
+        )
+        .with_signature("a:aa")
+        .in_group(OpGroup::FlowControl),
         OpSpec::both_modes(
             OP_DUP2,
             "dup2",
@@ -370,7 +663,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Duplicate top two stack values.",
-        ),
+        )
+        .with_signature("aa:aaaa")
+        .in_group(OpGroup::FlowControl),
         OpSpec::both_modes(
             OP_SWAP,
             "swap",
@@ -379,7 +674,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Swap the top two stack values.",
-        ),
+        )
+        .with_signature("aa:aa")
+        .in_group(OpGroup::FlowControl),
         OpSpec::both_modes(
             OP_SELECT,
             "select",
@@ -388,7 +685,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Select between two values based on condition.",
-        ),
+        )
+        .with_signature("aai:a")
+        .in_group(OpGroup::FlowControl),
         // Advanced stack operations
         OpSpec::both_modes(
             OP_BURY,
@@ -398,38 +697,59 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             2,
             "Bury value n deep in stack.",
-        ),
+        )
+        .with_immediates(&[Immediate::uint8("n")])
+        .with_signature("a:")
+        .in_group(OpGroup::FlowControl),
         OpSpec::both_modes(
             OP_DIG,
             "dig",
             op_dig,
-            8,
+            3,
             1,
             2,
             "Dig value n deep from stack.",
-        ),
+        )
+        .with_immediates(&[Immediate::uint8("n")])
+        .with_signature("a:aa")
+        .in_group(OpGroup::FlowControl),
         OpSpec::both_modes(
             OP_COVER,
             "cover",
             op_cover,
-            8,
+            5,
             1,
             2,
             "Cover top value with n values.",
-        ),
+        )
+        .with_immediates(&[Immediate::uint8("n")])
+        .with_signature("a:a")
+        .in_group(OpGroup::FlowControl),
         OpSpec::both_modes(
             OP_UNCOVER,
             "uncover",
             op_uncover,
-            8,
+            5,
             1,
             2,
             "Uncover value from n deep.",
-        ),
+        )
+        .with_immediates(&[Immediate::uint8("n")])
+        .with_signature("a:a")
+        .in_group(OpGroup::FlowControl),
         // Flow control
-        OpSpec::both_modes(OP_BNZ, "bnz", op_bnz, 1, 1, 3, "Branch if not zero."),
-        OpSpec::both_modes(OP_BZ, "bz", op_bz, 2, 1, 3, "Branch if zero."),
-        OpSpec::both_modes(OP_B, "b", op_b, 2, 1, 3, "Unconditional branch."),
+        OpSpec::both_modes(OP_BNZ, "bnz", op_bnz, 1, 1, 3, "Branch if not zero.")
+            .with_immediates(&[Immediate::label("target")])
+            .with_signature("i:")
+            .in_group(OpGroup::FlowControl),
+        OpSpec::both_modes(OP_BZ, "bz", op_bz, 2, 1, 3, "Branch if zero.")
+            .with_immediates(&[Immediate::label("target")])
+            .with_signature("i:")
+            .in_group(OpGroup::FlowControl),
+        OpSpec::both_modes(OP_B, "b", op_b, 2, 1, 3, "Unconditional branch.")
+            .with_immediates(&[Immediate::label("target")])
+            .with_signature(":")
+            .in_group(OpGroup::FlowControl),
         OpSpec::both_modes(
             OP_RETURN,
             "return",
@@ -438,7 +758,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Return from program.",
-        ),
+        )
+        .with_signature("i:")
+        .in_group(OpGroup::FlowControl),
         OpSpec::both_modes(
             OP_ASSERT,
             "assert",
@@ -447,7 +769,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Assert that A is not zero.",
-        ),
+        )
+        .with_signature("i:")
+        .in_group(OpGroup::FlowControl),
         OpSpec::both_modes(
             OP_CALLSUB,
             "callsub",
@@ -456,7 +780,10 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             3,
             "Call subroutine.",
-        ),
+        )
+        .with_immediates(&[Immediate::label("target")])
+        .with_signature(":")
+        .in_group(OpGroup::FlowControl),
         OpSpec::both_modes(
             OP_RETSUB,
             "retsub",
@@ -465,7 +792,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Return from subroutine.",
-        ),
+        )
+        .with_signature(":")
+        .in_group(OpGroup::FlowControl),
         // Function control operations
         OpSpec::both_modes(
             OP_PROTO,
@@ -475,7 +804,10 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             3,
             "Function prototype declaration.",
-        ),
+        )
+        .with_immediates(&[Immediate::uint8("a"), Immediate::uint8("r")])
+        .with_signature(":")
+        .in_group(OpGroup::FlowControl),
         OpSpec::both_modes(
             OP_FRAME_DIG,
             "frame_dig",
@@ -484,7 +816,10 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             2,
             "Access value from function frame.",
-        ),
+        )
+        .with_immediates(&[Immediate::int8("i")])
+        .with_signature(":a")
+        .in_group(OpGroup::FlowControl),
         OpSpec::both_modes(
             OP_FRAME_BURY,
             "frame_bury",
@@ -493,7 +828,10 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             2,
             "Store value in function frame.",
-        ),
+        )
+        .with_immediates(&[Immediate::int8("i")])
+        .with_signature("a:")
+        .in_group(OpGroup::FlowControl),
         OpSpec::both_modes(
             OP_SWITCH,
             "switch",
@@ -502,7 +840,10 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Switch statement - jump to one of many targets.",
-        ),
+        )
+        .with_immediates(&[Immediate::labels("target ...")])
+        .with_signature("i:")
+        .in_group(OpGroup::FlowControl),
         OpSpec::both_modes(
             OP_MATCH,
             "match",
@@ -511,9 +852,14 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Match statement - matches specific values.",
-        ),
+        )
+        .with_immediates(&[Immediate::labels("target ...")])
+        .with_signature("a:")
+        .in_group(OpGroup::FlowControl),
         // Crypto
-        OpSpec::both_modes(OP_SHA256, "sha256", op_sha256, 1, 35, 1, "SHA256 hash."),
+        OpSpec::both_modes(OP_SHA256, "sha256", op_sha256, 1, 35, 1, "SHA256 hash.")
+            .with_signature("b:b")
+            .in_group(OpGroup::Cryptography),
         OpSpec::both_modes(
             OP_KECCAK256,
             "keccak256",
@@ -522,7 +868,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             130,
             1,
             "Keccak256 hash.",
-        ),
+        )
+        .with_signature("b:b")
+        .in_group(OpGroup::Cryptography),
         OpSpec::both_modes(
             OP_SHA512_256,
             "sha512_256",
@@ -531,16 +879,20 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             45,
             1,
             "SHA512_256 hash.",
-        ),
+        )
+        .with_signature("b:b")
+        .in_group(OpGroup::Cryptography),
         OpSpec::both_modes(
             OP_SHA3_256,
             "sha3_256",
             op_sha3_256,
-            1,
+            7,
             45,
             1,
             "SHA3_256 hash.",
-        ),
+        )
+        .with_signature("b:b")
+        .in_group(OpGroup::Cryptography),
         OpSpec::both_modes(
             OP_ED25519VERIFY,
             "ed25519verify",
@@ -549,80 +901,105 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1900,
             1,
             "Ed25519 signature verification.",
-        ),
+        )
+        .with_signature("bbb:i")
+        .in_group(OpGroup::Cryptography),
         OpSpec::both_modes(
             OP_ED25519VERIFY_BARE,
             "ed25519verify_bare",
             op_ed25519verify_bare,
-            1,
+            7,
             1900,
             1,
             "Ed25519 bare signature verification.",
-        ),
+        )
+        .with_signature("bbb:i")
+        .in_group(OpGroup::Cryptography),
         OpSpec::both_modes(
             OP_ECDSA_VERIFY,
             "ecdsa_verify",
             op_ecdsa_verify,
-            1,
+            5,
             1700,
-            1,
+            2,
             "ECDSA signature verification.",
-        ),
+        )
+        .with_immediates(&[Immediate::field("v", FieldGroup::EcdsaCurve)])
+        .with_signature("bbbbb:i")
+        .in_group(OpGroup::Cryptography),
         OpSpec::both_modes(
             OP_ECDSA_PK_DECOMPRESS,
             "ecdsa_pk_decompress",
             op_ecdsa_pk_decompress,
-            1,
+            5,
             650,
-            1,
+            2,
             "ECDSA public key decompression.",
-        ),
+        )
+        .with_immediates(&[Immediate::field("v", FieldGroup::EcdsaCurve)])
+        .with_signature("b:bb")
+        .in_group(OpGroup::Cryptography),
         OpSpec::both_modes(
             OP_ECDSA_PK_RECOVER,
             "ecdsa_pk_recover",
             op_ecdsa_pk_recover,
-            1,
+            5,
             2000,
-            1,
+            2,
             "ECDSA public key recovery.",
-        ),
+        )
+        .with_immediates(&[Immediate::field("v", FieldGroup::EcdsaCurve)])
+        .with_signature("bibb:bb")
+        .in_group(OpGroup::Cryptography),
         // Constants
         OpSpec::both_modes(
             OP_PUSHINT,
             "pushint",
             op_pushint,
+            3,
             1,
             1,
-            9,
             "Push immediate integer.",
-        ),
+        )
+        .with_immediates(&[Immediate::varuint("uint")])
+        .with_signature(":i")
+        .in_group(OpGroup::LoadingValues),
         OpSpec::both_modes(
             OP_PUSHBYTES,
             "pushbytes",
             op_pushbytes,
-            1,
+            3,
             1,
             1,
             "Push immediate bytes.",
-        ),
+        )
+        .with_immediates(&[Immediate::bytes("bytes")])
+        .with_signature(":b")
+        .in_group(OpGroup::LoadingValues),
         OpSpec::both_modes(
             OP_PUSHBYTESS,
             "pushbytess",
             op_pushbytess,
-            1,
+            8,
             1,
             1,
             "Push multiple immediate byte arrays.",
-        ),
+        )
+        .with_immediates(&[Immediate::bytes_list("bytes ...")])
+        .with_signature(":")
+        .in_group(OpGroup::LoadingValues),
         OpSpec::both_modes(
             OP_PUSHINTS,
             "pushints",
             op_pushints,
-            1,
+            8,
             1,
             1,
             "Push multiple immediate integers.",
-        ),
+        )
+        .with_immediates(&[Immediate::varuints("uint ...")])
+        .with_signature(":")
+        .in_group(OpGroup::LoadingValues),
         // Constant blocks
         OpSpec::both_modes(
             OP_INTCBLOCK,
@@ -632,7 +1009,10 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Define integer constant block.",
-        ),
+        )
+        .with_immediates(&[Immediate::varuints("uint ...")])
+        .with_signature(":")
+        .in_group(OpGroup::LoadingValues),
         OpSpec::both_modes(
             OP_INTC,
             "intc",
@@ -641,7 +1021,10 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             2,
             "Load integer from constant block.",
-        ),
+        )
+        .with_immediates(&[Immediate::uint8("i")])
+        .with_signature(":i")
+        .in_group(OpGroup::LoadingValues),
         OpSpec::both_modes(
             OP_BYTECBLOCK,
             "bytecblock",
@@ -650,7 +1033,10 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Define byte constant block.",
-        ),
+        )
+        .with_immediates(&[Immediate::bytes_list("bytes ...")])
+        .with_signature(":")
+        .in_group(OpGroup::LoadingValues),
         OpSpec::both_modes(
             OP_BYTEC,
             "bytec",
@@ -659,7 +1045,10 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             2,
             "Load bytes from constant block.",
-        ),
+        )
+        .with_immediates(&[Immediate::uint8("i")])
+        .with_signature(":b")
+        .in_group(OpGroup::LoadingValues),
         // Integer constants
         OpSpec::both_modes(
             OP_INTC_0,
@@ -669,7 +1058,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Push integer constant 0.",
-        ),
+        )
+        .with_signature(":i")
+        .in_group(OpGroup::LoadingValues),
         OpSpec::both_modes(
             OP_INTC_1,
             "intc_1",
@@ -678,7 +1069,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Push integer constant 1.",
-        ),
+        )
+        .with_signature(":i")
+        .in_group(OpGroup::LoadingValues),
         OpSpec::both_modes(
             OP_INTC_2,
             "intc_2",
@@ -687,7 +1080,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Push integer constant 2.",
-        ),
+        )
+        .with_signature(":i")
+        .in_group(OpGroup::LoadingValues),
         OpSpec::both_modes(
             OP_INTC_3,
             "intc_3",
@@ -696,7 +1091,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Push integer constant 3.",
-        ),
+        )
+        .with_signature(":i")
+        .in_group(OpGroup::LoadingValues),
         // Byte constants
         OpSpec::both_modes(
             OP_BYTEC_0,
@@ -706,7 +1103,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Push byte constant 0.",
-        ),
+        )
+        .with_signature(":b")
+        .in_group(OpGroup::LoadingValues),
         OpSpec::both_modes(
             OP_BYTEC_1,
             "bytec_1",
@@ -715,7 +1114,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Push byte constant 1.",
-        ),
+        )
+        .with_signature(":b")
+        .in_group(OpGroup::LoadingValues),
         OpSpec::both_modes(
             OP_BYTEC_2,
             "bytec_2",
@@ -724,7 +1125,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Push byte constant 2.",
-        ),
+        )
+        .with_signature(":b")
+        .in_group(OpGroup::LoadingValues),
         OpSpec::both_modes(
             OP_BYTEC_3,
             "bytec_3",
@@ -733,9 +1136,13 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Push byte constant 3.",
-        ),
+        )
+        .with_signature(":b")
+        .in_group(OpGroup::LoadingValues),
         // Utility
-        OpSpec::both_modes(OP_LEN, "len", op_len, 1, 1, 1, "Length of byte string."),
+        OpSpec::both_modes(OP_LEN, "len", op_len, 1, 1, 1, "Length of byte string.")
+            .with_signature("b:i")
+            .in_group(OpGroup::Arithmetic),
         OpSpec::both_modes(
             OP_ITOB,
             "itob",
@@ -744,7 +1151,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Convert integer to bytes.",
-        ),
+        )
+        .with_signature("i:b")
+        .in_group(OpGroup::Arithmetic),
         OpSpec::both_modes(
             OP_BTOI,
             "btoi",
@@ -753,7 +1162,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Convert bytes to integer.",
-        ),
+        )
+        .with_signature("b:i")
+        .in_group(OpGroup::Arithmetic),
         OpSpec::both_modes(
             OP_CONCAT,
             "concat",
@@ -762,7 +1173,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Concatenate two byte strings.",
-        ),
+        )
+        .with_signature("bb:b")
+        .in_group(OpGroup::ByteArrayManipulation),
         OpSpec::both_modes(
             OP_SUBSTRING,
             "substring",
@@ -771,7 +1184,10 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             3,
             "Extract substring.",
-        ),
+        )
+        .with_immediates(&[Immediate::uint8("s"), Immediate::uint8("e")])
+        .with_signature("b:b")
+        .in_group(OpGroup::ByteArrayManipulation),
         OpSpec::both_modes(
             OP_SUBSTRING3,
             "substring3",
@@ -780,24 +1196,42 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Extract substring with stack args.",
-        ),
+        )
+        .with_signature("bii:b")
+        .in_group(OpGroup::ByteArrayManipulation),
         OpSpec::both_modes(
             OP_BZERO,
             "bzero",
             op_bzero,
-            1,
+            4,
             1,
             1,
             "Create zero-filled byte array.",
-        ),
+        )
+        .with_signature("i:b")
+        .in_group(OpGroup::ByteArrayManipulation),
         // Byte math operations
-        OpSpec::both_modes(OP_B_PLUS, "b+", op_b_plus, 4, 10, 1, "Byte addition."),
-        OpSpec::both_modes(OP_B_MINUS, "b-", op_b_minus, 4, 10, 1, "Byte subtraction."),
-        OpSpec::both_modes(OP_B_DIV, "b/", op_b_div, 4, 20, 1, "Byte division."),
-        OpSpec::both_modes(OP_B_MUL, "b*", op_b_mul, 4, 20, 1, "Byte multiplication."),
-        OpSpec::both_modes(OP_B_LT, "b<", op_b_lt, 4, 1, 1, "Byte less than."),
-        OpSpec::both_modes(OP_B_GT, "b>", op_b_gt, 4, 1, 1, "Byte greater than."),
-        OpSpec::both_modes(OP_B_LE, "b<=", op_b_le, 4, 1, 1, "Byte less than or equal."),
+        OpSpec::both_modes(OP_B_PLUS, "b+", op_b_plus, 4, 10, 1, "Byte addition.")
+            .with_signature("bb:b")
+            .in_group(OpGroup::ByteArrayArithmetic),
+        OpSpec::both_modes(OP_B_MINUS, "b-", op_b_minus, 4, 10, 1, "Byte subtraction.")
+            .with_signature("bb:b")
+            .in_group(OpGroup::ByteArrayArithmetic),
+        OpSpec::both_modes(OP_B_DIV, "b/", op_b_div, 4, 20, 1, "Byte division.")
+            .with_signature("bb:b")
+            .in_group(OpGroup::ByteArrayArithmetic),
+        OpSpec::both_modes(OP_B_MUL, "b*", op_b_mul, 4, 20, 1, "Byte multiplication.")
+            .with_signature("bb:b")
+            .in_group(OpGroup::ByteArrayArithmetic),
+        OpSpec::both_modes(OP_B_LT, "b<", op_b_lt, 4, 1, 1, "Byte less than.")
+            .with_signature("bb:i")
+            .in_group(OpGroup::ByteArrayArithmetic),
+        OpSpec::both_modes(OP_B_GT, "b>", op_b_gt, 4, 1, 1, "Byte greater than.")
+            .with_signature("bb:i")
+            .in_group(OpGroup::ByteArrayArithmetic),
+        OpSpec::both_modes(OP_B_LE, "b<=", op_b_le, 4, 1, 1, "Byte less than or equal.")
+            .with_signature("bb:i")
+            .in_group(OpGroup::ByteArrayArithmetic),
         OpSpec::both_modes(
             OP_B_GE,
             "b>=",
@@ -806,14 +1240,30 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Byte greater than or equal.",
-        ),
-        OpSpec::both_modes(OP_B_EQ, "b==", op_b_eq, 4, 1, 1, "Byte equal."),
-        OpSpec::both_modes(OP_B_NE, "b!=", op_b_ne, 4, 1, 1, "Byte not equal."),
-        OpSpec::both_modes(OP_B_MOD, "b%", op_b_mod, 4, 20, 1, "Byte modulo."),
-        OpSpec::both_modes(OP_B_OR, "b|", op_b_or, 4, 6, 1, "Byte bitwise OR."),
-        OpSpec::both_modes(OP_B_AND, "b&", op_b_and, 4, 6, 1, "Byte bitwise AND."),
-        OpSpec::both_modes(OP_B_XOR, "b^", op_b_xor, 4, 6, 1, "Byte bitwise XOR."),
-        OpSpec::both_modes(OP_B_NOT, "b~", op_b_not, 4, 4, 1, "Byte bitwise NOT."),
+        )
+        .with_signature("bb:i")
+        .in_group(OpGroup::ByteArrayArithmetic),
+        OpSpec::both_modes(OP_B_EQ, "b==", op_b_eq, 4, 1, 1, "Byte equal.")
+            .with_signature("bb:i")
+            .in_group(OpGroup::ByteArrayArithmetic),
+        OpSpec::both_modes(OP_B_NE, "b!=", op_b_ne, 4, 1, 1, "Byte not equal.")
+            .with_signature("bb:i")
+            .in_group(OpGroup::ByteArrayArithmetic),
+        OpSpec::both_modes(OP_B_MOD, "b%", op_b_mod, 4, 20, 1, "Byte modulo.")
+            .with_signature("bb:b")
+            .in_group(OpGroup::ByteArrayArithmetic),
+        OpSpec::both_modes(OP_B_OR, "b|", op_b_or, 4, 6, 1, "Byte bitwise OR.")
+            .with_signature("bb:b")
+            .in_group(OpGroup::ByteArrayLogic),
+        OpSpec::both_modes(OP_B_AND, "b&", op_b_and, 4, 6, 1, "Byte bitwise AND.")
+            .with_signature("bb:b")
+            .in_group(OpGroup::ByteArrayLogic),
+        OpSpec::both_modes(OP_B_XOR, "b^", op_b_xor, 4, 6, 1, "Byte bitwise XOR.")
+            .with_signature("bb:b")
+            .in_group(OpGroup::ByteArrayLogic),
+        OpSpec::both_modes(OP_B_NOT, "b~", op_b_not, 4, 4, 1, "Byte bitwise NOT.")
+            .with_signature("b:b")
+            .in_group(OpGroup::ByteArrayLogic),
         // Byte manipulation operations
         OpSpec::both_modes(
             OP_GETBIT,
@@ -823,8 +1273,12 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Get bit from bytes.",
-        ),
-        OpSpec::both_modes(OP_SETBIT, "setbit", op_setbit, 3, 1, 1, "Set bit in bytes."),
+        )
+        .with_signature("ai:i")
+        .in_group(OpGroup::ByteArrayManipulation),
+        OpSpec::both_modes(OP_SETBIT, "setbit", op_setbit, 3, 1, 1, "Set bit in bytes.")
+            .with_signature("aii:a")
+            .in_group(OpGroup::ByteArrayManipulation),
         OpSpec::both_modes(
             OP_GETBYTE,
             "getbyte",
@@ -833,7 +1287,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Get byte from bytes.",
-        ),
+        )
+        .with_signature("bi:i")
+        .in_group(OpGroup::ByteArrayManipulation),
         OpSpec::both_modes(
             OP_SETBYTE,
             "setbyte",
@@ -842,7 +1298,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Set byte in bytes.",
-        ),
+        )
+        .with_signature("bii:b")
+        .in_group(OpGroup::ByteArrayManipulation),
         OpSpec::both_modes(
             OP_EXTRACT,
             "extract",
@@ -851,7 +1309,10 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             3,
             "Extract bytes with immediate start and length.",
-        ),
+        )
+        .with_immediates(&[Immediate::uint8("s"), Immediate::uint8("l")])
+        .with_signature("b:b")
+        .in_group(OpGroup::ByteArrayManipulation),
         OpSpec::both_modes(
             OP_EXTRACT3,
             "extract3",
@@ -860,7 +1321,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Extract bytes with stack arguments.",
-        ),
+        )
+        .with_signature("bii:b")
+        .in_group(OpGroup::ByteArrayManipulation),
         OpSpec::both_modes(
             OP_EXTRACT_UINT16,
             "extract_uint16",
@@ -869,7 +1332,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Extract uint16 from bytes.",
-        ),
+        )
+        .with_signature("bi:i")
+        .in_group(OpGroup::ByteArrayManipulation),
         OpSpec::both_modes(
             OP_EXTRACT_UINT32,
             "extract_uint32",
@@ -878,7 +1343,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Extract uint32 from bytes.",
-        ),
+        )
+        .with_signature("bi:i")
+        .in_group(OpGroup::ByteArrayManipulation),
         OpSpec::both_modes(
             OP_EXTRACT_UINT64,
             "extract_uint64",
@@ -887,7 +1354,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Extract uint64 from bytes.",
-        ),
+        )
+        .with_signature("bi:i")
+        .in_group(OpGroup::ByteArrayManipulation),
         OpSpec::both_modes(
             OP_REPLACE2,
             "replace2",
@@ -896,7 +1365,10 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             2,
             "Replace bytes with immediate start.",
-        ),
+        )
+        .with_immediates(&[Immediate::uint8("s")])
+        .with_signature("bb:b")
+        .in_group(OpGroup::ByteArrayManipulation),
         OpSpec::both_modes(
             OP_REPLACE3,
             "replace3",
@@ -905,7 +1377,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Replace bytes with stack arguments.",
-        ),
+        )
+        .with_signature("bib:b")
+        .in_group(OpGroup::ByteArrayManipulation),
         OpSpec::both_modes(
             OP_BASE64_DECODE,
             "base64_decode",
@@ -914,7 +1388,10 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             2,
             "Base64 decode.",
-        ),
+        )
+        .with_immediates(&[Immediate::field("e", FieldGroup::Base64Encoding)])
+        .with_signature("b:b")
+        .in_group(OpGroup::ByteArrayManipulation),
         OpSpec::both_modes(
             OP_JSON_REF,
             "json_ref",
@@ -923,21 +1400,35 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             25,
             2,
             "JSON reference.",
-        ),
+        )
+        .with_immediates(&[Immediate::field("r", FieldGroup::JsonRefType)])
+        .with_signature("bb:a")
+        .in_group(OpGroup::ByteArrayManipulation),
         // Argument access
         OpSpec::both_modes(
             OP_ARG,
             "arg",
             op_arg,
-            2,
+            1,
             1,
             2,
             "Access argument with immediate index.",
-        ),
-        OpSpec::both_modes(OP_ARG_0, "arg_0", op_arg_0, 2, 1, 1, "Access argument 0."),
-        OpSpec::both_modes(OP_ARG_1, "arg_1", op_arg_1, 2, 1, 1, "Access argument 1."),
-        OpSpec::both_modes(OP_ARG_2, "arg_2", op_arg_2, 2, 1, 1, "Access argument 2."),
-        OpSpec::both_modes(OP_ARG_3, "arg_3", op_arg_3, 2, 1, 1, "Access argument 3."),
+        )
+        .with_immediates(&[Immediate::uint8("n")])
+        .with_signature(":b")
+        .in_group(OpGroup::LoadingValues),
+        OpSpec::both_modes(OP_ARG_0, "arg_0", op_arg_0, 1, 1, 1, "Access argument 0.")
+            .with_signature(":b")
+            .in_group(OpGroup::LoadingValues),
+        OpSpec::both_modes(OP_ARG_1, "arg_1", op_arg_1, 1, 1, 1, "Access argument 1.")
+            .with_signature(":b")
+            .in_group(OpGroup::LoadingValues),
+        OpSpec::both_modes(OP_ARG_2, "arg_2", op_arg_2, 1, 1, 1, "Access argument 2.")
+            .with_signature(":b")
+            .in_group(OpGroup::LoadingValues),
+        OpSpec::both_modes(OP_ARG_3, "arg_3", op_arg_3, 1, 1, 1, "Access argument 3.")
+            .with_signature(":b")
+            .in_group(OpGroup::LoadingValues),
         OpSpec::both_modes(
             OP_ARGS,
             "args",
@@ -946,7 +1437,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Access arguments with stack index.",
-        ),
+        )
+        .with_signature("i:b")
+        .in_group(OpGroup::LoadingValues),
         // Scratch space
         OpSpec::both_modes(
             OP_LOAD,
@@ -956,7 +1449,10 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             2,
             "Load from scratch space.",
-        ),
+        )
+        .with_immediates(&[Immediate::uint8("i")])
+        .with_signature(":a")
+        .in_group(OpGroup::LoadingValues),
         OpSpec::both_modes(
             OP_STORE,
             "store",
@@ -965,9 +1461,95 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             2,
             "Store to scratch space.",
-        ),
+        )
+        .with_immediates(&[Immediate::uint8("i")])
+        .with_signature("a:")
+        .in_group(OpGroup::LoadingValues),
+        OpSpec::both_modes(
+            OP_LOADS,
+            "loads",
+            op_loads,
+            5,
+            1,
+            1,
+            "Load from scratch space with stack index.",
+        )
+        .with_signature("i:a")
+        .in_group(OpGroup::LoadingValues),
+        OpSpec::both_modes(
+            OP_STORES,
+            "stores",
+            op_stores,
+            5,
+            1,
+            1,
+            "Store to scratch space with stack index.",
+        )
+        .with_signature("ia:")
+        .in_group(OpGroup::LoadingValues),
+        OpSpec::app_only(
+            OP_GLOAD,
+            "gload",
+            op_gload,
+            4,
+            1,
+            3,
+            "Load scratch space of an earlier transaction in the group.",
+        )
+        .with_immediates(&[Immediate::uint8("t"), Immediate::uint8("i")])
+        .with_signature(":a")
+        .in_group(OpGroup::LoadingValues),
+        OpSpec::app_only(
+            OP_GLOADS,
+            "gloads",
+            op_gloads,
+            4,
+            1,
+            2,
+            "Load scratch space of an earlier transaction with stack group index.",
+        )
+        .with_immediates(&[Immediate::uint8("i")])
+        .with_signature("i:a")
+        .in_group(OpGroup::LoadingValues),
+        OpSpec::app_only(
+            OP_GLOADSS,
+            "gloadss",
+            op_gloadss,
+            6,
+            1,
+            1,
+            "Load scratch space of an earlier transaction with stack group index and slot.",
+        )
+        .with_signature("ii:a")
+        .in_group(OpGroup::LoadingValues),
+        OpSpec::app_only(
+            OP_GAID,
+            "gaid",
+            op_gaid,
+            4,
+            1,
+            2,
+            "ID of the asset or application created by an earlier transaction.",
+        )
+        .with_immediates(&[Immediate::uint8("t")])
+        .with_signature(":i")
+        .in_group(OpGroup::LoadingValues),
+        OpSpec::app_only(
+            OP_GAIDS,
+            "gaids",
+            op_gaids,
+            4,
+            1,
+            1,
+            "ID of the asset or application created by an earlier transaction with stack index.",
+        )
+        .with_signature("i:i")
+        .in_group(OpGroup::LoadingValues),
         // Transaction fields (signature mode)
-        OpSpec::both_modes(OP_TXN, "txn", op_txn, 1, 1, 2, "Access transaction field."),
+        OpSpec::both_modes(OP_TXN, "txn", op_txn, 1, 1, 2, "Access transaction field.")
+            .with_immediates(&[Immediate::field("f", FieldGroup::Txn)])
+            .with_signature(":a")
+            .in_group(OpGroup::LoadingValues),
         OpSpec::both_modes(
             OP_GTXN,
             "gtxn",
@@ -976,7 +1558,13 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             3,
             "Access group transaction field.",
-        ),
+        )
+        .with_immediates(&[
+            Immediate::uint8("t"),
+            Immediate::field("f", FieldGroup::Txn),
+        ])
+        .with_signature(":a")
+        .in_group(OpGroup::LoadingValues),
         OpSpec::both_modes(
             OP_GLOBAL,
             "global",
@@ -985,34 +1573,79 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             2,
             "Access global field.",
-        ),
+        )
+        .with_immediates(&[Immediate::field("f", FieldGroup::Global)])
+        .with_signature(":a")
+        .in_group(OpGroup::LoadingValues),
         OpSpec::both_modes(
             OP_TXNA,
             "txna",
             op_txna,
-            1,
+            2,
             1,
             3,
             "Access transaction field array.",
-        ),
+        )
+        .with_immediates(&[
+            Immediate::field("f", FieldGroup::Txn),
+            Immediate::uint8("i"),
+        ])
+        .with_signature(":a")
+        .in_group(OpGroup::LoadingValues),
         OpSpec::both_modes(
             OP_GTXNSA,
             "gtxnsa",
             op_gtxnsa,
+            3,
             1,
+            3,
+            "Access group transaction field array with stack group index.",
+        )
+        .with_immediates(&[
+            Immediate::field("f", FieldGroup::Txn),
+            Immediate::uint8("i"),
+        ])
+        .with_signature("i:a")
+        .in_group(OpGroup::LoadingValues),
+        OpSpec::both_modes(
+            OP_GTXNAS,
+            "gtxnas",
+            op_gtxnas,
+            5,
             1,
-            4,
+            3,
+            "Access group transaction field array with stack array index.",
+        )
+        .with_immediates(&[
+            Immediate::uint8("t"),
+            Immediate::field("f", FieldGroup::Txn),
+        ])
+        .with_signature("i:a")
+        .in_group(OpGroup::LoadingValues),
+        OpSpec::both_modes(
+            OP_GTXNSAS,
+            "gtxnsas",
+            op_gtxnsas,
+            5,
+            1,
+            2,
             "Access group transaction field array with stack indices.",
-        ),
+        )
+        .with_immediates(&[Immediate::field("f", FieldGroup::Txn)])
+        .with_signature("ii:a")
+        .in_group(OpGroup::LoadingValues),
         OpSpec::both_modes(
             OP_TXNAS,
             "txnas",
             op_txnas,
-            1,
+            5,
             1,
             2,
             "Access transaction field array with stack index.",
-        ),
+        )
+        .with_immediates(&[Immediate::field("f", FieldGroup::Txn)])
+        .with_signature("i:a")
+        .in_group(OpGroup::LoadingValues),
         // Application state (application mode only)
         OpSpec::app_only(
             OP_APP_GLOBAL_GET,
@@ -1022,7 +1655,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Get global state.",
-        ),
+        )
+        .with_signature("b:a")
+        .in_group(OpGroup::StateAccess),
         OpSpec::app_only(
             OP_APP_GLOBAL_PUT,
             "app_global_put",
@@ -1031,7 +1666,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Put global state.",
-        ),
+        )
+        .with_signature("ba:")
+        .in_group(OpGroup::StateAccess),
         OpSpec::app_only(
             OP_APP_GLOBAL_DEL,
             "app_global_del",
@@ -1040,7 +1677,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Delete global state.",
-        ),
+        )
+        .with_signature("b:")
+        .in_group(OpGroup::StateAccess),
         OpSpec::app_only(
             OP_APP_LOCAL_GET,
             "app_local_get",
@@ -1049,7 +1688,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Get local state.",
-        ),
+        )
+        .with_signature("ab:a")
+        .in_group(OpGroup::StateAccess),
         OpSpec::app_only(
             OP_APP_LOCAL_PUT,
             "app_local_put",
@@ -1058,7 +1699,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Put local state.",
-        ),
+        )
+        .with_signature("aba:")
+        .in_group(OpGroup::StateAccess),
         OpSpec::app_only(
             OP_APP_LOCAL_DEL,
             "app_local_del",
@@ -1067,7 +1710,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Delete local state.",
-        ),
+        )
+        .with_signature("ab:")
+        .in_group(OpGroup::StateAccess),
         OpSpec::app_only(
             OP_BALANCE,
             "balance",
@@ -1076,7 +1721,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Get account balance.",
-        ),
+        )
+        .with_signature("a:i")
+        .in_group(OpGroup::StateAccess),
         OpSpec::app_only(
             OP_MIN_BALANCE,
             "min_balance",
@@ -1085,7 +1732,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Get minimum balance.",
-        ),
+        )
+        .with_signature("a:i")
+        .in_group(OpGroup::StateAccess),
         // Critical missing state opcodes
         OpSpec::app_only(
             OP_APP_OPTED_IN,
@@ -1095,7 +1744,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Check if account is opted into application.",
-        ),
+        )
+        .with_signature("ai:i")
+        .in_group(OpGroup::StateAccess),
         OpSpec::app_only(
             OP_APP_LOCAL_GET_EX,
             "app_local_get_ex",
@@ -1104,7 +1755,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Get local state (extended).",
-        ),
+        )
+        .with_signature("aib:ai")
+        .in_group(OpGroup::StateAccess),
         OpSpec::app_only(
             OP_APP_GLOBAL_GET_EX,
             "app_global_get_ex",
@@ -1113,7 +1766,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Get global state (extended).",
-        ),
+        )
+        .with_signature("ib:ai")
+        .in_group(OpGroup::StateAccess),
         OpSpec::app_only(
             OP_ASSET_HOLDING_GET,
             "asset_holding_get",
@@ -1122,7 +1777,10 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             2,
             "Get asset holding information.",
-        ),
+        )
+        .with_immediates(&[Immediate::field("f", FieldGroup::AssetHolding)])
+        .with_signature("ai:ai")
+        .in_group(OpGroup::StateAccess),
         OpSpec::app_only(
             OP_ASSET_PARAMS_GET,
             "asset_params_get",
@@ -1131,25 +1789,34 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             2,
             "Get asset parameters.",
-        ),
+        )
+        .with_immediates(&[Immediate::field("f", FieldGroup::AssetParams)])
+        .with_signature("i:ai")
+        .in_group(OpGroup::StateAccess),
         OpSpec::app_only(
             OP_APP_PARAMS_GET,
             "app_params_get",
             op_app_params_get,
-            2,
+            5,
             1,
             2,
             "Get application parameters.",
-        ),
+        )
+        .with_immediates(&[Immediate::field("f", FieldGroup::AppParams)])
+        .with_signature("i:ai")
+        .in_group(OpGroup::StateAccess),
         OpSpec::app_only(
             OP_ACCT_PARAMS_GET,
             "acct_params_get",
             op_acct_params_get,
-            2,
+            6,
             1,
             2,
             "Get account parameters.",
-        ),
+        )
+        .with_immediates(&[Immediate::field("f", FieldGroup::AcctParams)])
+        .with_signature("a:ai")
+        .in_group(OpGroup::StateAccess),
         OpSpec::both_modes(
             OP_GTXNA,
             "gtxna",
@@ -1158,7 +1825,14 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             4,
             "Access group transaction field array.",
-        ),
+        )
+        .with_immediates(&[
+            Immediate::uint8("t"),
+            Immediate::field("f", FieldGroup::Txn),
+            Immediate::uint8("i"),
+        ])
+        .with_signature(":a")
+        .in_group(OpGroup::LoadingValues),
         OpSpec::both_modes(
             OP_GTXNS,
             "gtxns",
@@ -1167,7 +1841,10 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             2,
             "Access group transaction field with stack index.",
-        ),
+        )
+        .with_immediates(&[Immediate::field("f", FieldGroup::Txn)])
+        .with_signature("i:a")
+        .in_group(OpGroup::LoadingValues),
         // Inner transaction operations
         OpSpec::app_only(
             OP_LOG,
@@ -1177,7 +1854,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Log event (application mode only).",
-        ),
+        )
+        .with_signature("b:")
+        .in_group(OpGroup::StateAccess),
         OpSpec::app_only(
             OP_ITXN_BEGIN,
             "itxn_begin",
@@ -1186,7 +1865,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Begin construction of an inner transaction.",
-        ),
+        )
+        .with_signature(":")
+        .in_group(OpGroup::InnerTransactions),
         OpSpec::app_only(
             OP_ITXN_FIELD,
             "itxn_field",
@@ -1195,7 +1876,10 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             2,
             "Set field for current inner transaction.",
-        ),
+        )
+        .with_immediates(&[Immediate::field("f", FieldGroup::Txn)])
+        .with_signature("a:")
+        .in_group(OpGroup::InnerTransactions),
         OpSpec::app_only(
             OP_ITXN_SUBMIT,
             "itxn_submit",
@@ -1204,7 +1888,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Submit current inner transaction.",
-        ),
+        )
+        .with_signature(":")
+        .in_group(OpGroup::InnerTransactions),
         OpSpec::app_only(
             OP_ITXN,
             "itxn",
@@ -1213,7 +1899,10 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             2,
             "Access field from last submitted inner transaction.",
-        ),
+        )
+        .with_immediates(&[Immediate::field("f", FieldGroup::Txn)])
+        .with_signature(":a")
+        .in_group(OpGroup::InnerTransactions),
         OpSpec::app_only(
             OP_ITXNA,
             "itxna",
@@ -1222,7 +1911,13 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             3,
             "Access array field from last submitted inner transaction.",
-        ),
+        )
+        .with_immediates(&[
+            Immediate::field("f", FieldGroup::Txn),
+            Immediate::uint8("i"),
+        ])
+        .with_signature(":a")
+        .in_group(OpGroup::InnerTransactions),
         OpSpec::app_only(
             OP_ITXN_NEXT,
             "itxn_next",
@@ -1231,7 +1926,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             1,
             "Begin construction of next inner transaction in group.",
-        ),
+        )
+        .with_signature(":")
+        .in_group(OpGroup::InnerTransactions),
         OpSpec::app_only(
             OP_GITXN,
             "gitxn",
@@ -1240,7 +1937,13 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             3,
             "Access field from specific inner transaction in group.",
-        ),
+        )
+        .with_immediates(&[
+            Immediate::uint8("t"),
+            Immediate::field("f", FieldGroup::Txn),
+        ])
+        .with_signature(":a")
+        .in_group(OpGroup::InnerTransactions),
         OpSpec::app_only(
             OP_GITXNA,
             "gitxna",
@@ -1249,7 +1952,14 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             4,
             "Access array field from specific inner transaction in group.",
-        ),
+        )
+        .with_immediates(&[
+            Immediate::uint8("t"),
+            Immediate::field("f", FieldGroup::Txn),
+            Immediate::uint8("i"),
+        ])
+        .with_signature(":a")
+        .in_group(OpGroup::InnerTransactions),
         OpSpec::app_only(
             OP_ITXNAS,
             "itxnas",
@@ -1258,7 +1968,10 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             2,
             "Access array field from last submitted inner transaction (stack index).",
-        ),
+        )
+        .with_immediates(&[Immediate::field("f", FieldGroup::Txn)])
+        .with_signature("i:a")
+        .in_group(OpGroup::InnerTransactions),
         OpSpec::app_only(
             OP_GITXNAS,
             "gitxnas",
@@ -1267,7 +1980,13 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1,
             3,
             "Access array field from specific inner transaction in group (stack index).",
-        ),
+        )
+        .with_immediates(&[
+            Immediate::uint8("t"),
+            Immediate::field("f", FieldGroup::Txn),
+        ])
+        .with_signature("i:a")
+        .in_group(OpGroup::InnerTransactions),
         // Box storage operations
         OpSpec::app_only(
             OP_BOX_CREATE,
@@ -1277,7 +1996,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             400,
             1,
             "Create a new box with the given name and size.",
-        ),
+        )
+        .with_signature("bi:i")
+        .in_group(OpGroup::BoxAccess),
         OpSpec::app_only(
             OP_BOX_EXTRACT,
             "box_extract",
@@ -1286,7 +2007,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             40,
             1,
             "Extract bytes from a box.",
-        ),
+        )
+        .with_signature("bii:b")
+        .in_group(OpGroup::BoxAccess),
         OpSpec::app_only(
             OP_BOX_REPLACE,
             "box_replace",
@@ -1295,8 +2018,12 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             40,
             1,
             "Replace bytes in a box.",
-        ),
-        OpSpec::app_only(OP_BOX_DEL, "box_del", op_box_del, 8, 40, 1, "Delete a box."),
+        )
+        .with_signature("bib:")
+        .in_group(OpGroup::BoxAccess),
+        OpSpec::app_only(OP_BOX_DEL, "box_del", op_box_del, 8, 40, 1, "Delete a box.")
+            .with_signature("b:i")
+            .in_group(OpGroup::BoxAccess),
         OpSpec::app_only(
             OP_BOX_LEN,
             "box_len",
@@ -1305,7 +2032,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             40,
             1,
             "Get the length of a box.",
-        ),
+        )
+        .with_signature("b:ii")
+        .in_group(OpGroup::BoxAccess),
         OpSpec::app_only(
             OP_BOX_GET,
             "box_get",
@@ -1314,7 +2043,9 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             40,
             1,
             "Get the entire contents of a box.",
-        ),
+        )
+        .with_signature("b:bi")
+        .in_group(OpGroup::BoxAccess),
         OpSpec::app_only(
             OP_BOX_PUT,
             "box_put",
@@ -1323,25 +2054,31 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             40,
             1,
             "Put bytes into a box (overwrite entire contents).",
-        ),
+        )
+        .with_signature("bb:")
+        .in_group(OpGroup::BoxAccess),
         OpSpec::app_only(
             OP_BOX_SPLICE,
             "box_splice",
             op_box_splice,
-            9,
+            10,
             40,
             1,
             "Splice bytes into a box (insert/replace with size change).",
-        ),
+        )
+        .with_signature("biib:")
+        .in_group(OpGroup::BoxAccess),
         OpSpec::app_only(
             OP_BOX_RESIZE,
             "box_resize",
             op_box_resize,
-            9,
+            10,
             40,
             1,
             "Resize a box.",
-        ),
+        )
+        .with_signature("bi:")
+        .in_group(OpGroup::BoxAccess),
         // Elliptic curve operations
         OpSpec::app_only(
             OP_EC_ADD,
@@ -1351,7 +2088,10 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             100,
             2,
             "Add two points on an elliptic curve.",
-        ),
+        )
+        .with_immediates(&[Immediate::field("g", FieldGroup::EcGroup)])
+        .with_signature("bb:b")
+        .in_group(OpGroup::Cryptography),
         OpSpec::app_only(
             OP_EC_SCALAR_MUL,
             "ec_scalar_mul",
@@ -1360,7 +2100,10 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             1000,
             2,
             "Multiply a point by a scalar on an elliptic curve.",
-        ),
+        )
+        .with_immediates(&[Immediate::field("g", FieldGroup::EcGroup)])
+        .with_signature("bb:b")
+        .in_group(OpGroup::Cryptography),
         OpSpec::app_only(
             OP_EC_PAIRING_CHECK,
             "ec_pairing_check",
@@ -1369,7 +2112,10 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             8000,
             2,
             "Check if pairing equation holds for given points.",
-        ),
+        )
+        .with_immediates(&[Immediate::field("g", FieldGroup::EcGroup)])
+        .with_signature("bb:i")
+        .in_group(OpGroup::Cryptography),
         OpSpec::app_only(
             OP_EC_MULTI_SCALAR_MUL,
             "ec_multi_scalar_mul",
@@ -1378,7 +2124,10 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             3000,
             2,
             "Multi-scalar multiplication on elliptic curves.",
-        ),
+        )
+        .with_immediates(&[Immediate::field("g", FieldGroup::EcGroup)])
+        .with_signature("bb:b")
+        .in_group(OpGroup::Cryptography),
         OpSpec::app_only(
             OP_EC_SUBGROUP_CHECK,
             "ec_subgroup_check",
@@ -1387,7 +2136,10 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             500,
             2,
             "Check if a point is in the correct subgroup.",
-        ),
+        )
+        .with_immediates(&[Immediate::field("g", FieldGroup::EcGroup)])
+        .with_signature("b:i")
+        .in_group(OpGroup::Cryptography),
         OpSpec::app_only(
             OP_EC_MAP_TO,
             "ec_map_to",
@@ -1396,7 +2148,10 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             200,
             2,
             "Map field element to curve point.",
-        ),
+        )
+        .with_immediates(&[Immediate::field("g", FieldGroup::EcGroup)])
+        .with_signature("b:b")
+        .in_group(OpGroup::Cryptography),
         // Advanced cryptography operations
         OpSpec::app_only(
             OP_VRF_VERIFY,
@@ -1404,9 +2159,12 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             op_vrf_verify,
             7,
             5700,
-            1,
+            2,
             "Verify a VRF proof and return the VRF output.",
-        ),
+        )
+        .with_immediates(&[Immediate::field("s", FieldGroup::VrfStandard)])
+        .with_signature("bbb:bi")
+        .in_group(OpGroup::Cryptography),
         OpSpec::app_only(
             OP_MIMC,
             "mimc",
@@ -1415,15 +2173,44 @@ pub fn get_standard_opcodes() -> Vec<OpSpec> {
             100,
             2,
             "Advanced cryptographic hash function (MiMC).",
-        ),
+        )
+        .with_immediates(&[Immediate::field("c", FieldGroup::MimcConfig)])
+        .with_signature("b:b")
+        .in_group(OpGroup::Cryptography),
         OpSpec::app_only(
             OP_BLOCK,
             "block",
             op_block,
-            11,
+            7,
             1,
             2,
             "Get random bytes from blockchain randomness beacon.",
-        ),
+        )
+        .with_immediates(&[Immediate::field("f", FieldGroup::Block)])
+        .with_signature("i:a")
+        .in_group(OpGroup::StateAccess),
     ]
 }
+
+/// Standard opcode specifications, built on first use
+pub fn standard_opcodes() -> &'static [OpSpec] {
+    static OPCODES: OnceLock<Vec<OpSpec>> = OnceLock::new();
+    OPCODES.get_or_init(get_standard_opcodes)
+}
+
+/// Look up a standard opcode by its mnemonic
+pub fn opcode_by_name(name: &str) -> Option<&'static OpSpec> {
+    standard_opcodes().iter().find(|spec| spec.name == name)
+}
+
+/// Look up a standard opcode by its byte value
+pub fn opcode_by_byte(opcode: u8) -> Option<&'static OpSpec> {
+    static BY_BYTE: OnceLock<[Option<&'static OpSpec>; 256]> = OnceLock::new();
+    BY_BYTE.get_or_init(|| {
+        let mut table = [None; 256];
+        for spec in standard_opcodes() {
+            table[spec.opcode as usize] = Some(spec);
+        }
+        table
+    })[opcode as usize]
+}
@@ -1,28 +1,48 @@
 //! State access opcodes (application mode only)
 
 use crate::error::{AvmError, AvmResult};
+use crate::state::application_address;
 use crate::types::{StackValue, TealValue};
 use crate::vm::EvalContext;
 
+/// Maximum length of an application state key in bytes
+pub const MAX_APP_KEY_LEN: usize = 64;
+
+/// Maximum combined length of a state key and its byte value
+pub const MAX_APP_SUM_KEY_VALUE_LENS: usize = 128;
+
+/// Check a state write against the key and key+value length limits
+fn validate_state_write(key: &[u8], value: &TealValue) -> AvmResult<()> {
+    if key.len() > MAX_APP_KEY_LEN {
+        return Err(AvmError::invalid_program(format!(
+            "key too long: length was {}, maximum is {MAX_APP_KEY_LEN}",
+            key.len()
+        )));
+    }
+    if let TealValue::Bytes(bytes) = value {
+        if key.len() + bytes.len() > MAX_APP_SUM_KEY_VALUE_LENS {
+            return Err(AvmError::invalid_program(format!(
+                "key/value total too long: length was {}, maximum is {MAX_APP_SUM_KEY_VALUE_LENS}",
+                key.len() + bytes.len()
+            )));
+        }
+    }
+    Ok(())
+}
+
 /// Get global state value
-/// Returns 2 values: the actual value and an exists flag (correct TEAL behavior)
+/// Returns the value, or 0 if the key does not exist
 pub fn op_app_global_get(ctx: &mut EvalContext) -> AvmResult<()> {
     let key = ctx.pop()?;
     let key_bytes = key.as_bytes()?;
-    let key_str = String::from_utf8_lossy(key_bytes);
 
     let app_id = ctx.ledger().current_application_id()?;
 
-    match ctx.ledger().app_global_get(app_id, &key_str)? {
-        Some(value) => {
-            ctx.push(value.to_stack_value())?;
-            ctx.push(StackValue::Uint(1))?; // exists
-        }
-        None => {
-            ctx.push(StackValue::Uint(0))?; // value (0 for non-existent)
-            ctx.push(StackValue::Uint(0))?; // exists
-        }
-    }
+    let value = match ctx.ledger().app_global_get(app_id, key_bytes)? {
+        Some(value) => value.to_stack_value(),
+        None => StackValue::Uint(0),
+    };
+    ctx.push(value)?;
 
     ctx.advance_pc(1)?;
     Ok(())
@@ -35,10 +55,9 @@ pub fn op_app_global_get_ex(ctx: &mut EvalContext) -> AvmResult<()> {
     let app_id = ctx.pop()?;
 
     let key_bytes = key.as_bytes()?;
-    let key_str = String::from_utf8_lossy(key_bytes);
     let app_id_val = app_id.as_uint()?;
 
-    match ctx.ledger().app_global_get(app_id_val, &key_str)? {
+    match ctx.ledger().app_global_get(app_id_val, key_bytes)? {
         Some(value) => {
             ctx.push(value.to_stack_value())?;
             ctx.push(StackValue::Uint(1))?; // exists
@@ -59,14 +78,14 @@ pub fn op_app_global_put(ctx: &mut EvalContext) -> AvmResult<()> {
     let key = ctx.pop()?;
 
     let key_bytes = key.as_bytes()?;
-    let key_str = String::from_utf8_lossy(key_bytes);
     let teal_value = TealValue::from_stack_value(&value);
+    validate_state_write(key_bytes, &teal_value)?;
 
     let app_id = ctx.ledger().current_application_id()?;
 
     // Use mutable ledger access
     ctx.ledger_mut()
-        .app_global_put(app_id, &key_str, teal_value)?;
+        .app_global_put(app_id, key_bytes, teal_value)?;
 
     ctx.advance_pc(1)?;
     Ok(())
@@ -76,42 +95,35 @@ pub fn op_app_global_put(ctx: &mut EvalContext) -> AvmResult<()> {
 pub fn op_app_global_del(ctx: &mut EvalContext) -> AvmResult<()> {
     let key = ctx.pop()?;
     let key_bytes = key.as_bytes()?;
-    let key_str = String::from_utf8_lossy(key_bytes);
 
     let app_id = ctx.ledger().current_application_id()?;
 
     // Use mutable ledger access
-    ctx.ledger_mut().app_global_del(app_id, &key_str)?;
+    ctx.ledger_mut().app_global_del(app_id, key_bytes)?;
 
     ctx.advance_pc(1)?;
     Ok(())
 }
 
 /// Get local state value
-/// Returns 2 values: the actual value and an exists flag (correct TEAL behavior)
+/// Returns the value, or 0 if the key does not exist
 pub fn op_app_local_get(ctx: &mut EvalContext) -> AvmResult<()> {
     let key = ctx.pop()?;
     let account = ctx.pop()?;
 
     let key_bytes = key.as_bytes()?;
-    let key_str = String::from_utf8_lossy(key_bytes);
     let account_addr = account.as_bytes()?.to_vec();
 
     let app_id = ctx.ledger().current_application_id()?;
 
-    match ctx
+    let value = match ctx
         .ledger()
-        .app_local_get(&account_addr, app_id, &key_str)?
+        .app_local_get(&account_addr, app_id, key_bytes)?
     {
-        Some(value) => {
-            ctx.push(value.to_stack_value())?;
-            ctx.push(StackValue::Uint(1))?; // exists
-        }
-        None => {
-            ctx.push(StackValue::Uint(0))?; // value (0 for non-existent)
-            ctx.push(StackValue::Uint(0))?; // exists
-        }
-    }
+        Some(value) => value.to_stack_value(),
+        None => StackValue::Uint(0),
+    };
+    ctx.push(value)?;
 
     ctx.advance_pc(1)?;
     Ok(())
@@ -125,13 +137,12 @@ pub fn op_app_local_get_ex(ctx: &mut EvalContext) -> AvmResult<()> {
     let account = ctx.pop()?;
 
     let key_bytes = key.as_bytes()?;
-    let key_str = String::from_utf8_lossy(key_bytes);
     let account_addr = account.as_bytes()?.to_vec();
     let app_id_val = app_id.as_uint()?;
 
     match ctx
         .ledger()
-        .app_local_get(&account_addr, app_id_val, &key_str)?
+        .app_local_get(&account_addr, app_id_val, key_bytes)?
     {
         Some(value) => {
             ctx.push(value.to_stack_value())?;
@@ -154,15 +165,15 @@ pub fn op_app_local_put(ctx: &mut EvalContext) -> AvmResult<()> {
     let account = ctx.pop()?;
 
     let key_bytes = key.as_bytes()?;
-    let key_str = String::from_utf8_lossy(key_bytes);
     let account_addr = account.as_bytes()?.to_vec();
     let teal_value = TealValue::from_stack_value(&value);
+    validate_state_write(key_bytes, &teal_value)?;
 
     let app_id = ctx.ledger().current_application_id()?;
 
     // Use mutable ledger access
     ctx.ledger_mut()
-        .app_local_put(&account_addr, app_id, &key_str, teal_value)?;
+        .app_local_put(&account_addr, app_id, key_bytes, teal_value)?;
 
     ctx.advance_pc(1)?;
     Ok(())
@@ -174,14 +185,13 @@ pub fn op_app_local_del(ctx: &mut EvalContext) -> AvmResult<()> {
     let account = ctx.pop()?;
 
     let key_bytes = key.as_bytes()?;
-    let key_str = String::from_utf8_lossy(key_bytes);
     let account_addr = account.as_bytes()?.to_vec();
 
     let app_id = ctx.ledger().current_application_id()?;
 
     // Use mutable ledger access
     ctx.ledger_mut()
-        .app_local_del(&account_addr, app_id, &key_str)?;
+        .app_local_del(&account_addr, app_id, key_bytes)?;
 
     ctx.advance_pc(1)?;
     Ok(())
@@ -227,8 +237,6 @@ pub fn op_min_balance(ctx: &mut EvalContext) -> AvmResult<()> {
 }
 
 /// Get asset holding information
-/// TODO: Test fails with "Invalid asset holding field: 112" - field parameter parsing incorrect
-/// Need to fix opcode parameter order: advance PC first, then read field parameter
 pub fn op_asset_holding_get(ctx: &mut EvalContext) -> AvmResult<()> {
     ctx.advance_pc(1)?; // advance past opcode first
     let field = ctx.read_bytes(1)?[0];
@@ -263,8 +271,6 @@ pub fn op_asset_holding_get(ctx: &mut EvalContext) -> AvmResult<()> {
 }
 
 /// Get asset parameters
-/// TODO: Test fails with "Invalid asset params field: 113" - field parameter parsing incorrect
-/// Need to fix opcode parameter order: advance PC first, then read field parameter
 pub fn op_asset_params_get(ctx: &mut EvalContext) -> AvmResult<()> {
     ctx.advance_pc(1)?; // advance past opcode first
     let field = ctx.read_bytes(1)?[0];
@@ -279,14 +285,14 @@ pub fn op_asset_params_get(ctx: &mut EvalContext) -> AvmResult<()> {
                 0 => ctx.push(StackValue::Uint(params.total))?, // AssetTotal
                 1 => ctx.push(StackValue::Uint(params.decimals as u64))?, // AssetDecimals
                 2 => ctx.push(StackValue::Uint(if params.default_frozen { 1 } else { 0 }))?, // AssetDefaultFrozen
-                3 => ctx.push(StackValue::Bytes(params.name.into_bytes()))?, // AssetName
-                4 => ctx.push(StackValue::Bytes(params.unit_name.into_bytes()))?, // AssetUnitName
-                5 => ctx.push(StackValue::Bytes(params.url.into_bytes()))?,  // AssetURL
-                6 => ctx.push(StackValue::Bytes(params.metadata_hash))?,     // AssetMetadataHash
-                7 => ctx.push(StackValue::Bytes(params.manager))?,           // AssetManager
-                8 => ctx.push(StackValue::Bytes(params.reserve))?,           // AssetReserve
-                9 => ctx.push(StackValue::Bytes(params.freeze))?,            // AssetFreeze
-                10 => ctx.push(StackValue::Bytes(params.clawback))?,         // AssetClawback
+                3 => ctx.push(StackValue::Bytes(params.unit_name.into_bytes()))?, // AssetUnitName
+                4 => ctx.push(StackValue::Bytes(params.name.into_bytes()))?,      // AssetName
+                5 => ctx.push(StackValue::Bytes(params.url.into_bytes()))?,       // AssetURL
+                6 => ctx.push(StackValue::Bytes(params.metadata_hash))?, // AssetMetadataHash
+                7 => ctx.push(StackValue::Bytes(params.manager))?,       // AssetManager
+                8 => ctx.push(StackValue::Bytes(params.reserve))?,       // AssetReserve
+                9 => ctx.push(StackValue::Bytes(params.freeze))?,        // AssetFreeze
+                10 => ctx.push(StackValue::Bytes(params.clawback))?,     // AssetClawback
                 _ => {
                     return Err(AvmError::invalid_program(format!(
                         "Invalid asset params field: {field}"
@@ -305,8 +311,6 @@ pub fn op_asset_params_get(ctx: &mut EvalContext) -> AvmResult<()> {
 }
 
 /// Get application parameters
-/// TODO: Test fails with "Invalid app params field: 114" - field parameter parsing incorrect
-/// Need to fix opcode parameter order: advance PC first, then read field parameter
 pub fn op_app_params_get(ctx: &mut EvalContext) -> AvmResult<()> {
     ctx.advance_pc(1)?; // advance past opcode first
     let field = ctx.read_bytes(1)?[0];
@@ -326,6 +330,7 @@ pub fn op_app_params_get(ctx: &mut EvalContext) -> AvmResult<()> {
                 5 => ctx.push(StackValue::Uint(params.local_state_schema.num_byte_slice))?, // AppLocalNumByteSlice
                 6 => ctx.push(StackValue::Uint(params.extra_program_pages as u64))?, // AppExtraProgramPages
                 7 => ctx.push(StackValue::Bytes(params.creator))?,                   // AppCreator
+                8 => ctx.push(StackValue::Bytes(application_address(app_id_val)))?,  // AppAddress
                 _ => {
                     return Err(AvmError::invalid_program(format!(
                         "Invalid app params field: {field}"
@@ -344,8 +349,6 @@ pub fn op_app_params_get(ctx: &mut EvalContext) -> AvmResult<()> {
 }
 
 /// Get account parameters
-/// TODO: Test fails with "Invalid account params field" - field parameter parsing incorrect
-/// Need to fix opcode parameter order: advance PC first, then read field parameter
 pub fn op_acct_params_get(ctx: &mut EvalContext) -> AvmResult<()> {
     ctx.advance_pc(1)?; // advance past opcode first
     let field = ctx.read_bytes(1)?[0];
@@ -358,13 +361,24 @@ pub fn op_acct_params_get(ctx: &mut EvalContext) -> AvmResult<()> {
         Some(params) => {
             match field {
                 0 => ctx.push(StackValue::Uint(params.micro_algos))?, // AcctBalance
-                1 => ctx.push(StackValue::Uint(params.total_apps_schema.num_uint))?, // AcctTotalNumUint
-                2 => ctx.push(StackValue::Uint(params.total_apps_schema.num_byte_slice))?, // AcctTotalNumByteSlice
-                3 => ctx.push(StackValue::Uint(params.total_apps_extra_pages as u64))?, // AcctTotalExtraAppPages
-                4 => ctx.push(StackValue::Uint(params.total_created_assets))?, // AcctTotalCreatedAssets
-                5 => ctx.push(StackValue::Uint(params.total_created_apps))?, // AcctTotalCreatedApps
-                6 => ctx.push(StackValue::Uint(params.total_boxes))?,        // AcctTotalBoxes
-                7 => ctx.push(StackValue::Uint(params.total_box_bytes))?,    // AcctTotalBoxBytes
+                1 => {
+                    // AcctMinBalance
+                    let min_balance = ctx.ledger().min_balance(&account_addr)?;
+                    ctx.push(StackValue::Uint(min_balance))?
+                }
+                2 => {
+                    // AcctAuthAddr, the zero address when not rekeyed
+                    let auth_addr = params.auth_addr.unwrap_or_else(|| vec![0u8; 32]);
+                    ctx.push(StackValue::Bytes(auth_addr))?
+                }
+                3 => ctx.push(StackValue::Uint(params.total_apps_schema.num_uint))?, // AcctTotalNumUint
+                4 => ctx.push(StackValue::Uint(params.total_apps_schema.num_byte_slice))?, // AcctTotalNumByteSlice
+                5 => ctx.push(StackValue::Uint(params.total_apps_extra_pages as u64))?, // AcctTotalExtraAppPages
+                6 => ctx.push(StackValue::Uint(params.total_created_apps))?, // AcctTotalAppsCreated
+                8 => ctx.push(StackValue::Uint(params.total_created_assets))?, // AcctTotalAssetsCreated
+                9 => ctx.push(StackValue::Uint(params.total_assets))?,         // AcctTotalAssets
+                10 => ctx.push(StackValue::Uint(params.total_boxes))?,         // AcctTotalBoxes
+                11 => ctx.push(StackValue::Uint(params.total_box_bytes))?,     // AcctTotalBoxBytes
                 _ => {
                     return Err(AvmError::invalid_program(format!(
                         "Invalid account params field: {field}"
@@ -1,8 +1,9 @@
 //! Transaction field access opcodes
 
 use crate::error::{AvmError, AvmResult};
+use crate::state::{Transaction, TransactionType};
 use crate::types::{GlobalField, StackValue, TxnField};
-use crate::vm::EvalContext;
+use crate::vm::{EvalContext, SCRATCH_SIZE};
 
 /// Access transaction field
 pub fn op_txn(ctx: &mut EvalContext) -> AvmResult<()> {
@@ -127,17 +128,43 @@ pub fn op_gtxns(ctx: &mut EvalContext) -> AvmResult<()> {
     Ok(())
 }
 
-/// Access group transaction field array with stack indices
+/// Access group transaction field array with the group index from the stack
 pub fn op_gtxnsa(ctx: &mut EvalContext) -> AvmResult<()> {
     // Advance past the opcode first
     ctx.advance_pc(1)?;
     let field_id = ctx.read_bytes(1)?[0];
     ctx.advance_pc(1)?;
+    let array_idx = ctx.read_bytes(1)?[0] as usize;
+    ctx.advance_pc(1)?;
 
-    let array_index = ctx.pop()?;
     let group_index = ctx.pop()?;
-
     let group_idx = group_index.as_uint()? as usize;
+
+    if group_idx >= ctx.group_size() {
+        return Err(AvmError::invalid_program(format!(
+            "Group index {} out of bounds (group size: {})",
+            group_idx,
+            ctx.group_size()
+        )));
+    }
+
+    let field = parse_txn_field(field_id)?;
+    let value = get_group_txn_field_array(ctx, group_idx, field, array_idx)?;
+
+    ctx.push(value)?;
+    Ok(())
+}
+
+/// Access group transaction field array with the array index from the stack
+pub fn op_gtxnas(ctx: &mut EvalContext) -> AvmResult<()> {
+    // Advance past the opcode first
+    ctx.advance_pc(1)?;
+    let group_idx = ctx.read_bytes(1)?[0] as usize;
+    ctx.advance_pc(1)?;
+    let field_id = ctx.read_bytes(1)?[0];
+    ctx.advance_pc(1)?;
+
+    let array_index = ctx.pop()?;
     let array_idx = array_index.as_uint()? as usize;
 
     if group_idx >= ctx.group_size() {
@@ -170,7 +197,7 @@ pub fn op_global(ctx: &mut EvalContext) -> AvmResult<()> {
 }
 
 /// Parse transaction field ID
-fn parse_txn_field(field_id: u8) -> AvmResult<TxnField> {
+pub(crate) fn parse_txn_field(field_id: u8) -> AvmResult<TxnField> {
     match field_id {
         0 => Ok(TxnField::Sender),
         1 => Ok(TxnField::Fee),
@@ -209,7 +236,7 @@ fn parse_txn_field(field_id: u8) -> AvmResult<TxnField> {
         34 => Ok(TxnField::ConfigAssetTotal),
         35 => Ok(TxnField::ConfigAssetDecimals),
         36 => Ok(TxnField::ConfigAssetDefaultFrozen),
-        37 => Ok(TxnField::ConfigAssetName), // ConfigAssetUnitName in assembler
+        37 => Ok(TxnField::ConfigAssetUnitName),
         38 => Ok(TxnField::ConfigAssetName),
         39 => Ok(TxnField::ConfigAssetURL),
         40 => Ok(TxnField::ConfigAssetMetadataHash),
@@ -247,7 +274,7 @@ fn parse_txn_field(field_id: u8) -> AvmResult<TxnField> {
 }
 
 /// Parse global field ID
-fn parse_global_field(field_id: u8) -> AvmResult<GlobalField> {
+pub(crate) fn parse_global_field(field_id: u8) -> AvmResult<GlobalField> {
     match field_id {
         0 => Ok(GlobalField::MinTxnFee),
         1 => Ok(GlobalField::MinBalance),
@@ -267,6 +294,11 @@ fn parse_global_field(field_id: u8) -> AvmResult<GlobalField> {
         15 => Ok(GlobalField::AssetCreateMinBalance),
         16 => Ok(GlobalField::AssetOptInMinBalance),
         17 => Ok(GlobalField::GenesisHash),
+        18 => Ok(GlobalField::PayoutsEnabled),
+        19 => Ok(GlobalField::PayoutsGoOnlineFee),
+        20 => Ok(GlobalField::PayoutsPercent),
+        21 => Ok(GlobalField::PayoutsMinBalance),
+        22 => Ok(GlobalField::PayoutsMaxBalance),
         _ => Err(AvmError::InvalidGlobalField {
             field: format!("Unknown field ID: {field_id}"),
         }),
@@ -362,63 +394,63 @@ fn get_global_field(ctx: &EvalContext, field: GlobalField) -> AvmResult<StackVal
     Ok(teal_value.to_stack_value())
 }
 
-/// Access group transaction field with both group and field from stack (v5 enhanced)
+/// Access group transaction field array with both indices from the stack
 pub fn op_gtxnsas(ctx: &mut EvalContext) -> AvmResult<()> {
     ctx.advance_pc(1)?;
+    let field_id = ctx.read_bytes(1)?[0];
+    ctx.advance_pc(1)?;
 
     let array_index = ctx.pop()?;
-    let field_index = ctx.pop()?;
     let group_index = ctx.pop()?;
 
-    let field_id = field_index.as_uint()? as u8;
     let field = parse_txn_field(field_id)?;
     let group_idx = group_index.as_uint()? as usize;
     let array_idx = array_index.as_uint()? as usize;
 
+    if group_idx >= ctx.group_size() {
+        return Err(AvmError::invalid_program(format!(
+            "Group index {} out of bounds (group size: {})",
+            group_idx,
+            ctx.group_size()
+        )));
+    }
+
     let result = get_group_txn_field_array(ctx, group_idx, field, array_idx)?;
     ctx.push(result)?;
     Ok(())
 }
 
-/// Get scratch space value from specific slot and account (enhanced)
+/// Load a scratch slot of an earlier transaction, both taken from the stack
 pub fn op_gloadss(ctx: &mut EvalContext) -> AvmResult<()> {
     ctx.advance_pc(1)?;
 
-    let slot = ctx.pop()?;
-    let account = ctx.pop()?;
+    let slot = ctx.pop()?.as_uint()?;
+    let group_index = ctx.pop()?.as_uint()?;
 
-    let slot_idx = slot.as_uint()? as u8;
-    let _account_idx = account.as_uint()? as usize;
-
-    // In a real implementation, this would access scratch space for a specific account
-    // For now, use the current scratch space
-    let value = ctx.get_scratch(slot_idx)?;
-    ctx.push(value.clone())?;
+    let value = load_group_scratch(ctx, group_index, slot)?;
+    ctx.push(value)?;
     Ok(())
 }
 
-/// Get application ID from current or specific app call transaction
+/// Get the ID of the asset or application created by an earlier transaction
 pub fn op_gaid(ctx: &mut EvalContext) -> AvmResult<()> {
     ctx.advance_pc(1)?;
-    let _group_index = ctx.read_bytes(1)?[0] as usize;
+    let group_index = ctx.read_bytes(1)?[0];
     ctx.advance_pc(1)?;
 
-    // In a real implementation, this would get the application ID from the specified transaction
-    // For now, return placeholder value
-    ctx.push(StackValue::Uint(0))?;
+    let id = created_id(ctx, group_index as u64)?;
+    ctx.push(StackValue::Uint(id))?;
     Ok(())
 }
 
-/// Get application ID from current or specific app call transaction (stack index)
+/// Get the ID of the asset or application created by an earlier transaction (stack index)
 pub fn op_gaids(ctx: &mut EvalContext) -> AvmResult<()> {
     ctx.advance_pc(1)?;
 
-    let group_index = ctx.pop()?;
-    let _group_idx = group_index.as_uint()? as usize;
+    let group_index = ctx.pop()?.as_uint()?;
 
-    // In a real implementation, this would get the application ID from the specified transaction
-    // For now, return placeholder value
-    ctx.push(StackValue::Uint(0))?;
+    let id = created_id(ctx, group_index)?;
+    ctx.push(StackValue::Uint(id))?;
     Ok(())
 }
 
@@ -426,10 +458,9 @@ pub fn op_gaids(ctx: &mut EvalContext) -> AvmResult<()> {
 pub fn op_loads(ctx: &mut EvalContext) -> AvmResult<()> {
     ctx.advance_pc(1)?;
 
-    let slot = ctx.pop()?;
-    let slot_idx = slot.as_uint()? as u8;
+    let slot = scratch_slot(ctx.pop()?.as_uint()?)?;
 
-    let value = ctx.get_scratch(slot_idx)?;
+    let value = ctx.get_scratch(slot)?;
     ctx.push(value.clone())?;
     Ok(())
 }
@@ -439,41 +470,91 @@ pub fn op_stores(ctx: &mut EvalContext) -> AvmResult<()> {
     ctx.advance_pc(1)?;
 
     let value = ctx.pop()?;
-    let slot = ctx.pop()?;
-    let slot_idx = slot.as_uint()? as u8;
+    let slot = scratch_slot(ctx.pop()?.as_uint()?)?;
 
-    ctx.set_scratch(slot_idx, value)?;
+    ctx.set_scratch(slot, value)?;
     Ok(())
 }
 
-/// Get scratch space value from specific slot (immediate index)
+/// Load a scratch slot of an earlier transaction (immediate indices)
 pub fn op_gload(ctx: &mut EvalContext) -> AvmResult<()> {
     ctx.advance_pc(1)?;
-    let _group_index = ctx.read_bytes(1)?[0] as usize;
+    let group_index = ctx.read_bytes(1)?[0];
     ctx.advance_pc(1)?;
     let slot = ctx.read_bytes(1)?[0];
     ctx.advance_pc(1)?;
 
-    // In a real implementation, this would access scratch space from a specific transaction
-    // For now, use the current scratch space
-    let value = ctx.get_scratch(slot)?;
-    ctx.push(value.clone())?;
+    let value = load_group_scratch(ctx, group_index as u64, slot as u64)?;
+    ctx.push(value)?;
     Ok(())
 }
 
-/// Get scratch space value from specific slot (stack indices)
+/// Load a scratch slot of an earlier transaction (group index from stack)
 pub fn op_gloads(ctx: &mut EvalContext) -> AvmResult<()> {
     ctx.advance_pc(1)?;
+    let slot = ctx.read_bytes(1)?[0];
+    ctx.advance_pc(1)?;
 
-    let slot = ctx.pop()?;
-    let group_index = ctx.pop()?;
-
-    let slot_idx = slot.as_uint()? as u8;
-    let _group_idx = group_index.as_uint()? as usize;
+    let group_index = ctx.pop()?.as_uint()?;
 
-    // In a real implementation, this would access scratch space from a specific transaction
-    // For now, use the current scratch space
-    let value = ctx.get_scratch(slot_idx)?;
-    ctx.push(value.clone())?;
+    let value = load_group_scratch(ctx, group_index, slot as u64)?;
+    ctx.push(value)?;
     Ok(())
 }
+
+/// Validate a scratch slot index taken from the stack
+fn scratch_slot(slot: u64) -> AvmResult<u8> {
+    u8::try_from(slot).map_err(|_| {
+        AvmError::invalid_program(format!(
+            "scratch slot {slot} out of range (max {})",
+            SCRATCH_SIZE - 1
+        ))
+    })
+}
+
+/// Get a transaction that precedes the current one in the group
+fn earlier_transaction(ctx: &EvalContext, group_index: u64) -> AvmResult<Transaction> {
+    if group_index >= ctx.group_index() as u64 {
+        return Err(AvmError::invalid_program(format!(
+            "transaction {group_index} is not before the current transaction {}",
+            ctx.group_index()
+        )));
+    }
+
+    ctx.ledger()
+        .transaction_group()?
+        .into_iter()
+        .nth(group_index as usize)
+        .ok_or_else(|| {
+            AvmError::invalid_program(format!(
+                "Group index {group_index} out of bounds (group size: {})",
+                ctx.group_size()
+            ))
+        })
+}
+
+/// Load a scratch slot from an earlier application call in the group
+fn load_group_scratch(ctx: &EvalContext, group_index: u64, slot: u64) -> AvmResult<StackValue> {
+    let slot = scratch_slot(slot)?;
+    let txn = earlier_transaction(ctx, group_index)?;
+    if txn.tx_type != TransactionType::ApplicationCall {
+        return Err(AvmError::invalid_program(format!(
+            "transaction {group_index} is not an application call"
+        )));
+    }
+
+    let value = ctx.ledger().group_scratch(group_index as usize, slot)?;
+    Ok(value.to_stack_value())
+}
+
+/// Get the asset or application ID created by an earlier transaction
+fn created_id(ctx: &EvalContext, group_index: u64) -> AvmResult<u64> {
+    let txn = earlier_transaction(ctx, group_index)?;
+    txn.created_asset_id
+        .or(txn.created_application_id)
+        .ok_or_else(|| {
+            AvmError::invalid_program(format!(
+                "transaction {group_index} did not create an asset or application"
+            ))
+        })
+}
@@ -0,0 +1,336 @@
+//! Ledger effects of applying transactions
+//!
+//! These are shared by inner transactions and the group evaluator. Programs
+//! are run by the caller; this module only moves balances, assets and
+//! application parameters around.
+
+use super::{
+    Address, AppId, AppParams, AssetHolding, AssetId, AssetParams, LedgerAccess, MicroAlgos,
+    StateSchema, Transaction, TransactionType,
+};
+use crate::error::{AvmError, AvmResult};
+
+/// Application call OnCompletion actions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnCompletion {
+    NoOp = 0,
+    OptIn = 1,
+    CloseOut = 2,
+    ClearState = 3,
+    UpdateApplication = 4,
+    DeleteApplication = 5,
+}
+
+impl OnCompletion {
+    /// Convert an OnCompletion field value
+    pub fn from_u64(value: u64) -> AvmResult<Self> {
+        match value {
+            0 => Ok(Self::NoOp),
+            1 => Ok(Self::OptIn),
+            2 => Ok(Self::CloseOut),
+            3 => Ok(Self::ClearState),
+            4 => Ok(Self::UpdateApplication),
+            5 => Ok(Self::DeleteApplication),
+            _ => Err(AvmError::invalid_program(format!(
+                "invalid OnCompletion value {value}"
+            ))),
+        }
+    }
+}
+
+/// Debit the transaction fee from the sender
+pub(crate) fn charge_fee(ledger: &mut dyn LedgerAccess, txn: &Transaction) -> AvmResult<()> {
+    debit(ledger, &txn.sender, txn.fee)
+}
+
+/// Apply the effects of a non application call transaction
+pub(crate) fn apply_effects(ledger: &mut dyn LedgerAccess, txn: &mut Transaction) -> AvmResult<()> {
+    match txn.tx_type {
+        TransactionType::Payment => apply_payment(ledger, txn),
+        TransactionType::AssetTransfer => apply_asset_transfer(ledger, txn),
+        TransactionType::AssetConfig => apply_asset_config(ledger, txn),
+        TransactionType::AssetFreeze => apply_asset_freeze(ledger, txn),
+        TransactionType::ApplicationCall
+        | TransactionType::KeyRegistration
+        | TransactionType::StateProof => Ok(()),
+    }
+}
+
+/// Look up the called application, creating it first if the application ID is 0
+pub(crate) fn resolve_application(
+    ledger: &mut dyn LedgerAccess,
+    txn: &mut Transaction,
+) -> AvmResult<(AppId, AppParams)> {
+    let app_id = txn.application_id.unwrap_or(0);
+    if app_id != 0 {
+        let params = ledger.app_params(app_id)?.ok_or_else(|| {
+            AvmError::ledger_error(format!("application {app_id} does not exist"))
+        })?;
+        return Ok((app_id, params));
+    }
+
+    let app_id = ledger.next_available_id()?;
+    let params = AppParams {
+        approval_program: txn.approval_program.clone().unwrap_or_default(),
+        clear_state_program: txn.clear_state_program.clone().unwrap_or_default(),
+        global_state_schema: StateSchema {
+            num_uint: txn.global_num_uint.unwrap_or(0),
+            num_byte_slice: txn.global_num_byte_slice.unwrap_or(0),
+        },
+        local_state_schema: StateSchema {
+            num_uint: txn.local_num_uint.unwrap_or(0),
+            num_byte_slice: txn.local_num_byte_slice.unwrap_or(0),
+        },
+        extra_program_pages: txn.extra_program_pages.unwrap_or(0),
+        creator: txn.sender.clone(),
+    };
+    ledger.app_params_put(app_id, params.clone())?;
+    txn.created_application_id = Some(app_id);
+    Ok((app_id, params))
+}
+
+/// Apply the OnCompletion effects of an approved application call
+pub(crate) fn complete_app_call(
+    ledger: &mut dyn LedgerAccess,
+    txn: &Transaction,
+    app_id: AppId,
+    params: AppParams,
+    on_completion: OnCompletion,
+) -> AvmResult<()> {
+    match on_completion {
+        OnCompletion::NoOp => Ok(()),
+        OnCompletion::OptIn => ledger.app_opt_in(&txn.sender, app_id),
+        OnCompletion::CloseOut | OnCompletion::ClearState => {
+            ledger.app_opt_out(&txn.sender, app_id)
+        }
+        OnCompletion::UpdateApplication => {
+            let mut updated = params;
+            updated.approval_program = txn.approval_program.clone().unwrap_or_default();
+            updated.clear_state_program = txn.clear_state_program.clone().unwrap_or_default();
+            ledger.app_params_put(app_id, updated)
+        }
+        OnCompletion::DeleteApplication => ledger.app_params_del(app_id),
+    }
+}
+
+fn debit(ledger: &mut dyn LedgerAccess, addr: &Address, amount: MicroAlgos) -> AvmResult<()> {
+    let balance = ledger.balance(addr)?;
+    let remaining = balance.checked_sub(amount).ok_or_else(|| {
+        AvmError::ledger_error(format!(
+            "overspend: {} has {balance}, needs {amount}",
+            hex::encode(addr)
+        ))
+    })?;
+    ledger.balance_put(addr, remaining)
+}
+
+fn credit(ledger: &mut dyn LedgerAccess, addr: &Address, amount: MicroAlgos) -> AvmResult<()> {
+    let balance = ledger.balance(addr)?;
+    let total = balance
+        .checked_add(amount)
+        .ok_or(AvmError::IntegerOverflow)?;
+    ledger.balance_put(addr, total)
+}
+
+/// Accounts that keep a balance must stay above their minimum balance
+pub(crate) fn check_min_balance(ledger: &dyn LedgerAccess, addr: &Address) -> AvmResult<()> {
+    let balance = ledger.balance(addr)?;
+    let min_balance = ledger.min_balance(addr)?;
+    if balance != 0 && balance < min_balance {
+        return Err(AvmError::ledger_error(format!(
+            "{} balance {balance} below min {min_balance}",
+            hex::encode(addr)
+        )));
+    }
+    Ok(())
+}
+
+fn apply_payment(ledger: &mut dyn LedgerAccess, txn: &Transaction) -> AvmResult<()> {
+    let amount = txn.amount.unwrap_or(0);
+    let receiver = txn.receiver.clone().unwrap_or_else(|| vec![0; 32]);
+    debit(ledger, &txn.sender, amount)?;
+    credit(ledger, &receiver, amount)?;
+
+    if let Some(close_to) = &txn.close_remainder_to {
+        let remainder = ledger.balance(&txn.sender)?;
+        debit(ledger, &txn.sender, remainder)?;
+        credit(ledger, close_to, remainder)?;
+    }
+    Ok(())
+}
+
+fn existing_asset(ledger: &dyn LedgerAccess, asset_id: AssetId) -> AvmResult<AssetParams> {
+    ledger
+        .asset_params(asset_id)?
+        .ok_or_else(|| AvmError::ledger_error(format!("asset {asset_id} does not exist")))
+}
+
+fn existing_holding(
+    ledger: &dyn LedgerAccess,
+    addr: &Address,
+    asset_id: AssetId,
+) -> AvmResult<AssetHolding> {
+    ledger.asset_holding(addr, asset_id)?.ok_or_else(|| {
+        AvmError::ledger_error(format!(
+            "{} is not opted in to asset {asset_id}",
+            hex::encode(addr)
+        ))
+    })
+}
+
+fn apply_asset_transfer(ledger: &mut dyn LedgerAccess, txn: &Transaction) -> AvmResult<()> {
+    let asset_id = txn.xfer_asset.unwrap_or(0);
+    let params = existing_asset(ledger, asset_id)?;
+    let receiver = txn.asset_receiver.clone().unwrap_or_else(|| vec![0; 32]);
+    let amount = txn.asset_amount.unwrap_or(0);
+
+    let clawback = txn.asset_sender.is_some();
+    let source = match &txn.asset_sender {
+        Some(asset_sender) => {
+            if txn.sender != params.clawback {
+                return Err(AvmError::ledger_error(format!(
+                    "only the clawback address may revoke asset {asset_id}"
+                )));
+            }
+            asset_sender.clone()
+        }
+        None => txn.sender.clone(),
+    };
+
+    // A zero transfer to yourself opts in
+    if !clawback
+        && source == receiver
+        && amount == 0
+        && ledger.asset_holding(&source, asset_id)?.is_none()
+    {
+        let holding = AssetHolding {
+            amount: 0,
+            frozen: params.default_frozen,
+        };
+        return ledger.asset_holding_put(&source, asset_id, holding);
+    }
+
+    move_asset(ledger, asset_id, &source, &receiver, amount, clawback)?;
+
+    if let Some(close_to) = &txn.asset_close_to {
+        if clawback {
+            return Err(AvmError::ledger_error(
+                "clawback transactions may not close out",
+            ));
+        }
+        let remainder = existing_holding(ledger, &source, asset_id)?.amount;
+        move_asset(ledger, asset_id, &source, close_to, remainder, false)?;
+        ledger.asset_holding_del(&source, asset_id)?;
+    }
+    Ok(())
+}
+
+fn move_asset(
+    ledger: &mut dyn LedgerAccess,
+    asset_id: AssetId,
+    from: &Address,
+    to: &Address,
+    amount: u64,
+    clawback: bool,
+) -> AvmResult<()> {
+    let mut from_holding = existing_holding(ledger, from, asset_id)?;
+    let mut to_holding = existing_holding(ledger, to, asset_id)?;
+    if !clawback && (from_holding.frozen || to_holding.frozen) {
+        return Err(AvmError::ledger_error(format!(
+            "asset {asset_id} is frozen"
+        )));
+    }
+    if from == to {
+        return Ok(());
+    }
+
+    from_holding.amount = from_holding.amount.checked_sub(amount).ok_or_else(|| {
+        AvmError::ledger_error(format!(
+            "underflow on asset {asset_id}: {} holds {}, needs {amount}",
+            hex::encode(from),
+            from_holding.amount
+        ))
+    })?;
+    to_holding.amount = to_holding
+        .amount
+        .checked_add(amount)
+        .ok_or(AvmError::IntegerOverflow)?;
+
+    ledger.asset_holding_put(from, asset_id, from_holding)?;
+    ledger.asset_holding_put(to, asset_id, to_holding)
+}
+
+fn apply_asset_config(ledger: &mut dyn LedgerAccess, txn: &mut Transaction) -> AvmResult<()> {
+    let asset_id = txn.config_asset.unwrap_or(0);
+
+    if asset_id == 0 {
+        let asset_id = ledger.next_available_id()?;
+        let total = txn.config_asset_total.unwrap_or(0);
+        let params = AssetParams {
+            total,
+            decimals: txn.config_asset_decimals.unwrap_or(0),
+            default_frozen: txn.config_asset_default_frozen.unwrap_or(false),
+            name: txn.config_asset_name.clone().unwrap_or_default(),
+            unit_name: txn.config_asset_unit_name.clone().unwrap_or_default(),
+            url: txn.config_asset_url.clone().unwrap_or_default(),
+            metadata_hash: txn.config_asset_metadata_hash.clone().unwrap_or_default(),
+            manager: txn.config_asset_manager.clone().unwrap_or_default(),
+            reserve: txn.config_asset_reserve.clone().unwrap_or_default(),
+            freeze: txn.config_asset_freeze.clone().unwrap_or_default(),
+            clawback: txn.config_asset_clawback.clone().unwrap_or_default(),
+        };
+        ledger.asset_params_put(asset_id, params)?;
+        ledger.asset_holding_put(
+            &txn.sender,
+            asset_id,
+            AssetHolding {
+                amount: total,
+                frozen: false,
+            },
+        )?;
+        txn.created_asset_id = Some(asset_id);
+        return Ok(());
+    }
+
+    let mut params = existing_asset(ledger, asset_id)?;
+    if txn.sender != params.manager {
+        return Err(AvmError::ledger_error(format!(
+            "only the manager may reconfigure asset {asset_id}"
+        )));
+    }
+
+    let reconfigure = [
+        &txn.config_asset_manager,
+        &txn.config_asset_reserve,
+        &txn.config_asset_freeze,
+        &txn.config_asset_clawback,
+    ]
+    .iter()
+    .any(|addr| addr.is_some());
+
+    if !reconfigure {
+        ledger.asset_params_del(asset_id)?;
+        return ledger.asset_holding_del(&txn.sender, asset_id);
+    }
+
+    params.manager = txn.config_asset_manager.clone().unwrap_or_default();
+    params.reserve = txn.config_asset_reserve.clone().unwrap_or_default();
+    params.freeze = txn.config_asset_freeze.clone().unwrap_or_default();
+    params.clawback = txn.config_asset_clawback.clone().unwrap_or_default();
+    ledger.asset_params_put(asset_id, params)
+}
+
+fn apply_asset_freeze(ledger: &mut dyn LedgerAccess, txn: &Transaction) -> AvmResult<()> {
+    let asset_id = txn.freeze_asset.unwrap_or(0);
+    let params = existing_asset(ledger, asset_id)?;
+    if txn.sender != params.freeze {
+        return Err(AvmError::ledger_error(format!(
+            "only the freeze address may freeze asset {asset_id}"
+        )));
+    }
+
+    let account = txn.freeze_asset_account.clone().unwrap_or_default();
+    let mut holding = existing_holding(ledger, &account, asset_id)?;
+    holding.frozen = txn.freeze_asset_frozen.unwrap_or(false);
+    ledger.asset_holding_put(&account, asset_id, holding)
+}
@@ -0,0 +1,322 @@
+//! Ledger view presenting one transaction of a group to a program
+
+use super::{
+    Address, AppId, AppParams, AssetHolding, AssetId, AssetParams, LedgerAccess, MicroAlgos,
+    Transaction, application_address,
+};
+use crate::error::{AvmError, AvmResult};
+use crate::types::{GlobalField, TealValue, TxnField};
+
+/// Ledger view for evaluating one transaction of a group
+///
+/// State reads and writes go straight to the wrapped ledger, while the
+/// transaction group, current application and program arguments are those of
+/// the transaction being evaluated.
+#[derive(Debug)]
+pub struct ContextLedger<'a> {
+    ledger: &'a mut dyn LedgerAccess,
+    group: Vec<Transaction>,
+    group_index: usize,
+    app_id: AppId,
+    creator: Address,
+    caller: Option<(AppId, Address)>,
+    group_id: Option<Vec<u8>>,
+    args: Vec<Vec<u8>>,
+    scratch: Vec<Option<Vec<TealValue>>>,
+}
+
+impl<'a> ContextLedger<'a> {
+    /// Create a view of the transaction at `group_index` with no current application
+    pub fn new(
+        ledger: &'a mut dyn LedgerAccess,
+        group: Vec<Transaction>,
+        group_index: usize,
+    ) -> AvmResult<Self> {
+        if group_index >= group.len() {
+            return Err(AvmError::invalid_program(format!(
+                "group index {group_index} out of bounds (group size: {})",
+                group.len()
+            )));
+        }
+        Ok(Self {
+            ledger,
+            group,
+            group_index,
+            app_id: 0,
+            creator: vec![0; 32],
+            caller: None,
+            group_id: None,
+            args: Vec::new(),
+            scratch: Vec::new(),
+        })
+    }
+
+    /// Set the application being called and its creator
+    pub fn with_application(mut self, app_id: AppId, creator: Address) -> Self {
+        self.app_id = app_id;
+        self.creator = creator;
+        self
+    }
+
+    /// Set the application that issued this transaction as an inner transaction
+    pub fn with_caller(mut self, app_id: AppId, address: Address) -> Self {
+        self.caller = Some((app_id, address));
+        self
+    }
+
+    /// Override the group ID reported by the wrapped ledger
+    pub fn with_group_id(mut self, group_id: Vec<u8>) -> Self {
+        self.group_id = Some(group_id);
+        self
+    }
+
+    /// Set the logic signature arguments
+    pub fn with_args(mut self, args: Vec<Vec<u8>>) -> Self {
+        self.args = args;
+        self
+    }
+
+    /// Set the final scratch space of each group transaction evaluated so far
+    pub fn with_group_scratch(mut self, scratch: Vec<Option<Vec<TealValue>>>) -> Self {
+        self.scratch = scratch;
+        self
+    }
+
+    fn app_address(&self) -> Address {
+        if self.app_id == 0 {
+            vec![0; 32]
+        } else {
+            application_address(self.app_id)
+        }
+    }
+}
+
+impl LedgerAccess for ContextLedger<'_> {
+    fn balance(&self, addr: &Address) -> AvmResult<MicroAlgos> {
+        self.ledger.balance(addr)
+    }
+
+    fn min_balance(&self, addr: &Address) -> AvmResult<MicroAlgos> {
+        self.ledger.min_balance(addr)
+    }
+
+    fn app_global_get(&self, app_id: AppId, key: &[u8]) -> AvmResult<Option<TealValue>> {
+        self.ledger.app_global_get(app_id, key)
+    }
+
+    fn app_global_put(&mut self, app_id: AppId, key: &[u8], value: TealValue) -> AvmResult<()> {
+        self.ledger.app_global_put(app_id, key, value)
+    }
+
+    fn app_global_del(&mut self, app_id: AppId, key: &[u8]) -> AvmResult<()> {
+        self.ledger.app_global_del(app_id, key)
+    }
+
+    fn app_local_get(
+        &self,
+        addr: &Address,
+        app_id: AppId,
+        key: &[u8],
+    ) -> AvmResult<Option<TealValue>> {
+        self.ledger.app_local_get(addr, app_id, key)
+    }
+
+    fn app_local_put(
+        &mut self,
+        addr: &Address,
+        app_id: AppId,
+        key: &[u8],
+        value: TealValue,
+    ) -> AvmResult<()> {
+        self.ledger.app_local_put(addr, app_id, key, value)
+    }
+
+    fn app_local_del(&mut self, addr: &Address, app_id: AppId, key: &[u8]) -> AvmResult<()> {
+        self.ledger.app_local_del(addr, app_id, key)
+    }
+
+    fn app_opted_in(&self, addr: &Address, app_id: AppId) -> AvmResult<bool> {
+        self.ledger.app_opted_in(addr, app_id)
+    }
+
+    fn asset_holding(&self, addr: &Address, asset_id: AssetId) -> AvmResult<Option<AssetHolding>> {
+        self.ledger.asset_holding(addr, asset_id)
+    }
+
+    fn asset_params(&self, asset_id: AssetId) -> AvmResult<Option<AssetParams>> {
+        self.ledger.asset_params(asset_id)
+    }
+
+    fn app_params(&self, app_id: AppId) -> AvmResult<Option<AppParams>> {
+        self.ledger.app_params(app_id)
+    }
+
+    fn account_params(&self, addr: &Address) -> AvmResult<Option<crate::state::AccountParams>> {
+        self.ledger.account_params(addr)
+    }
+
+    fn current_round(&self) -> AvmResult<u64> {
+        self.ledger.current_round()
+    }
+
+    fn latest_timestamp(&self) -> AvmResult<u64> {
+        self.ledger.latest_timestamp()
+    }
+
+    fn genesis_hash(&self) -> AvmResult<Vec<u8>> {
+        self.ledger.genesis_hash()
+    }
+
+    fn current_application_id(&self) -> AvmResult<AppId> {
+        Ok(self.app_id)
+    }
+
+    fn creator_address(&self) -> AvmResult<Address> {
+        Ok(self.creator.clone())
+    }
+
+    fn current_application_address(&self) -> AvmResult<Address> {
+        Ok(self.app_address())
+    }
+
+    fn group_id(&self) -> AvmResult<Vec<u8>> {
+        match &self.group_id {
+            Some(group_id) => Ok(group_id.clone()),
+            None => self.ledger.group_id(),
+        }
+    }
+
+    fn opcode_budget(&self) -> AvmResult<u64> {
+        self.ledger.opcode_budget()
+    }
+
+    fn caller_application_id(&self) -> AvmResult<Option<AppId>> {
+        Ok(self.caller.as_ref().map(|(app_id, _)| *app_id))
+    }
+
+    fn caller_application_address(&self) -> AvmResult<Option<Address>> {
+        Ok(self.caller.as_ref().map(|(_, address)| address.clone()))
+    }
+
+    fn get_txn_field(&self, txn_index: usize, field: TxnField) -> AvmResult<TealValue> {
+        self.group
+            .get(txn_index)
+            .map(|txn| txn.field(field))
+            .ok_or_else(|| AvmError::InvalidTransactionField {
+                field: format!("transaction index {txn_index}"),
+            })
+    }
+
+    fn get_global_field(&self, field: GlobalField) -> AvmResult<TealValue> {
+        match field {
+            GlobalField::GroupSize => Ok(TealValue::Uint(self.group.len() as u64)),
+            GlobalField::CurrentApplicationID => Ok(TealValue::Uint(self.app_id)),
+            GlobalField::CreatorAddress => Ok(TealValue::Bytes(self.creator.clone())),
+            GlobalField::CurrentApplicationAddress => Ok(TealValue::Bytes(self.app_address())),
+            GlobalField::GroupID => Ok(TealValue::Bytes(self.group_id()?)),
+            GlobalField::CallerApplicationID => Ok(TealValue::Uint(
+                self.caller.as_ref().map_or(0, |(app_id, _)| *app_id),
+            )),
+            GlobalField::CallerApplicationAddress => Ok(TealValue::Bytes(
+                self.caller
+                    .as_ref()
+                    .map_or_else(|| vec![0; 32], |(_, address)| address.clone()),
+            )),
+            _ => self.ledger.get_global_field(field),
+        }
+    }
+
+    fn current_transaction(&self) -> AvmResult<Transaction> {
+        Ok(self.group[self.group_index].clone())
+    }
+
+    fn transaction_group(&self) -> AvmResult<Vec<Transaction>> {
+        Ok(self.group.clone())
+    }
+
+    fn program_args(&self) -> AvmResult<Vec<Vec<u8>>> {
+        Ok(self.args.clone())
+    }
+
+    fn group_scratch(&self, group_index: usize, slot: u8) -> AvmResult<TealValue> {
+        self.scratch
+            .get(group_index)
+            .and_then(Option::as_ref)
+            .and_then(|scratch| scratch.get(slot as usize))
+            .cloned()
+            .ok_or_else(|| {
+                AvmError::invalid_program(format!(
+                    "scratch space of transaction {group_index} is not available"
+                ))
+            })
+    }
+
+    fn box_create(&mut self, app_id: AppId, name: &[u8], size: u64) -> AvmResult<bool> {
+        self.ledger.box_create(app_id, name, size)
+    }
+
+    fn box_len(&self, app_id: AppId, name: &[u8]) -> AvmResult<Option<u64>> {
+        self.ledger.box_len(app_id, name)
+    }
+
+    fn box_read(&self, app_id: AppId, name: &[u8], start: u64, length: u64) -> AvmResult<Vec<u8>> {
+        self.ledger.box_read(app_id, name, start, length)
+    }
+
+    fn box_write(&mut self, app_id: AppId, name: &[u8], start: u64, data: &[u8]) -> AvmResult<()> {
+        self.ledger.box_write(app_id, name, start, data)
+    }
+
+    fn box_delete(&mut self, app_id: AppId, name: &[u8]) -> AvmResult<bool> {
+        self.ledger.box_delete(app_id, name)
+    }
+
+    fn box_resize(&mut self, app_id: AppId, name: &[u8], size: u64) -> AvmResult<()> {
+        self.ledger.box_resize(app_id, name, size)
+    }
+
+    fn balance_put(&mut self, addr: &Address, amount: MicroAlgos) -> AvmResult<()> {
+        self.ledger.balance_put(addr, amount)
+    }
+
+    fn asset_holding_put(
+        &mut self,
+        addr: &Address,
+        asset_id: AssetId,
+        holding: AssetHolding,
+    ) -> AvmResult<()> {
+        self.ledger.asset_holding_put(addr, asset_id, holding)
+    }
+
+    fn asset_holding_del(&mut self, addr: &Address, asset_id: AssetId) -> AvmResult<()> {
+        self.ledger.asset_holding_del(addr, asset_id)
+    }
+
+    fn asset_params_put(&mut self, asset_id: AssetId, params: AssetParams) -> AvmResult<()> {
+        self.ledger.asset_params_put(asset_id, params)
+    }
+
+    fn asset_params_del(&mut self, asset_id: AssetId) -> AvmResult<()> {
+        self.ledger.asset_params_del(asset_id)
+    }
+
+    fn app_params_put(&mut self, app_id: AppId, params: AppParams) -> AvmResult<()> {
+        self.ledger.app_params_put(app_id, params)
+    }
+
+    fn app_params_del(&mut self, app_id: AppId) -> AvmResult<()> {
+        self.ledger.app_params_del(app_id)
+    }
+
+    fn app_opt_in(&mut self, addr: &Address, app_id: AppId) -> AvmResult<()> {
+        self.ledger.app_opt_in(addr, app_id)
+    }
+
+    fn app_opt_out(&mut self, addr: &Address, app_id: AppId) -> AvmResult<()> {
+        self.ledger.app_opt_out(addr, app_id)
+    }
+
+    fn next_available_id(&self) -> AvmResult<u64> {
+        self.ledger.next_available_id()
+    }
+}
@@ -0,0 +1,341 @@
+//! State deltas of an application call in go-algorand's `EvalDelta` shape
+
+use super::{Address, AppId, MicroAlgos, StateChange, Transaction, encode_address};
+use crate::types::TealValue;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::{Serialize, Serializer};
+use serde_json::{Map, Value, json};
+use std::collections::BTreeMap;
+
+/// Change to a single state value
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValueDelta {
+    /// Key set to a byte value (action 1)
+    SetBytes(Vec<u8>),
+    /// Key set to an integer value (action 2)
+    SetUint(u64),
+    /// Key deleted (action 3)
+    Delete,
+}
+
+impl ValueDelta {
+    /// The go-algorand action code for this change
+    pub fn action(&self) -> u64 {
+        match self {
+            Self::SetBytes(_) => 1,
+            Self::SetUint(_) => 2,
+            Self::Delete => 3,
+        }
+    }
+
+    fn set(value: &TealValue) -> Self {
+        match value {
+            TealValue::Bytes(bytes) => Self::SetBytes(bytes.clone()),
+            TealValue::Uint(value) => Self::SetUint(*value),
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        match self {
+            Self::SetBytes(bytes) => json!({ "action": 1, "bytes": BASE64.encode(bytes) }),
+            Self::SetUint(value) => json!({ "action": 2, "uint": value }),
+            Self::Delete => json!({ "action": 3 }),
+        }
+    }
+}
+
+/// Final change to each key of a key-value store
+pub type StateDelta = BTreeMap<Vec<u8>, ValueDelta>;
+
+/// Balance of an account before and after an application call
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BalanceDelta {
+    pub before: MicroAlgos,
+    pub after: MicroAlgos,
+}
+
+impl BalanceDelta {
+    /// Net change in microalgos
+    pub fn change(&self) -> i128 {
+        self.after as i128 - self.before as i128
+    }
+}
+
+/// An inner transaction together with its own delta
+#[derive(Debug, Clone)]
+pub struct InnerTxnDelta {
+    pub txn: Transaction,
+    pub delta: EvalDelta,
+}
+
+/// Everything an application call changed
+///
+/// Global, local and box changes are those of the called application itself;
+/// changes made by applications called through inner transactions are in the
+/// delta of that inner transaction. Balance changes cover every account whose
+/// balance differs after the call, including through inner transactions.
+///
+/// Serializes to the JSON returned by algod for a confirmed transaction:
+/// `global-state-delta`, `local-state-delta`, `logs` and `inner-txns`, with
+/// keys and bytes in base64 and addresses in their text form. Box and balance
+/// changes, which algod does not report, are added as `box-state-delta` and
+/// `balance-delta`. Empty fields are omitted as algod does.
+#[derive(Debug, Clone, Default)]
+pub struct EvalDelta {
+    /// Changes to the application's global state
+    pub global_delta: StateDelta,
+    /// Changes to the application's local state, per account
+    pub local_deltas: BTreeMap<Address, StateDelta>,
+    /// Box contents after the call, or `None` for deleted boxes
+    pub box_deltas: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+    /// Accounts whose balance changed
+    pub balance_deltas: BTreeMap<Address, BalanceDelta>,
+    /// Values emitted with `log`
+    pub logs: Vec<Vec<u8>>,
+    /// Inner transactions issued by the call
+    pub inner_txns: Vec<InnerTxnDelta>,
+}
+
+impl EvalDelta {
+    /// Build the delta of a call to `app_id` from the writes it made
+    pub fn from_changes(
+        app_id: AppId,
+        changes: &[StateChange],
+        logs: Vec<Vec<u8>>,
+        inner_txns: Vec<InnerTxnDelta>,
+    ) -> Self {
+        let mut delta = Self {
+            logs,
+            inner_txns,
+            ..Self::default()
+        };
+
+        for change in changes {
+            match change {
+                StateChange::GlobalPut {
+                    app_id: id,
+                    key,
+                    value,
+                } if *id == app_id => {
+                    delta
+                        .global_delta
+                        .insert(key.clone(), ValueDelta::set(value));
+                }
+                StateChange::GlobalDel { app_id: id, key } if *id == app_id => {
+                    delta.global_delta.insert(key.clone(), ValueDelta::Delete);
+                }
+                StateChange::LocalPut {
+                    address,
+                    app_id: id,
+                    key,
+                    value,
+                } if *id == app_id => {
+                    delta
+                        .local_deltas
+                        .entry(address.clone())
+                        .or_default()
+                        .insert(key.clone(), ValueDelta::set(value));
+                }
+                StateChange::LocalDel {
+                    address,
+                    app_id: id,
+                    key,
+                } if *id == app_id => {
+                    delta
+                        .local_deltas
+                        .entry(address.clone())
+                        .or_default()
+                        .insert(key.clone(), ValueDelta::Delete);
+                }
+                StateChange::BoxPut {
+                    app_id: id,
+                    name,
+                    contents,
+                } if *id == app_id => {
+                    delta
+                        .box_deltas
+                        .insert(name.clone(), Some(contents.clone()));
+                }
+                StateChange::BoxDel { app_id: id, name } if *id == app_id => {
+                    delta.box_deltas.insert(name.clone(), None);
+                }
+                StateChange::Balance {
+                    address,
+                    previous,
+                    amount,
+                } => {
+                    delta
+                        .balance_deltas
+                        .entry(address.clone())
+                        .or_insert(BalanceDelta {
+                            before: *previous,
+                            after: *previous,
+                        })
+                        .after = *amount;
+                }
+                _ => {}
+            }
+        }
+
+        delta
+            .balance_deltas
+            .retain(|_, balance| balance.before != balance.after);
+        delta
+    }
+
+    /// Whether the call changed nothing and issued no logs or inner transactions
+    pub fn is_empty(&self) -> bool {
+        self.global_delta.is_empty()
+            && self.local_deltas.is_empty()
+            && self.box_deltas.is_empty()
+            && self.balance_deltas.is_empty()
+            && self.logs.is_empty()
+            && self.inner_txns.is_empty()
+    }
+
+    /// Convert to the JSON shape used by algod
+    pub fn to_json(&self) -> Value {
+        let mut object = Map::new();
+
+        if !self.global_delta.is_empty() {
+            object.insert(
+                "global-state-delta".into(),
+                state_delta_json(&self.global_delta),
+            );
+        }
+        if !self.local_deltas.is_empty() {
+            let accounts = self
+                .local_deltas
+                .iter()
+                .map(|(address, delta)| {
+                    json!({
+                        "address": encode_address(address),
+                        "delta": state_delta_json(delta),
+                    })
+                })
+                .collect();
+            object.insert("local-state-delta".into(), Value::Array(accounts));
+        }
+        if !self.logs.is_empty() {
+            let logs = self.logs.iter().map(|log| json!(BASE64.encode(log)));
+            object.insert("logs".into(), Value::Array(logs.collect()));
+        }
+        if !self.inner_txns.is_empty() {
+            let inner = self.inner_txns.iter().map(InnerTxnDelta::to_json);
+            object.insert("inner-txns".into(), Value::Array(inner.collect()));
+        }
+        if !self.box_deltas.is_empty() {
+            let boxes = self
+                .box_deltas
+                .iter()
+                .map(|(name, contents)| {
+                    let value = match contents {
+                        Some(contents) => ValueDelta::SetBytes(contents.clone()),
+                        None => ValueDelta::Delete,
+                    };
+                    json!({ "name": BASE64.encode(name), "value": value.to_json() })
+                })
+                .collect();
+            object.insert("box-state-delta".into(), Value::Array(boxes));
+        }
+        if !self.balance_deltas.is_empty() {
+            let balances = self
+                .balance_deltas
+                .iter()
+                .map(|(address, balance)| {
+                    json!({
+                        "address": encode_address(address),
+                        "before": balance.before,
+                        "after": balance.after,
+                    })
+                })
+                .collect();
+            object.insert("balance-delta".into(), Value::Array(balances));
+        }
+
+        Value::Object(object)
+    }
+}
+
+impl Serialize for EvalDelta {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_json().serialize(serializer)
+    }
+}
+
+impl InnerTxnDelta {
+    /// Convert to algod's pending transaction JSON shape
+    pub fn to_json(&self) -> Value {
+        let mut object = match self.delta.to_json() {
+            Value::Object(object) => object,
+            _ => Map::new(),
+        };
+        object.insert("pool-error".into(), json!(""));
+        object.insert("txn".into(), json!({ "txn": transaction_json(&self.txn) }));
+        if let Some(id) = self.txn.created_application_id {
+            object.insert("application-index".into(), json!(id));
+        }
+        if let Some(id) = self.txn.created_asset_id {
+            object.insert("asset-index".into(), json!(id));
+        }
+        Value::Object(object)
+    }
+}
+
+fn state_delta_json(delta: &StateDelta) -> Value {
+    delta
+        .iter()
+        .map(|(key, value)| json!({ "key": BASE64.encode(key), "value": value.to_json() }))
+        .collect()
+}
+
+/// Transaction fields in algod's JSON encoding, omitting empty values
+fn transaction_json(txn: &Transaction) -> Value {
+    let mut object = Map::new();
+    let mut put = |name: &str, value: Value| {
+        let empty = match &value {
+            Value::Null => true,
+            Value::Number(n) => n.as_u64() == Some(0),
+            Value::String(s) => s.is_empty(),
+            Value::Array(items) => items.is_empty(),
+            _ => false,
+        };
+        if !empty {
+            object.insert(name.into(), value);
+        }
+    };
+    let address = |addr: &Option<Address>| addr.as_deref().map(encode_address);
+
+    put("type", json!(txn.tx_type.as_str()));
+    put("snd", json!(encode_address(&txn.sender)));
+    put("fee", json!(txn.fee));
+    put("fv", json!(txn.first_valid));
+    put("lv", json!(txn.last_valid));
+    put("note", json!(BASE64.encode(&txn.note)));
+    put("rcv", json!(address(&txn.receiver)));
+    put("amt", json!(txn.amount));
+    put("close", json!(address(&txn.close_remainder_to)));
+    put("xaid", json!(txn.xfer_asset));
+    put("aamt", json!(txn.asset_amount));
+    put("asnd", json!(address(&txn.asset_sender)));
+    put("arcv", json!(address(&txn.asset_receiver)));
+    put("aclose", json!(address(&txn.asset_close_to)));
+    put("caid", json!(txn.config_asset));
+    put("faid", json!(txn.freeze_asset));
+    put("fadd", json!(address(&txn.freeze_asset_account)));
+    put("apid", json!(txn.application_id));
+    put("apan", json!(txn.on_completion));
+    let args: Vec<String> = txn
+        .application_args
+        .iter()
+        .map(|a| BASE64.encode(a))
+        .collect();
+    put("apaa", json!(args));
+    let accounts: Vec<String> = txn.accounts.iter().map(|a| encode_address(a)).collect();
+    put("apat", json!(accounts));
+    put("apfa", json!(txn.applications));
+    put("apas", json!(txn.assets));
+    put("rekey", json!(address(&txn.rekey_to)));
+    Value::Object(object)
+}
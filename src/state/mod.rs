@@ -1,9 +1,21 @@
 //! State management interfaces and implementations
 
-use crate::error::AvmResult;
+use crate::error::{AvmError, AvmResult};
 use crate::types::{GlobalField, TealValue, TxnField};
 use std::collections::HashMap;
 
+pub(crate) mod apply;
+mod context;
+mod delta;
+mod overlay;
+mod recording;
+
+pub use apply::OnCompletion;
+pub use context::ContextLedger;
+pub use delta::{BalanceDelta, EvalDelta, InnerTxnDelta, StateDelta, ValueDelta};
+pub use overlay::OverlayLedger;
+pub use recording::{RecordingLedger, StateChange};
+
 /// Account address type
 pub type Address = Vec<u8>;
 
@@ -28,6 +40,62 @@ pub enum TransactionType {
     StateProof,
 }
 
+impl TransactionType {
+    /// Get the type string used by the `Type` transaction field
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransactionType::Payment => "pay",
+            TransactionType::KeyRegistration => "keyreg",
+            TransactionType::AssetConfig => "acfg",
+            TransactionType::AssetTransfer => "axfer",
+            TransactionType::AssetFreeze => "afrz",
+            TransactionType::ApplicationCall => "appl",
+            TransactionType::StateProof => "stpf",
+        }
+    }
+
+    /// Parse a type string as used by the `Type` transaction field
+    pub fn from_type_str(s: &[u8]) -> Option<Self> {
+        match s {
+            b"pay" => Some(TransactionType::Payment),
+            b"keyreg" => Some(TransactionType::KeyRegistration),
+            b"acfg" => Some(TransactionType::AssetConfig),
+            b"axfer" => Some(TransactionType::AssetTransfer),
+            b"afrz" => Some(TransactionType::AssetFreeze),
+            b"appl" => Some(TransactionType::ApplicationCall),
+            b"stpf" => Some(TransactionType::StateProof),
+            _ => None,
+        }
+    }
+
+    /// Get the value of the `TypeEnum` transaction field
+    pub fn type_enum(&self) -> u64 {
+        match self {
+            TransactionType::Payment => 1,
+            TransactionType::KeyRegistration => 2,
+            TransactionType::AssetConfig => 3,
+            TransactionType::AssetTransfer => 4,
+            TransactionType::AssetFreeze => 5,
+            TransactionType::ApplicationCall => 6,
+            TransactionType::StateProof => 7,
+        }
+    }
+
+    /// Parse a `TypeEnum` transaction field value
+    pub fn from_type_enum(value: u64) -> Option<Self> {
+        match value {
+            1 => Some(TransactionType::Payment),
+            2 => Some(TransactionType::KeyRegistration),
+            3 => Some(TransactionType::AssetConfig),
+            4 => Some(TransactionType::AssetTransfer),
+            5 => Some(TransactionType::AssetFreeze),
+            6 => Some(TransactionType::ApplicationCall),
+            7 => Some(TransactionType::StateProof),
+            _ => None,
+        }
+    }
+}
+
 /// Transaction data
 #[derive(Debug, Clone)]
 pub struct Transaction {
@@ -179,6 +247,144 @@ impl Transaction {
         tx
     }
 
+    /// Get the value of a transaction field
+    ///
+    /// Array fields return their first element, or an empty value if the array is empty.
+    pub fn field(&self, field: TxnField) -> TealValue {
+        fn bytes(value: &Option<Vec<u8>>) -> TealValue {
+            TealValue::Bytes(value.clone().unwrap_or_default())
+        }
+        fn string(value: &Option<String>) -> TealValue {
+            TealValue::Bytes(value.clone().unwrap_or_default().into_bytes())
+        }
+        fn flag(value: Option<bool>) -> TealValue {
+            TealValue::Uint(value.unwrap_or(false) as u64)
+        }
+
+        match field {
+            TxnField::Sender => TealValue::Bytes(self.sender.clone()),
+            TxnField::Fee => TealValue::Uint(self.fee),
+            TxnField::FirstValid => TealValue::Uint(self.first_valid),
+            TxnField::FirstValidTime => TealValue::Uint(self.first_valid_time),
+            TxnField::LastValid => TealValue::Uint(self.last_valid),
+            TxnField::Note => TealValue::Bytes(self.note.clone()),
+            TxnField::Lease => TealValue::Bytes(self.lease.clone()),
+            TxnField::Receiver => bytes(&self.receiver),
+            TxnField::Amount => TealValue::Uint(self.amount.unwrap_or(0)),
+            TxnField::CloseRemainderTo => bytes(&self.close_remainder_to),
+            TxnField::VotePK => bytes(&self.vote_pk),
+            TxnField::SelectionPK => bytes(&self.selection_pk),
+            TxnField::VoteFirst => TealValue::Uint(self.vote_first.unwrap_or(0)),
+            TxnField::VoteLast => TealValue::Uint(self.vote_last.unwrap_or(0)),
+            TxnField::VoteKeyDilution => TealValue::Uint(self.vote_key_dilution.unwrap_or(0)),
+            TxnField::Type => TealValue::Bytes(self.tx_type.as_str().as_bytes().to_vec()),
+            TxnField::TypeEnum => TealValue::Uint(self.type_enum),
+            TxnField::XferAsset => TealValue::Uint(self.xfer_asset.unwrap_or(0)),
+            TxnField::AssetAmount => TealValue::Uint(self.asset_amount.unwrap_or(0)),
+            TxnField::AssetSender => bytes(&self.asset_sender),
+            TxnField::AssetReceiver => bytes(&self.asset_receiver),
+            TxnField::AssetCloseTo => bytes(&self.asset_close_to),
+            TxnField::GroupIndex => TealValue::Uint(self.group_index),
+            TxnField::TxID => TealValue::Bytes(self.tx_id.clone()),
+            TxnField::ApplicationID => TealValue::Uint(self.application_id.unwrap_or(0)),
+            TxnField::OnCompletion => TealValue::Uint(self.on_completion.unwrap_or(0)),
+            TxnField::NumAppArgs => TealValue::Uint(self.application_args.len() as u64),
+            TxnField::NumAccounts => TealValue::Uint(self.accounts.len() as u64),
+            TxnField::ApprovalProgram => bytes(&self.approval_program),
+            TxnField::ClearStateProgram => bytes(&self.clear_state_program),
+            TxnField::RekeyTo => bytes(&self.rekey_to),
+            TxnField::ConfigAsset => TealValue::Uint(self.config_asset.unwrap_or(0)),
+            TxnField::ConfigAssetTotal => TealValue::Uint(self.config_asset_total.unwrap_or(0)),
+            TxnField::ConfigAssetDecimals => {
+                TealValue::Uint(self.config_asset_decimals.unwrap_or(0) as u64)
+            }
+            TxnField::ConfigAssetDefaultFrozen => flag(self.config_asset_default_frozen),
+            TxnField::ConfigAssetUnitName => string(&self.config_asset_unit_name),
+            TxnField::ConfigAssetName => string(&self.config_asset_name),
+            TxnField::ConfigAssetURL => string(&self.config_asset_url),
+            TxnField::ConfigAssetMetadataHash => bytes(&self.config_asset_metadata_hash),
+            TxnField::ConfigAssetManager => bytes(&self.config_asset_manager),
+            TxnField::ConfigAssetReserve => bytes(&self.config_asset_reserve),
+            TxnField::ConfigAssetFreeze => bytes(&self.config_asset_freeze),
+            TxnField::ConfigAssetClawback => bytes(&self.config_asset_clawback),
+            TxnField::FreezeAsset => TealValue::Uint(self.freeze_asset.unwrap_or(0)),
+            TxnField::FreezeAssetAccount => bytes(&self.freeze_asset_account),
+            TxnField::FreezeAssetFrozen => flag(self.freeze_asset_frozen),
+            TxnField::NumAssets => TealValue::Uint(self.assets.len() as u64),
+            TxnField::NumApplications => TealValue::Uint(self.applications.len() as u64),
+            TxnField::GlobalNumUint => TealValue::Uint(self.global_num_uint.unwrap_or(0)),
+            TxnField::GlobalNumByteSlice => {
+                TealValue::Uint(self.global_num_byte_slice.unwrap_or(0))
+            }
+            TxnField::LocalNumUint => TealValue::Uint(self.local_num_uint.unwrap_or(0)),
+            TxnField::LocalNumByteSlice => TealValue::Uint(self.local_num_byte_slice.unwrap_or(0)),
+            TxnField::ExtraProgramPages => {
+                TealValue::Uint(self.extra_program_pages.unwrap_or(0) as u64)
+            }
+            TxnField::Nonparticipation => flag(self.nonparticipation),
+            TxnField::NumLogs => TealValue::Uint(self.logs.len() as u64),
+            TxnField::CreatedAssetID => TealValue::Uint(self.created_asset_id.unwrap_or(0)),
+            TxnField::CreatedApplicationID => {
+                TealValue::Uint(self.created_application_id.unwrap_or(0))
+            }
+            TxnField::LastLog => TealValue::Bytes(
+                self.last_log
+                    .clone()
+                    .or_else(|| self.logs.last().cloned())
+                    .unwrap_or_default(),
+            ),
+            TxnField::StateProofPK => bytes(&self.state_proof_pk),
+            TxnField::NumApprovalProgramPages => {
+                TealValue::Uint(self.approval_program_pages.len() as u64)
+            }
+            TxnField::NumClearStateProgramPages => {
+                TealValue::Uint(self.clear_state_program_pages.len() as u64)
+            }
+            TxnField::Assets | TxnField::Applications => {
+                self.array_field(field, 0).unwrap_or(TealValue::Uint(0))
+            }
+            TxnField::ApplicationArgs
+            | TxnField::Accounts
+            | TxnField::Logs
+            | TxnField::ApprovalProgramPages
+            | TxnField::ClearStateProgramPages => self
+                .array_field(field, 0)
+                .unwrap_or(TealValue::Bytes(Vec::new())),
+        }
+    }
+
+    /// Get an element of an array transaction field
+    pub fn array_field(&self, field: TxnField, index: usize) -> AvmResult<TealValue> {
+        let out_of_range = |len: usize| AvmError::InvalidTransactionField {
+            field: format!("{field:?} index {index} out of range (length {len})"),
+        };
+        let element = |items: &[Vec<u8>]| {
+            items
+                .get(index)
+                .map(|item| TealValue::Bytes(item.clone()))
+                .ok_or_else(|| out_of_range(items.len()))
+        };
+        let id = |items: &[u64]| {
+            items
+                .get(index)
+                .map(|item| TealValue::Uint(*item))
+                .ok_or_else(|| out_of_range(items.len()))
+        };
+
+        match field {
+            TxnField::ApplicationArgs => element(&self.application_args),
+            TxnField::Accounts => element(&self.accounts),
+            TxnField::Logs => element(&self.logs),
+            TxnField::ApprovalProgramPages => element(&self.approval_program_pages),
+            TxnField::ClearStateProgramPages => element(&self.clear_state_program_pages),
+            TxnField::Assets => id(&self.assets),
+            TxnField::Applications => id(&self.applications),
+            _ => Err(AvmError::InvalidTransactionField {
+                field: format!("Field {field:?} is not an array"),
+            }),
+        }
+    }
+
     /// Create an asset transfer transaction
     pub fn asset_transfer(
         sender: Address,
@@ -197,15 +403,41 @@ impl Transaction {
     }
 }
 
+/// Encode an address in the standard Algorand text form
+///
+/// This is base32 without padding of the 32-byte public key followed by the
+/// last four bytes of its SHA-512/256 hash.
+pub fn encode_address(addr: &[u8]) -> String {
+    use base32::Alphabet;
+    use sha2::{Digest, Sha512_256};
+
+    let hash = Sha512_256::digest(addr);
+    let mut bytes = addr.to_vec();
+    bytes.extend_from_slice(&hash[hash.len() - 4..]);
+    base32::encode(Alphabet::Rfc4648 { padding: false }, &bytes)
+}
+
+/// Compute the address of an application account
+///
+/// This is the SHA-512/256 hash of `"appID"` followed by the big-endian application ID.
+pub fn application_address(app_id: AppId) -> Address {
+    use sha2::{Digest, Sha512_256};
+
+    let mut hasher = Sha512_256::new();
+    hasher.update(b"appID");
+    hasher.update(app_id.to_be_bytes());
+    hasher.finalize().to_vec()
+}
+
 /// Asset holding information
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct AssetHolding {
     pub amount: u64,
     pub frozen: bool,
 }
 
 /// Asset parameters
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct AssetParams {
     pub total: u64,
     pub decimals: u8,
@@ -221,7 +453,7 @@ pub struct AssetParams {
 }
 
 /// Application parameters
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct AppParams {
     pub approval_program: Vec<u8>,
     pub clear_state_program: Vec<u8>,
@@ -232,7 +464,7 @@ pub struct AppParams {
 }
 
 /// State schema defining storage allocation
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct StateSchema {
     pub num_uint: u64,
     pub num_byte_slice: u64,
@@ -264,20 +496,20 @@ pub trait LedgerAccess: std::fmt::Debug {
     fn min_balance(&self, addr: &Address) -> AvmResult<MicroAlgos>;
 
     /// Get global state value
-    fn app_global_get(&self, app_id: AppId, key: &str) -> AvmResult<Option<TealValue>>;
+    fn app_global_get(&self, app_id: AppId, key: &[u8]) -> AvmResult<Option<TealValue>>;
 
     /// Set global state value
-    fn app_global_put(&mut self, app_id: AppId, key: &str, value: TealValue) -> AvmResult<()>;
+    fn app_global_put(&mut self, app_id: AppId, key: &[u8], value: TealValue) -> AvmResult<()>;
 
     /// Delete global state value
-    fn app_global_del(&mut self, app_id: AppId, key: &str) -> AvmResult<()>;
+    fn app_global_del(&mut self, app_id: AppId, key: &[u8]) -> AvmResult<()>;
 
     /// Get local state value
     fn app_local_get(
         &self,
         addr: &Address,
         app_id: AppId,
-        key: &str,
+        key: &[u8],
     ) -> AvmResult<Option<TealValue>>;
 
     /// Set local state value
@@ -285,12 +517,12 @@ pub trait LedgerAccess: std::fmt::Debug {
         &mut self,
         addr: &Address,
         app_id: AppId,
-        key: &str,
+        key: &[u8],
         value: TealValue,
     ) -> AvmResult<()>;
 
     /// Delete local state value
-    fn app_local_del(&mut self, addr: &Address, app_id: AppId, key: &str) -> AvmResult<()>;
+    fn app_local_del(&mut self, addr: &Address, app_id: AppId, key: &[u8]) -> AvmResult<()>;
 
     /// Check if account has opted into application
     fn app_opted_in(&self, addr: &Address, app_id: AppId) -> AvmResult<bool>;
@@ -351,20 +583,78 @@ pub trait LedgerAccess: std::fmt::Debug {
 
     /// Get program arguments for current transaction (cloned)
     fn program_args(&self) -> AvmResult<Vec<Vec<u8>>>;
+
+    /// Get a scratch slot from the final scratch space of an evaluated
+    /// application call in the group
+    fn group_scratch(&self, group_index: usize, slot: u8) -> AvmResult<TealValue>;
+
+    /// Create a zero-filled box, returning false if a box with this name already exists
+    fn box_create(&mut self, app_id: AppId, name: &[u8], size: u64) -> AvmResult<bool>;
+
+    /// Get the length of a box, or None if it does not exist
+    fn box_len(&self, app_id: AppId, name: &[u8]) -> AvmResult<Option<u64>>;
+
+    /// Read `length` bytes of a box starting at `start`
+    fn box_read(&self, app_id: AppId, name: &[u8], start: u64, length: u64) -> AvmResult<Vec<u8>>;
+
+    /// Overwrite bytes of a box starting at `start`
+    fn box_write(&mut self, app_id: AppId, name: &[u8], start: u64, data: &[u8]) -> AvmResult<()>;
+
+    /// Delete a box, returning whether it existed
+    fn box_delete(&mut self, app_id: AppId, name: &[u8]) -> AvmResult<bool>;
+
+    /// Resize a box, truncating or zero-extending its contents
+    fn box_resize(&mut self, app_id: AppId, name: &[u8], size: u64) -> AvmResult<()>;
+
+    /// Set account balance
+    fn balance_put(&mut self, addr: &Address, amount: MicroAlgos) -> AvmResult<()>;
+
+    /// Set asset holding for an account, opting it in if needed
+    fn asset_holding_put(
+        &mut self,
+        addr: &Address,
+        asset_id: AssetId,
+        holding: AssetHolding,
+    ) -> AvmResult<()>;
+
+    /// Remove an asset holding from an account (opt out)
+    fn asset_holding_del(&mut self, addr: &Address, asset_id: AssetId) -> AvmResult<()>;
+
+    /// Set asset parameters, creating the asset if needed
+    fn asset_params_put(&mut self, asset_id: AssetId, params: AssetParams) -> AvmResult<()>;
+
+    /// Delete an asset
+    fn asset_params_del(&mut self, asset_id: AssetId) -> AvmResult<()>;
+
+    /// Set application parameters, creating the application if needed
+    fn app_params_put(&mut self, app_id: AppId, params: AppParams) -> AvmResult<()>;
+
+    /// Delete an application
+    fn app_params_del(&mut self, app_id: AppId) -> AvmResult<()>;
+
+    /// Opt an account into an application
+    fn app_opt_in(&mut self, addr: &Address, app_id: AppId) -> AvmResult<()>;
+
+    /// Opt an account out of an application, clearing its local state
+    fn app_opt_out(&mut self, addr: &Address, app_id: AppId) -> AvmResult<()>;
+
+    /// Get the next unused asset or application ID
+    fn next_available_id(&self) -> AvmResult<u64>;
 }
 
 /// Mock ledger implementation for testing
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct MockLedger {
     balances: HashMap<Address, MicroAlgos>,
     min_balances: HashMap<Address, MicroAlgos>,
-    global_state: HashMap<(AppId, String), TealValue>,
-    local_state: HashMap<(Address, AppId, String), TealValue>,
+    global_state: HashMap<(AppId, Vec<u8>), TealValue>,
+    local_state: HashMap<(Address, AppId, Vec<u8>), TealValue>,
     opted_in: HashMap<(Address, AppId), bool>,
     asset_holdings: HashMap<(Address, AssetId), AssetHolding>,
     asset_params: HashMap<AssetId, AssetParams>,
     app_params: HashMap<AppId, AppParams>,
     account_params: HashMap<Address, AccountParams>,
+    boxes: HashMap<(AppId, Vec<u8>), Vec<u8>>,
     current_round: u64,
     latest_timestamp: u64,
     genesis_hash: Vec<u8>,
@@ -378,6 +668,7 @@ pub struct MockLedger {
     transactions: Vec<Transaction>,
     current_txn_index: usize,
     program_args: Vec<Vec<u8>>,
+    group_scratch: HashMap<usize, Vec<TealValue>>,
 }
 
 impl MockLedger {
@@ -397,13 +688,19 @@ impl MockLedger {
     }
 
     /// Set global state
-    pub fn set_global_state(&mut self, app_id: AppId, key: String, value: TealValue) {
-        self.global_state.insert((app_id, key), value);
+    pub fn set_global_state(&mut self, app_id: AppId, key: impl Into<Vec<u8>>, value: TealValue) {
+        self.global_state.insert((app_id, key.into()), value);
     }
 
     /// Set local state
-    pub fn set_local_state(&mut self, addr: Address, app_id: AppId, key: String, value: TealValue) {
-        self.local_state.insert((addr, app_id, key), value);
+    pub fn set_local_state(
+        &mut self,
+        addr: Address,
+        app_id: AppId,
+        key: impl Into<Vec<u8>>,
+        value: TealValue,
+    ) {
+        self.local_state.insert((addr, app_id, key.into()), value);
     }
 
     /// Set application opt-in status
@@ -431,6 +728,18 @@ impl MockLedger {
         self.account_params.insert(addr, params);
     }
 
+    /// Set box contents
+    pub fn set_box(&mut self, app_id: AppId, name: impl Into<Vec<u8>>, contents: Vec<u8>) {
+        self.boxes.insert((app_id, name.into()), contents);
+    }
+
+    /// Get box contents
+    pub fn get_box(&self, app_id: AppId, name: &[u8]) -> Option<&[u8]> {
+        self.boxes
+            .get(&(app_id, name.to_vec()))
+            .map(|contents| contents.as_slice())
+    }
+
     /// Set current round
     pub fn set_current_round(&mut self, round: u64) {
         self.current_round = round;
@@ -491,11 +800,21 @@ impl MockLedger {
         self.current_txn_index = index;
     }
 
+    /// Get the current transaction for editing
+    pub fn current_transaction_mut(&mut self) -> Option<&mut Transaction> {
+        self.transactions.get_mut(self.current_txn_index)
+    }
+
     /// Set program arguments
     pub fn set_program_args(&mut self, args: Vec<Vec<u8>>) {
         self.program_args = args;
     }
 
+    /// Set the final scratch space of a transaction in the group
+    pub fn set_group_scratch(&mut self, group_index: usize, scratch: Vec<TealValue>) {
+        self.group_scratch.insert(group_index, scratch);
+    }
+
     /// Set up a simple payment transaction group
     pub fn setup_payment_transaction(
         &mut self,
@@ -541,6 +860,7 @@ impl MockLedger {
             asset_params: HashMap::new(),
             app_params: HashMap::new(),
             account_params: HashMap::new(),
+            boxes: HashMap::new(),
             current_round: 1000,
             latest_timestamp: 1640995200, // 2022-01-01
             genesis_hash: vec![0; 32],
@@ -554,6 +874,7 @@ impl MockLedger {
             transactions: Vec::new(),
             current_txn_index: 0,
             program_args: Vec::new(),
+            group_scratch: HashMap::new(),
         };
 
         // Add a default payment transaction
@@ -580,17 +901,17 @@ impl LedgerAccess for MockLedger {
         Ok(self.min_balances.get(addr).copied().unwrap_or(100000))
     }
 
-    fn app_global_get(&self, app_id: AppId, key: &str) -> AvmResult<Option<TealValue>> {
-        Ok(self.global_state.get(&(app_id, key.to_string())).cloned())
+    fn app_global_get(&self, app_id: AppId, key: &[u8]) -> AvmResult<Option<TealValue>> {
+        Ok(self.global_state.get(&(app_id, key.to_vec())).cloned())
     }
 
-    fn app_global_put(&mut self, app_id: AppId, key: &str, value: TealValue) -> AvmResult<()> {
-        self.global_state.insert((app_id, key.to_string()), value);
+    fn app_global_put(&mut self, app_id: AppId, key: &[u8], value: TealValue) -> AvmResult<()> {
+        self.global_state.insert((app_id, key.to_vec()), value);
         Ok(())
     }
 
-    fn app_global_del(&mut self, app_id: AppId, key: &str) -> AvmResult<()> {
-        self.global_state.remove(&(app_id, key.to_string()));
+    fn app_global_del(&mut self, app_id: AppId, key: &[u8]) -> AvmResult<()> {
+        self.global_state.remove(&(app_id, key.to_vec()));
         Ok(())
     }
 
@@ -598,11 +919,11 @@ impl LedgerAccess for MockLedger {
         &self,
         addr: &Address,
         app_id: AppId,
-        key: &str,
+        key: &[u8],
     ) -> AvmResult<Option<TealValue>> {
         Ok(self
             .local_state
-            .get(&(addr.clone(), app_id, key.to_string()))
+            .get(&(addr.clone(), app_id, key.to_vec()))
             .cloned())
     }
 
@@ -610,17 +931,17 @@ impl LedgerAccess for MockLedger {
         &mut self,
         addr: &Address,
         app_id: AppId,
-        key: &str,
+        key: &[u8],
         value: TealValue,
     ) -> AvmResult<()> {
         self.local_state
-            .insert((addr.clone(), app_id, key.to_string()), value);
+            .insert((addr.clone(), app_id, key.to_vec()), value);
         Ok(())
     }
 
-    fn app_local_del(&mut self, addr: &Address, app_id: AppId, key: &str) -> AvmResult<()> {
+    fn app_local_del(&mut self, addr: &Address, app_id: AppId, key: &[u8]) -> AvmResult<()> {
         self.local_state
-            .remove(&(addr.clone(), app_id, key.to_string()));
+            .remove(&(addr.clone(), app_id, key.to_vec()));
         Ok(())
     }
 
@@ -695,68 +1016,7 @@ impl LedgerAccess for MockLedger {
             }
         })?;
 
-        let value = match field {
-            TxnField::Sender => TealValue::Bytes(tx.sender.clone()),
-            TxnField::Fee => TealValue::Uint(tx.fee),
-            TxnField::FirstValid => TealValue::Uint(tx.first_valid),
-            TxnField::FirstValidTime => TealValue::Uint(tx.first_valid_time),
-            TxnField::LastValid => TealValue::Uint(tx.last_valid),
-            TxnField::Note => TealValue::Bytes(tx.note.clone()),
-            TxnField::Lease => TealValue::Bytes(tx.lease.clone()),
-            TxnField::Receiver => TealValue::Bytes(tx.receiver.clone().unwrap_or_default()),
-            TxnField::Amount => TealValue::Uint(tx.amount.unwrap_or(0)),
-            TxnField::CloseRemainderTo => {
-                TealValue::Bytes(tx.close_remainder_to.clone().unwrap_or_default())
-            }
-            TxnField::VotePK => TealValue::Bytes(tx.vote_pk.clone().unwrap_or_default()),
-            TxnField::SelectionPK => TealValue::Bytes(tx.selection_pk.clone().unwrap_or_default()),
-            TxnField::VoteFirst => TealValue::Uint(tx.vote_first.unwrap_or(0)),
-            TxnField::VoteLast => TealValue::Uint(tx.vote_last.unwrap_or(0)),
-            TxnField::VoteKeyDilution => TealValue::Uint(tx.vote_key_dilution.unwrap_or(0)),
-            TxnField::Type => TealValue::Bytes(match tx.tx_type {
-                TransactionType::Payment => b"pay".to_vec(),
-                TransactionType::KeyRegistration => b"keyreg".to_vec(),
-                TransactionType::AssetConfig => b"acfg".to_vec(),
-                TransactionType::AssetTransfer => b"axfer".to_vec(),
-                TransactionType::AssetFreeze => b"afrz".to_vec(),
-                TransactionType::ApplicationCall => b"appl".to_vec(),
-                TransactionType::StateProof => b"stpf".to_vec(),
-            }),
-            TxnField::TypeEnum => TealValue::Uint(tx.type_enum),
-            TxnField::XferAsset => TealValue::Uint(tx.xfer_asset.unwrap_or(0)),
-            TxnField::AssetAmount => TealValue::Uint(tx.asset_amount.unwrap_or(0)),
-            TxnField::AssetSender => TealValue::Bytes(tx.asset_sender.clone().unwrap_or_default()),
-            TxnField::AssetReceiver => {
-                TealValue::Bytes(tx.asset_receiver.clone().unwrap_or_default())
-            }
-            TxnField::AssetCloseTo => {
-                TealValue::Bytes(tx.asset_close_to.clone().unwrap_or_default())
-            }
-            TxnField::GroupIndex => TealValue::Uint(tx.group_index),
-            TxnField::TxID => TealValue::Bytes(tx.tx_id.clone()),
-            TxnField::ApplicationID => TealValue::Uint(tx.application_id.unwrap_or(0)),
-            TxnField::OnCompletion => TealValue::Uint(tx.on_completion.unwrap_or(0)),
-            TxnField::ApplicationArgs => {
-                // For now, return the first application arg if it exists
-                TealValue::Bytes(tx.application_args.first().cloned().unwrap_or_default())
-            }
-            TxnField::NumAppArgs => TealValue::Uint(tx.application_args.len() as u64),
-            TxnField::Accounts => {
-                // For now, return the first account if it exists
-                TealValue::Bytes(tx.accounts.first().cloned().unwrap_or_default())
-            }
-            TxnField::NumAccounts => TealValue::Uint(tx.accounts.len() as u64),
-            TxnField::ApprovalProgram => {
-                TealValue::Bytes(tx.approval_program.clone().unwrap_or_default())
-            }
-            TxnField::ClearStateProgram => {
-                TealValue::Bytes(tx.clear_state_program.clone().unwrap_or_default())
-            }
-            TxnField::RekeyTo => TealValue::Bytes(tx.rekey_to.clone().unwrap_or_default()),
-            _ => TealValue::Uint(0), // Default for other fields
-        };
-
-        Ok(value)
+        Ok(tx.field(field))
     }
 
     fn get_global_field(&self, field: GlobalField) -> AvmResult<TealValue> {
@@ -803,4 +1063,147 @@ impl LedgerAccess for MockLedger {
     fn program_args(&self) -> AvmResult<Vec<Vec<u8>>> {
         Ok(self.program_args.clone())
     }
+
+    fn group_scratch(&self, group_index: usize, slot: u8) -> AvmResult<TealValue> {
+        self.group_scratch
+            .get(&group_index)
+            .and_then(|scratch| scratch.get(slot as usize))
+            .cloned()
+            .ok_or_else(|| {
+                crate::error::AvmError::invalid_program(format!(
+                    "scratch space of transaction {group_index} is not available"
+                ))
+            })
+    }
+
+    fn box_create(&mut self, app_id: AppId, name: &[u8], size: u64) -> AvmResult<bool> {
+        let key = (app_id, name.to_vec());
+        if self.boxes.contains_key(&key) {
+            return Ok(false);
+        }
+        self.boxes.insert(key, vec![0; size as usize]);
+        Ok(true)
+    }
+
+    fn box_len(&self, app_id: AppId, name: &[u8]) -> AvmResult<Option<u64>> {
+        Ok(self
+            .boxes
+            .get(&(app_id, name.to_vec()))
+            .map(|contents| contents.len() as u64))
+    }
+
+    fn box_read(&self, app_id: AppId, name: &[u8], start: u64, length: u64) -> AvmResult<Vec<u8>> {
+        let contents = self.boxes.get(&(app_id, name.to_vec())).ok_or_else(|| {
+            AvmError::state_error(format!("box {} does not exist", hex::encode(name)))
+        })?;
+        let range = box_range(contents.len(), start, length)?;
+        Ok(contents[range].to_vec())
+    }
+
+    fn box_write(&mut self, app_id: AppId, name: &[u8], start: u64, data: &[u8]) -> AvmResult<()> {
+        let contents = self
+            .boxes
+            .get_mut(&(app_id, name.to_vec()))
+            .ok_or_else(|| {
+                AvmError::state_error(format!("box {} does not exist", hex::encode(name)))
+            })?;
+        let range = box_range(contents.len(), start, data.len() as u64)?;
+        contents[range].copy_from_slice(data);
+        Ok(())
+    }
+
+    fn box_delete(&mut self, app_id: AppId, name: &[u8]) -> AvmResult<bool> {
+        Ok(self.boxes.remove(&(app_id, name.to_vec())).is_some())
+    }
+
+    fn box_resize(&mut self, app_id: AppId, name: &[u8], size: u64) -> AvmResult<()> {
+        let contents = self
+            .boxes
+            .get_mut(&(app_id, name.to_vec()))
+            .ok_or_else(|| {
+                AvmError::state_error(format!("box {} does not exist", hex::encode(name)))
+            })?;
+        contents.resize(size as usize, 0);
+        Ok(())
+    }
+
+    fn balance_put(&mut self, addr: &Address, amount: MicroAlgos) -> AvmResult<()> {
+        self.balances.insert(addr.clone(), amount);
+        Ok(())
+    }
+
+    fn asset_holding_put(
+        &mut self,
+        addr: &Address,
+        asset_id: AssetId,
+        holding: AssetHolding,
+    ) -> AvmResult<()> {
+        self.asset_holdings
+            .insert((addr.clone(), asset_id), holding);
+        Ok(())
+    }
+
+    fn asset_holding_del(&mut self, addr: &Address, asset_id: AssetId) -> AvmResult<()> {
+        self.asset_holdings.remove(&(addr.clone(), asset_id));
+        Ok(())
+    }
+
+    fn asset_params_put(&mut self, asset_id: AssetId, params: AssetParams) -> AvmResult<()> {
+        self.asset_params.insert(asset_id, params);
+        Ok(())
+    }
+
+    fn asset_params_del(&mut self, asset_id: AssetId) -> AvmResult<()> {
+        self.asset_params.remove(&asset_id);
+        Ok(())
+    }
+
+    fn app_params_put(&mut self, app_id: AppId, params: AppParams) -> AvmResult<()> {
+        self.app_params.insert(app_id, params);
+        Ok(())
+    }
+
+    fn app_params_del(&mut self, app_id: AppId) -> AvmResult<()> {
+        self.app_params.remove(&app_id);
+        Ok(())
+    }
+
+    fn app_opt_in(&mut self, addr: &Address, app_id: AppId) -> AvmResult<()> {
+        self.opted_in.insert((addr.clone(), app_id), true);
+        Ok(())
+    }
+
+    fn app_opt_out(&mut self, addr: &Address, app_id: AppId) -> AvmResult<()> {
+        self.opted_in.remove(&(addr.clone(), app_id));
+        self.local_state
+            .retain(|(account, app, _), _| !(account == addr && *app == app_id));
+        Ok(())
+    }
+
+    fn next_available_id(&self) -> AvmResult<u64> {
+        let highest = self
+            .asset_params
+            .keys()
+            .chain(self.app_params.keys())
+            .copied()
+            .max()
+            .unwrap_or(0);
+        Ok(highest.max(MIN_CREATED_ID) + 1)
+    }
 }
+
+/// Validate that `start..start + length` lies within a box of `box_len` bytes
+fn box_range(box_len: usize, start: u64, length: u64) -> AvmResult<std::ops::Range<usize>> {
+    let end = start
+        .checked_add(length)
+        .filter(|end| *end <= box_len as u64)
+        .ok_or_else(|| {
+            AvmError::state_error(format!(
+                "box range {start}+{length} out of bounds for box of length {box_len}"
+            ))
+        })?;
+    Ok(start as usize..end as usize)
+}
+
+/// IDs handed out by [`MockLedger::next_available_id`] start above this value
+const MIN_CREATED_ID: u64 = 1000;
@@ -0,0 +1,423 @@
+//! Copy-on-write ledger overlay with commit and discard
+
+use super::{
+    AccountParams, Address, AppId, AppParams, AssetHolding, AssetId, AssetParams, LedgerAccess,
+    MicroAlgos, Transaction, box_range,
+};
+use crate::error::{AvmError, AvmResult};
+use crate::types::{GlobalField, TealValue, TxnField};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Ledger wrapper that buffers writes until they are committed
+///
+/// Reads are served from the buffered writes first and fall through to the
+/// wrapped ledger. Nothing reaches the wrapped ledger until [`commit`] is
+/// called; dropping the overlay or calling [`discard`] throws the writes away.
+/// Overlays can be stacked to give nested transactions, for example one per
+/// inner application call inside an overlay for the whole group.
+///
+/// [`commit`]: OverlayLedger::commit
+/// [`discard`]: OverlayLedger::discard
+#[derive(Debug)]
+pub struct OverlayLedger<'a> {
+    ledger: &'a mut dyn LedgerAccess,
+    global: BTreeMap<(AppId, Vec<u8>), Option<TealValue>>,
+    local: BTreeMap<(Address, AppId, Vec<u8>), Option<TealValue>>,
+    /// Opt-in status changes; opting out clears the account's local state
+    opted_in: BTreeMap<(Address, AppId), bool>,
+    /// Accounts whose local state in the wrapped ledger was cleared by an opt-out
+    cleared: BTreeSet<(Address, AppId)>,
+    boxes: BTreeMap<(AppId, Vec<u8>), Option<Vec<u8>>>,
+    balances: BTreeMap<Address, MicroAlgos>,
+    asset_holdings: BTreeMap<(Address, AssetId), Option<AssetHolding>>,
+    asset_params: BTreeMap<AssetId, Option<AssetParams>>,
+    app_params: BTreeMap<AppId, Option<AppParams>>,
+}
+
+impl<'a> OverlayLedger<'a> {
+    /// Wrap a ledger with an empty write buffer
+    pub fn new(ledger: &'a mut dyn LedgerAccess) -> Self {
+        Self {
+            ledger,
+            global: BTreeMap::new(),
+            local: BTreeMap::new(),
+            opted_in: BTreeMap::new(),
+            cleared: BTreeSet::new(),
+            boxes: BTreeMap::new(),
+            balances: BTreeMap::new(),
+            asset_holdings: BTreeMap::new(),
+            asset_params: BTreeMap::new(),
+            app_params: BTreeMap::new(),
+        }
+    }
+
+    /// Whether any writes are buffered
+    pub fn is_empty(&self) -> bool {
+        self.global.is_empty()
+            && self.local.is_empty()
+            && self.opted_in.is_empty()
+            && self.boxes.is_empty()
+            && self.balances.is_empty()
+            && self.asset_holdings.is_empty()
+            && self.asset_params.is_empty()
+            && self.app_params.is_empty()
+    }
+
+    /// Apply the buffered writes to the wrapped ledger
+    pub fn commit(self) -> AvmResult<()> {
+        let ledger = self.ledger;
+
+        for (app_id, params) in self.app_params {
+            match params {
+                Some(params) => ledger.app_params_put(app_id, params)?,
+                None => ledger.app_params_del(app_id)?,
+            }
+        }
+        for ((app_id, key), value) in self.global {
+            match value {
+                Some(value) => ledger.app_global_put(app_id, &key, value)?,
+                None => ledger.app_global_del(app_id, &key)?,
+            }
+        }
+
+        // Opt-outs clear local state, so they go before any local writes
+        for (addr, app_id) in &self.cleared {
+            ledger.app_opt_out(addr, *app_id)?;
+        }
+        for ((addr, app_id), opted_in) in &self.opted_in {
+            if *opted_in {
+                ledger.app_opt_in(addr, *app_id)?;
+            }
+        }
+        for ((addr, app_id, key), value) in self.local {
+            match value {
+                Some(value) => ledger.app_local_put(&addr, app_id, &key, value)?,
+                None => ledger.app_local_del(&addr, app_id, &key)?,
+            }
+        }
+
+        for ((app_id, name), contents) in self.boxes {
+            ledger.box_delete(app_id, &name)?;
+            if let Some(contents) = contents {
+                ledger.box_create(app_id, &name, contents.len() as u64)?;
+                ledger.box_write(app_id, &name, 0, &contents)?;
+            }
+        }
+
+        for (addr, amount) in self.balances {
+            ledger.balance_put(&addr, amount)?;
+        }
+        for (asset_id, params) in self.asset_params {
+            match params {
+                Some(params) => ledger.asset_params_put(asset_id, params)?,
+                None => ledger.asset_params_del(asset_id)?,
+            }
+        }
+        for ((addr, asset_id), holding) in self.asset_holdings {
+            match holding {
+                Some(holding) => ledger.asset_holding_put(&addr, asset_id, holding)?,
+                None => ledger.asset_holding_del(&addr, asset_id)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Throw away the buffered writes, leaving the wrapped ledger untouched
+    pub fn discard(self) {}
+
+    /// Current contents of a box, copied from the wrapped ledger if not yet buffered
+    fn box_contents(&self, app_id: AppId, name: &[u8]) -> AvmResult<Option<Vec<u8>>> {
+        if let Some(contents) = self.boxes.get(&(app_id, name.to_vec())) {
+            return Ok(contents.clone());
+        }
+        match self.ledger.box_len(app_id, name)? {
+            Some(len) => Ok(Some(self.ledger.box_read(app_id, name, 0, len)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Mutable buffered contents of an existing box
+    fn box_mut(&mut self, app_id: AppId, name: &[u8]) -> AvmResult<&mut Vec<u8>> {
+        let contents = self.box_contents(app_id, name)?;
+        self.boxes
+            .entry((app_id, name.to_vec()))
+            .or_insert(contents)
+            .as_mut()
+            .ok_or_else(|| {
+                AvmError::state_error(format!("box {} does not exist", hex::encode(name)))
+            })
+    }
+}
+
+impl LedgerAccess for OverlayLedger<'_> {
+    fn balance(&self, addr: &Address) -> AvmResult<MicroAlgos> {
+        match self.balances.get(addr) {
+            Some(amount) => Ok(*amount),
+            None => self.ledger.balance(addr),
+        }
+    }
+
+    fn min_balance(&self, addr: &Address) -> AvmResult<MicroAlgos> {
+        self.ledger.min_balance(addr)
+    }
+
+    fn app_global_get(&self, app_id: AppId, key: &[u8]) -> AvmResult<Option<TealValue>> {
+        match self.global.get(&(app_id, key.to_vec())) {
+            Some(value) => Ok(value.clone()),
+            None => self.ledger.app_global_get(app_id, key),
+        }
+    }
+
+    fn app_global_put(&mut self, app_id: AppId, key: &[u8], value: TealValue) -> AvmResult<()> {
+        self.global.insert((app_id, key.to_vec()), Some(value));
+        Ok(())
+    }
+
+    fn app_global_del(&mut self, app_id: AppId, key: &[u8]) -> AvmResult<()> {
+        self.global.insert((app_id, key.to_vec()), None);
+        Ok(())
+    }
+
+    fn app_local_get(
+        &self,
+        addr: &Address,
+        app_id: AppId,
+        key: &[u8],
+    ) -> AvmResult<Option<TealValue>> {
+        if let Some(value) = self.local.get(&(addr.clone(), app_id, key.to_vec())) {
+            return Ok(value.clone());
+        }
+        if self.cleared.contains(&(addr.clone(), app_id)) {
+            return Ok(None);
+        }
+        self.ledger.app_local_get(addr, app_id, key)
+    }
+
+    fn app_local_put(
+        &mut self,
+        addr: &Address,
+        app_id: AppId,
+        key: &[u8],
+        value: TealValue,
+    ) -> AvmResult<()> {
+        self.local
+            .insert((addr.clone(), app_id, key.to_vec()), Some(value));
+        Ok(())
+    }
+
+    fn app_local_del(&mut self, addr: &Address, app_id: AppId, key: &[u8]) -> AvmResult<()> {
+        self.local
+            .insert((addr.clone(), app_id, key.to_vec()), None);
+        Ok(())
+    }
+
+    fn app_opted_in(&self, addr: &Address, app_id: AppId) -> AvmResult<bool> {
+        match self.opted_in.get(&(addr.clone(), app_id)) {
+            Some(opted_in) => Ok(*opted_in),
+            None => self.ledger.app_opted_in(addr, app_id),
+        }
+    }
+
+    fn asset_holding(&self, addr: &Address, asset_id: AssetId) -> AvmResult<Option<AssetHolding>> {
+        match self.asset_holdings.get(&(addr.clone(), asset_id)) {
+            Some(holding) => Ok(holding.clone()),
+            None => self.ledger.asset_holding(addr, asset_id),
+        }
+    }
+
+    fn asset_params(&self, asset_id: AssetId) -> AvmResult<Option<AssetParams>> {
+        match self.asset_params.get(&asset_id) {
+            Some(params) => Ok(params.clone()),
+            None => self.ledger.asset_params(asset_id),
+        }
+    }
+
+    fn app_params(&self, app_id: AppId) -> AvmResult<Option<AppParams>> {
+        match self.app_params.get(&app_id) {
+            Some(params) => Ok(params.clone()),
+            None => self.ledger.app_params(app_id),
+        }
+    }
+
+    fn account_params(&self, addr: &Address) -> AvmResult<Option<AccountParams>> {
+        self.ledger.account_params(addr)
+    }
+
+    fn current_round(&self) -> AvmResult<u64> {
+        self.ledger.current_round()
+    }
+
+    fn latest_timestamp(&self) -> AvmResult<u64> {
+        self.ledger.latest_timestamp()
+    }
+
+    fn genesis_hash(&self) -> AvmResult<Vec<u8>> {
+        self.ledger.genesis_hash()
+    }
+
+    fn current_application_id(&self) -> AvmResult<AppId> {
+        self.ledger.current_application_id()
+    }
+
+    fn creator_address(&self) -> AvmResult<Address> {
+        self.ledger.creator_address()
+    }
+
+    fn current_application_address(&self) -> AvmResult<Address> {
+        self.ledger.current_application_address()
+    }
+
+    fn group_id(&self) -> AvmResult<Vec<u8>> {
+        self.ledger.group_id()
+    }
+
+    fn opcode_budget(&self) -> AvmResult<u64> {
+        self.ledger.opcode_budget()
+    }
+
+    fn caller_application_id(&self) -> AvmResult<Option<AppId>> {
+        self.ledger.caller_application_id()
+    }
+
+    fn caller_application_address(&self) -> AvmResult<Option<Address>> {
+        self.ledger.caller_application_address()
+    }
+
+    fn get_txn_field(&self, txn_index: usize, field: TxnField) -> AvmResult<TealValue> {
+        self.ledger.get_txn_field(txn_index, field)
+    }
+
+    fn get_global_field(&self, field: GlobalField) -> AvmResult<TealValue> {
+        self.ledger.get_global_field(field)
+    }
+
+    fn current_transaction(&self) -> AvmResult<Transaction> {
+        self.ledger.current_transaction()
+    }
+
+    fn transaction_group(&self) -> AvmResult<Vec<Transaction>> {
+        self.ledger.transaction_group()
+    }
+
+    fn program_args(&self) -> AvmResult<Vec<Vec<u8>>> {
+        self.ledger.program_args()
+    }
+
+    fn group_scratch(&self, group_index: usize, slot: u8) -> AvmResult<TealValue> {
+        self.ledger.group_scratch(group_index, slot)
+    }
+
+    fn box_create(&mut self, app_id: AppId, name: &[u8], size: u64) -> AvmResult<bool> {
+        if self.box_contents(app_id, name)?.is_some() {
+            return Ok(false);
+        }
+        self.boxes
+            .insert((app_id, name.to_vec()), Some(vec![0; size as usize]));
+        Ok(true)
+    }
+
+    fn box_len(&self, app_id: AppId, name: &[u8]) -> AvmResult<Option<u64>> {
+        match self.boxes.get(&(app_id, name.to_vec())) {
+            Some(contents) => Ok(contents.as_ref().map(|contents| contents.len() as u64)),
+            None => self.ledger.box_len(app_id, name),
+        }
+    }
+
+    fn box_read(&self, app_id: AppId, name: &[u8], start: u64, length: u64) -> AvmResult<Vec<u8>> {
+        match self.boxes.get(&(app_id, name.to_vec())) {
+            Some(Some(contents)) => {
+                let range = box_range(contents.len(), start, length)?;
+                Ok(contents[range].to_vec())
+            }
+            Some(None) => Err(AvmError::state_error(format!(
+                "box {} does not exist",
+                hex::encode(name)
+            ))),
+            None => self.ledger.box_read(app_id, name, start, length),
+        }
+    }
+
+    fn box_write(&mut self, app_id: AppId, name: &[u8], start: u64, data: &[u8]) -> AvmResult<()> {
+        let contents = self.box_mut(app_id, name)?;
+        let range = box_range(contents.len(), start, data.len() as u64)?;
+        contents[range].copy_from_slice(data);
+        Ok(())
+    }
+
+    fn box_delete(&mut self, app_id: AppId, name: &[u8]) -> AvmResult<bool> {
+        let existed = self.box_contents(app_id, name)?.is_some();
+        self.boxes.insert((app_id, name.to_vec()), None);
+        Ok(existed)
+    }
+
+    fn box_resize(&mut self, app_id: AppId, name: &[u8], size: u64) -> AvmResult<()> {
+        self.box_mut(app_id, name)?.resize(size as usize, 0);
+        Ok(())
+    }
+
+    fn balance_put(&mut self, addr: &Address, amount: MicroAlgos) -> AvmResult<()> {
+        self.balances.insert(addr.clone(), amount);
+        Ok(())
+    }
+
+    fn asset_holding_put(
+        &mut self,
+        addr: &Address,
+        asset_id: AssetId,
+        holding: AssetHolding,
+    ) -> AvmResult<()> {
+        self.asset_holdings
+            .insert((addr.clone(), asset_id), Some(holding));
+        Ok(())
+    }
+
+    fn asset_holding_del(&mut self, addr: &Address, asset_id: AssetId) -> AvmResult<()> {
+        self.asset_holdings.insert((addr.clone(), asset_id), None);
+        Ok(())
+    }
+
+    fn asset_params_put(&mut self, asset_id: AssetId, params: AssetParams) -> AvmResult<()> {
+        self.asset_params.insert(asset_id, Some(params));
+        Ok(())
+    }
+
+    fn asset_params_del(&mut self, asset_id: AssetId) -> AvmResult<()> {
+        self.asset_params.insert(asset_id, None);
+        Ok(())
+    }
+
+    fn app_params_put(&mut self, app_id: AppId, params: AppParams) -> AvmResult<()> {
+        self.app_params.insert(app_id, Some(params));
+        Ok(())
+    }
+
+    fn app_params_del(&mut self, app_id: AppId) -> AvmResult<()> {
+        self.app_params.insert(app_id, None);
+        Ok(())
+    }
+
+    fn app_opt_in(&mut self, addr: &Address, app_id: AppId) -> AvmResult<()> {
+        self.opted_in.insert((addr.clone(), app_id), true);
+        Ok(())
+    }
+
+    fn app_opt_out(&mut self, addr: &Address, app_id: AppId) -> AvmResult<()> {
+        self.opted_in.insert((addr.clone(), app_id), false);
+        self.local
+            .retain(|(account, app, _), _| !(account == addr && *app == app_id));
+        self.cleared.insert((addr.clone(), app_id));
+        Ok(())
+    }
+
+    fn next_available_id(&self) -> AvmResult<u64> {
+        // IDs created in this overlay are never handed out again, even if deleted
+        let highest = self
+            .asset_params
+            .keys()
+            .chain(self.app_params.keys())
+            .copied()
+            .max()
+            .unwrap_or(0);
+        Ok(self.ledger.next_available_id()?.max(highest + 1))
+    }
+}
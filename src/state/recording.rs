@@ -0,0 +1,394 @@
+//! Ledger wrapper that records every state write made during evaluation
+
+use super::{
+    AccountParams, Address, AppId, AppParams, AssetHolding, AssetId, AssetParams, LedgerAccess,
+    MicroAlgos, Transaction,
+};
+use crate::error::AvmResult;
+use crate::types::{GlobalField, TealValue, TxnField};
+
+/// A single state write observed through [`LedgerAccess`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum StateChange {
+    /// Global state key set
+    GlobalPut {
+        app_id: AppId,
+        key: Vec<u8>,
+        value: TealValue,
+    },
+    /// Global state key deleted
+    GlobalDel { app_id: AppId, key: Vec<u8> },
+    /// Local state key set
+    LocalPut {
+        address: Address,
+        app_id: AppId,
+        key: Vec<u8>,
+        value: TealValue,
+    },
+    /// Local state key deleted
+    LocalDel {
+        address: Address,
+        app_id: AppId,
+        key: Vec<u8>,
+    },
+    /// Box created or written, with its contents after the write
+    BoxPut {
+        app_id: AppId,
+        name: Vec<u8>,
+        contents: Vec<u8>,
+    },
+    /// Box deleted
+    BoxDel { app_id: AppId, name: Vec<u8> },
+    /// Account balance changed
+    Balance {
+        address: Address,
+        previous: MicroAlgos,
+        amount: MicroAlgos,
+    },
+    /// Asset holding created or updated
+    AssetHoldingPut {
+        address: Address,
+        asset_id: AssetId,
+        holding: AssetHolding,
+    },
+    /// Asset holding removed
+    AssetHoldingDel { address: Address, asset_id: AssetId },
+    /// Asset created or reconfigured
+    AssetParamsPut {
+        asset_id: AssetId,
+        params: AssetParams,
+    },
+    /// Asset destroyed
+    AssetParamsDel { asset_id: AssetId },
+    /// Application created or updated
+    AppParamsPut { app_id: AppId, params: AppParams },
+    /// Application deleted
+    AppParamsDel { app_id: AppId },
+    /// Account opted in to an application
+    AppOptIn { address: Address, app_id: AppId },
+    /// Account opted out of an application
+    AppOptOut { address: Address, app_id: AppId },
+}
+
+/// Ledger wrapper that forwards to another ledger and records successful writes
+#[derive(Debug)]
+pub struct RecordingLedger<'a> {
+    ledger: &'a mut dyn LedgerAccess,
+    changes: Vec<StateChange>,
+}
+
+impl<'a> RecordingLedger<'a> {
+    /// Wrap a ledger
+    pub fn new(ledger: &'a mut dyn LedgerAccess) -> Self {
+        Self {
+            ledger,
+            changes: Vec::new(),
+        }
+    }
+
+    /// State writes recorded so far, in the order they were made
+    pub fn changes(&self) -> &[StateChange] {
+        &self.changes
+    }
+
+    /// Consume the wrapper and return the recorded writes
+    pub fn into_changes(self) -> Vec<StateChange> {
+        self.changes
+    }
+
+    /// Remove and return the writes recorded so far
+    pub fn take_changes(&mut self) -> Vec<StateChange> {
+        std::mem::take(&mut self.changes)
+    }
+
+    /// Consume the wrapper and return the wrapped ledger
+    pub fn into_inner(self) -> &'a mut dyn LedgerAccess {
+        self.ledger
+    }
+
+    fn record_box(&mut self, app_id: AppId, name: &[u8]) -> AvmResult<()> {
+        let len = self.ledger.box_len(app_id, name)?.unwrap_or(0);
+        let contents = self.ledger.box_read(app_id, name, 0, len)?;
+        self.changes.push(StateChange::BoxPut {
+            app_id,
+            name: name.to_vec(),
+            contents,
+        });
+        Ok(())
+    }
+}
+
+impl LedgerAccess for RecordingLedger<'_> {
+    fn balance(&self, addr: &Address) -> AvmResult<MicroAlgos> {
+        self.ledger.balance(addr)
+    }
+
+    fn min_balance(&self, addr: &Address) -> AvmResult<MicroAlgos> {
+        self.ledger.min_balance(addr)
+    }
+
+    fn app_global_get(&self, app_id: AppId, key: &[u8]) -> AvmResult<Option<TealValue>> {
+        self.ledger.app_global_get(app_id, key)
+    }
+
+    fn app_global_put(&mut self, app_id: AppId, key: &[u8], value: TealValue) -> AvmResult<()> {
+        self.ledger.app_global_put(app_id, key, value.clone())?;
+        self.changes.push(StateChange::GlobalPut {
+            app_id,
+            key: key.to_vec(),
+            value,
+        });
+        Ok(())
+    }
+
+    fn app_global_del(&mut self, app_id: AppId, key: &[u8]) -> AvmResult<()> {
+        self.ledger.app_global_del(app_id, key)?;
+        self.changes.push(StateChange::GlobalDel {
+            app_id,
+            key: key.to_vec(),
+        });
+        Ok(())
+    }
+
+    fn app_local_get(
+        &self,
+        addr: &Address,
+        app_id: AppId,
+        key: &[u8],
+    ) -> AvmResult<Option<TealValue>> {
+        self.ledger.app_local_get(addr, app_id, key)
+    }
+
+    fn app_local_put(
+        &mut self,
+        addr: &Address,
+        app_id: AppId,
+        key: &[u8],
+        value: TealValue,
+    ) -> AvmResult<()> {
+        self.ledger
+            .app_local_put(addr, app_id, key, value.clone())?;
+        self.changes.push(StateChange::LocalPut {
+            address: addr.clone(),
+            app_id,
+            key: key.to_vec(),
+            value,
+        });
+        Ok(())
+    }
+
+    fn app_local_del(&mut self, addr: &Address, app_id: AppId, key: &[u8]) -> AvmResult<()> {
+        self.ledger.app_local_del(addr, app_id, key)?;
+        self.changes.push(StateChange::LocalDel {
+            address: addr.clone(),
+            app_id,
+            key: key.to_vec(),
+        });
+        Ok(())
+    }
+
+    fn app_opted_in(&self, addr: &Address, app_id: AppId) -> AvmResult<bool> {
+        self.ledger.app_opted_in(addr, app_id)
+    }
+
+    fn asset_holding(&self, addr: &Address, asset_id: AssetId) -> AvmResult<Option<AssetHolding>> {
+        self.ledger.asset_holding(addr, asset_id)
+    }
+
+    fn asset_params(&self, asset_id: AssetId) -> AvmResult<Option<AssetParams>> {
+        self.ledger.asset_params(asset_id)
+    }
+
+    fn app_params(&self, app_id: AppId) -> AvmResult<Option<AppParams>> {
+        self.ledger.app_params(app_id)
+    }
+
+    fn account_params(&self, addr: &Address) -> AvmResult<Option<AccountParams>> {
+        self.ledger.account_params(addr)
+    }
+
+    fn current_round(&self) -> AvmResult<u64> {
+        self.ledger.current_round()
+    }
+
+    fn latest_timestamp(&self) -> AvmResult<u64> {
+        self.ledger.latest_timestamp()
+    }
+
+    fn genesis_hash(&self) -> AvmResult<Vec<u8>> {
+        self.ledger.genesis_hash()
+    }
+
+    fn current_application_id(&self) -> AvmResult<AppId> {
+        self.ledger.current_application_id()
+    }
+
+    fn creator_address(&self) -> AvmResult<Address> {
+        self.ledger.creator_address()
+    }
+
+    fn current_application_address(&self) -> AvmResult<Address> {
+        self.ledger.current_application_address()
+    }
+
+    fn group_id(&self) -> AvmResult<Vec<u8>> {
+        self.ledger.group_id()
+    }
+
+    fn opcode_budget(&self) -> AvmResult<u64> {
+        self.ledger.opcode_budget()
+    }
+
+    fn caller_application_id(&self) -> AvmResult<Option<AppId>> {
+        self.ledger.caller_application_id()
+    }
+
+    fn caller_application_address(&self) -> AvmResult<Option<Address>> {
+        self.ledger.caller_application_address()
+    }
+
+    fn get_txn_field(&self, txn_index: usize, field: TxnField) -> AvmResult<TealValue> {
+        self.ledger.get_txn_field(txn_index, field)
+    }
+
+    fn get_global_field(&self, field: GlobalField) -> AvmResult<TealValue> {
+        self.ledger.get_global_field(field)
+    }
+
+    fn current_transaction(&self) -> AvmResult<Transaction> {
+        self.ledger.current_transaction()
+    }
+
+    fn transaction_group(&self) -> AvmResult<Vec<Transaction>> {
+        self.ledger.transaction_group()
+    }
+
+    fn program_args(&self) -> AvmResult<Vec<Vec<u8>>> {
+        self.ledger.program_args()
+    }
+
+    fn group_scratch(&self, group_index: usize, slot: u8) -> AvmResult<TealValue> {
+        self.ledger.group_scratch(group_index, slot)
+    }
+
+    fn box_create(&mut self, app_id: AppId, name: &[u8], size: u64) -> AvmResult<bool> {
+        let created = self.ledger.box_create(app_id, name, size)?;
+        if created {
+            self.record_box(app_id, name)?;
+        }
+        Ok(created)
+    }
+
+    fn box_len(&self, app_id: AppId, name: &[u8]) -> AvmResult<Option<u64>> {
+        self.ledger.box_len(app_id, name)
+    }
+
+    fn box_read(&self, app_id: AppId, name: &[u8], start: u64, length: u64) -> AvmResult<Vec<u8>> {
+        self.ledger.box_read(app_id, name, start, length)
+    }
+
+    fn box_write(&mut self, app_id: AppId, name: &[u8], start: u64, data: &[u8]) -> AvmResult<()> {
+        self.ledger.box_write(app_id, name, start, data)?;
+        self.record_box(app_id, name)
+    }
+
+    fn box_delete(&mut self, app_id: AppId, name: &[u8]) -> AvmResult<bool> {
+        let deleted = self.ledger.box_delete(app_id, name)?;
+        if deleted {
+            self.changes.push(StateChange::BoxDel {
+                app_id,
+                name: name.to_vec(),
+            });
+        }
+        Ok(deleted)
+    }
+
+    fn box_resize(&mut self, app_id: AppId, name: &[u8], size: u64) -> AvmResult<()> {
+        self.ledger.box_resize(app_id, name, size)?;
+        self.record_box(app_id, name)
+    }
+
+    fn balance_put(&mut self, addr: &Address, amount: MicroAlgos) -> AvmResult<()> {
+        let previous = self.ledger.balance(addr)?;
+        self.ledger.balance_put(addr, amount)?;
+        self.changes.push(StateChange::Balance {
+            address: addr.clone(),
+            previous,
+            amount,
+        });
+        Ok(())
+    }
+
+    fn asset_holding_put(
+        &mut self,
+        addr: &Address,
+        asset_id: AssetId,
+        holding: AssetHolding,
+    ) -> AvmResult<()> {
+        self.ledger
+            .asset_holding_put(addr, asset_id, holding.clone())?;
+        self.changes.push(StateChange::AssetHoldingPut {
+            address: addr.clone(),
+            asset_id,
+            holding,
+        });
+        Ok(())
+    }
+
+    fn asset_holding_del(&mut self, addr: &Address, asset_id: AssetId) -> AvmResult<()> {
+        self.ledger.asset_holding_del(addr, asset_id)?;
+        self.changes.push(StateChange::AssetHoldingDel {
+            address: addr.clone(),
+            asset_id,
+        });
+        Ok(())
+    }
+
+    fn asset_params_put(&mut self, asset_id: AssetId, params: AssetParams) -> AvmResult<()> {
+        self.ledger.asset_params_put(asset_id, params.clone())?;
+        self.changes
+            .push(StateChange::AssetParamsPut { asset_id, params });
+        Ok(())
+    }
+
+    fn asset_params_del(&mut self, asset_id: AssetId) -> AvmResult<()> {
+        self.ledger.asset_params_del(asset_id)?;
+        self.changes.push(StateChange::AssetParamsDel { asset_id });
+        Ok(())
+    }
+
+    fn app_params_put(&mut self, app_id: AppId, params: AppParams) -> AvmResult<()> {
+        self.ledger.app_params_put(app_id, params.clone())?;
+        self.changes
+            .push(StateChange::AppParamsPut { app_id, params });
+        Ok(())
+    }
+
+    fn app_params_del(&mut self, app_id: AppId) -> AvmResult<()> {
+        self.ledger.app_params_del(app_id)?;
+        self.changes.push(StateChange::AppParamsDel { app_id });
+        Ok(())
+    }
+
+    fn app_opt_in(&mut self, addr: &Address, app_id: AppId) -> AvmResult<()> {
+        self.ledger.app_opt_in(addr, app_id)?;
+        self.changes.push(StateChange::AppOptIn {
+            address: addr.clone(),
+            app_id,
+        });
+        Ok(())
+    }
+
+    fn app_opt_out(&mut self, addr: &Address, app_id: AppId) -> AvmResult<()> {
+        self.ledger.app_opt_out(addr, app_id)?;
+        self.changes.push(StateChange::AppOptOut {
+            address: addr.clone(),
+            app_id,
+        });
+        Ok(())
+    }
+
+    fn next_available_id(&self) -> AvmResult<u64> {
+        self.ledger.next_available_id()
+    }
+}
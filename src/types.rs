@@ -215,11 +215,11 @@ pub enum TealVersion {
     V7 = 7,
     /// TEAL version 8 (added box storage)
     V8 = 8,
-    /// TEAL version 9 (added more box operations)
+    /// TEAL version 9 (added group resource sharing)
     V9 = 9,
-    /// TEAL version 10 (added elliptic curve operations)
+    /// TEAL version 10 (added elliptic curve and box splicing operations)
     V10 = 10,
-    /// TEAL version 11 (added MIMC hash and block opcode)
+    /// TEAL version 11 (added MIMC hash)
     V11 = 11,
 }
 
@@ -366,6 +366,7 @@ pub enum TxnField {
     ConfigAssetTotal,
     ConfigAssetDecimals,
     ConfigAssetDefaultFrozen,
+    ConfigAssetUnitName,
     ConfigAssetName,
     ConfigAssetURL,
     ConfigAssetMetadataHash,
@@ -419,4 +420,9 @@ pub enum GlobalField {
     AssetCreateMinBalance,
     AssetOptInMinBalance,
     GenesisHash,
+    PayoutsEnabled,
+    PayoutsGoOnlineFee,
+    PayoutsPercent,
+    PayoutsMinBalance,
+    PayoutsMaxBalance,
 }
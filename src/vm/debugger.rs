@@ -0,0 +1,616 @@
+//! Breakpoint debugger driving an evaluation one instruction at a time
+//!
+//! A [`Debugger`] owns an [`EvalContext`] and stops at breakpoints set by pc,
+//! label or source line, optionally guarded by a [`Condition`] such as
+//! `cost > 500`. Conditions without a location stop the program on the
+//! instruction where they become true. [`Expr`] names the parts of the
+//! machine state that conditions, watches and edits refer to.
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::{EvalContext, ExecutionConfig, VirtualMachine};
+use crate::assembler::Assembler;
+use crate::assembler::disassembler::Disassembly;
+use crate::assembler::source_map::{SourceLocation, SourceMap};
+use crate::error::{AvmError, AvmResult};
+use crate::state::OverlayLedger;
+use crate::types::StackValue;
+
+/// A value read from the machine state, or a constant
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Pc,
+    Cost,
+    /// Number of values on the stack
+    Height,
+    /// Stack value counted from the bottom, or from the top if negative
+    Stack(i64),
+    Scratch(u8),
+    /// Variable of the current subroutine, addressed as by `frame_dig`
+    Frame(i8),
+    Const(StackValue),
+}
+
+impl Expr {
+    /// Evaluate against the current machine state
+    pub fn evaluate(&self, ctx: &EvalContext) -> AvmResult<StackValue> {
+        Ok(match self {
+            Self::Pc => StackValue::Uint(ctx.pc() as u64),
+            Self::Cost => StackValue::Uint(ctx.cost()),
+            Self::Height => StackValue::Uint(ctx.stack_size() as u64),
+            Self::Stack(index) => ctx.stack()[stack_index(ctx, *index)?].clone(),
+            Self::Scratch(index) => ctx.get_scratch(*index)?.clone(),
+            Self::Frame(depth) => ctx.frame_dig(*depth)?,
+            Self::Const(value) => value.clone(),
+        })
+    }
+
+    /// Replace the stack value, scratch slot or frame variable this names
+    pub fn assign(&self, ctx: &mut EvalContext, value: StackValue) -> AvmResult<()> {
+        match self {
+            Self::Stack(index) => {
+                let index = stack_index(ctx, *index)?;
+                ctx.stack_mut()[index] = value;
+                Ok(())
+            }
+            Self::Scratch(index) => ctx.set_scratch(*index, value),
+            Self::Frame(depth) => ctx.frame_bury(*depth, value),
+            _ => Err(AvmError::parse_error(format!("cannot assign to {self}"))),
+        }
+    }
+}
+
+/// Position in the stack of `index`, which counts from the top if negative
+fn stack_index(ctx: &EvalContext, index: i64) -> AvmResult<usize> {
+    let height = ctx.stack_size() as i64;
+    let position = if index < 0 { height + index } else { index };
+    if (0..height).contains(&position) {
+        Ok(position as usize)
+    } else {
+        Err(AvmError::invalid_program(format!(
+            "stack[{index}] is outside a stack of {height}"
+        )))
+    }
+}
+
+impl FromStr for Expr {
+    type Err = AvmError;
+
+    /// Parse `pc`, `cost`, `height`, `stack[i]`, `scratch[i]`, `frame[i]` or a
+    /// constant written as the argument of `int` or `byte`
+    fn from_str(text: &str) -> AvmResult<Self> {
+        let text = text.trim();
+        match text {
+            "pc" => return Ok(Self::Pc),
+            "cost" => return Ok(Self::Cost),
+            "height" => return Ok(Self::Height),
+            _ => {}
+        }
+        if let Some((name, index)) = text.strip_suffix(']').and_then(|text| text.split_once('[')) {
+            let index = index.trim();
+            let invalid = || AvmError::parse_error(format!("invalid index in {text}"));
+            return match name.trim() {
+                "stack" => index.parse().map(Self::Stack).map_err(|_| invalid()),
+                "scratch" => index.parse().map(Self::Scratch).map_err(|_| invalid()),
+                "frame" => index.parse().map(Self::Frame).map_err(|_| invalid()),
+                name => Err(AvmError::parse_error(format!("unknown value: {name}"))),
+            };
+        }
+
+        let assembler = Assembler::new();
+        assembler
+            .parse_constant(text, false)
+            .or_else(|_| assembler.parse_constant(text, true))
+            .map(Self::Const)
+            .map_err(|_| AvmError::parse_error(format!("invalid expression: {text}")))
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pc => f.write_str("pc"),
+            Self::Cost => f.write_str("cost"),
+            Self::Height => f.write_str("height"),
+            Self::Stack(index) => write!(f, "stack[{index}]"),
+            Self::Scratch(index) => write!(f, "scratch[{index}]"),
+            Self::Frame(depth) => write!(f, "frame[{depth}]"),
+            Self::Const(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+/// Comparison operator of a [`Condition`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Comparison {
+    /// Operators as written, longest first so `<=` is not read as `<`
+    const SYMBOLS: [(&'static str, Self); 6] = [
+        ("==", Self::Eq),
+        ("!=", Self::Ne),
+        ("<=", Self::Le),
+        (">=", Self::Ge),
+        ("<", Self::Lt),
+        (">", Self::Gt),
+    ];
+
+    fn symbol(self) -> &'static str {
+        Self::SYMBOLS
+            .iter()
+            .find(|(_, op)| *op == self)
+            .map_or("", |(symbol, _)| symbol)
+    }
+}
+
+/// Comparison of two expressions, such as `cost > 500`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    pub left: Expr,
+    pub op: Comparison,
+    pub right: Expr,
+}
+
+impl Condition {
+    /// Evaluate against the current machine state
+    ///
+    /// Values of different types are never equal, and only integers can be
+    /// ordered.
+    pub fn holds(&self, ctx: &EvalContext) -> AvmResult<bool> {
+        let left = self.left.evaluate(ctx)?;
+        let right = self.right.evaluate(ctx)?;
+        match self.op {
+            Comparison::Eq => return Ok(left == right),
+            Comparison::Ne => return Ok(left != right),
+            _ => {}
+        }
+        let (left, right) = (left.as_uint()?, right.as_uint()?);
+        Ok(match self.op {
+            Comparison::Lt => left < right,
+            Comparison::Le => left <= right,
+            Comparison::Gt => left > right,
+            _ => left >= right,
+        })
+    }
+}
+
+impl FromStr for Condition {
+    type Err = AvmError;
+
+    fn from_str(text: &str) -> AvmResult<Self> {
+        let (position, symbol, op) = Comparison::SYMBOLS
+            .iter()
+            .filter_map(|(symbol, op)| text.find(symbol).map(|position| (position, symbol, *op)))
+            .min_by_key(|(position, symbol, _)| (*position, usize::MAX - symbol.len()))
+            .ok_or_else(|| AvmError::parse_error(format!("no comparison in {text:?}")))?;
+        Ok(Self {
+            left: text[..position].parse()?,
+            op,
+            right: text[position + symbol.len()..].parse()?,
+        })
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.left, self.op.symbol(), self.right)
+    }
+}
+
+/// Where a breakpoint is set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Location {
+    Pc(usize),
+    Label(String),
+    /// One-based source line, resolved through the source map
+    Line(usize),
+}
+
+impl FromStr for Location {
+    type Err = AvmError;
+
+    /// Parse a decimal or `0x` pc, `line N`, or a label
+    fn from_str(text: &str) -> AvmResult<Self> {
+        let text = text.trim();
+        if let Some(line) = text.strip_prefix("line ") {
+            return line
+                .trim()
+                .parse()
+                .map(Self::Line)
+                .map_err(|_| AvmError::parse_error(format!("invalid line: {line}")));
+        }
+        if let Some(hex) = text.strip_prefix("0x") {
+            return usize::from_str_radix(hex, 16)
+                .map(Self::Pc)
+                .map_err(|_| AvmError::parse_error(format!("invalid pc: {text}")));
+        }
+        if let Ok(pc) = text.parse() {
+            return Ok(Self::Pc(pc));
+        }
+        if text.is_empty() || text.contains(char::is_whitespace) {
+            return Err(AvmError::parse_error(format!("invalid location: {text:?}")));
+        }
+        Ok(Self::Label(text.to_string()))
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pc(pc) => write!(f, "pc {pc}"),
+            Self::Label(label) => f.write_str(label),
+            Self::Line(line) => write!(f, "line {line}"),
+        }
+    }
+}
+
+/// A place or condition to stop at
+#[derive(Debug, Clone, PartialEq)]
+pub struct Breakpoint {
+    pub id: usize,
+    /// Location as given
+    pub location: Option<Location>,
+    /// Pc the location resolved to
+    pub pc: Option<usize>,
+    pub condition: Option<Condition>,
+    /// Number of times the program stopped here
+    pub hits: usize,
+    /// Whether the condition held at the last instruction, for breakpoints
+    /// without a location
+    held: bool,
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.id)?;
+        match (&self.location, self.pc) {
+            (Some(Location::Pc(_)), Some(pc)) => write!(f, " at pc {pc}")?,
+            (Some(location), Some(pc)) => write!(f, " at {location} (pc {pc})")?,
+            _ => {}
+        }
+        if let Some(condition) = &self.condition {
+            write!(f, " if {condition}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Why execution stopped
+#[derive(Debug, Clone, PartialEq)]
+pub enum StopReason {
+    /// The requested step completed
+    Step,
+    /// The breakpoint with this id was reached
+    Breakpoint(usize),
+    /// The program ran to completion
+    Finished,
+    /// The instruction at `pc` failed
+    Error { pc: usize, message: String },
+}
+
+/// Evaluation under the control of breakpoints and stepping commands
+pub struct Debugger<'a> {
+    vm: &'a VirtualMachine,
+    config: ExecutionConfig,
+    ctx: EvalContext<'a>,
+    disassembly: Disassembly,
+    source_map: Option<SourceMap>,
+    breakpoints: Vec<Breakpoint>,
+    next_id: usize,
+    watches: Vec<Expr>,
+    steps: usize,
+    /// Whether execution has stopped at the current pc, so breakpoints there
+    /// were already considered
+    stopped: bool,
+    error: Option<(usize, String)>,
+}
+
+impl<'a> Debugger<'a> {
+    /// Prepare to evaluate `program`, stopped before its first instruction
+    ///
+    /// State writes are buffered in `ledger`, which the caller should only
+    /// commit if the program approves, as [`VirtualMachine::execute`] does.
+    pub fn new<'l: 'a>(
+        vm: &'a VirtualMachine,
+        program: &'a [u8],
+        config: ExecutionConfig,
+        ledger: &'a mut OverlayLedger<'l>,
+    ) -> AvmResult<Self> {
+        let disassembly = Disassembly::new(program)?;
+        let ctx = vm.create_eval_context(program, config.clone(), ledger)?;
+        Ok(Self {
+            vm,
+            config,
+            ctx,
+            disassembly,
+            source_map: None,
+            breakpoints: Vec::new(),
+            next_id: 1,
+            watches: Vec::new(),
+            steps: 0,
+            stopped: false,
+            error: None,
+        })
+    }
+
+    /// Resolve labels and source lines with the assembler's source map
+    pub fn with_source_map(mut self, source_map: SourceMap) -> Self {
+        self.source_map = Some(source_map);
+        self
+    }
+
+    /// The evaluation state
+    pub fn context(&self) -> &EvalContext<'a> {
+        &self.ctx
+    }
+
+    /// The evaluation state, for editing
+    pub fn context_mut(&mut self) -> &mut EvalContext<'a> {
+        &mut self.ctx
+    }
+
+    pub fn disassembly(&self) -> &Disassembly {
+        &self.disassembly
+    }
+
+    pub fn source_map(&self) -> Option<&SourceMap> {
+        self.source_map.as_ref()
+    }
+
+    /// Source location of the next instruction
+    pub fn location(&self) -> Option<SourceLocation> {
+        self.source_map.as_ref()?.location(self.ctx.pc())
+    }
+
+    /// Label at `pc`, from the source if known
+    pub fn label_at(&self, pc: usize) -> Option<&str> {
+        match &self.source_map {
+            Some(source_map) => source_map
+                .labels
+                .iter()
+                .find(|(_, label_pc)| **label_pc == pc)
+                .map(|(label, _)| label.as_str()),
+            None => self.disassembly.labels.get(&pc).map(String::as_str),
+        }
+    }
+
+    /// Number of instructions executed
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    /// Whether the program completed or failed
+    pub fn is_finished(&self) -> bool {
+        self.error.is_some() || self.ctx.is_finished()
+    }
+
+    /// Pc and message of the failed instruction
+    pub fn error(&self) -> Option<(usize, &str)> {
+        self.error
+            .as_ref()
+            .map(|(pc, message)| (*pc, message.as_str()))
+    }
+
+    /// Outcome of a finished program: whether it approved
+    pub fn result(&self) -> AvmResult<bool> {
+        if let Some((_, message)) = &self.error {
+            return Err(AvmError::invalid_program(message.clone()));
+        }
+        if !self.ctx.is_finished() {
+            return Err(AvmError::invalid_program("program has not finished"));
+        }
+        match self.ctx.stack() {
+            [value] => value.as_bool(),
+            stack => Err(AvmError::invalid_program(format!(
+                "stack has {} values at the end of the program",
+                stack.len()
+            ))),
+        }
+    }
+
+    /// Pc of the instruction at `location`
+    pub fn resolve(&self, location: &Location) -> AvmResult<usize> {
+        let pc = match location {
+            Location::Pc(pc) => *pc,
+            Location::Label(label) => {
+                let pc = match &self.source_map {
+                    Some(source_map) => source_map.labels.get(label).copied(),
+                    None => self
+                        .disassembly
+                        .labels
+                        .iter()
+                        .find(|(_, name)| *name == label)
+                        .map(|(pc, _)| *pc),
+                };
+                pc.ok_or_else(|| AvmError::parse_error(format!("unknown label: {label}")))?
+            }
+            Location::Line(line) => {
+                let source_map = self.source_map.as_ref().ok_or_else(|| {
+                    AvmError::parse_error("line breakpoints need the program source")
+                })?;
+                line.checked_sub(1)
+                    .and_then(|line| source_map.pc_for_line(line))
+                    .ok_or_else(|| {
+                        AvmError::parse_error(format!("no instructions on line {line}"))
+                    })?
+            }
+        };
+        if self.disassembly.instruction_at(pc).is_none() {
+            return Err(AvmError::parse_error(format!(
+                "pc {pc} is not the start of an instruction"
+            )));
+        }
+        Ok(pc)
+    }
+
+    /// Add a breakpoint, returning its id
+    ///
+    /// At least one of `location` and `condition` must be given.
+    pub fn add_breakpoint(
+        &mut self,
+        location: Option<Location>,
+        condition: Option<Condition>,
+    ) -> AvmResult<usize> {
+        let pc = location
+            .as_ref()
+            .map(|location| self.resolve(location))
+            .transpose()?;
+        if pc.is_none() && condition.is_none() {
+            return Err(AvmError::parse_error(
+                "a breakpoint needs a location or a condition",
+            ));
+        }
+        // A condition that already holds stops once it has been false
+        let held = condition
+            .as_ref()
+            .is_some_and(|condition| condition.holds(&self.ctx).unwrap_or(false));
+        let id = self.next_id;
+        self.next_id += 1;
+        self.breakpoints.push(Breakpoint {
+            id,
+            location,
+            pc,
+            condition,
+            hits: 0,
+            held,
+        });
+        Ok(id)
+    }
+
+    /// Remove a breakpoint, returning whether it existed
+    pub fn remove_breakpoint(&mut self, id: usize) -> bool {
+        let count = self.breakpoints.len();
+        self.breakpoints.retain(|breakpoint| breakpoint.id != id);
+        self.breakpoints.len() != count
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    /// Add an expression to report at every stop
+    pub fn add_watch(&mut self, expr: Expr) {
+        self.watches.push(expr);
+    }
+
+    /// Remove the watch at `index`
+    pub fn remove_watch(&mut self, index: usize) -> Option<Expr> {
+        (index < self.watches.len()).then(|| self.watches.remove(index))
+    }
+
+    /// Each watch with its current value, or the error evaluating it
+    pub fn watches(&self) -> Vec<(&Expr, AvmResult<StackValue>)> {
+        self.watches
+            .iter()
+            .map(|expr| (expr, expr.evaluate(&self.ctx)))
+            .collect()
+    }
+
+    /// Evaluate an expression against the current state
+    pub fn evaluate(&self, expr: &Expr) -> AvmResult<StackValue> {
+        expr.evaluate(&self.ctx)
+    }
+
+    /// Replace the stack value, scratch slot or frame variable `target` names
+    pub fn set_value(&mut self, target: &Expr, value: StackValue) -> AvmResult<()> {
+        target.assign(&mut self.ctx, value)
+    }
+
+    /// Execute one instruction
+    pub fn step(&mut self) -> StopReason {
+        self.run_until(|_| true)
+    }
+
+    /// Execute one instruction, running a `callsub` through to its `retsub`
+    pub fn step_over(&mut self) -> StopReason {
+        let depth = self.ctx.call_stack().len();
+        self.run_until(|ctx| ctx.call_stack().len() <= depth)
+    }
+
+    /// Run until the current subroutine returns
+    pub fn step_out(&mut self) -> StopReason {
+        let depth = self.ctx.call_stack().len();
+        self.run_until(|ctx| ctx.call_stack().len() < depth)
+    }
+
+    /// Run until a breakpoint or the end of the program
+    pub fn resume(&mut self) -> StopReason {
+        self.run_until(|_| false)
+    }
+
+    /// Execute instructions until `done` holds after one, a breakpoint is
+    /// reached or the program ends
+    ///
+    /// Breakpoints are checked before each instruction runs, except for the
+    /// instruction execution is already stopped at.
+    fn run_until(&mut self, done: impl Fn(&EvalContext) -> bool) -> StopReason {
+        let mut stepped = false;
+        loop {
+            if let Some((pc, message)) = &self.error {
+                return StopReason::Error {
+                    pc: *pc,
+                    message: message.clone(),
+                };
+            }
+            if self.ctx.is_finished() {
+                return StopReason::Finished;
+            }
+            if (stepped || !self.stopped)
+                && let Some(id) = self.check_breakpoints()
+            {
+                self.stopped = true;
+                return StopReason::Breakpoint(id);
+            }
+            if stepped && done(&self.ctx) {
+                self.stopped = true;
+                return StopReason::Step;
+            }
+
+            let pc = self.ctx.pc();
+            if let Err(err) = self.ctx.step(self.vm, &self.config) {
+                let message = err.to_string();
+                self.error = Some((pc, message.clone()));
+                return StopReason::Error { pc, message };
+            }
+            self.steps += 1;
+            stepped = true;
+        }
+    }
+
+    /// Id of the first breakpoint stopping at the next instruction
+    fn check_breakpoints(&mut self) -> Option<usize> {
+        let pc = self.ctx.pc();
+        let mut hit = None;
+        for breakpoint in &mut self.breakpoints {
+            let holds = breakpoint
+                .condition
+                .as_ref()
+                .is_none_or(|condition| condition.holds(&self.ctx).unwrap_or(false));
+            let stop = match breakpoint.pc {
+                Some(breakpoint_pc) => breakpoint_pc == pc && holds,
+                None => {
+                    let rising = holds && !breakpoint.held;
+                    breakpoint.held = holds;
+                    rising
+                }
+            };
+            if stop && hit.is_none() {
+                breakpoint.hits += 1;
+                hit = Some(breakpoint.id);
+            }
+        }
+        hit
+    }
+}
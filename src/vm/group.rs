@@ -0,0 +1,282 @@
+//! Atomic evaluation of transaction groups
+
+use super::{ExecutionConfig, ExecutionResult, VirtualMachine};
+use crate::error::{AvmError, AvmResult};
+use crate::state::{
+    ContextLedger, LedgerAccess, OnCompletion, OverlayLedger, Transaction, TransactionType, apply,
+};
+use crate::types::{RunMode, TealValue};
+
+/// Maximum number of transactions in a group
+pub const MAX_GROUP_SIZE: usize = 16;
+
+/// Logic signature attached to a group transaction
+#[derive(Debug, Clone, Default)]
+pub struct LogicSig {
+    /// Program bytecode
+    pub program: Vec<u8>,
+    /// Arguments available through `arg`
+    pub args: Vec<Vec<u8>>,
+}
+
+/// A transaction in a group together with the programs that evaluate it
+#[derive(Debug, Clone)]
+pub struct GroupTransaction {
+    /// The transaction; its group index is assigned by the evaluator
+    pub txn: Transaction,
+    /// Logic signature authorizing the transaction, if any
+    pub logic_sig: Option<LogicSig>,
+    /// Program to run for an application call instead of the one on the ledger
+    pub app_program: Option<Vec<u8>>,
+}
+
+impl GroupTransaction {
+    /// Wrap a transaction with no programs attached
+    pub fn new(txn: Transaction) -> Self {
+        Self {
+            txn,
+            logic_sig: None,
+            app_program: None,
+        }
+    }
+
+    /// Attach a logic signature
+    pub fn with_logic_sig(mut self, program: Vec<u8>, args: Vec<Vec<u8>>) -> Self {
+        self.logic_sig = Some(LogicSig { program, args });
+        self
+    }
+
+    /// Run the given program for this application call
+    pub fn with_app_program(mut self, program: Vec<u8>) -> Self {
+        self.app_program = Some(program);
+        self
+    }
+}
+
+/// Evaluation result of one transaction in a group
+#[derive(Debug, Clone)]
+pub struct TransactionResult {
+    /// The transaction as evaluated, with logs and created IDs filled in
+    pub txn: Transaction,
+    /// Result of the logic signature program
+    pub logic_sig: Option<ExecutionResult>,
+    /// Result of the application program
+    pub app_call: Option<ExecutionResult>,
+    /// Why this transaction failed, if it did
+    pub error: Option<String>,
+}
+
+/// Evaluation result of a whole group
+#[derive(Debug, Clone)]
+pub struct GroupResult {
+    /// Per-transaction results, in group order
+    pub transactions: Vec<TransactionResult>,
+    /// Index of the transaction that failed the group, if any
+    pub failed_index: Option<usize>,
+}
+
+impl GroupResult {
+    /// Whether every transaction in the group was approved and applied
+    pub fn approved(&self) -> bool {
+        self.failed_index.is_none()
+    }
+}
+
+impl VirtualMachine {
+    /// Evaluate a transaction group atomically
+    ///
+    /// Logic signatures are evaluated first, then each transaction is applied in
+    /// order, running the approval program of application calls. Later
+    /// transactions see the state changes, logs, created IDs and final scratch
+    /// space of earlier ones.
+    /// Writes are buffered for the whole group and only applied to `ledger` if
+    /// every transaction succeeds. `config` supplies the version and
+    /// per-program cost budget.
+    pub fn evaluate_group(
+        &self,
+        group: Vec<GroupTransaction>,
+        config: &ExecutionConfig,
+        ledger: &mut dyn LedgerAccess,
+    ) -> AvmResult<GroupResult> {
+        if group.is_empty() || group.len() > MAX_GROUP_SIZE {
+            return Err(AvmError::invalid_program(format!(
+                "group size {} must be between 1 and {MAX_GROUP_SIZE}",
+                group.len()
+            )));
+        }
+
+        let mut overlay = OverlayLedger::new(ledger);
+        let mut evaluation = GroupEvaluation::new(self, config, group);
+        let failed_index = evaluation.run(&mut overlay).err();
+        if failed_index.is_none() {
+            overlay.commit()?;
+        }
+
+        Ok(GroupResult {
+            transactions: evaluation.results,
+            failed_index,
+        })
+    }
+}
+
+/// State of a group evaluation in progress
+struct GroupEvaluation<'v> {
+    vm: &'v VirtualMachine,
+    config: &'v ExecutionConfig,
+    programs: Vec<(Option<LogicSig>, Option<Vec<u8>>)>,
+    txns: Vec<Transaction>,
+    scratch: Vec<Option<Vec<TealValue>>>,
+    results: Vec<TransactionResult>,
+}
+
+impl<'v> GroupEvaluation<'v> {
+    fn new(
+        vm: &'v VirtualMachine,
+        config: &'v ExecutionConfig,
+        group: Vec<GroupTransaction>,
+    ) -> Self {
+        let mut programs = Vec::with_capacity(group.len());
+        let mut txns = Vec::with_capacity(group.len());
+        for (index, entry) in group.into_iter().enumerate() {
+            let mut txn = entry.txn;
+            txn.group_index = index as u64;
+            txns.push(txn);
+            programs.push((entry.logic_sig, entry.app_program));
+        }
+        let results = txns
+            .iter()
+            .map(|txn| TransactionResult {
+                txn: txn.clone(),
+                logic_sig: None,
+                app_call: None,
+                error: None,
+            })
+            .collect();
+
+        Self {
+            vm,
+            config,
+            programs,
+            scratch: vec![None; txns.len()],
+            txns,
+            results,
+        }
+    }
+
+    /// Evaluate every transaction, returning the index of the first failure
+    fn run(&mut self, ledger: &mut dyn LedgerAccess) -> Result<(), usize> {
+        for index in 0..self.txns.len() {
+            self.check_logic_sig(ledger, index)
+                .map_err(|e| self.fail(index, e))?;
+        }
+        for index in 0..self.txns.len() {
+            self.apply(ledger, index).map_err(|e| self.fail(index, e))?;
+            self.results[index].txn = self.txns[index].clone();
+        }
+        Ok(())
+    }
+
+    fn fail(&mut self, index: usize, error: AvmError) -> usize {
+        self.results[index].error = Some(error.to_string());
+        index
+    }
+
+    fn check_logic_sig(&mut self, ledger: &mut dyn LedgerAccess, index: usize) -> AvmResult<()> {
+        let Some(logic_sig) = self.programs[index].0.clone() else {
+            return Ok(());
+        };
+
+        let mut view =
+            ContextLedger::new(ledger, self.txns.clone(), index)?.with_args(logic_sig.args);
+        let result = self.execute(&logic_sig.program, RunMode::Signature, index, &mut view)?;
+        let approved = result.approved();
+        let outcome = result.outcome.clone();
+        self.results[index].logic_sig = Some(result);
+
+        if !approved {
+            return Err(AvmError::execution_halted(format!(
+                "logic signature of transaction {index} rejected: {outcome:?}"
+            )));
+        }
+        Ok(())
+    }
+
+    fn apply(&mut self, ledger: &mut dyn LedgerAccess, index: usize) -> AvmResult<()> {
+        let mut txn = self.txns[index].clone();
+        apply::charge_fee(ledger, &txn)?;
+
+        if txn.tx_type == TransactionType::ApplicationCall {
+            self.apply_app_call(ledger, index, &mut txn)?;
+        } else {
+            apply::apply_effects(ledger, &mut txn)?;
+        }
+        apply::check_min_balance(ledger, &txn.sender)?;
+
+        self.txns[index] = txn;
+        Ok(())
+    }
+
+    fn apply_app_call(
+        &mut self,
+        ledger: &mut dyn LedgerAccess,
+        index: usize,
+        txn: &mut Transaction,
+    ) -> AvmResult<()> {
+        let on_completion = OnCompletion::from_u64(txn.on_completion.unwrap_or(0))?;
+        let (app_id, params) = apply::resolve_application(ledger, txn)?;
+        self.txns[index] = txn.clone();
+
+        let program = match (&self.programs[index].1, on_completion) {
+            (_, OnCompletion::ClearState) => params.clear_state_program.clone(),
+            (Some(program), _) => program.clone(),
+            (None, _) => params.approval_program.clone(),
+        };
+
+        let mut view = ContextLedger::new(ledger, self.txns.clone(), index)?
+            .with_application(app_id, params.creator.clone())
+            .with_group_scratch(self.scratch.clone());
+        let result = self.execute(&program, RunMode::Application, index, &mut view);
+
+        let approved = match result {
+            Ok(result) => {
+                txn.logs = result.logs.clone();
+                self.scratch[index] = Some(
+                    result
+                        .scratch
+                        .iter()
+                        .map(TealValue::from_stack_value)
+                        .collect(),
+                );
+                let approved = result.approved();
+                self.results[index].app_call = Some(result);
+                approved
+            }
+            // The clear state program cannot prevent clearing state
+            Err(_) if on_completion == OnCompletion::ClearState => false,
+            Err(e) => return Err(e),
+        };
+
+        if !approved && on_completion != OnCompletion::ClearState {
+            return Err(AvmError::execution_halted(format!(
+                "application call to {app_id} in transaction {index} rejected"
+            )));
+        }
+
+        apply::complete_app_call(ledger, txn, app_id, params, on_completion)
+    }
+
+    fn execute(
+        &self,
+        program: &[u8],
+        run_mode: RunMode,
+        index: usize,
+        ledger: &mut dyn LedgerAccess,
+    ) -> AvmResult<ExecutionResult> {
+        let config = self
+            .config
+            .clone()
+            .with_run_mode(run_mode)
+            .with_group(index, self.txns.len());
+        self.vm.execute_detailed(program, config, ledger)
+    }
+}
@@ -1,13 +1,28 @@
 //! Virtual Machine implementation
 
+use crate::assembler::disassembler::{Operand, disassemble_immediates};
 use crate::error::{AvmError, AvmResult};
 use crate::opcodes::{OpSpec, get_standard_opcodes};
-use crate::state::LedgerAccess;
+use crate::state::{
+    EvalDelta, InnerTxnDelta, LedgerAccess, OverlayLedger, RecordingLedger, Transaction,
+};
 #[cfg(feature = "tracing")]
 use crate::tracing::TracingConfig;
 use crate::types::{RunMode, StackValue, TealValue, TealVersion};
+use crate::varuint::decode_varuint;
+use std::borrow::Cow;
 use std::collections::HashMap;
 
+mod debugger;
+mod group;
+mod result;
+mod trace;
+
+pub use debugger::{Breakpoint, Comparison, Condition, Debugger, Expr, Location, StopReason};
+pub use group::{GroupResult, GroupTransaction, LogicSig, MAX_GROUP_SIZE, TransactionResult};
+pub use result::{ExecutionOutcome, ExecutionResult};
+pub use trace::{ScratchWrite, TraceStep};
+
 /// Maximum stack size
 pub const MAX_STACK_SIZE: usize = 1000;
 
@@ -17,14 +32,45 @@ pub const MAX_CALL_STACK_DEPTH: usize = 8;
 /// Scratch space size
 pub const SCRATCH_SIZE: usize = 256;
 
+/// Maximum number of inner transactions in a single `itxn_submit`
+pub const MAX_INNER_GROUP_SIZE: usize = 16;
+
+/// Maximum number of inner transactions issued by a top-level transaction group
+pub const MAX_INNER_TRANSACTIONS: usize = 256;
+
+/// Maximum depth of nested inner application calls
+pub const MAX_INNER_CALL_DEPTH: usize = 8;
+
+/// Maximum number of `log` calls per application call
+pub const MAX_LOG_CALLS: usize = 32;
+
+/// Maximum total size in bytes of all logs of an application call
+pub const MAX_LOG_SIZE: usize = 1024;
+
+/// Earliest program version that may run in application mode
+pub const MIN_APP_PROGRAM_VERSION: TealVersion = TealVersion::V2;
+
+/// Decode the version header of a program
+///
+/// Returns the declared version and the length of the header in bytes.
+pub fn program_version(program: &[u8]) -> AvmResult<(u64, usize)> {
+    if program.is_empty() {
+        return Err(AvmError::invalid_program("Empty program"));
+    }
+    decode_varuint(program).map_err(|_| AvmError::invalid_program("Invalid program version header"))
+}
+
 /// Execution configuration
 #[derive(Debug, Clone)]
 pub struct ExecutionConfig {
     pub run_mode: RunMode,
     pub cost_budget: u64,
+    /// Highest program version accepted
     pub version: TealVersion,
     pub group_index: usize,
     pub group_size: usize,
+    /// Record a [`TraceStep`] for each of at most this many steps
+    pub step_trace: Option<usize>,
     #[cfg(feature = "tracing")]
     pub tracing: TracingConfig,
 }
@@ -38,6 +84,7 @@ impl ExecutionConfig {
             version,
             group_index: 0,
             group_size: 1,
+            step_trace: None,
             #[cfg(feature = "tracing")]
             tracing: TracingConfig::default(),
         }
@@ -51,6 +98,7 @@ impl ExecutionConfig {
             version,
             group_index: 0,
             group_size: 1,
+            step_trace: None,
             #[cfg(feature = "tracing")]
             tracing: TracingConfig::default(),
         }
@@ -75,6 +123,12 @@ impl ExecutionConfig {
         self
     }
 
+    /// Record a structured trace of the first `max_steps` steps
+    pub fn with_step_trace(mut self, max_steps: usize) -> Self {
+        self.step_trace = Some(max_steps);
+        self
+    }
+
     /// Set tracing configuration
     #[cfg(feature = "tracing")]
     pub fn with_tracing(mut self, tracing: TracingConfig) -> Self {
@@ -90,6 +144,27 @@ impl ExecutionConfig {
     }
 }
 
+/// A subroutine call in progress
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    /// Pc to continue at after `retsub`
+    pub return_pc: usize,
+    /// Stack height when the subroutine was called
+    pub height: usize,
+    /// Arguments and return values declared with `proto`
+    pub proto: Option<(usize, usize)>,
+}
+
+/// The ledger of an evaluation context
+#[derive(Debug)]
+enum EvalLedger<'a> {
+    Direct(&'a mut dyn LedgerAccess),
+    /// Wrapped to record state writes while tracing
+    Recording(RecordingLedger<'a>),
+    /// Placeholder while switching between the other two
+    Detached,
+}
+
 /// Evaluation context for the AVM
 #[derive(Debug)]
 pub struct EvalContext<'a> {
@@ -97,7 +172,7 @@ pub struct EvalContext<'a> {
     stack: Vec<StackValue>,
 
     /// Program bytecode
-    program: &'a [u8],
+    program: Cow<'a, [u8]>,
 
     /// Program counter
     pc: usize,
@@ -117,8 +192,8 @@ pub struct EvalContext<'a> {
     /// Scratch space (256 slots)
     scratch: [StackValue; SCRATCH_SIZE],
 
-    /// Call stack for subroutines
-    call_stack: Vec<usize>,
+    /// Frames of the subroutines being executed
+    call_stack: Vec<Frame>,
 
     /// Group index in transaction group
     group_index: usize,
@@ -127,7 +202,7 @@ pub struct EvalContext<'a> {
     group_size: usize,
 
     /// Ledger access interface
-    ledger: &'a mut dyn LedgerAccess,
+    ledger: EvalLedger<'a>,
 
     /// Global state cache
     #[allow(dead_code)]
@@ -137,11 +212,17 @@ pub struct EvalContext<'a> {
     #[allow(dead_code)]
     local_state_cache: HashMap<(Vec<u8>, String), TealValue>,
 
-    /// Execution trace (for debugging)
-    trace: Vec<String>,
+    /// Structured record of executed steps
+    trace: Vec<TraceStep>,
+
+    /// Most steps to record, or `None` when not tracing
+    trace_limit: Option<usize>,
+
+    /// Whether steps went unrecorded for lack of room
+    trace_truncated: bool,
 
-    /// Enable tracing
-    trace_enabled: bool,
+    /// Record of the step being executed, while tracing
+    current_step: Option<TraceStep>,
 
     /// Tracing configuration
     #[cfg(feature = "tracing")]
@@ -151,17 +232,35 @@ pub struct EvalContext<'a> {
     #[cfg(feature = "tracing")]
     current_span: Option<tracing::Span>,
 
-    /// Function frame stack
-    frame_stack: Vec<Vec<StackValue>>,
-
-    /// Current function prototype (args, returns)
-    function_prototype: Option<(usize, usize)>,
-
     /// Integer constants from intcblock
     int_constants: Vec<u64>,
 
     /// Byte constants from bytecblock
     byte_constants: Vec<Vec<u8>>,
+
+    /// Inner transaction group under construction (between itxn_begin and itxn_submit)
+    pending_inner_group: Option<Vec<Transaction>>,
+
+    /// Most recently submitted inner transaction group
+    last_inner_group: Vec<Transaction>,
+
+    /// All inner transactions submitted by this program
+    inner_transactions: Vec<Transaction>,
+
+    /// Delta of each submitted inner transaction
+    inner_deltas: Vec<EvalDelta>,
+
+    /// Inner transactions issued so far, including those of nested application calls
+    inner_transaction_count: usize,
+
+    /// Unused fee credit available for pooling with inner transactions
+    fee_credit: Option<u64>,
+
+    /// Depth of nested inner application calls (0 for a top-level program)
+    inner_call_depth: usize,
+
+    /// Values emitted with `log`
+    logs: Vec<Vec<u8>>,
 }
 
 impl<'a> EvalContext<'a> {
@@ -179,7 +278,7 @@ impl<'a> EvalContext<'a> {
     ) -> Self {
         Self {
             stack: Vec::new(),
-            program,
+            program: Cow::Borrowed(program),
             pc: 0,
             run_mode,
             cost_budget,
@@ -189,37 +288,64 @@ impl<'a> EvalContext<'a> {
             call_stack: Vec::new(),
             group_index,
             group_size,
-            ledger,
+            ledger: EvalLedger::Direct(ledger),
             global_state_cache: HashMap::new(),
             local_state_cache: HashMap::new(),
             trace: Vec::new(),
-            trace_enabled: false,
+            trace_limit: None,
+            trace_truncated: false,
+            current_step: None,
             #[cfg(feature = "tracing")]
             tracing_config,
             #[cfg(feature = "tracing")]
             current_span: None,
-            frame_stack: Vec::new(),
-            function_prototype: None,
             int_constants: Vec::new(),
             byte_constants: Vec::new(),
+            pending_inner_group: None,
+            last_inner_group: Vec::new(),
+            inner_transactions: Vec::new(),
+            inner_deltas: Vec::new(),
+            inner_transaction_count: 0,
+            fee_credit: None,
+            inner_call_depth: 0,
+            logs: Vec::new(),
         }
     }
 
-    /// Enable or disable execution tracing
+    /// Enable or disable execution tracing, without a limit on its length
     pub fn set_trace_enabled(&mut self, enabled: bool) {
-        self.trace_enabled = enabled;
+        self.set_trace_limit(enabled.then_some(usize::MAX));
+    }
+
+    /// Record at most `max_steps` steps in total, or stop tracing if `None`
+    ///
+    /// While tracing, state writes are recorded through a [`RecordingLedger`]
+    /// wrapped around the context's ledger.
+    pub fn set_trace_limit(&mut self, max_steps: Option<usize>) {
+        self.trace_limit = max_steps;
+        let ledger = std::mem::replace(&mut self.ledger, EvalLedger::Detached);
+        self.ledger = match (ledger, max_steps) {
+            (EvalLedger::Direct(ledger), Some(_)) => {
+                EvalLedger::Recording(RecordingLedger::new(ledger))
+            }
+            (EvalLedger::Recording(recorder), None) => EvalLedger::Direct(recorder.into_inner()),
+            (ledger, _) => ledger,
+        };
     }
 
     /// Get the execution trace
-    pub fn trace(&self) -> &[String] {
+    pub fn trace(&self) -> &[TraceStep] {
         &self.trace
     }
 
-    /// Add a trace entry
-    pub fn add_trace(&mut self, message: String) {
-        if self.trace_enabled {
-            self.trace.push(message);
-        }
+    /// Remove and return the execution trace
+    pub fn take_trace(&mut self) -> Vec<TraceStep> {
+        std::mem::take(&mut self.trace)
+    }
+
+    /// Whether steps were executed after the trace reached its limit
+    pub fn trace_truncated(&self) -> bool {
+        self.trace_truncated
     }
 
     /// Get tracing configuration
@@ -245,6 +371,11 @@ impl<'a> EvalContext<'a> {
         &self.stack
     }
 
+    /// Get the stack for editing values in place
+    pub fn stack_mut(&mut self) -> &mut [StackValue] {
+        &mut self.stack
+    }
+
     /// Push a value onto the stack
     pub fn push(&mut self, value: StackValue) -> AvmResult<()> {
         if self.stack.len() >= MAX_STACK_SIZE {
@@ -420,10 +551,21 @@ impl<'a> EvalContext<'a> {
                 limit: SCRATCH_SIZE as u8,
             });
         }
+        if let Some(step) = &mut self.current_step {
+            step.scratch_writes.push(ScratchWrite {
+                slot: index,
+                value: value.clone(),
+            });
+        }
         self.scratch[idx] = value;
         Ok(())
     }
 
+    /// Get the whole scratch space
+    pub fn scratch(&self) -> &[StackValue] {
+        &self.scratch
+    }
+
     /// Call a subroutine
     pub fn call_subroutine(&mut self, target: usize) -> AvmResult<()> {
         if self.call_stack.len() >= MAX_CALL_STACK_DEPTH {
@@ -431,26 +573,61 @@ impl<'a> EvalContext<'a> {
                 limit: MAX_CALL_STACK_DEPTH,
             });
         }
-        self.call_stack.push(self.pc);
+        self.call_stack.push(Frame {
+            return_pc: self.pc,
+            height: self.stack.len(),
+            proto: None,
+        });
         self.set_pc(target)?;
         Ok(())
     }
 
     /// Return from a subroutine
+    ///
+    /// If the subroutine declared a prototype, its arguments and anything
+    /// above its return values are removed from the stack.
     pub fn return_from_subroutine(&mut self) -> AvmResult<()> {
-        let return_pc = self.call_stack.pop().ok_or(AvmError::CallStackUnderflow)?;
-        self.set_pc(return_pc)?;
+        let frame = self.call_stack.pop().ok_or(AvmError::CallStackUnderflow)?;
+        if let Some((args, returns)) = frame.proto {
+            let height = self.stack.len();
+            if height < frame.height + returns {
+                return Err(AvmError::invalid_program(format!(
+                    "retsub executed with {} return values on stack, proto declared {returns}",
+                    height.saturating_sub(frame.height)
+                )));
+            }
+            let start = frame.height - args;
+            self.stack.drain(start..height - returns);
+        }
+        self.set_pc(frame.return_pc)?;
         Ok(())
     }
 
+    /// Frames of the subroutines being executed, outermost first
+    pub fn call_stack(&self) -> &[Frame] {
+        &self.call_stack
+    }
+
     /// Get the ledger access interface
     pub fn ledger(&self) -> &dyn LedgerAccess {
-        self.ledger
+        match &self.ledger {
+            EvalLedger::Direct(ledger) => *ledger,
+            EvalLedger::Recording(recorder) => recorder,
+            EvalLedger::Detached => {
+                unreachable!("ledger is only detached while tracing is toggled")
+            }
+        }
     }
 
     /// Get mutable access to the ledger
     pub fn ledger_mut(&mut self) -> &mut dyn LedgerAccess {
-        self.ledger
+        match &mut self.ledger {
+            EvalLedger::Direct(ledger) => *ledger,
+            EvalLedger::Recording(recorder) => recorder,
+            EvalLedger::Detached => {
+                unreachable!("ledger is only detached while tracing is toggled")
+            }
+        }
     }
 
     /// Check if execution is finished
@@ -458,60 +635,57 @@ impl<'a> EvalContext<'a> {
         self.pc >= self.program.len()
     }
 
-    /// Set function prototype
+    /// Declare the arguments and return values of the current subroutine
     pub fn set_function_prototype(&mut self, args: usize, returns: usize) -> AvmResult<()> {
-        self.function_prototype = Some((args, returns));
+        let height = self.stack.len();
+        let frame = self
+            .call_stack
+            .last_mut()
+            .ok_or_else(|| AvmError::invalid_program("proto was executed without a callsub"))?;
+        if args > height {
+            return Err(AvmError::invalid_program(format!(
+                "callsub to proto that requires {args} args with stack height {height}"
+            )));
+        }
+        frame.proto = Some((args, returns));
         Ok(())
     }
 
-    /// Dig value from function frame
-    pub fn frame_dig(&self, depth: i8) -> AvmResult<StackValue> {
-        if self.frame_stack.is_empty() {
-            return Err(AvmError::invalid_program("No function frame available"));
+    /// Index into the stack of a frame variable
+    ///
+    /// Negative depths are the arguments of the current subroutine, counting
+    /// back from the last, and other depths count up from its first local.
+    fn frame_index(&self, depth: i8) -> AvmResult<usize> {
+        let frame = self
+            .call_stack
+            .last()
+            .ok_or_else(|| AvmError::invalid_program("frame access with empty callstack"))?;
+        if let Some((args, _)) = frame.proto
+            && -(depth as isize) > args as isize
+        {
+            return Err(AvmError::invalid_program(format!(
+                "frame access {depth} in sub with {args} args"
+            )));
         }
-
-        let current_frame = self.frame_stack.last().unwrap();
-        let index = if depth < 0 {
-            // Negative depth: access from bottom of frame
-            (-depth - 1) as usize
-        } else {
-            // Positive depth: access from top of frame
-            if current_frame.len() <= depth as usize {
-                return Err(AvmError::invalid_program("Frame dig index out of bounds"));
-            }
-            current_frame.len() - 1 - (depth as usize)
-        };
-
-        if index >= current_frame.len() {
-            return Err(AvmError::invalid_program("Frame dig index out of bounds"));
+        let index = frame.height as isize + depth as isize;
+        if index < 0 {
+            return Err(AvmError::invalid_program("frame access below stack"));
+        }
+        if index as usize >= self.stack.len() {
+            return Err(AvmError::invalid_program("frame access above stack"));
         }
+        Ok(index as usize)
+    }
 
-        Ok(current_frame[index].clone())
+    /// Read a variable of the current subroutine frame
+    pub fn frame_dig(&self, depth: i8) -> AvmResult<StackValue> {
+        Ok(self.stack[self.frame_index(depth)?].clone())
     }
 
-    /// Bury value in function frame
+    /// Replace a variable of the current subroutine frame
     pub fn frame_bury(&mut self, depth: i8, value: StackValue) -> AvmResult<()> {
-        if self.frame_stack.is_empty() {
-            return Err(AvmError::invalid_program("No function frame available"));
-        }
-
-        let current_frame = self.frame_stack.last_mut().unwrap();
-        let index = if depth < 0 {
-            // Negative depth: access from bottom of frame
-            (-depth - 1) as usize
-        } else {
-            // Positive depth: access from top of frame
-            if current_frame.len() <= depth as usize {
-                return Err(AvmError::invalid_program("Frame bury index out of bounds"));
-            }
-            current_frame.len() - 1 - (depth as usize)
-        };
-
-        if index >= current_frame.len() {
-            return Err(AvmError::invalid_program("Frame bury index out of bounds"));
-        }
-
-        current_frame[index] = value;
+        let index = self.frame_index(depth)?;
+        self.stack[index] = value;
         Ok(())
     }
 
@@ -542,9 +716,153 @@ impl<'a> EvalContext<'a> {
             })
     }
 
+    /// Start a new inner transaction group with the given transaction
+    pub fn begin_inner_group(&mut self, mut txn: Transaction) -> AvmResult<()> {
+        if self.pending_inner_group.is_some() {
+            return Err(AvmError::invalid_program(
+                "itxn_begin without itxn_submit for the previous group",
+            ));
+        }
+        txn.group_index = 0;
+        self.pending_inner_group = Some(vec![txn]);
+        Ok(())
+    }
+
+    /// Append a transaction to the inner group under construction
+    pub fn push_inner_transaction(&mut self, mut txn: Transaction) -> AvmResult<()> {
+        let group = self
+            .pending_inner_group
+            .as_mut()
+            .ok_or_else(|| AvmError::invalid_program("itxn_next without itxn_begin"))?;
+        if group.len() >= MAX_INNER_GROUP_SIZE {
+            return Err(AvmError::invalid_program(format!(
+                "too many inner transactions in one group (max {MAX_INNER_GROUP_SIZE})"
+            )));
+        }
+        txn.group_index = group.len() as u64;
+        group.push(txn);
+        Ok(())
+    }
+
+    /// Get the inner transaction currently under construction
+    pub fn current_inner_transaction_mut(&mut self) -> AvmResult<&mut Transaction> {
+        self.pending_inner_group
+            .as_mut()
+            .and_then(|group| group.last_mut())
+            .ok_or_else(|| AvmError::invalid_program("itxn_field without itxn_begin"))
+    }
+
+    /// Take the inner group under construction for submission
+    pub fn take_inner_group(&mut self) -> AvmResult<Vec<Transaction>> {
+        self.pending_inner_group
+            .take()
+            .ok_or_else(|| AvmError::invalid_program("itxn_submit without itxn_begin"))
+    }
+
+    /// Record a submitted inner transaction group and the delta of each transaction
+    pub fn record_inner_group(&mut self, group: Vec<Transaction>, deltas: Vec<EvalDelta>) {
+        self.inner_transactions.extend(group.iter().cloned());
+        self.inner_deltas.extend(deltas);
+        self.last_inner_group = group;
+    }
+
+    /// Get the most recently submitted inner transaction group
+    pub fn last_inner_group(&self) -> &[Transaction] {
+        &self.last_inner_group
+    }
+
+    /// Get all inner transactions submitted by this program
+    pub fn inner_transactions(&self) -> &[Transaction] {
+        &self.inner_transactions
+    }
+
+    /// Get all submitted inner transactions paired with their deltas
+    pub fn inner_transaction_deltas(&self) -> Vec<InnerTxnDelta> {
+        self.inner_transactions
+            .iter()
+            .zip(&self.inner_deltas)
+            .map(|(txn, delta)| InnerTxnDelta {
+                txn: txn.clone(),
+                delta: delta.clone(),
+            })
+            .collect()
+    }
+
+    /// Get the number of inner transactions issued, including nested application calls
+    pub fn inner_transaction_count(&self) -> usize {
+        self.inner_transaction_count
+    }
+
+    /// Set the number of inner transactions issued
+    pub fn set_inner_transaction_count(&mut self, count: usize) {
+        self.inner_transaction_count = count;
+    }
+
+    /// Get the unused fee credit, if it has been computed
+    pub fn fee_credit(&self) -> Option<u64> {
+        self.fee_credit
+    }
+
+    /// Set the unused fee credit
+    pub fn set_fee_credit(&mut self, credit: u64) {
+        self.fee_credit = Some(credit);
+    }
+
+    /// Get the depth of nested inner application calls
+    pub fn inner_call_depth(&self) -> usize {
+        self.inner_call_depth
+    }
+
+    /// Set the depth of nested inner application calls
+    pub fn set_inner_call_depth(&mut self, depth: usize) {
+        self.inner_call_depth = depth;
+    }
+
+    /// Get the values emitted with `log`
+    pub fn logs(&self) -> &[Vec<u8>] {
+        &self.logs
+    }
+
+    /// Append a log entry, enforcing the per-call count and size limits
+    pub fn append_log(&mut self, data: Vec<u8>) -> AvmResult<()> {
+        if self.logs.len() >= MAX_LOG_CALLS {
+            return Err(AvmError::invalid_program(format!(
+                "too many log calls in program. up to {MAX_LOG_CALLS} is allowed"
+            )));
+        }
+        let total: usize = self.logs.iter().map(Vec::len).sum::<usize>() + data.len();
+        if total > MAX_LOG_SIZE {
+            return Err(AvmError::invalid_program(format!(
+                "program logs too large. {total} bytes > {MAX_LOG_SIZE} bytes limit"
+            )));
+        }
+        self.logs.push(data);
+        Ok(())
+    }
+
     /// Get program reference for reading bytes
     pub fn get_program(&self) -> &[u8] {
-        self.program
+        &self.program
+    }
+
+    /// Replace the program, keeping the stack, scratch space and cost
+    ///
+    /// Execution continues after the new program's header, with no subroutine
+    /// frames and empty constant blocks. The new program must have the same
+    /// version as the one it replaces.
+    pub fn load_program(&mut self, program: Vec<u8>) -> AvmResult<()> {
+        let (version, header_len) = program_version(&program)?;
+        if version != self.version.as_u8() as u64 {
+            return Err(AvmError::invalid_program(format!(
+                "program version {version} does not match the running version {}",
+                self.version.as_u8()
+            )));
+        }
+        self.program = Cow::Owned(program);
+        self.call_stack.clear();
+        self.int_constants.clear();
+        self.byte_constants.clear();
+        self.set_pc(header_len)
     }
 
     /// Branch to a relative target
@@ -570,6 +888,42 @@ impl<'a> EvalContext<'a> {
         })
     }
 
+    /// Begin recording the step about to execute `spec`, if tracing
+    ///
+    /// Returns the height from which the step's stack effect is recorded.
+    fn start_trace_step(&mut self, spec: &OpSpec) -> usize {
+        let Some(limit) = self.trace_limit else {
+            return 0;
+        };
+        if self.trace.len() >= limit {
+            self.trace_truncated = true;
+            return 0;
+        }
+        let base = trace::stack_base(self, spec);
+        let immediates = disassemble_immediates(spec, &self.program, self.pc + 1)
+            .map(|(operands, _)| {
+                operands
+                    .into_iter()
+                    .map(|operand| match operand {
+                        Operand::Text(text) => text,
+                        Operand::Target(target) => target.to_string(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        self.current_step = Some(TraceStep {
+            pc: self.pc,
+            opcode: spec.name.clone(),
+            immediates,
+            cost: spec.cost,
+            popped: self.stack[base..].to_vec(),
+            pushed: Vec::new(),
+            scratch_writes: Vec::new(),
+            state_writes: Vec::new(),
+        });
+        base
+    }
+
     /// Execute a single step with the given VM and config
     pub fn step(&mut self, vm: &VirtualMachine, config: &ExecutionConfig) -> AvmResult<()> {
         if self.is_finished() {
@@ -583,9 +937,9 @@ impl<'a> EvalContext<'a> {
         })?;
 
         // Check version and mode compatibility
-        if spec.min_version > config.version.as_u8() {
+        if spec.min_version > self.version.as_u8() {
             return Err(AvmError::OpcodeNotAvailable {
-                version: config.version.as_u8(),
+                version: self.version.as_u8(),
                 opcode: spec.name.clone(),
             });
         }
@@ -625,12 +979,23 @@ impl<'a> EvalContext<'a> {
         // Add execution cost
         self.add_cost(spec.cost)?;
 
-        // Add trace entry (legacy tracing)
-        let pc = self.pc();
-        self.add_trace(format!("PC:{pc:04} {} (cost: {})", spec.name, spec.cost));
+        // Start a trace record, copying the values the step will touch
+        let base = self.start_trace_step(spec);
 
         // Execute the opcode
-        (spec.execute)(self)?;
+        let result = (spec.execute)(self);
+        let state_writes = match &mut self.ledger {
+            EvalLedger::Recording(recorder) => recorder.take_changes(),
+            _ => Vec::new(),
+        };
+        if let Some(mut step) = self.current_step.take()
+            && result.is_ok()
+        {
+            step.pushed = self.stack.get(base..).unwrap_or_default().to_vec();
+            step.state_writes = state_writes;
+            self.trace.push(step);
+        }
+        result?;
 
         // Log stack state after execution
         #[cfg(feature = "tracing")]
@@ -723,30 +1088,16 @@ impl VirtualMachine {
     }
 
     /// Load standard opcodes for a specific TEAL version
+    ///
+    /// Only opcodes introduced in `version` or earlier are registered.
     pub fn load_standard_opcodes(&mut self, version: TealVersion) {
         for spec in get_standard_opcodes() {
-            // Only register opcodes that are available in the specified version
-            if Self::is_opcode_available_in_version(&spec, version) {
+            if spec.min_version <= version.as_u8() {
                 self.opcodes.insert(spec.opcode, spec);
             }
         }
     }
 
-    /// Check if an opcode is available in a specific TEAL version
-    fn is_opcode_available_in_version(spec: &OpSpec, version: TealVersion) -> bool {
-        // For now, we'll include all opcodes since we don't have version restrictions implemented
-        // In a full implementation, this would check the minimum version for each opcode
-        match spec.name.as_str() {
-            // Subroutines were added in v4
-            "callsub" | "retsub" => version >= TealVersion::V4,
-            // Box operations were added in v8
-            "box_create" | "box_extract" | "box_replace" | "box_del" | "box_len" | "box_get"
-            | "box_put" => version >= TealVersion::V8,
-            // Most basic opcodes are available from v1
-            _ => true,
-        }
-    }
-
     /// Register an opcode specification
     pub fn register_opcode(&mut self, opcode: u8, spec: OpSpec) {
         self.opcodes.insert(opcode, spec);
@@ -785,40 +1136,58 @@ impl VirtualMachine {
     }
 
     /// Create an evaluation context for step-by-step execution
+    ///
+    /// The program's version is read from its header and must not exceed
+    /// `config.version`. Execution starts after the header.
     pub fn create_eval_context<'a>(
         &'a self,
         program: &'a [u8],
         config: ExecutionConfig,
         ledger: &'a mut dyn LedgerAccess,
     ) -> AvmResult<EvalContext<'a>> {
-        if program.is_empty() {
-            return Err(AvmError::invalid_program("Empty program"));
+        let (version, header_len) = program_version(program)?;
+        if version > config.version.as_u8() as u64 {
+            return Err(AvmError::invalid_program(format!(
+                "program version {version} is greater than the maximum supported version {}",
+                config.version.as_u8()
+            )));
+        }
+        let version = TealVersion::from_u8(version as u8)?;
+        if config.run_mode == RunMode::Application && version < MIN_APP_PROGRAM_VERSION {
+            return Err(AvmError::invalid_program(format!(
+                "program version must be >= {} for application programs",
+                MIN_APP_PROGRAM_VERSION.as_u8()
+            )));
         }
 
-        Ok(EvalContext::new(
+        let mut ctx = EvalContext::new(
             program,
             config.run_mode,
             config.cost_budget,
-            config.version,
+            version,
             config.group_index,
             config.group_size,
             ledger,
             #[cfg(feature = "tracing")]
             config.tracing.clone(),
-        ))
+        );
+        ctx.set_pc(header_len)?;
+        if config.step_trace.is_some() {
+            ctx.set_trace_limit(config.step_trace);
+        }
+        Ok(ctx)
     }
 
     /// Execute a TEAL program
+    ///
+    /// State writes are buffered and only applied to `ledger` if the program
+    /// approves.
     pub fn execute(
         &self,
         program: &[u8],
         config: ExecutionConfig,
         ledger: &mut dyn LedgerAccess,
     ) -> AvmResult<bool> {
-        if program.is_empty() {
-            return Err(AvmError::invalid_program("Empty program"));
-        }
-
         // Initialize tracing if enabled
         #[cfg(feature = "tracing")]
         let _tracing_guard = if config.tracing.enabled {
@@ -829,36 +1198,114 @@ impl VirtualMachine {
             None
         };
 
-        let mut ctx = EvalContext::new(
-            program,
-            config.run_mode,
-            config.cost_budget,
-            config.version,
-            config.group_index,
-            config.group_size,
-            ledger,
-            #[cfg(feature = "tracing")]
-            config.tracing.clone(),
-        );
+        let mut overlay = OverlayLedger::new(ledger);
+        let mut ctx = self.create_eval_context(program, config.clone(), &mut overlay)?;
 
         // Log execution start
         #[cfg(feature = "tracing")]
         if ctx.tracing_config().enabled {
             tracing::info!(
                 program_length = program.len(),
-                version = config.version.as_u8(),
+                version = ctx.version().as_u8(),
                 run_mode = ?config.run_mode,
                 cost_budget = config.cost_budget,
                 "Starting AVM execution"
             );
         }
 
+        let approved = self.run(&mut ctx, &config)?;
+        drop(ctx);
+        if approved {
+            overlay.commit()?;
+        }
+        Ok(approved)
+    }
+
+    /// Execute a TEAL program and report everything observable about the run
+    ///
+    /// Evaluation failures are reported in the result's outcome rather than as
+    /// an error; an error is only returned if evaluation could not start. As
+    /// with [`execute`](Self::execute), state writes are only applied to
+    /// `ledger` if the program approves.
+    pub fn execute_detailed(
+        &self,
+        program: &[u8],
+        config: ExecutionConfig,
+        ledger: &mut dyn LedgerAccess,
+    ) -> AvmResult<ExecutionResult> {
+        #[cfg(feature = "tracing")]
+        let _tracing_guard = if config.tracing.enabled {
+            Some(crate::tracing::init_tracing(&config.tracing).map_err(|e| {
+                AvmError::invalid_program(format!("Failed to initialize tracing: {e}"))
+            })?)
+        } else {
+            None
+        };
+
+        let mut overlay = OverlayLedger::new(ledger);
+        let mut recorder = RecordingLedger::new(&mut overlay);
+        let mut ctx = self.create_eval_context(program, config.clone(), &mut recorder)?;
+
+        let mut failure = None;
+        while !ctx.is_finished() {
+            let pc = ctx.pc();
+            if let Err(e) = ctx.step(self, &config) {
+                failure = Some(ExecutionOutcome::Error {
+                    pc,
+                    message: e.to_string(),
+                });
+                break;
+            }
+        }
+
+        let stack = ctx.stack().to_vec();
+        let outcome = failure.unwrap_or_else(|| match Self::finish(&mut ctx) {
+            Ok(true) => ExecutionOutcome::Approve,
+            Ok(false) => ExecutionOutcome::Reject,
+            Err(e) => ExecutionOutcome::Error {
+                pc: ctx.pc(),
+                message: e.to_string(),
+            },
+        });
+
+        let mut result = ExecutionResult {
+            outcome,
+            cost: ctx.cost(),
+            remaining_budget: ctx.cost_budget().saturating_sub(ctx.cost()),
+            logs: ctx.logs().to_vec(),
+            stack,
+            scratch: ctx.scratch().to_vec(),
+            inner_transactions: ctx.inner_transactions().to_vec(),
+            state_changes: Vec::new(),
+            eval_delta: EvalDelta::default(),
+            trace_truncated: ctx.trace_truncated(),
+            trace: ctx.take_trace(),
+        };
+        let inner_txns = ctx.inner_transaction_deltas();
+        drop(ctx);
+        let app_id = recorder.current_application_id().unwrap_or_default();
+        result.eval_delta =
+            EvalDelta::from_changes(app_id, recorder.changes(), result.logs.clone(), inner_txns);
+        result.state_changes = recorder.into_changes();
+        if result.approved() {
+            overlay.commit()?;
+        }
+
+        Ok(result)
+    }
+
+    /// Run an evaluation context to completion and return the program's result
+    pub fn run(&self, ctx: &mut EvalContext, config: &ExecutionConfig) -> AvmResult<bool> {
         // Main execution loop - simply reuse step method
         while !ctx.is_finished() {
-            ctx.step(self, &config)?;
+            ctx.step(self, config)?;
         }
 
-        // Check final result
+        Self::finish(ctx)
+    }
+
+    /// Check the final stack of a finished program and return its result
+    fn finish(ctx: &mut EvalContext) -> AvmResult<bool> {
         if ctx.stack_size() != 1 {
             let stack_size = ctx.stack_size();
 
@@ -0,0 +1,69 @@
+//! Detailed results of program execution
+
+use crate::state::{EvalDelta, StateChange, Transaction};
+use crate::types::StackValue;
+
+use super::TraceStep;
+
+/// How a program finished
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExecutionOutcome {
+    /// The program finished with a single non-zero value on the stack
+    Approve,
+    /// The program finished with a single zero value on the stack
+    Reject,
+    /// Evaluation failed at the given program counter
+    Error { pc: usize, message: String },
+}
+
+/// Everything observable about a finished execution
+#[derive(Debug, Clone)]
+pub struct ExecutionResult {
+    /// How the program finished
+    pub outcome: ExecutionOutcome,
+    /// Opcode cost consumed
+    pub cost: u64,
+    /// Cost budget left unused
+    pub remaining_budget: u64,
+    /// Values emitted with `log`
+    pub logs: Vec<Vec<u8>>,
+    /// Final stack contents
+    pub stack: Vec<StackValue>,
+    /// Final contents of all scratch slots
+    pub scratch: Vec<StackValue>,
+    /// Inner transactions submitted by the program
+    pub inner_transactions: Vec<Transaction>,
+    /// State writes, in the order they were made; only applied to the ledger
+    /// if the program approved
+    pub state_changes: Vec<StateChange>,
+    /// What the program changed, in go-algorand's `EvalDelta` shape
+    pub eval_delta: EvalDelta,
+    /// Record of each step, if enabled with
+    /// [`ExecutionConfig::with_step_trace`](super::ExecutionConfig::with_step_trace)
+    pub trace: Vec<TraceStep>,
+    /// Whether steps went unrecorded because the trace reached its limit
+    pub trace_truncated: bool,
+}
+
+impl ExecutionResult {
+    /// Whether the program approved
+    pub fn approved(&self) -> bool {
+        self.outcome == ExecutionOutcome::Approve
+    }
+
+    /// The error message and program counter if evaluation failed
+    pub fn error(&self) -> Option<(usize, &str)> {
+        match &self.outcome {
+            ExecutionOutcome::Error { pc, message } => Some((*pc, message)),
+            _ => None,
+        }
+    }
+
+    /// Scratch slots holding something other than the initial zero value
+    pub fn used_scratch(&self) -> impl Iterator<Item = (usize, &StackValue)> {
+        self.scratch
+            .iter()
+            .enumerate()
+            .filter(|(_, value)| **value != StackValue::Uint(0))
+    }
+}
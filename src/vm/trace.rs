@@ -0,0 +1,88 @@
+//! Structured record of each step of an execution
+//!
+//! Tracing is off unless enabled with [`ExecutionConfig::with_step_trace`] or
+//! [`EvalContext::set_trace_enabled`]; when off, stepping neither copies the
+//! stack nor records state writes.
+//!
+//! [`ExecutionConfig::with_step_trace`]: super::ExecutionConfig::with_step_trace
+//! [`EvalContext::set_trace_enabled`]: super::EvalContext::set_trace_enabled
+
+use std::fmt;
+
+use super::{EvalContext, Frame};
+use crate::opcodes::OpSpec;
+use crate::state::StateChange;
+use crate::types::StackValue;
+
+/// One executed instruction
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceStep {
+    /// Pc of the opcode
+    pub pc: usize,
+    /// Opcode name
+    pub opcode: String,
+    /// Immediates as TEAL, with branch targets as the pc they land on
+    pub immediates: Vec<String>,
+    /// Opcode cost
+    pub cost: u64,
+    /// Values from the deepest one the instruction reads or replaces up to the
+    /// top of the stack, bottom first
+    ///
+    /// These are the arguments of the opcode's signature, or for opcodes
+    /// reaching further down, such as `dig 2` or `retsub` from a `proto`
+    /// subroutine, every value down to the deepest one they touch.
+    pub popped: Vec<StackValue>,
+    /// The same part of the stack after the instruction, bottom first, so
+    /// `dup` pops one value and pushes two
+    pub pushed: Vec<StackValue>,
+    /// Scratch slots written, in order
+    pub scratch_writes: Vec<ScratchWrite>,
+    /// State writes made through the ledger, in order
+    pub state_writes: Vec<StateChange>,
+}
+
+/// A value stored in a scratch slot
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScratchWrite {
+    pub slot: u8,
+    pub value: StackValue,
+}
+
+/// Height of the deepest stack value `spec` reads or replaces, before it runs
+///
+/// Never above the current height, even when a subroutine has already popped
+/// below its frame.
+pub(super) fn stack_base(ctx: &EvalContext, spec: &OpSpec) -> usize {
+    let height = ctx.stack.len();
+    let immediate = ctx.program.get(ctx.pc + 1).copied().unwrap_or(0);
+    let base = match spec.name.as_str() {
+        // The value `n` below the top is copied, replaced or moved
+        "dig" | "bury" | "cover" | "uncover" => height.saturating_sub(immediate as usize + 1),
+        "popn" => height.saturating_sub(immediate as usize),
+        // The matched value and one case per label
+        "match" => height.saturating_sub(immediate as usize + 1),
+        "frame_dig" | "frame_bury" => ctx.frame_index(immediate as i8).unwrap_or(height),
+        // Arguments, locals and return values of a `proto` subroutine
+        "retsub" => match ctx.call_stack.last() {
+            Some(Frame {
+                height: frame_height,
+                proto: Some((args, _)),
+                ..
+            }) => frame_height.saturating_sub(*args),
+            _ => height,
+        },
+        _ => height.saturating_sub(spec.stack_args.len()),
+    };
+    base.min(height)
+}
+
+/// Formats as `PC:0004 + (cost: 1)`, with any immediates after the opcode
+impl fmt::Display for TraceStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PC:{:04} {}", self.pc, self.opcode)?;
+        for immediate in &self.immediates {
+            write!(f, " {immediate}")?;
+        }
+        write!(f, " (cost: {})", self.cost)
+    }
+}
@@ -0,0 +1,92 @@
+//! Byte-for-byte comparison against reference bytecode from goal
+
+use std::{fs, path::Path};
+
+use avm_rs::assembler::Assembler;
+
+#[test]
+fn test_corpus_matches_reference_bytecode() {
+    let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+    let mut sources: Vec<_> = fs::read_dir(&corpus)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "teal"))
+        .collect();
+    sources.sort();
+    assert!(!sources.is_empty());
+
+    for source in sources {
+        let name = source.file_stem().unwrap().to_string_lossy();
+        let teal = fs::read_to_string(&source).unwrap();
+        let expected = fs::read_to_string(source.with_extension("hex"))
+            .unwrap_or_else(|e| panic!("{name}: missing reference bytecode: {e}"));
+
+        let program = Assembler::new()
+            .assemble(&teal)
+            .unwrap_or_else(|e| panic!("{name}: {e}"));
+        assert_eq!(hex::encode(program), expected.trim(), "{name}");
+    }
+}
+
+#[test]
+fn test_version_dependent_errors() {
+    let error = Assembler::new()
+        .assemble("#pragma version 3\nloop:\nint 1\nbnz loop")
+        .unwrap_err();
+    assert!(error.to_string().contains("back reference"), "{error}");
+    assert!(
+        Assembler::new()
+            .assemble("#pragma version 4\nloop:\nint 1\nbnz loop")
+            .is_ok()
+    );
+
+    // Without a pragma the program is v1
+    let error = Assembler::new().assemble("int 1\nreturn").unwrap_err();
+    assert!(error.to_string().contains("introduced in v2"), "{error}");
+    let program = Assembler::new()
+        .with_default_version(8)
+        .assemble("int 1\nreturn")
+        .unwrap();
+    assert_eq!(program, [8, 0x81, 0x01, 0x43]);
+
+    let error = Assembler::new()
+        .assemble("#pragma version 8\nint 1\nintcblock 1")
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("intcblock following int"),
+        "{error}"
+    );
+
+    let error = Assembler::new()
+        .assemble("#pragma version 3\nintcblock 1\nint 2")
+        .unwrap_err();
+    assert!(error.to_string().contains("does not appear"), "{error}");
+
+    let error = Assembler::new()
+        .assemble("#pragma version 8\na:\na:\nint 1")
+        .unwrap_err();
+    assert!(error.to_string().contains("Duplicate label"), "{error}");
+}
+
+#[test]
+fn test_integer_literals_follow_go_syntax() {
+    let assemble =
+        |literal: &str| Assembler::new().assemble(&format!("#pragma version 8\nint {literal}"));
+    for (literal, value) in [
+        ("0x1f", 31),
+        ("017", 15),
+        ("0o17", 15),
+        ("0b101", 5),
+        ("1_000", 1000),
+        ("0x_ff", 255),
+    ] {
+        assert_eq!(
+            assemble(literal).unwrap(),
+            assemble(&value.to_string()).unwrap(),
+            "{literal}"
+        );
+    }
+    for literal in ["1__0", "_1", "1_", "08", "+1", "0x"] {
+        assert!(assemble(literal).is_err(), "{literal}");
+    }
+}
@@ -0,0 +1,156 @@
+//! Tests for assembling the syntax shown in the TEAL opcode documentation
+
+use avm_rs::{assembler::Assembler, types::TealVersion};
+use sha2::{Digest, Sha512_256};
+
+use crate::common::*;
+
+fn assemble(source: &str) -> Vec<u8> {
+    Assembler::new()
+        .assemble(&format!("#pragma version 11\n{source}"))
+        .unwrap_or_else(|e| panic!("{source}: {e}"))
+}
+
+/// Examples from the opcode reference, one instruction or pseudo-op per line
+const DOC_EXAMPLES: &[&str] = &[
+    "byte base64 AAAA",
+    "byte b64 AAAA",
+    "byte base64(AAAA)",
+    "byte b64(AAAA)",
+    "byte base32 AAAA",
+    "byte b32 AAAA",
+    "byte base32(AAAA)",
+    "byte b32(AAAA)",
+    "byte 0x0123456789abcdef",
+    r#"byte "\x01\x02""#,
+    r#"byte "string literal""#,
+    "int 0x10",
+    "int pay",
+    "int appl",
+    "int NoOp",
+    "int DeleteApplication",
+    "addr AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAY5HFKQ",
+    r#"method "add(uint64,uint64)uint128""#,
+    "intcblock 1 2 300",
+    r#"bytecblock 0x01 "two""#,
+    "pushint 10",
+    "pushbytes 0x0102",
+    "pushints 1 2 3",
+    r#"pushbytess "a" "b c""#,
+    "txn Sender",
+    "txn Accounts 1",
+    "txna ApplicationArgs 0",
+    "txnas ApplicationArgs",
+    "gtxn 0 Fee",
+    "gtxn 1 Accounts 0",
+    "gtxna 0 ApplicationArgs 1",
+    "gtxnas 0 ApplicationArgs",
+    "gtxns Receiver",
+    "gtxnsa Accounts 1",
+    "gtxnsas ApplicationArgs",
+    "global PayoutsEnabled",
+    "global CurrentApplicationAddress",
+    "itxn_begin",
+    "itxn_field Receiver",
+    "itxn_field TypeEnum",
+    "itxn_next",
+    "itxn_submit",
+    "itxn CreatedApplicationID",
+    "itxn Logs 0",
+    "itxna Logs 1",
+    "itxnas Logs",
+    "gitxn 0 Amount",
+    "gitxn 0 Logs 1",
+    "gitxna 0 Logs 1",
+    "gitxnas 0 Logs",
+    "asset_holding_get AssetBalance",
+    "asset_params_get AssetName",
+    "app_params_get AppAddress",
+    "acct_params_get AcctTotalBoxBytes",
+    "block BlkTimestamp",
+    "base64_decode URLEncoding",
+    "json_ref JSONUint64",
+    "ecdsa_verify Secp256k1",
+    "ecdsa_pk_decompress Secp256r1",
+    "ecdsa_pk_recover Secp256k1",
+    "vrf_verify VrfAlgorand",
+    "ec_add BN254g1",
+    "ec_multi_scalar_mul BLS12_381g2",
+    "mimc BN254Mp110",
+    "b+",
+    "b*",
+    "b<",
+    "mulw",
+    "addw",
+    "divmodw",
+    "expw",
+    "log",
+    "extract 1 2",
+    "substring 0 4",
+    "replace2 3",
+    "frame_dig -1",
+    "proto 1 1",
+];
+
+#[test]
+fn test_doc_examples_assemble() {
+    for example in DOC_EXAMPLES {
+        let program = assemble(example);
+        assert!(program.len() > 1, "{example}");
+    }
+}
+
+#[test]
+fn test_array_shorthand_matches_array_opcodes() {
+    assert_eq!(assemble("txn Accounts 1"), assemble("txna Accounts 1"));
+    assert_eq!(
+        assemble("gtxn 0 Accounts 1"),
+        assemble("gtxna 0 Accounts 1")
+    );
+    assert_eq!(assemble("gtxns Accounts 1"), assemble("gtxnsa Accounts 1"));
+    assert_eq!(assemble("itxn Logs 0"), assemble("itxna Logs 0"));
+    assert_eq!(assemble("gitxn 1 Logs 0"), assemble("gitxna 1 Logs 0"));
+}
+
+#[test]
+fn test_byte_encodings() {
+    let expected = assemble("byte 0x000000");
+    for encoding in ["base64 AAAA", "b64(AAAA)", "base32 AAAAA===", "b32(AAAAA)"] {
+        assert_eq!(
+            assemble(&format!("byte {encoding}")),
+            expected,
+            "{encoding}"
+        );
+    }
+
+    let zero = assemble("addr AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAY5HFKQ");
+    assert_eq!(zero, assemble(&format!("byte 0x{}", "00".repeat(32))));
+
+    let selector = &Sha512_256::digest(b"add(uint64,uint64)uint128")[..4];
+    let method = assemble(r#"method "add(uint64,uint64)uint128""#);
+    assert_eq!(&method[3..], selector);
+}
+
+#[test]
+fn test_named_int_constants() {
+    assert_eq!(assemble("int appl"), assemble("int 6"));
+    assert_eq!(assemble("int axfer"), assemble("int 4"));
+    assert_eq!(assemble("int OptIn"), assemble("int 1"));
+    assert_eq!(assemble("int DeleteApplication"), assemble("int 5"));
+}
+
+#[test]
+fn test_statements_comments_and_strings() {
+    let source = r#"
+        byte "hello world; // not a comment" // a comment
+        len; int 29; ==
+        done: return
+    "#;
+    let program = assemble(source);
+    let config = test_config_with_version(TealVersion::V11);
+    let mut ledger = setup_mock_ledger();
+    assert!(setup_vm().execute(&program, config, &mut ledger).unwrap());
+
+    let error = Assembler::new().assemble(r#"byte "open"#).unwrap_err();
+    assert!(error.to_string().contains("Unterminated string literal"));
+}
@@ -0,0 +1,119 @@
+//! Tests for assembler diagnostics
+
+use avm_rs::{
+    AvmError,
+    assembler::{
+        Assembler,
+        diagnostics::{Diagnostic, Severity, codes},
+    },
+};
+
+fn diagnostics(source: &str) -> Vec<Diagnostic> {
+    let mut assembler = Assembler::new().with_source_name("test.teal");
+    match assembler.assemble(source) {
+        Err(AvmError::AssemblyDiagnostics(diagnostics)) => {
+            assert_eq!(diagnostics, assembler.diagnostics());
+            diagnostics
+        }
+        other => panic!("expected diagnostics, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_all_errors_reported_in_one_pass() {
+    let source = "#pragma version 8
+int 1
+txn Nonce
+frobnicate 3
+  byte 0xZZ
+pushint 1 2
+itob";
+    let found: Vec<_> = diagnostics(source)
+        .iter()
+        .map(|d| (d.line, d.columns.clone(), d.code))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            (3, 4..9, codes::FIELD),
+            (4, 0..10, codes::UNKNOWN_OPCODE),
+            (5, 7..11, codes::BYTES),
+            (6, 10..11, codes::ARGUMENT_COUNT),
+        ]
+    );
+}
+
+#[test]
+fn test_help_notes() {
+    let found = diagnostics("#pragma version 3\ntxn Fe\nitobb\nswitch a b\na:\nb:");
+    assert_eq!(found[0].help.as_deref(), Some("did you mean `Fee`?"));
+    assert_eq!(found[1].help.as_deref(), Some("did you mean `itob`?"));
+    assert_eq!(found[2].code, codes::VERSION);
+    assert_eq!(
+        found[2].help.as_deref(),
+        Some("the program is v3; use `#pragma version 8` or later")
+    );
+    assert!(found.iter().all(|d| d.severity == Severity::Error));
+    assert!(found.iter().all(|d| d.file.as_deref() == Some("test.teal")));
+}
+
+#[test]
+fn test_label_errors_point_at_reference() {
+    let found = diagnostics("#pragma version 3\nloop:\nint 1\nbnz loop\nb  endd\nend:");
+    assert_eq!(found.len(), 2);
+    assert_eq!((found[0].line, found[0].columns.clone()), (4, 4..8));
+    assert_eq!(found[0].code, codes::BRANCH);
+    assert_eq!((found[1].line, found[1].columns.clone()), (5, 3..7));
+    assert_eq!(found[1].code, codes::UNDEFINED_LABEL);
+    assert_eq!(found[1].help.as_deref(), Some("did you mean `end`?"));
+}
+
+#[test]
+fn test_render_and_json() {
+    let source = "#pragma version 8\n  txn Nonsense";
+    let found = diagnostics(source);
+    assert_eq!(
+        found[0].render(source),
+        "error[E0008]: Unknown transaction field 'Nonsense'
+ --> test.teal:2:7
+  |
+2 |   txn Nonsense
+  |       ^^^^^^^^
+"
+    );
+    assert_eq!(
+        found[0].to_string(),
+        "error[E0008] at test.teal:2:7: Unknown transaction field 'Nonsense'"
+    );
+
+    let json = serde_json::to_value(&found[0]).unwrap();
+    assert_eq!(json["severity"], "error");
+    assert_eq!(json["code"], "E0008");
+    assert_eq!(json["line"], 2);
+    assert_eq!(json["columns"], serde_json::json!({"start": 6, "end": 14}));
+    assert_eq!(json["help"], serde_json::Value::Null);
+}
+
+#[test]
+fn test_reused_assembler_starts_afresh() {
+    let first = "#pragma version 8\nint 7\nb end\nint 7\nend:\nint 1";
+    let second = "int 2\nbnz end\nend:\nint 3";
+    let mut assembler = Assembler::new();
+    assembler.assemble(first).unwrap();
+
+    let mut fresh = Assembler::new();
+    assert_eq!(
+        assembler.assemble(second).unwrap(),
+        fresh.assemble(second).unwrap()
+    );
+    assert_eq!(assembler.program_version(), 1);
+    assert_eq!(
+        assembler.source_map().to_json(),
+        fresh.source_map().to_json()
+    );
+
+    // Reusing the assembler keeps its options
+    let mut assembler = Assembler::new().with_default_version(8);
+    assembler.assemble(first).unwrap();
+    assert_eq!(assembler.assemble(second).unwrap()[0], 8);
+}
@@ -0,0 +1,171 @@
+//! Tests for the disassembler
+
+use std::{fs, path::Path};
+
+use avm_rs::assembler::{
+    Assembler,
+    analysis::{Analysis, ModeRequirement},
+    disassemble,
+    disassembler::Disassembly,
+};
+use quickcheck_macros::quickcheck;
+
+/// Assemble the disassembly of `program`
+fn round_trip(program: &[u8]) -> Vec<u8> {
+    let source = disassemble(program).unwrap();
+    Assembler::new()
+        .assemble(&source)
+        .unwrap_or_else(|e| panic!("{e}\n{source}"))
+}
+
+#[test]
+fn test_labels_constants_and_fields() {
+    let program = Assembler::new()
+        .assemble(
+            "#pragma version 2
+            int 7
+            int 7
+            byte \"hi\"
+            pop
+            txn Fee
+            bnz skip
+            int 1
+            bnz middle
+            middle:
+            int 1
+            skip:
+            ==",
+        )
+        .unwrap();
+    assert_eq!(
+        disassemble(&program).unwrap(),
+        "#pragma version 2
+intcblock 7 1
+bytecblock \"hi\"
+intc_0 // 7
+intc_0 // 7
+bytec_0 // \"hi\"
+pop
+txn Fee
+bnz label2
+intc_1 // 1
+bnz label1
+label1:
+intc_1 // 1
+label2:
+==
+"
+    );
+
+    let disassembly = Disassembly::new(&program).unwrap();
+    assert_eq!(disassembly.version, 2);
+    let source = disassembly.to_source(true);
+    assert!(source.contains("intc_0 // 000a: 7\n"), "{source}");
+    assert!(source.contains("pop // 000d\n"), "{source}");
+    assert_eq!(disassembly.instruction_at(13).unwrap().text, "pop");
+}
+
+#[test]
+fn test_targets_at_program_end() {
+    let program = Assembler::new()
+        .assemble("#pragma version 8\ncallsub end\nswitch end end\nend:")
+        .unwrap();
+    let source = disassemble(&program).unwrap();
+    assert!(
+        source.ends_with("switch label1 label1\nlabel1:\n"),
+        "{source}"
+    );
+    assert_eq!(round_trip(&program), program);
+}
+
+#[test]
+fn test_corpus_round_trips() {
+    let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+    for entry in fs::read_dir(corpus).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|ext| ext != "teal") {
+            continue;
+        }
+        let expected = fs::read_to_string(path.with_extension("hex")).unwrap();
+        let expected = hex::decode(expected.trim()).unwrap();
+        assert_eq!(round_trip(&expected), expected, "{path:?}");
+
+        let source = fs::read_to_string(&path).unwrap();
+        for optimize in [false, true] {
+            let program = Assembler::new()
+                .with_optimize(optimize)
+                .assemble(&source)
+                .unwrap();
+            assert_eq!(round_trip(&program), program, "{path:?}");
+        }
+    }
+}
+
+/// Statements for generated programs, with `{n}` replaced to keep labels unique
+const SNIPPETS: [&str; 10] = [
+    "int 5",
+    "int 1000000",
+    "byte 0x00ff",
+    "byte \"a b;c // d\"",
+    "txna ApplicationArgs 1",
+    "global ZeroAddress",
+    "b skip{n}\nint 1\nskip{n}:",
+    "back{n}:\nint 0\nbnz back{n}",
+    "callsub sub{n}\nb done{n}\nsub{n}:\nretsub\ndone{n}:",
+    "int 1\nswitch a{n} b{n}\na{n}:\nb{n}:",
+];
+
+#[test]
+fn test_analysis() {
+    let program = Assembler::new()
+        .assemble(
+            "#pragma version 8
+            int 5
+            int 5
+            callsub double
+            byte \"k\"
+            app_global_get
+            pop
+            callsub double
+            return
+            double:
+            int 5
+            *
+            retsub",
+        )
+        .unwrap();
+    let analysis = Analysis::new(&Disassembly::new(&program).unwrap(), program.len());
+
+    assert_eq!(analysis.version, 8);
+    assert_eq!(analysis.min_version, 4);
+    assert_eq!(analysis.mode, ModeRequirement::Application);
+    assert_eq!(analysis.size, program.len());
+    assert_eq!(analysis.int_blocks, vec![vec!["5".to_string()]]);
+    assert!(analysis.byte_blocks.is_empty());
+    assert_eq!(analysis.opcodes[0], ("intc_0".to_string(), 3));
+    assert_eq!(analysis.opcodes[1], ("callsub".to_string(), 2));
+    assert_eq!(analysis.subroutines.len(), 1);
+    assert_eq!(analysis.subroutines[0].label, "label1");
+    assert_eq!(analysis.subroutines[0].calls, 2);
+    assert_eq!(
+        analysis.static_cost, analysis.instructions as u64,
+        "every opcode here costs 1"
+    );
+}
+
+#[quickcheck]
+fn prop_generated_programs_round_trip(picks: Vec<u8>, pushed: Vec<u8>, version: u8) -> bool {
+    let version = 4 + version % 8;
+    let mut source = format!("#pragma version {version}\n");
+    for (n, pick) in picks.iter().enumerate() {
+        let snippet = SNIPPETS[*pick as usize % SNIPPETS.len()].replace("{n}", &n.to_string());
+        if version < 8 && snippet.contains("switch") {
+            continue;
+        }
+        source.push_str(&format!("{snippet}\n"));
+    }
+    source.push_str(&format!("pushbytes 0x{}\n", hex::encode(&pushed)));
+
+    let program = Assembler::new().assemble(&source).unwrap();
+    round_trip(&program) == program
+}
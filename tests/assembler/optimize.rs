@@ -0,0 +1,105 @@
+//! Tests for size-optimized constant block layout
+
+use avm_rs::assembler::Assembler;
+use quickcheck_macros::quickcheck;
+
+use crate::common::*;
+
+fn assemble(source: &str, optimize: bool) -> Vec<u8> {
+    Assembler::new()
+        .with_optimize(optimize)
+        .assemble(source)
+        .unwrap_or_else(|e| panic!("{e}\n{source}"))
+}
+
+#[test]
+fn test_small_constants_are_pushed() {
+    let source = "#pragma version 8\ntxn Fee\nint 1\n+\nint 1\n-";
+    // goal puts anything used twice in the block
+    assert_eq!(
+        assemble(source, false),
+        [8, 0x20, 1, 1, 0x31, 1, 0x22, 0x08, 0x22, 0x09]
+    );
+    assert_eq!(
+        assemble(source, true),
+        [8, 0x31, 1, 0x81, 1, 0x08, 0x81, 1, 0x09]
+    );
+}
+
+#[test]
+fn test_repeated_large_constants_stay_in_block() {
+    let source = "#pragma version 8
+        byte 0x0102030405060708
+        byte 0x0102030405060708
+        ==
+        byte 0x0102030405060708
+        len
+        int 8
+        ==
+        &&";
+    let program = assemble(source, true);
+    assert_eq!(&program[..12], [8, 0x26, 1, 8, 1, 2, 3, 4, 5, 6, 7, 8]);
+    assert_eq!(program[12..15], [0x28, 0x28, 0x12]);
+    assert!(program.len() <= assemble(source, false).len());
+    execute_and_check(&program, true).unwrap();
+}
+
+#[test]
+fn test_blocks_hold_every_constant_before_v3() {
+    let program = assemble(
+        "#pragma version 2\nint 7\nint 9\nint 9\n+\n+\npop\nint 1",
+        true,
+    );
+    // No pushint, so single use values stay in the block after repeated ones
+    assert_eq!(
+        program,
+        [
+            2, 0x20, 3, 9, 7, 1, 0x23, 0x22, 0x22, 0x08, 0x08, 0x48, 0x24
+        ]
+    );
+    execute_and_check(&program, true).unwrap();
+}
+
+#[test]
+fn test_optimize_applies_before_v4() {
+    let source = "#pragma version 3\ntxn Fee\nint 1\n+\nint 1\n-";
+    assert_eq!(
+        assemble(source, false),
+        [3, 0x20, 1, 1, 0x31, 1, 0x22, 0x08, 0x22, 0x09]
+    );
+    assert_eq!(
+        assemble(source, true),
+        [3, 0x31, 1, 0x81, 1, 0x08, 0x81, 1, 0x09]
+    );
+}
+
+/// Constants drawn from a small pool so they repeat
+const POOL: [u64; 6] = [0, 1, 300, 1 << 40, u64::MAX, 7];
+
+#[quickcheck]
+fn prop_optimized_is_smaller_and_equivalent(picks: Vec<(u8, bool)>) -> bool {
+    let mut source = String::from("#pragma version 8\nint 0\n");
+    let mut expected = 0u64;
+    for (pick, bytes) in picks {
+        let value = POOL[pick as usize % POOL.len()];
+        if bytes {
+            // The hex digits of the value, used through their length
+            let hex = format!("{value:x}");
+            let hex = if hex.len() % 2 == 0 {
+                hex
+            } else {
+                format!("0{hex}")
+            };
+            source.push_str(&format!("byte 0x{hex}\nlen\n^\n"));
+            expected ^= hex.len() as u64 / 2;
+        } else {
+            source.push_str(&format!("int {value}\n^\n"));
+            expected ^= value;
+        }
+    }
+    source.push_str(&format!("int {expected}\n==\n"));
+
+    let default = assemble(&source, false);
+    let optimized = assemble(&source, true);
+    optimized.len() <= default.len() && execute_and_check(&optimized, true).is_ok()
+}
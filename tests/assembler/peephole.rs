@@ -0,0 +1,226 @@
+//! Tests for the peephole optimizer
+
+use avm_rs::assembler::{
+    Assembler,
+    disassembler::Disassembly,
+    optimizer::{RewriteKind, optimize},
+};
+use quickcheck_macros::quickcheck;
+use std::collections::HashMap;
+
+use crate::common::*;
+
+/// Assemble with optimization, returning the listing and the rewrite kinds
+fn optimized(source: &str) -> (Vec<u8>, Vec<RewriteKind>) {
+    let mut assembler = Assembler::new().with_optimize(true);
+    let program = assembler
+        .assemble(source)
+        .unwrap_or_else(|e| panic!("{e}\n{source}"));
+    let kinds = assembler.rewrites().iter().map(|r| r.kind).collect();
+    (program, kinds)
+}
+
+/// Instructions of a program, with branch targets written as their pcs
+fn listing(program: &[u8]) -> Vec<String> {
+    let disassembly = Disassembly::new(program).unwrap();
+    let pcs: HashMap<&str, usize> = disassembly
+        .labels
+        .iter()
+        .map(|(pc, label)| (label.as_str(), *pc))
+        .collect();
+    disassembly
+        .instructions
+        .iter()
+        .map(|instruction| {
+            let words: Vec<String> = instruction
+                .text
+                .split(' ')
+                .map(|word| match pcs.get(word) {
+                    Some(pc) => format!("{pc:04x}"),
+                    None => word.to_string(),
+                })
+                .collect();
+            words.join(" ")
+        })
+        .collect()
+}
+
+#[test]
+fn test_constant_folding() {
+    let (program, kinds) = optimized(
+        "#pragma version 8
+        int 6
+        int 7
+        *
+        int 2
+        -
+        byte \"ab\"
+        byte \"cd\"
+        concat
+        len
+        +
+        int 44
+        ==",
+    );
+    assert_eq!(listing(&program), ["pushint 1"]);
+    assert!(kinds.iter().all(|k| *k == RewriteKind::ConstantFold));
+    execute_and_check(&program, true).unwrap();
+}
+
+#[test]
+fn test_failing_arithmetic_is_not_folded() {
+    for op in ["/", "%"] {
+        let (program, kinds) = optimized(&format!("#pragma version 8\nint 1\nint 0\n{op}"));
+        assert!(kinds.is_empty(), "{op}");
+        execute_expect_error(&program).unwrap();
+    }
+    let (_, kinds) = optimized("#pragma version 8\nint 1\nint 2\n-\npop\nint 1");
+    assert!(!kinds.contains(&RewriteKind::ConstantFold));
+
+    // Folding must not grow the program
+    let (program, kinds) = optimized("#pragma version 8\nint 1\nint 63\nshl\npop\nint 1");
+    assert!(kinds.is_empty());
+    assert_eq!(listing(&program)[2], "shl");
+}
+
+#[test]
+fn test_stack_shuffles_removed() {
+    let (program, kinds) = optimized(
+        "#pragma version 8
+        txn Fee
+        dup
+        pop
+        txn Fee
+        swap
+        swap
+        ==",
+    );
+    assert_eq!(listing(&program), ["txn Fee", "txn Fee", "=="]);
+    assert_eq!(kinds, [RewriteKind::DupPop, RewriteKind::SwapSwap]);
+}
+
+#[test]
+fn test_rewrites_stop_at_labels() {
+    let (program, kinds) = optimized(
+        "#pragma version 8
+        txn Fee
+        bnz skip
+        txn Fee
+        dup
+        skip:
+        pop
+        int 1",
+    );
+    assert!(kinds.is_empty());
+    assert_eq!(listing(&program)[3], "dup");
+}
+
+#[test]
+fn test_jumps_threaded_and_dead_code_removed() {
+    let (program, kinds) = optimized(
+        "#pragma version 8
+        txn Fee
+        bnz first
+        int 0
+        return
+        int 5
+        first:
+        b second
+        err
+        second:
+        int 1",
+    );
+    assert_eq!(
+        listing(&program),
+        ["txn Fee", "bnz 0009", "pushint 0", "return", "pushint 1"]
+    );
+    assert!(kinds.contains(&RewriteKind::JumpThread));
+    assert!(kinds.contains(&RewriteKind::DeadCode));
+    execute_and_check(&program, true).unwrap();
+}
+
+#[test]
+fn test_threading_before_v4() {
+    let (program, kinds) = optimized(
+        "#pragma version 3
+        txn Fee
+        bnz ahead
+        int 1
+        return
+        ahead:
+        b end
+        end:
+        int 1",
+    );
+    assert!(kinds.contains(&RewriteKind::JumpThread));
+    assert_eq!(
+        listing(&program),
+        ["txn Fee", "bnz 0009", "pushint 1", "return", "pushint 1"]
+    );
+    execute_and_check(&program, true).unwrap();
+}
+
+#[test]
+fn test_switch_targets_follow_rewrites() {
+    let (program, _) = optimized(
+        "#pragma version 8
+        int 1
+        switch zero one
+        err
+        zero:
+        err
+        one:
+        b done
+        done:
+        int 1",
+    );
+    assert_eq!(listing(&program)[1], "switch 000a 000b");
+    execute_and_check(&program, true).unwrap();
+}
+
+#[test]
+fn test_rewrites_report_savings() {
+    let mut assembler = Assembler::new().with_optimize(true);
+    let program = assembler
+        .assemble("#pragma version 8\nint 2\nint 3\n+\npop\nint 1")
+        .unwrap();
+    let rewrite = &assembler.rewrites()[0];
+    assert_eq!(rewrite.pc, 1);
+    assert_eq!(rewrite.kind, RewriteKind::ConstantFold);
+    assert_eq!(rewrite.bytes_saved, 3);
+    assert_eq!(rewrite.cost_saved, 2);
+
+    // Optimizing again finds nothing more
+    let again = optimize(&program).unwrap();
+    assert_eq!(again.program, program);
+    assert!(again.rewrites.is_empty());
+}
+
+/// Snippets that leave one uint on the stack, some of them optimizable
+const SNIPPETS: [&str; 8] = [
+    "int 3\nint 4\n+",
+    "int 9\nint 2\n/",
+    "txn Fee\ndup\npop",
+    "int 6\nint 5\nswap\nswap\n-",
+    "byte \"abc\"\nlen",
+    "int 1\n!",
+    "int 7\nb skip{n}\nerr\nskip{n}:",
+    "int 2\ndup\nbz zero{n}\nint 3\n+\nzero{n}:",
+];
+
+#[quickcheck]
+fn prop_optimized_programs_behave_the_same(picks: Vec<u8>) -> bool {
+    let mut source = String::from("#pragma version 8\nint 0\n");
+    for (n, pick) in picks.iter().enumerate() {
+        let snippet = SNIPPETS[*pick as usize % SNIPPETS.len()].replace("{n}", &n.to_string());
+        source.push_str(&format!("{snippet}\n^\n"));
+    }
+
+    let plain = Assembler::new().assemble(&source).unwrap();
+    let (program, _) = optimized(&source);
+    let mut ledger = setup_mock_ledger();
+    let expected = setup_vm().execute(&plain, test_config(), &mut ledger);
+    let mut ledger = setup_mock_ledger();
+    let actual = setup_vm().execute(&program, test_config(), &mut ledger);
+    program.len() <= plain.len() && format!("{expected:?}") == format!("{actual:?}")
+}
@@ -0,0 +1,145 @@
+//! Tests for assembling and disassembling from the opcode registry
+
+use avm_rs::{
+    assembler::{Assembler, disassemble, disassembler::Disassembly},
+    opcodes::{ImmediateKind, OP_PUSHINT, OP_TXN, OpSpec, standard_opcodes},
+    types::TealVersion,
+};
+
+use crate::common::*;
+
+/// Source text of example immediates for an opcode
+fn example_immediates(spec: &OpSpec) -> Vec<String> {
+    let mut args = Vec::new();
+    for immediate in &spec.immediates {
+        match immediate.kind {
+            ImmediateKind::Uint8 => args.push("5".to_string()),
+            ImmediateKind::Int8 => args.push("-1".to_string()),
+            ImmediateKind::Varuint => args.push("300".to_string()),
+            ImmediateKind::Bytes => args.push("0x0102".to_string()),
+            ImmediateKind::Label => args.push("end".to_string()),
+            ImmediateKind::Labels => args.extend(["end".to_string(), "end".to_string()]),
+            ImmediateKind::Varuints => args.extend(["1".to_string(), "300".to_string()]),
+            ImmediateKind::BytesList => args.extend(["\"a\"".to_string(), "0x01".to_string()]),
+            ImmediateKind::Field(group) => args.push(group.names().last().unwrap().to_string()),
+        }
+    }
+    args
+}
+
+#[test]
+fn test_fixed_sizes_match_immediates() {
+    for spec in standard_opcodes() {
+        let sizes: Option<Vec<usize>> = spec.immediates.iter().map(|i| i.fixed_size()).collect();
+        if let Some(sizes) = sizes {
+            assert_eq!(spec.size, 1 + sizes.iter().sum::<usize>(), "{}", spec.name);
+        }
+    }
+}
+
+#[test]
+fn test_every_opcode_round_trips() {
+    let version = TealVersion::latest().as_u8();
+    // Constant loads need their blocks, and index 5 has no single byte opcode
+    let blocks = "intcblock 0 1 2 3 4 5\nbytecblock 0x00 0x01 0x02 0x03 0x04 0x05";
+    for spec in standard_opcodes() {
+        let args = example_immediates(spec);
+        let instruction = format!("{} {}", spec.name, args.join(" "));
+        let source = format!("#pragma version {version}\n{blocks}\n{instruction}\nend:");
+        let program = Assembler::new()
+            .assemble(&source)
+            .unwrap_or_else(|e| panic!("{}: {e}", spec.name));
+
+        // The only label is the end of the program
+        let expected = instruction.replace("end", "label1");
+        let disassembly = Disassembly::new(&program).unwrap();
+        let decoded = &disassembly.instructions[2];
+        assert_eq!(decoded.text, expected.trim_end(), "{}", spec.name);
+        assert_eq!(program[decoded.pc], spec.opcode, "{}", spec.name);
+
+        let source = disassemble(&program).unwrap();
+        let reassembled = Assembler::new().assemble(&source);
+        assert_eq!(reassembled.ok(), Some(program), "{}:\n{source}", spec.name);
+    }
+}
+
+#[test]
+fn test_push_immediates_use_varuints() {
+    let program = Assembler::new()
+        .assemble("#pragma version 8\npushint 300\npushbytes 0x0102\nint 1")
+        .unwrap();
+    assert_eq!(
+        program,
+        [
+            8, OP_PUSHINT, 0xac, 0x02, 0x80, 0x02, 0x01, 0x02, OP_PUSHINT, 0x01
+        ]
+    );
+
+    let long = format!("byte 0x{}\nlen\nint 200\n==", "ab".repeat(200));
+    execute_teal_program(&long, true).unwrap();
+}
+
+#[test]
+fn test_field_immediates_by_name() {
+    let program = Assembler::new()
+        .assemble("#pragma version 6\ntxn Receiver")
+        .unwrap();
+    assert_eq!(program, [6, OP_TXN, 7]);
+
+    let error = Assembler::new()
+        .assemble("#pragma version 6\ntxn Nonsense")
+        .unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("Unknown transaction field 'Nonsense'")
+    );
+
+    let error = Assembler::new()
+        .assemble("#pragma version 6\nglobal Round extra")
+        .unwrap_err();
+    assert!(error.to_string().contains("expects 1 immediate arguments"));
+
+    let error = Assembler::new()
+        .assemble("#pragma version 6\nsubstring 1")
+        .unwrap_err();
+    assert!(error.to_string().contains("missing immediate e"));
+}
+
+#[test]
+fn test_switch_and_match_branch_to_labels() {
+    let switch = r#"
+        int 1
+        switch zero one
+        err
+        zero:
+        err
+        one:
+        int 1
+    "#;
+    let program = Assembler::new()
+        .assemble(&format!("#pragma version 8\n{switch}"))
+        .unwrap();
+    execute_and_check(&program, true).unwrap();
+
+    let program = Assembler::new()
+        .assemble(
+            r#"#pragma version 8
+            byte "x"
+            byte "b"
+            int 7
+            byte "c"
+            byte "c"
+            match first second third
+            err
+            first:
+            second:
+            err
+            third:
+            pop
+            int 1
+            "#,
+        )
+        .unwrap();
+    execute_and_check(&program, true).unwrap();
+}
@@ -0,0 +1,130 @@
+//! Tests for assembler source maps
+
+use std::{fs, path::Path};
+
+use avm_rs::{
+    assembler::{
+        Assembler,
+        disassembler::Disassembly,
+        source_map::{SourceLocation, SourceMap},
+    },
+    opcodes::{opcode_by_byte, opcode_by_name},
+};
+
+/// Opcodes the assembler may encode as a shorter or array form
+const REWRITTEN: [&str; 8] = [
+    "intc", "bytec", "arg", "txn", "gtxn", "gtxns", "itxn", "gitxn",
+];
+
+fn location(line: usize, column: usize) -> SourceLocation {
+    SourceLocation { line, column }
+}
+
+/// Check every mapped pc starts an instruction written at its location
+fn check_locations(name: &str, source: &str, program: &[u8], map: &SourceMap) {
+    let disassembly = Disassembly::new(program).unwrap();
+    let starts: Vec<usize> = disassembly.instructions.iter().map(|i| i.pc).collect();
+    let lines: Vec<&str> = source.lines().collect();
+
+    for (&pc, location) in &map.locations {
+        assert!(
+            starts.contains(&pc),
+            "{name}: pc {pc} is not an instruction"
+        );
+        let token = lines[location.line][location.column..]
+            .split_whitespace()
+            .next()
+            .unwrap();
+        let spec = opcode_by_byte(program[pc]).unwrap();
+        if opcode_by_name(token).is_some() && !REWRITTEN.contains(&token) {
+            assert_eq!(spec.name, token, "{name}: pc {pc}");
+        }
+    }
+}
+
+#[test]
+fn test_pcs_map_to_lines_and_columns() {
+    let source = "#pragma version 8
+int 1
+  loop: txn Fee; pop
+b loop";
+    let mut assembler = Assembler::new();
+    assembler.assemble(source).unwrap();
+    let map = assembler.source_map();
+
+    assert_eq!(
+        map.locations
+            .iter()
+            .map(|(pc, l)| (*pc, *l))
+            .collect::<Vec<_>>(),
+        [
+            (1, location(1, 0)),
+            (3, location(2, 8)),
+            (5, location(2, 17)),
+            (6, location(3, 0)),
+        ]
+    );
+    assert_eq!(map.labels["loop"], 3);
+    assert_eq!(map.location(4), Some(location(2, 8)));
+    assert_eq!(map.pc_for_line(2), Some(3));
+}
+
+#[test]
+fn test_json_matches_goal_format() {
+    let mut assembler = Assembler::new();
+    assembler
+        .assemble("#pragma version 8\nint 1\n\n  txn Fee\n+")
+        .unwrap();
+    let mut map = assembler.source_map().clone();
+    map.sources = vec!["add.teal".to_string()];
+
+    let json = map.to_json();
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["version"], 3);
+    assert_eq!(value["sources"], serde_json::json!(["add.teal"]));
+    assert_eq!(value["names"], serde_json::json!([]));
+    assert_eq!(value["mappings"], ";AACA;;AAEE;;AACF");
+
+    assert_eq!(SourceMap::from_json(&json).unwrap(), map);
+}
+
+#[test]
+fn test_vlq_handles_large_deltas() {
+    let mut map = SourceMap::default();
+    map.locations.insert(0, location(1000, 40));
+    map.locations.insert(3, location(2, 0));
+    map.labels.insert("end".to_string(), 4);
+    assert_eq!(SourceMap::from_json(&map.to_json()).unwrap(), map);
+
+    assert!(
+        SourceMap::from_json("{\"version\":2,\"sources\":[],\"names\":[],\"mappings\":\"\"}")
+            .is_err()
+    );
+    assert!(
+        SourceMap::from_json("{\"version\":3,\"sources\":[],\"names\":[],\"mappings\":\"!\"}")
+            .is_err()
+    );
+}
+
+#[test]
+fn test_corpus_locations_survive_rewrites() {
+    let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+    for entry in fs::read_dir(corpus).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|ext| ext != "teal") {
+            continue;
+        }
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        let source = fs::read_to_string(&path).unwrap();
+        for optimize in [false, true] {
+            let mut assembler = Assembler::new().with_optimize(optimize);
+            let program = assembler.assemble(&source).unwrap();
+            let map = assembler.source_map();
+            assert!(!map.locations.is_empty(), "{name}");
+            check_locations(&name, &source, &program, map);
+            for (label, pc) in &map.labels {
+                assert!(*pc <= program.len(), "{name}: {label}");
+            }
+        }
+    }
+}
@@ -0,0 +1,104 @@
+//! Tests for `#pragma typetrack` stack type checking
+
+use std::{fs, path::Path};
+
+use avm_rs::{
+    AvmError,
+    assembler::{Assembler, diagnostics::codes},
+};
+
+/// Line, code and message of each problem typetrack finds in `body`
+fn problems(body: &str) -> Vec<(usize, &'static str, String)> {
+    let source = format!("#pragma version 8\n#pragma typetrack true\n{body}");
+    match Assembler::new().assemble(&source) {
+        Ok(_) => Vec::new(),
+        Err(AvmError::AssemblyDiagnostics(diagnostics)) => diagnostics
+            .into_iter()
+            .map(|d| (d.line - 2, d.code, d.message))
+            .collect(),
+        Err(e) => panic!("unexpected error {e}"),
+    }
+}
+
+#[test]
+fn test_type_mismatches() {
+    let body = "int 1\nbtoi\nbyte \"ab\"\nint 2\n+\nreturn";
+    assert_eq!(
+        problems(body),
+        [
+            (
+                2,
+                codes::TYPE,
+                "btoi arg 0 wanted type []byte got uint64".to_string()
+            ),
+            (
+                5,
+                codes::TYPE,
+                "+ arg 0 wanted type uint64 got [2]byte".to_string()
+            ),
+        ]
+    );
+    assert_eq!(
+        problems("txn Fee\ntxn Sender\n==")[0].2,
+        "== compares uint64 with [32]byte"
+    );
+
+    // Off unless asked for
+    let mut assembler = Assembler::new();
+    assert!(assembler.assemble("#pragma version 8\nint 1\nbtoi").is_ok());
+}
+
+#[test]
+fn test_bounded_types() {
+    let body = "txn Sender\nsha256\nbyte 0x01\ntxn Sender\ned25519verify";
+    assert_eq!(
+        problems(body)[0].2,
+        "ed25519verify arg 1 wanted type [64]byte got [1]byte"
+    );
+    assert!(problems("byte 0x01\nglobal ZeroAddress\ntxn Note\ned25519verify_bare").len() == 1);
+    assert!(problems("txn Note\ntxn Note\ntxn Sender\nsha256\ned25519verify").is_empty());
+}
+
+#[test]
+fn test_stack_effects_and_scratch() {
+    assert_eq!(problems("int 1\nbyte \"a\"\nswap\nbtoi")[0].0, 4);
+    assert!(problems("int 1\nbyte \"a\"\nuncover 1\nitob\nconcat").is_empty());
+    assert!(problems("int 1\nbyte \"a\"\ncover 1\nitob\nconcat").is_empty());
+    assert_eq!(problems("byte \"a\"\nint 1\ndig 1\n+")[0].0, 4);
+    assert_eq!(problems("byte \"a\"\nstore 1\nload 1\nint 1\n+")[0].0, 5);
+    // Scratch slots start as zero
+    assert!(problems("load 2\nint 1\n+").is_empty());
+}
+
+#[test]
+fn test_underflow_and_labels() {
+    assert_eq!(
+        problems("int 1\npop\npop\npop"),
+        [(
+            3,
+            codes::STACK,
+            "pop expects 1 stack arguments but stack height is 0".to_string()
+        )]
+    );
+    // Control may reach a label with anything on the stack, and code after an
+    // unconditional branch is unreachable until one
+    assert!(problems("b skip\npop\nbtoi\nskip:\npop\nbtoi\nint 1").is_empty());
+    assert!(problems("callsub sub\npop\nint 1\nreturn\nsub:\nretsub").is_empty());
+    assert_eq!(problems("main: int 1\nbtoi")[0].0, 2);
+}
+
+#[test]
+fn test_corpus_type_checks() {
+    let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+    for entry in fs::read_dir(corpus).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|ext| ext != "teal") {
+            continue;
+        }
+        let source = fs::read_to_string(&path).unwrap();
+        let (pragma, rest) = source.split_once('\n').unwrap();
+        let source = format!("{pragma}\n#pragma typetrack true\n{rest}");
+        let result = Assembler::new().assemble(&source);
+        assert!(result.is_ok(), "{path:?}: {result:?}");
+    }
+}
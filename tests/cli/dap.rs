@@ -0,0 +1,262 @@
+//! End-to-end tests driving `avm-rs dap` over the Debug Adapter Protocol
+
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use serde_json::{Value, json};
+
+const PROGRAM: &str = "#pragma version 8
+txn Fee
+int 2000
+==
+assert
+int 3
+int 4
+callsub add
+store 1
+load 1
+int 7
+==
+return
+add:
+proto 2 1
+frame_dig -2
+frame_dig -1
++
+retsub
+";
+
+const LEDGER: &str = r#"{ "txn": { "Fee": 2000 } }"#;
+
+/// A client talking to an adapter process
+struct Client {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    seq: u64,
+    /// Events read while waiting for responses
+    events: VecDeque<Value>,
+}
+
+impl Client {
+    fn start() -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_avm-rs"))
+            .arg("dap")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        Self {
+            stdin: child.stdin.take().unwrap(),
+            stdout: BufReader::new(child.stdout.take().unwrap()),
+            child,
+            seq: 0,
+            events: VecDeque::new(),
+        }
+    }
+
+    fn read(&mut self) -> Value {
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            assert!(
+                self.stdout.read_line(&mut line).unwrap() > 0,
+                "adapter exited"
+            );
+            match line.trim_end().split_once(": ") {
+                Some(("Content-Length", value)) => length = value.parse().unwrap(),
+                _ if line.trim_end().is_empty() => break,
+                _ => panic!("unexpected header {line:?}"),
+            }
+        }
+        let mut body = vec![0; length];
+        self.stdout.read_exact(&mut body).unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    /// Send a request and return its response
+    fn request(&mut self, command: &str, arguments: Value) -> Value {
+        self.seq += 1;
+        let body = json!({
+            "seq": self.seq,
+            "type": "request",
+            "command": command,
+            "arguments": arguments,
+        })
+        .to_string();
+        write!(self.stdin, "Content-Length: {}\r\n\r\n{body}", body.len()).unwrap();
+        self.stdin.flush().unwrap();
+
+        loop {
+            let message = self.read();
+            if message["type"] == "event" {
+                self.events.push_back(message);
+                continue;
+            }
+            assert_eq!(message["request_seq"], self.seq);
+            assert_eq!(message["command"], command);
+            return message;
+        }
+    }
+
+    /// Wait for an event, skipping any others before it
+    fn event(&mut self, name: &str) -> Value {
+        loop {
+            let event = match self.events.pop_front() {
+                Some(event) => event,
+                None => self.read(),
+            };
+            if event["event"] == name {
+                return event["body"].clone();
+            }
+        }
+    }
+
+    /// Disconnect and wait for the adapter to exit
+    fn disconnect(mut self) {
+        let response = self.request("disconnect", json!({}));
+        assert_eq!(response["success"], true);
+        assert!(self.child.wait().unwrap().success());
+    }
+}
+
+/// Write `contents` to a file unique to this test process
+fn temp_file(name: &str, contents: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("avm-rs-dap-{}-{name}", std::process::id()));
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn test_debug_session() {
+    let program = temp_file("session.teal", PROGRAM);
+    let ledger = temp_file("session.json", LEDGER);
+    let mut client = Client::start();
+
+    let response = client.request("initialize", json!({ "adapterID": "avm-rs" }));
+    assert_eq!(response["body"]["supportsConditionalBreakpoints"], true);
+    let response = client.request("launch", json!({ "program": program, "ledger": ledger }));
+    assert_eq!(response["success"], true, "{response}");
+    client.event("initialized");
+
+    // Line 14 holds only a label
+    let response = client.request(
+        "setBreakpoints",
+        json!({
+            "source": { "path": program },
+            "breakpoints": [{ "line": 18 }, { "line": 14 }],
+        }),
+    );
+    let breakpoints = &response["body"]["breakpoints"];
+    assert_eq!(breakpoints[0]["verified"], true);
+    assert_eq!(breakpoints[1]["verified"], false);
+
+    client.request("configurationDone", json!({}));
+    let stopped = client.event("stopped");
+    assert_eq!(stopped["reason"], "breakpoint");
+
+    let response = client.request("stackTrace", json!({ "threadId": 1 }));
+    let frames = &response["body"]["stackFrames"];
+    assert_eq!(frames[0]["name"], "add");
+    assert_eq!(frames[0]["line"], 18);
+    assert_eq!(frames[1]["name"], "main");
+    assert_eq!(frames[1]["line"], 8);
+
+    let frame_id = frames[0]["id"].clone();
+    let response = client.request("scopes", json!({ "frameId": frame_id }));
+    let scopes = response["body"]["scopes"].as_array().unwrap().clone();
+    let names: Vec<&str> = scopes.iter().map(|s| s["name"].as_str().unwrap()).collect();
+    assert_eq!(names, ["Stack", "Scratch", "Frame"]);
+
+    let response = client.request(
+        "variables",
+        json!({ "variablesReference": scopes[2]["variablesReference"] }),
+    );
+    let variables: Vec<String> = response["body"]["variables"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|v| {
+            format!(
+                "{}={}",
+                v["name"].as_str().unwrap(),
+                v["value"].as_str().unwrap()
+            )
+        })
+        .collect();
+    assert_eq!(variables, ["-2=3", "-1=4", "0=3", "1=4"]);
+
+    // Change the second operand of `+`, so the sum is 8
+    let response = client.request(
+        "setVariable",
+        json!({
+            "variablesReference": scopes[0]["variablesReference"],
+            "name": "3",
+            "value": "5",
+        }),
+    );
+    assert_eq!(response["body"]["value"], "5");
+
+    client.request("stepOut", json!({ "threadId": 1 }));
+    assert_eq!(client.event("stopped")["reason"], "step");
+    let response = client.request("evaluate", json!({ "expression": "stack[-1]" }));
+    assert_eq!(response["body"]["result"], "8");
+    let response = client.request("evaluate", json!({ "expression": "cost > 100" }));
+    assert_eq!(response["body"]["result"], "false");
+
+    client.request("continue", json!({ "threadId": 1 }));
+    assert_eq!(client.event("output")["output"], "Program rejected\n");
+    assert_eq!(client.event("exited")["exitCode"], 1);
+    client.event("terminated");
+    client.disconnect();
+
+    fs::remove_file(program).unwrap();
+    fs::remove_file(ledger).unwrap();
+}
+
+#[test]
+fn test_failed_launch() {
+    let mut client = Client::start();
+    client.request("initialize", json!({ "adapterID": "avm-rs" }));
+
+    let response = client.request("launch", json!({ "program": "/nonexistent.teal" }));
+    assert_eq!(response["success"], false);
+    assert!(
+        response["message"]
+            .as_str()
+            .unwrap()
+            .contains("Failed to read program")
+    );
+    let response = client.request("threads", json!({}));
+    assert_eq!(response["success"], false);
+    client.disconnect();
+}
+
+#[test]
+fn test_breakpoint_on_first_line() {
+    let program = temp_file("first.teal", PROGRAM);
+    let ledger = temp_file("first.json", LEDGER);
+    let mut client = Client::start();
+    client.request("initialize", json!({ "adapterID": "avm-rs" }));
+    client.request("launch", json!({ "program": program, "ledger": ledger }));
+    client.event("initialized");
+    client.request(
+        "setBreakpoints",
+        json!({ "source": { "path": program }, "breakpoints": [{ "line": 2 }] }),
+    );
+
+    client.request("configurationDone", json!({}));
+    assert_eq!(client.event("stopped")["reason"], "breakpoint");
+    let response = client.request("stackTrace", json!({ "threadId": 1 }));
+    assert_eq!(response["body"]["stackFrames"][0]["line"], 2);
+
+    client.request("continue", json!({ "threadId": 1 }));
+    assert_eq!(client.event("exited")["exitCode"], 0);
+    client.disconnect();
+
+    fs::remove_file(program).unwrap();
+    fs::remove_file(ledger).unwrap();
+}
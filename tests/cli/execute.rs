@@ -0,0 +1,22 @@
+//! End-to-end tests for `avm-rs execute`
+
+use std::process::Command;
+
+use serde_json::Value;
+
+#[test]
+fn test_step_trace_keeps_json_output_parseable() {
+    let output = Command::new(env!("CARGO_BIN_EXE_avm-rs"))
+        .args(["execute", "-t", "inline", "#pragma version 8\nint 1"])
+        .args(["--step-trace", "json", "--format", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    // The result is the only document on standard output
+    let result: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(result.is_object());
+    assert!(result.get("steps").is_none());
+    let trace: Value = serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(trace["steps"][0]["opcode"], "pushint");
+}
@@ -0,0 +1,51 @@
+//! Tests for ledger fixtures
+
+use avm_rs::{
+    cli::LedgerFixture,
+    state::{LedgerAccess, MockLedger},
+    types::{GlobalField, TealValue, TxnField},
+};
+
+#[test]
+fn test_apply_fixture() {
+    let fixture = LedgerFixture::from_json(
+        r#"{
+            "txn": { "Fee": 2000, "Note": "0x0102", "ApplicationArgs": ["hello", "\"a b\""] },
+            "global": { "Round": 77 },
+            "args": ["base64 AQI="],
+            "balances": { "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAY5HFKQ": 5000 },
+            "global_state": { "5": { "counter": 3, "owner": "alice" } },
+            "boxes": { "5": { "scores": "0x0001" } }
+        }"#,
+    )
+    .unwrap();
+    let mut ledger = MockLedger::default();
+    fixture.apply(&mut ledger).unwrap();
+
+    let txn = ledger.current_transaction().unwrap();
+    assert_eq!(txn.fee, 2000);
+    assert_eq!(txn.note, [1, 2]);
+    assert_eq!(txn.application_args, [b"hello".to_vec(), b"a b".to_vec()]);
+    assert_eq!(
+        ledger.get_global_field(GlobalField::Round).unwrap(),
+        TealValue::Uint(77)
+    );
+    assert_eq!(ledger.program_args().unwrap(), [vec![1, 2]]);
+    assert_eq!(ledger.balance(&vec![0; 32]).unwrap(), 5000);
+    assert_eq!(
+        ledger.app_global_get(5, b"owner").unwrap(),
+        Some(TealValue::Bytes(b"alice".to_vec()))
+    );
+    assert_eq!(ledger.get_box(5, b"scores"), Some([0, 1].as_slice()));
+    assert_eq!(txn.field(TxnField::NumAppArgs), TealValue::Uint(2));
+}
+
+#[test]
+fn test_invalid_fixtures() {
+    let apply = |json: &str| LedgerFixture::from_json(json)?.apply(&mut MockLedger::default());
+    assert!(apply(r#"{ "accounts": {} }"#).is_err());
+    assert!(apply(r#"{ "txn": { "Sender2": 1 } }"#).is_err());
+    assert!(apply(r#"{ "txn": { "Fee": "much" } }"#).is_err());
+    assert!(apply(r#"{ "txn": { "Note": 5 } }"#).is_err());
+    assert!(apply(r#"{ "global": { "MinTxnFee": 5 } }"#).is_err());
+}
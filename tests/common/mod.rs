@@ -10,6 +10,8 @@ use avm_rs::{
     vm::{ExecutionConfig, VirtualMachine},
 };
 
+pub use avm_rs::varuint::encode_varuint;
+
 /// Create a test VM with default settings
 pub fn setup_vm() -> VirtualMachine {
     VirtualMachine::with_version(TealVersion::V11)
@@ -20,6 +22,11 @@ pub fn setup_vm_with_version(version: TealVersion) -> VirtualMachine {
     VirtualMachine::with_version(version)
 }
 
+/// Start a hand-built program with the latest version header
+pub fn program_header() -> Vec<u8> {
+    vec![TealVersion::latest().as_u8()]
+}
+
 /// Create default test execution config
 pub fn test_config() -> ExecutionConfig {
     ExecutionConfig::new(TealVersion::V11).with_cost_budget(10000000) // Unlimited budget for testing
@@ -204,6 +211,14 @@ pub fn create_teal_program(program: &str) -> AvmResult<Vec<u8>> {
     assembler.assemble(&full_program)
 }
 
+/// Assemble a version 8 program, panicking on assembly errors
+pub fn assemble_v8(source: &str) -> Vec<u8> {
+    use avm_rs::assembler::Assembler;
+    Assembler::new()
+        .assemble(&format!("#pragma version 8\n{source}"))
+        .unwrap()
+}
+
 /// Assert stack contains expected values
 pub fn assert_stack_contains(_vm: &VirtualMachine, _expected: &[StackValue]) -> AvmResult<()> {
     // This is a simplified check - in reality we'd need access to the VM's internal state
@@ -225,18 +240,18 @@ pub fn execute_teal_expect_error(program: &str) -> AvmResult<()> {
 
 /// Build a simple opcode test with given stack values and opcode
 pub fn build_simple_op_test(values: Vec<StackValue>, opcode: u8) -> Vec<u8> {
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
 
     // Push each value onto stack
     for value in values {
         match value {
             StackValue::Uint(val) => {
                 bytecode.push(OP_PUSHINT); // pushint
-                bytecode.extend_from_slice(&val.to_be_bytes());
+                bytecode.extend_from_slice(&encode_varuint(val));
             }
             StackValue::Bytes(bytes) => {
                 bytecode.push(OP_PUSHBYTES); // pushbytes
-                bytecode.push(bytes.len() as u8);
+                bytecode.extend_from_slice(&encode_varuint(bytes.len() as u64));
                 bytecode.extend_from_slice(&bytes);
             }
         }
@@ -253,11 +268,11 @@ pub fn with_assert_equals(mut bytecode: Vec<u8>, expected: StackValue) -> Vec<u8
     match expected {
         StackValue::Uint(val) => {
             bytecode.push(OP_PUSHINT); // pushint
-            bytecode.extend_from_slice(&val.to_be_bytes());
+            bytecode.extend_from_slice(&encode_varuint(val));
         }
         StackValue::Bytes(bytes) => {
             bytecode.push(OP_PUSHBYTES); // pushbytes
-            bytecode.push(bytes.len() as u8);
+            bytecode.extend_from_slice(&encode_varuint(bytes.len() as u64));
             bytecode.extend_from_slice(&bytes);
         }
     }
@@ -12,7 +12,7 @@ intcblock 42
     let bytecode = assembler.assemble(teal_program).expect("Assembly failed");
 
     // Expected: OP_INTCBLOCK (0x20) + count varuint (1) + value varuint (42)
-    let mut expected = vec![0x20]; // OP_INTCBLOCK
+    let mut expected = vec![0x06, 0x20]; // version 6, OP_INTCBLOCK
     expected.extend_from_slice(&encode_varuint(1)); // count = 1
     expected.extend_from_slice(&encode_varuint(42)); // value = 42
 
@@ -30,7 +30,7 @@ intcblock 10 20 300 0x100 0xFF
     let bytecode = assembler.assemble(teal_program).expect("Assembly failed");
 
     // Expected: OP_INTCBLOCK + count varuint (5) + five varuint values
-    let mut expected = vec![0x20]; // OP_INTCBLOCK
+    let mut expected = vec![0x06, 0x20]; // version 6, OP_INTCBLOCK
     expected.extend_from_slice(&encode_varuint(5)); // count = 5
     expected.extend_from_slice(&encode_varuint(10)); // 10
     expected.extend_from_slice(&encode_varuint(20)); // 20
@@ -52,7 +52,7 @@ bytecblock "hello"
     let bytecode = assembler.assemble(teal_program).expect("Assembly failed");
 
     // Expected: OP_BYTECBLOCK + count varuint (1) + length varuint (5) + "hello"
-    let mut expected = vec![0x26]; // OP_BYTECBLOCK
+    let mut expected = vec![0x06, 0x26]; // version 6, OP_BYTECBLOCK
     expected.extend_from_slice(&encode_varuint(1)); // count = 1
     expected.extend_from_slice(&encode_varuint(5)); // length = 5
     expected.extend_from_slice(b"hello"); // "hello"
@@ -71,7 +71,7 @@ bytecblock "hi" 0x42 "test"
     let bytecode = assembler.assemble(teal_program).expect("Assembly failed");
 
     // Expected: OP_BYTECBLOCK + count varuint (3) + three length-prefixed byte arrays
-    let mut expected = vec![0x26]; // OP_BYTECBLOCK
+    let mut expected = vec![0x06, 0x26]; // version 6, OP_BYTECBLOCK
     expected.extend_from_slice(&encode_varuint(3)); // count = 3
 
     // "hi"
@@ -100,7 +100,7 @@ intcblock 127 128 16383 16384
     let bytecode = assembler.assemble(teal_program).expect("Assembly failed");
 
     // Test that large values are properly encoded as varuint
-    let mut expected = vec![0x20]; // OP_INTCBLOCK
+    let mut expected = vec![0x06, 0x20]; // version 6, OP_INTCBLOCK
     expected.extend_from_slice(&encode_varuint(4)); // count = 4
     expected.extend_from_slice(&encode_varuint(127)); // Single byte: 0x7F
     expected.extend_from_slice(&encode_varuint(128)); // Two bytes: 0x80, 0x01
@@ -114,12 +114,13 @@ intcblock 127 128 16383 16384
 fn test_disassemble_intcblock_varuint() {
     use avm_rs::assembler::disassemble;
 
-    let mut bytecode = vec![0x20]; // OP_INTCBLOCK
+    let mut bytecode = vec![0x06, 0x20]; // version 6, OP_INTCBLOCK
     bytecode.extend_from_slice(&encode_varuint(2)); // count = 2
     bytecode.extend_from_slice(&encode_varuint(123)); // 123
     bytecode.extend_from_slice(&encode_varuint(456)); // 456
 
     let result = disassemble(&bytecode).expect("Disassembly failed");
+    assert!(result.starts_with("#pragma version 6\n"));
     assert!(result.contains("intcblock 123 456"));
 }
 
@@ -127,7 +128,7 @@ fn test_disassemble_intcblock_varuint() {
 fn test_disassemble_bytecblock_varuint() {
     use avm_rs::assembler::disassemble;
 
-    let mut bytecode = vec![0x26]; // OP_BYTECBLOCK
+    let mut bytecode = vec![0x06, 0x26]; // version 6, OP_BYTECBLOCK
     bytecode.extend_from_slice(&encode_varuint(2)); // count = 2
 
     // "hi"
@@ -201,9 +202,9 @@ fn test_large_constant_counts() {
     let bytecode = assembler.assemble(&teal_program).expect("Assembly failed");
 
     // Verify the count is properly encoded as varuint
-    // Should start with OP_INTCBLOCK (0x20) followed by varuint 200
-    assert_eq!(bytecode[0], 0x20);
+    // Should start with the version, OP_INTCBLOCK (0x20) and varuint 200
+    assert_eq!(&bytecode[..2], &[0x06, 0x20]);
 
     let count_bytes = encode_varuint(200);
-    assert_eq!(&bytecode[1..1 + count_bytes.len()], &count_bytes);
+    assert_eq!(&bytecode[2..2 + count_bytes.len()], &count_bytes);
 }
@@ -1,17 +1,56 @@
 #![allow(clippy::vec_init_then_push)]
 
 pub mod common;
+
+// Assembler tests
+pub mod assembler {
+    pub mod corpus;
+    pub mod coverage;
+    pub mod diagnostics;
+    pub mod disassembler;
+    pub mod optimize;
+    pub mod peephole;
+    pub mod registry;
+    pub mod source_map;
+    pub mod typetrack;
+}
+// Command-line tests
+pub mod cli {
+    pub mod dap;
+    pub mod execute;
+    pub mod fixture;
+}
 pub mod constant_blocks;
 pub mod property;
 
 // Opcode category tests
 pub mod opcodes {
     pub mod arithmetic;
+    pub mod box_storage;
     pub mod constants;
     pub mod crypto;
     pub mod flow;
+    pub mod inner_transaction;
     pub mod integration;
+    pub mod log;
+    pub mod scratch;
     pub mod stack;
     pub mod state;
     pub mod transaction;
 }
+
+// Ledger state tests
+pub mod state {
+    pub mod eval_delta;
+    pub mod overlay;
+}
+
+// Virtual machine tests
+pub mod vm {
+    pub mod debugger;
+    pub mod eval_context;
+    pub mod execution_result;
+    pub mod group;
+    pub mod trace;
+    pub mod versions;
+}
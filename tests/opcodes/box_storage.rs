@@ -0,0 +1,205 @@
+//! Tests for box storage opcodes (application mode only)
+
+use avm_rs::{assembler::Assembler, error::AvmResult, state::MockLedger, types::RunMode};
+
+use crate::common::*;
+
+const APP_ID: u64 = 123;
+
+/// Assemble and run a box program in application mode against the given ledger
+fn run_box_program(source: &str, ledger: &mut MockLedger) -> AvmResult<bool> {
+    let mut assembler = Assembler::new();
+    let bytecode = assembler.assemble(&format!("#pragma version 10\n{source}"))?;
+    let config = test_config().with_run_mode(RunMode::Application);
+    setup_vm().execute(&bytecode, config, ledger)
+}
+
+#[test]
+fn test_box_create_and_len() {
+    let mut ledger = setup_mock_ledger();
+    let result = run_box_program(
+        r#"
+        byte "box"
+        int 16
+        box_create
+        assert
+        byte "box"
+        box_len
+        assert
+        int 16
+        ==
+        "#,
+        &mut ledger,
+    )
+    .unwrap();
+    assert!(result);
+    assert_eq!(ledger.get_box(APP_ID, b"box"), Some(&[0u8; 16][..]));
+}
+
+#[test]
+fn test_box_create_existing() {
+    let mut ledger = setup_mock_ledger();
+    ledger.set_box(APP_ID, "box", vec![1; 8]);
+
+    // Same size: returns 0 and leaves the contents alone
+    let result = run_box_program("byte \"box\"\nint 8\nbox_create\n!", &mut ledger).unwrap();
+    assert!(result);
+    assert_eq!(ledger.get_box(APP_ID, b"box"), Some(&[1u8; 8][..]));
+
+    // Different size: fails
+    assert!(run_box_program("byte \"box\"\nint 9\nbox_create", &mut ledger).is_err());
+}
+
+#[test]
+fn test_box_name_and_size_limits() {
+    let mut ledger = setup_mock_ledger();
+    assert!(run_box_program("byte \"\"\nint 1\nbox_create", &mut ledger).is_err());
+
+    let long_name = "a".repeat(65);
+    let source = format!("byte \"{long_name}\"\nint 1\nbox_create");
+    assert!(run_box_program(&source, &mut ledger).is_err());
+
+    assert!(run_box_program("byte \"box\"\nint 32769\nbox_create", &mut ledger).is_err());
+}
+
+#[test]
+fn test_box_put_get() {
+    let mut ledger = setup_mock_ledger();
+    let result = run_box_program(
+        r#"
+        byte "box"
+        byte "hello"
+        box_put
+        byte "box"
+        box_get
+        assert
+        byte "hello"
+        ==
+        "#,
+        &mut ledger,
+    )
+    .unwrap();
+    assert!(result);
+    assert_eq!(ledger.get_box(APP_ID, b"box"), Some(&b"hello"[..]));
+
+    // Overwriting with the same size succeeds, a different size fails
+    assert!(run_box_program("byte \"box\"\nbyte \"world\"\nbox_put\nint 1", &mut ledger).unwrap());
+    assert_eq!(ledger.get_box(APP_ID, b"box"), Some(&b"world"[..]));
+    assert!(run_box_program("byte \"box\"\nbyte \"worlds\"\nbox_put\nint 1", &mut ledger).is_err());
+}
+
+#[test]
+fn test_box_get_missing() {
+    let mut ledger = setup_mock_ledger();
+    let result = run_box_program(
+        r#"
+        byte "missing"
+        box_get
+        !
+        assert
+        len
+        !
+        "#,
+        &mut ledger,
+    )
+    .unwrap();
+    assert!(result);
+}
+
+#[test]
+fn test_box_extract_and_replace() {
+    let mut ledger = setup_mock_ledger();
+    ledger.set_box(APP_ID, "box", b"abcdef".to_vec());
+
+    let result = run_box_program(
+        r#"
+        byte "box"
+        int 2
+        byte "XY"
+        box_replace
+        byte "box"
+        int 1
+        int 4
+        box_extract
+        byte "bXYe"
+        ==
+        "#,
+        &mut ledger,
+    )
+    .unwrap();
+    assert!(result);
+    assert_eq!(ledger.get_box(APP_ID, b"box"), Some(&b"abXYef"[..]));
+
+    // Out of range reads and writes fail
+    assert!(run_box_program("byte \"box\"\nint 4\nint 3\nbox_extract", &mut ledger).is_err());
+    assert!(run_box_program("byte \"box\"\nint 5\nbyte \"XY\"\nbox_replace", &mut ledger).is_err());
+    assert!(run_box_program("byte \"nope\"\nint 0\nint 1\nbox_extract", &mut ledger).is_err());
+}
+
+#[test]
+fn test_box_del() {
+    let mut ledger = setup_mock_ledger();
+    ledger.set_box(APP_ID, "box", vec![0; 4]);
+
+    assert!(run_box_program("byte \"box\"\nbox_del", &mut ledger).unwrap());
+    assert_eq!(ledger.get_box(APP_ID, b"box"), None);
+    assert!(!run_box_program("byte \"box\"\nbox_del", &mut ledger).unwrap());
+}
+
+#[test]
+fn test_box_splice() {
+    let mut ledger = setup_mock_ledger();
+    ledger.set_box(APP_ID, "box", b"abcdef".to_vec());
+
+    // Replacement longer than the removed range truncates the tail
+    assert!(
+        run_box_program(
+            "byte \"box\"\nint 1\nint 1\nbyte \"XYZ\"\nbox_splice\nint 1",
+            &mut ledger
+        )
+        .unwrap()
+    );
+    assert_eq!(ledger.get_box(APP_ID, b"box"), Some(&b"aXYZcd"[..]));
+
+    // Replacement shorter than the removed range zero-pads the tail
+    assert!(
+        run_box_program(
+            "byte \"box\"\nint 0\nint 3\nbyte \"Q\"\nbox_splice\nint 1",
+            &mut ledger
+        )
+        .unwrap()
+    );
+    assert_eq!(ledger.get_box(APP_ID, b"box"), Some(&b"QZcd\0\0"[..]));
+
+    assert!(
+        run_box_program(
+            "byte \"box\"\nint 4\nint 3\nbyte \"Q\"\nbox_splice\nint 1",
+            &mut ledger
+        )
+        .is_err()
+    );
+}
+
+#[test]
+fn test_box_resize() {
+    let mut ledger = setup_mock_ledger();
+    ledger.set_box(APP_ID, "box", b"abcd".to_vec());
+
+    assert!(run_box_program("byte \"box\"\nint 6\nbox_resize\nint 1", &mut ledger).unwrap());
+    assert_eq!(ledger.get_box(APP_ID, b"box"), Some(&b"abcd\0\0"[..]));
+
+    assert!(run_box_program("byte \"box\"\nint 2\nbox_resize\nint 1", &mut ledger).unwrap());
+    assert_eq!(ledger.get_box(APP_ID, b"box"), Some(&b"ab"[..]));
+
+    assert!(run_box_program("byte \"nope\"\nint 2\nbox_resize\nint 1", &mut ledger).is_err());
+}
+
+#[test]
+fn test_boxes_scoped_to_current_app() {
+    let mut ledger = setup_mock_ledger();
+    ledger.set_box(999, "box", b"other".to_vec());
+
+    let result = run_box_program("byte \"box\"\nbox_len\n!\nswap\npop", &mut ledger).unwrap();
+    assert!(result);
+    assert_eq!(ledger.get_box(999, b"box"), Some(&b"other"[..]));
+}
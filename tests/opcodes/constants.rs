@@ -7,25 +7,25 @@ use crate::common::*;
 #[test]
 fn test_op_pushint() {
     // Test pushing various integer values
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHINT);
-    bytecode.extend_from_slice(&42u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(42u64));
     bytecode = with_assert_equals(bytecode, StackValue::Uint(42));
 
     execute_and_check(&bytecode, true).unwrap();
 
     // Test pushing zero
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHINT);
-    bytecode.extend_from_slice(&0u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(0u64));
     bytecode = with_assert_equals(bytecode, StackValue::Uint(0));
 
     execute_and_check(&bytecode, true).unwrap();
 
     // Test pushing max value
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHINT);
-    bytecode.extend_from_slice(&u64::MAX.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(u64::MAX));
     bytecode = with_assert_equals(bytecode, StackValue::Uint(u64::MAX));
 
     execute_and_check(&bytecode, true).unwrap();
@@ -34,7 +34,7 @@ fn test_op_pushint() {
 #[test]
 fn test_op_pushbytes() {
     // Test pushing byte arrays
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHBYTES);
     bytecode.push(5); // length
     bytecode.extend_from_slice(b"hello");
@@ -43,17 +43,17 @@ fn test_op_pushbytes() {
     execute_and_check(&bytecode, true).unwrap();
 
     // Test empty bytes
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHBYTES);
     bytecode.push(0); // length
     bytecode = with_assert_equals(bytecode, StackValue::Bytes(vec![]));
 
     execute_and_check(&bytecode, true).unwrap();
 
-    // Test max length (255 bytes)
-    let mut bytecode = Vec::new();
+    // Test a length that needs a two byte varuint
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHBYTES);
-    bytecode.push(255); // max length
+    bytecode.extend_from_slice(&encode_varuint(255));
     let data = vec![0xAB; 255];
     bytecode.extend_from_slice(&data);
     bytecode.push(OP_LEN);
@@ -65,12 +65,12 @@ fn test_op_pushbytes() {
 #[test]
 fn test_op_pushints() {
     // Test pushing multiple integers
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHINTS);
     bytecode.push(3); // count
-    bytecode.extend_from_slice(&10u64.to_be_bytes());
-    bytecode.extend_from_slice(&20u64.to_be_bytes());
-    bytecode.extend_from_slice(&30u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(10));
+    bytecode.extend_from_slice(&encode_varuint(20));
+    bytecode.extend_from_slice(&encode_varuint(30));
 
     // Stack should have [10, 20, 30] with 30 on top
     // Pop the bottom two values
@@ -83,9 +83,9 @@ fn test_op_pushints() {
     execute_and_check(&bytecode, true).unwrap();
 
     // Test pushing zero integers (no-op)
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&42u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(42u64));
     bytecode.push(OP_PUSHINTS);
     bytecode.push(0); // count = 0
     bytecode = with_assert_equals(bytecode, StackValue::Uint(42));
@@ -96,7 +96,7 @@ fn test_op_pushints() {
 #[test]
 fn test_op_pushbytess() {
     // Test pushing multiple byte arrays
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHBYTESS);
     bytecode.push(2); // count
     bytecode.push(3); // length of first
@@ -113,7 +113,7 @@ fn test_op_pushbytess() {
     execute_and_check(&bytecode, true).unwrap();
 
     // Test with mixed lengths
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHBYTESS);
     bytecode.push(3); // count
     bytecode.push(0); // empty
@@ -137,7 +137,7 @@ fn test_op_intc_shortcuts() {
     use avm_rs::varuint::encode_varuint;
 
     // Set up integer constant block first
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_INTCBLOCK);
     bytecode.extend_from_slice(&encode_varuint(4)); // count
     bytecode.extend_from_slice(&encode_varuint(100)); // constant 0
@@ -152,7 +152,7 @@ fn test_op_intc_shortcuts() {
     execute_and_check(&bytecode, true).unwrap();
 
     // Test intc_1
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_INTCBLOCK);
     bytecode.extend_from_slice(&encode_varuint(4)); // count
     bytecode.extend_from_slice(&encode_varuint(100)); // constant 0
@@ -166,7 +166,7 @@ fn test_op_intc_shortcuts() {
     execute_and_check(&bytecode, true).unwrap();
 
     // Test intc_2
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_INTCBLOCK);
     bytecode.extend_from_slice(&encode_varuint(4)); // count
     bytecode.extend_from_slice(&encode_varuint(100)); // constant 0
@@ -180,7 +180,7 @@ fn test_op_intc_shortcuts() {
     execute_and_check(&bytecode, true).unwrap();
 
     // Test intc_3
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_INTCBLOCK);
     bytecode.extend_from_slice(&encode_varuint(4)); // count
     bytecode.extend_from_slice(&encode_varuint(100)); // constant 0
@@ -199,7 +199,7 @@ fn test_op_bytec_shortcuts() {
     use avm_rs::varuint::encode_varuint;
 
     // Set up byte constant block first
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_BYTECBLOCK);
     bytecode.extend_from_slice(&encode_varuint(4)); // count
     bytecode.extend_from_slice(&encode_varuint(3)); // length of "foo"
@@ -218,7 +218,7 @@ fn test_op_bytec_shortcuts() {
     execute_and_check(&bytecode, true).unwrap();
 
     // Test bytec_1
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_BYTECBLOCK);
     bytecode.extend_from_slice(&encode_varuint(4)); // count
     bytecode.extend_from_slice(&encode_varuint(3)); // length of "foo"
@@ -236,7 +236,7 @@ fn test_op_bytec_shortcuts() {
     execute_and_check(&bytecode, true).unwrap();
 
     // Test bytec_2
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_BYTECBLOCK);
     bytecode.extend_from_slice(&encode_varuint(4)); // count
     bytecode.extend_from_slice(&encode_varuint(3)); // length of "foo"
@@ -254,7 +254,7 @@ fn test_op_bytec_shortcuts() {
     execute_and_check(&bytecode, true).unwrap();
 
     // Test bytec_3
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_BYTECBLOCK);
     bytecode.extend_from_slice(&encode_varuint(4)); // count
     bytecode.extend_from_slice(&encode_varuint(3)); // length of "foo"
@@ -275,9 +275,9 @@ fn test_op_bytec_shortcuts() {
 #[test]
 fn test_op_bzero() {
     // Test creating zero-filled byte arrays
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&10u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(10u64));
     bytecode.push(OP_BZERO);
     bytecode.push(OP_LEN);
     bytecode = with_assert_equals(bytecode, StackValue::Uint(10));
@@ -285,9 +285,9 @@ fn test_op_bzero() {
     execute_and_check(&bytecode, true).unwrap();
 
     // Test all bytes are zero
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&5u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(5u64));
     bytecode.push(OP_BZERO);
     bytecode.push(OP_PUSHBYTES); // pushbytes
     bytecode.push(5);
@@ -298,9 +298,9 @@ fn test_op_bzero() {
     execute_and_check(&bytecode, true).unwrap();
 
     // Test empty array
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&0u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(0u64));
     bytecode.push(OP_BZERO);
     bytecode = with_assert_equals(bytecode, StackValue::Bytes(vec![]));
 
@@ -310,9 +310,9 @@ fn test_op_bzero() {
 #[test]
 fn test_op_bzero_too_large() {
     // Test bzero with size > 4096
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&4097u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(4097u64));
     bytecode.push(OP_BZERO);
 
     execute_expect_error(&bytecode).unwrap();
@@ -323,7 +323,7 @@ fn test_constant_blocks() {
     use avm_rs::varuint::encode_varuint;
 
     // Test intcblock with varuint encoding
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_INTCBLOCK);
     bytecode.extend_from_slice(&encode_varuint(3)); // count
     bytecode.extend_from_slice(&encode_varuint(100)); // constant 0
@@ -338,7 +338,7 @@ fn test_constant_blocks() {
     execute_and_check(&bytecode, true).unwrap();
 
     // Test bytecblock with varuint encoding
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_BYTECBLOCK);
     bytecode.extend_from_slice(&encode_varuint(2)); // count
     bytecode.extend_from_slice(&encode_varuint(3)); // length of "foo"
@@ -359,7 +359,7 @@ fn test_mixed_constants() {
     use avm_rs::varuint::encode_varuint;
 
     // Test mixing different constant operations
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
 
     // Set up integer constant block
     bytecode.push(OP_INTCBLOCK);
@@ -369,7 +369,7 @@ fn test_mixed_constants() {
 
     // Push some values
     bytecode.push(OP_PUSHINT);
-    bytecode.extend_from_slice(&42u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(42u64));
 
     bytecode.push(OP_PUSHBYTES);
     bytecode.push(4);
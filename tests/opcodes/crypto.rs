@@ -1,17 +1,19 @@
 //! Tests for cryptographic opcodes
 
 use avm_rs::{
+    assembler::Assembler,
     opcodes::*,
     types::{StackValue, TealVersion},
 };
 use hex;
+use sha2::Digest;
 
 use crate::common::*;
 
 #[test]
 fn test_op_sha256() {
     // Test SHA256 hash of "hello"
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHBYTES); // pushbytes
     bytecode.push(5); // length
     bytecode.extend_from_slice(b"hello");
@@ -38,7 +40,7 @@ fn test_op_sha256() {
 #[test]
 fn test_op_keccak256() {
     // Test Keccak256 hash of "hello"
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHBYTES); // pushbytes
     bytecode.push(5); // length
     bytecode.extend_from_slice(b"hello");
@@ -55,7 +57,7 @@ fn test_op_keccak256() {
 #[test]
 fn test_op_sha512_256() {
     // Test SHA512/256 hash of "hello"
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHBYTES); // pushbytes
     bytecode.push(5); // length
     bytecode.extend_from_slice(b"hello");
@@ -71,7 +73,7 @@ fn test_op_sha512_256() {
 #[test]
 fn test_op_sha3_256() {
     // Test SHA3-256 hash
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHBYTES); // pushbytes
     bytecode.push(5); // length
     bytecode.extend_from_slice(b"hello");
@@ -88,7 +90,7 @@ fn test_op_sha3_256() {
 fn test_op_ed25519verify_valid() {
     // Test Ed25519 signature verification with a valid signature
     // This is a test vector with known values
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
 
     // Data to verify (message)
     bytecode.push(OP_PUSHBYTES); // pushbytes
@@ -116,7 +118,7 @@ fn test_op_ed25519verify_valid() {
 #[test]
 fn test_op_ed25519verify_invalid_key_length() {
     // Test Ed25519 verification with invalid public key length
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
 
     // Data
     bytecode.push(OP_PUSHBYTES); // pushbytes
@@ -141,7 +143,7 @@ fn test_op_ed25519verify_invalid_key_length() {
 #[test]
 fn test_op_ed25519verify_invalid_sig_length() {
     // Test Ed25519 verification with invalid signature length
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
 
     // Data
     bytecode.push(OP_PUSHBYTES); // pushbytes
@@ -166,7 +168,7 @@ fn test_op_ed25519verify_invalid_sig_length() {
 #[test]
 fn test_op_ed25519verify_bare() {
     // Test Ed25519 bare signature verification
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
 
     // Data to verify
     bytecode.push(OP_PUSHBYTES); // pushbytes
@@ -191,89 +193,110 @@ fn test_op_ed25519verify_bare() {
     execute_and_check(&bytecode, true).unwrap();
 }
 
-#[test]
-fn test_op_ecdsa_verify_placeholder() {
-    // Test ECDSA verification (placeholder implementation)
-    let mut bytecode = Vec::new();
-
-    // Recovery ID
-    bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&0u64.to_be_bytes());
-
-    // Data
-    bytecode.push(OP_PUSHBYTES); // pushbytes
-    bytecode.push(5); // length
-    bytecode.extend_from_slice(b"hello");
-
-    // Signature
-    bytecode.push(OP_PUSHBYTES); // pushbytes
-    bytecode.push(64); // length
-    bytecode.extend_from_slice(&[0u8; 64]);
-
-    // Public key
-    bytecode.push(OP_PUSHBYTES); // pushbytes
-    bytecode.push(33); // length (compressed)
-    bytecode.extend_from_slice(&[0u8; 33]);
-
-    bytecode.push(OP_ECDSA_VERIFY);
-
-    // Placeholder returns 0
-    bytecode = with_assert_equals(bytecode, StackValue::Uint(0));
-
-    execute_and_check(&bytecode, true).unwrap();
+/// Assemble a v7 program and run it in application mode
+fn execute_v7_source(source: &str) -> bool {
+    let bytecode = Assembler::new()
+        .assemble(&format!("#pragma version 7\n{source}"))
+        .unwrap();
+    let vm = setup_vm_with_version(TealVersion::V7);
+    let mut ledger = setup_mock_ledger();
+    let config = test_config_with_version(TealVersion::V7)
+        .with_run_mode(avm_rs::types::RunMode::Application);
+    vm.execute(&bytecode, config, &mut ledger).unwrap()
 }
 
 #[test]
-fn test_op_ecdsa_pk_decompress_placeholder() {
-    // Test ECDSA public key decompression (placeholder)
-    let mut bytecode = Vec::new();
-
-    // Compressed public key
-    bytecode.push(OP_PUSHBYTES); // pushbytes
-    bytecode.push(33); // length
-    bytecode.extend_from_slice(&[0u8; 33]);
-
-    bytecode.push(OP_ECDSA_PK_DECOMPRESS);
-
-    // Placeholder returns 64-byte result
-    bytecode.push(OP_LEN);
-    bytecode = with_assert_equals(bytecode, StackValue::Uint(64));
-
-    execute_and_check(&bytecode, true).unwrap();
+fn test_op_ecdsa_verify_secp256k1() {
+    use secp256k1::{Message, PublicKey, SECP256K1, SecretKey};
+
+    let secret = SecretKey::from_slice(&[7u8; 32]).unwrap();
+    let digest = sha2::Sha256::digest(b"hello");
+    let message = Message::from_digest_slice(&digest).unwrap();
+    let signature = SECP256K1.sign_ecdsa(&message, &secret).serialize_compact();
+    let key = PublicKey::from_secret_key(SECP256K1, &secret).serialize_uncompressed();
+
+    let program = |data: &[u8]| {
+        format!(
+            "byte 0x{}\nbyte 0x{}\nbyte 0x{}\nbyte 0x{}\nbyte 0x{}\necdsa_verify Secp256k1",
+            hex::encode(data),
+            hex::encode(&signature[..32]),
+            hex::encode(&signature[32..]),
+            hex::encode(&key[1..33]),
+            hex::encode(&key[33..]),
+        )
+    };
+    assert!(execute_v7_source(&program(&digest)));
+    assert!(!execute_v7_source(&program(&[0u8; 32])));
 }
 
 #[test]
-fn test_op_ecdsa_pk_recover_placeholder() {
-    // Test ECDSA public key recovery (placeholder)
-    let mut bytecode = Vec::new();
-
-    // Data
-    bytecode.push(OP_PUSHBYTES); // pushbytes
-    bytecode.push(5); // length
-    bytecode.extend_from_slice(b"hello");
-
-    // Signature
-    bytecode.push(OP_PUSHBYTES); // pushbytes
-    bytecode.push(64); // length
-    bytecode.extend_from_slice(&[0u8; 64]);
-
-    // Recovery ID
-    bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&0u64.to_be_bytes());
-
-    bytecode.push(OP_ECDSA_PK_RECOVER);
+fn test_op_ecdsa_verify_secp256r1() {
+    use p256::ecdsa::{Signature, SigningKey, signature::hazmat::PrehashSigner};
+
+    let secret = SigningKey::from_slice(&[7u8; 32]).unwrap();
+    let digest = sha2::Sha256::digest(b"hello");
+    let signature: Signature = secret.sign_prehash(&digest).unwrap();
+    let signature = signature.to_bytes();
+    let key = secret.verifying_key().to_encoded_point(false);
+
+    let source = format!(
+        "byte 0x{}\nbyte 0x{}\nbyte 0x{}\nbyte 0x{}\nbyte 0x{}\necdsa_verify Secp256r1",
+        hex::encode(digest),
+        hex::encode(&signature[..32]),
+        hex::encode(&signature[32..]),
+        hex::encode(key.x().unwrap()),
+        hex::encode(key.y().unwrap()),
+    );
+    assert!(execute_v7_source(&source));
+}
 
-    // Placeholder returns 64-byte result
-    bytecode.push(OP_LEN);
-    bytecode = with_assert_equals(bytecode, StackValue::Uint(64));
+#[test]
+fn test_op_ecdsa_pk_decompress() {
+    use secp256k1::{PublicKey, SECP256K1, SecretKey};
+
+    let secret = SecretKey::from_slice(&[7u8; 32]).unwrap();
+    let key = PublicKey::from_secret_key(SECP256K1, &secret);
+    let uncompressed = key.serialize_uncompressed();
+
+    // Decompression pushes X then Y
+    let source = format!(
+        "byte 0x{}\necdsa_pk_decompress Secp256k1\nbyte 0x{}\n==\nswap\nbyte 0x{}\n==\n&&",
+        hex::encode(key.serialize()),
+        hex::encode(&uncompressed[33..]),
+        hex::encode(&uncompressed[1..33]),
+    );
+    assert!(execute_v7_source(&source));
+}
 
-    execute_and_check(&bytecode, true).unwrap();
+#[test]
+fn test_op_ecdsa_pk_recover() {
+    use secp256k1::{Message, PublicKey, SECP256K1, SecretKey};
+
+    let secret = SecretKey::from_slice(&[7u8; 32]).unwrap();
+    let digest = sha2::Sha256::digest(b"hello");
+    let message = Message::from_digest_slice(&digest).unwrap();
+    let (recovery_id, signature) = SECP256K1
+        .sign_ecdsa_recoverable(&message, &secret)
+        .serialize_compact();
+    let key = PublicKey::from_secret_key(SECP256K1, &secret).serialize_uncompressed();
+
+    let source = format!(
+        "byte 0x{}\nint {}\nbyte 0x{}\nbyte 0x{}\necdsa_pk_recover Secp256k1\n\
+         byte 0x{}\n==\nswap\nbyte 0x{}\n==\n&&",
+        hex::encode(digest),
+        recovery_id.to_i32(),
+        hex::encode(&signature[..32]),
+        hex::encode(&signature[32..]),
+        hex::encode(&key[33..]),
+        hex::encode(&key[1..33]),
+    );
+    assert!(execute_v7_source(&source));
 }
 
 #[test]
 fn test_op_vrf_verify_placeholder() {
     // Test VRF verification (placeholder)
-    let mut bytecode = Vec::new();
+    let mut bytecode = vec![TealVersion::V7.as_u8()];
 
     // Data
     bytecode.push(OP_PUSHBYTES); // pushbytes
@@ -291,6 +314,7 @@ fn test_op_vrf_verify_placeholder() {
     bytecode.extend_from_slice(&[0u8; 32]);
 
     bytecode.push(OP_VRF_VERIFY);
+    bytecode.push(0); // VrfAlgorand
 
     // VRF verify pushes two values: output (64 bytes) and verification result (0/1)
     // Check verification result is 0 (placeholder)
@@ -311,7 +335,7 @@ fn test_op_vrf_verify_placeholder() {
 #[test]
 fn test_hash_algorithms_different_outputs() {
     // Test that different hash algorithms produce different outputs for same input
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
 
     let test_data = b"test data";
 
@@ -7,14 +7,14 @@ use crate::common::*;
 #[test]
 fn test_op_bnz_branch_taken() {
     // Test branch if not zero - branch taken
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&1u64.to_be_bytes()); // non-zero value
+    bytecode.extend_from_slice(&encode_varuint(1u64)); // non-zero value
     bytecode.push(OP_BNZ);
     bytecode.extend_from_slice(&0x0001u16.to_be_bytes()); // offset to skip err
     bytecode.push(OP_ERR); // This should be skipped
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&1u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(1u64));
     bytecode.push(OP_RETURN); // return
 
     execute_and_check(&bytecode, true).unwrap();
@@ -23,13 +23,13 @@ fn test_op_bnz_branch_taken() {
 #[test]
 fn test_op_bnz_branch_not_taken() {
     // Test branch if not zero - branch not taken
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&0u64.to_be_bytes()); // zero value
+    bytecode.extend_from_slice(&encode_varuint(0u64)); // zero value
     bytecode.push(OP_BNZ);
     bytecode.extend_from_slice(&0x0001u16.to_be_bytes()); // offset (not taken)
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&1u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(1u64));
     bytecode.push(OP_RETURN); // return
     bytecode.push(OP_ERR); // This would cause error if reached
 
@@ -39,14 +39,14 @@ fn test_op_bnz_branch_not_taken() {
 #[test]
 fn test_op_bz_branch_taken() {
     // Test branch if zero - branch taken
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&0u64.to_be_bytes()); // zero value
+    bytecode.extend_from_slice(&encode_varuint(0u64)); // zero value
     bytecode.push(OP_BZ);
     bytecode.extend_from_slice(&0x0001u16.to_be_bytes()); // offset to skip err
     bytecode.push(OP_ERR); // This should be skipped
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&1u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(1u64));
     bytecode.push(OP_RETURN); // return
 
     execute_and_check(&bytecode, true).unwrap();
@@ -55,13 +55,13 @@ fn test_op_bz_branch_taken() {
 #[test]
 fn test_op_bz_branch_not_taken() {
     // Test branch if zero - branch not taken
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&42u64.to_be_bytes()); // non-zero value
+    bytecode.extend_from_slice(&encode_varuint(42u64)); // non-zero value
     bytecode.push(OP_BZ);
     bytecode.extend_from_slice(&0x0001u16.to_be_bytes()); // offset (not taken)
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&1u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(1u64));
     bytecode.push(OP_RETURN); // return
     bytecode.push(OP_ERR); // This would cause error if reached
 
@@ -71,12 +71,12 @@ fn test_op_bz_branch_not_taken() {
 #[test]
 fn test_op_b_unconditional() {
     // Test unconditional branch
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_B);
     bytecode.extend_from_slice(&0x0001u16.to_be_bytes()); // offset to skip err
     bytecode.push(OP_ERR); // This should be skipped
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&1u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(1u64));
     bytecode.push(OP_RETURN); // return
 
     execute_and_check(&bytecode, true).unwrap();
@@ -85,19 +85,19 @@ fn test_op_b_unconditional() {
 #[test]
 fn test_op_b_backward_jump() {
     // Test backward jump (simple loop that increments counter)
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
 
     // Initialize counter to 0
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&0u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(0u64));
     bytecode.push(OP_STORE); // store at scratch[0]
     bytecode.push(0);
 
-    // Loop start (PC = 13)
+    // Loop start (PC = 5)
     bytecode.push(OP_LOAD); // load counter
     bytecode.push(0);
     bytecode.push(OP_PUSHINT); // pushint 1
-    bytecode.extend_from_slice(&1u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(1u64));
     bytecode.push(OP_PLUS); // increment
     bytecode.push(OP_DUP); // duplicate for comparison
     bytecode.push(OP_STORE); // store back
@@ -105,12 +105,12 @@ fn test_op_b_backward_jump() {
 
     // Check if counter < 3
     bytecode.push(OP_PUSHINT); // pushint 3
-    bytecode.extend_from_slice(&3u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(3u64));
     bytecode.push(OP_LT); // counter < 3?
 
     // Calculate offset for backward jump
     // We need to jump back to "Loop start"
-    let jump_offset = -28i16; // Negative offset to jump back
+    let jump_offset = -14i16; // Negative offset to jump back
     bytecode.push(OP_BNZ);
     bytecode.extend_from_slice(&jump_offset.to_be_bytes());
 
@@ -127,18 +127,18 @@ fn test_op_b_backward_jump() {
 #[test]
 fn test_op_return() {
     // Test return opcode
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&1u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(1u64));
     bytecode.push(OP_RETURN); // return with 1 on stack
     bytecode.push(OP_ERR); // This should never execute
 
     execute_and_check(&bytecode, true).unwrap();
 
     // Test return with 0
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&0u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(0u64));
     bytecode.push(OP_RETURN); // return with 0 on stack
 
     execute_and_check(&bytecode, false).unwrap();
@@ -147,12 +147,12 @@ fn test_op_return() {
 #[test]
 fn test_op_assert_success() {
     // Test assert with true condition
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&1u64.to_be_bytes()); // true
+    bytecode.extend_from_slice(&encode_varuint(1u64)); // true
     bytecode.push(OP_ASSERT); // assert succeeds
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&1u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(1u64));
     bytecode.push(OP_RETURN); // return
 
     execute_and_check(&bytecode, true).unwrap();
@@ -161,12 +161,12 @@ fn test_op_assert_success() {
 #[test]
 fn test_op_assert_failure() {
     // Test assert with false condition
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&0u64.to_be_bytes()); // false
+    bytecode.extend_from_slice(&encode_varuint(0u64)); // false
     bytecode.push(OP_ASSERT); // assert fails
     bytecode.push(OP_PUSHINT); // This should not execute
-    bytecode.extend_from_slice(&1u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(1u64));
     bytecode.push(OP_RETURN); // return
 
     execute_expect_error(&bytecode).unwrap();
@@ -175,13 +175,13 @@ fn test_op_assert_failure() {
 #[test]
 fn test_op_callsub_retsub() {
     // Test subroutine call and return
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
 
     // Main program
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&10u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(10u64));
     bytecode.push(OP_CALLSUB);
-    bytecode.extend_from_slice(&0x000Eu16.to_be_bytes()); // offset to subroutine
+    bytecode.extend_from_slice(&0x0007u16.to_be_bytes()); // offset to subroutine
     // After return, result should be doubled
     bytecode = with_assert_equals(bytecode, StackValue::Uint(20));
     // Skip over subroutine
@@ -199,24 +199,24 @@ fn test_op_callsub_retsub() {
 #[test]
 fn test_nested_subroutines() {
     // Test nested subroutine calls
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
 
     // Main program
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&5u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(5u64));
     bytecode.push(OP_CALLSUB);
-    bytecode.extend_from_slice(&0x000Eu16.to_be_bytes()); // call sub1
+    bytecode.extend_from_slice(&0x0007u16.to_be_bytes()); // call sub1
     // Result should be (5 * 2) + 1 = 11
     bytecode = with_assert_equals(bytecode, StackValue::Uint(11));
     // Jump to end
     bytecode.push(OP_B);
-    bytecode.extend_from_slice(&0x0011u16.to_be_bytes());
+    bytecode.extend_from_slice(&0x000Au16.to_be_bytes());
 
     // Subroutine 1: doubles then adds 1
     bytecode.push(OP_CALLSUB);
-    bytecode.extend_from_slice(&0x000Bu16.to_be_bytes()); // call sub2
+    bytecode.extend_from_slice(&0x0004u16.to_be_bytes()); // call sub2
     bytecode.push(OP_PUSHINT); // pushint 1
-    bytecode.extend_from_slice(&1u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(1u64));
     bytecode.push(OP_PLUS);
     bytecode.push(OP_RETSUB);
 
@@ -231,13 +231,13 @@ fn test_nested_subroutines() {
 #[test]
 fn test_conditional_logic_complex() {
     // Test complex conditional logic: if (a > b) { result = a - b } else { result = b - a }
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
 
     // Test case 1: a=10, b=5
     bytecode.push(OP_PUSHINT); // pushint a
-    bytecode.extend_from_slice(&10u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(10u64));
     bytecode.push(OP_PUSHINT); // pushint b
-    bytecode.extend_from_slice(&5u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(5u64));
 
     // Duplicate top two values for comparison: [10, 5] -> [10, 5, 10, 5]
     bytecode.push(OP_DUP2); // Stack: [10, 5, 10, 5] (TEAL spec)
@@ -261,3 +261,65 @@ fn test_conditional_logic_complex() {
 
     execute_and_check(&bytecode, true).unwrap();
 }
+
+/// Assemble a v8 program
+
+#[test]
+fn test_proto_frame_dig_and_bury() {
+    // sum(a, b) = a + b, using a local to hold the result
+    let bytecode = assemble_v8(
+        "int 7
+        int 2
+        int 3
+        callsub sum
+        int 5
+        ==
+        assert
+        int 7
+        ==
+        return
+        sum:
+        proto 2 1
+        int 0
+        frame_dig -2
+        frame_dig -1
+        +
+        frame_bury 0
+        retsub",
+    );
+    execute_and_check(&bytecode, true).unwrap();
+}
+
+#[test]
+fn test_retsub_checks_declared_returns() {
+    let bytecode = assemble_v8(
+        "int 1
+        callsub f
+        return
+        f:
+        proto 1 2
+        int 5
+        retsub",
+    );
+    execute_expect_error(&bytecode).unwrap();
+}
+
+#[test]
+fn test_frame_dig_below_declared_args() {
+    let bytecode = assemble_v8(
+        "int 1
+        int 2
+        callsub f
+        return
+        f:
+        proto 1 1
+        frame_dig -2
+        retsub",
+    );
+    execute_expect_error(&bytecode).unwrap();
+}
+
+#[test]
+fn test_proto_without_callsub() {
+    execute_expect_error(&assemble_v8("proto 0 0\nint 1")).unwrap();
+}
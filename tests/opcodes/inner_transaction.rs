@@ -0,0 +1,338 @@
+//! Tests for inner transaction opcodes (application mode only)
+
+use avm_rs::{
+    assembler::Assembler,
+    error::AvmResult,
+    state::{AppParams, LedgerAccess, MockLedger, StateSchema, application_address},
+    types::{RunMode, TealValue},
+    vm::MAX_INNER_CALL_DEPTH,
+};
+
+use crate::common::*;
+
+const APP_ID: u64 = 123;
+
+/// Ledger where the current application has a funded account
+fn setup_app_ledger() -> MockLedger {
+    let mut ledger = setup_mock_ledger();
+    ledger.set_current_application_address(application_address(APP_ID));
+    ledger.set_balance(application_address(APP_ID), 1_000_000);
+    ledger
+}
+
+/// Assemble and run a program in application mode against the given ledger
+fn run_app_program(source: &str, ledger: &mut MockLedger) -> AvmResult<bool> {
+    let config = test_config().with_run_mode(RunMode::Application);
+    setup_vm().execute(&assemble_v8(source), config, ledger)
+}
+
+#[test]
+fn test_inner_payment() {
+    let mut ledger = setup_app_ledger();
+    let result = run_app_program(
+        r#"
+        itxn_begin
+        int 1
+        itxn_field TypeEnum
+        txn Sender
+        itxn_field Receiver
+        int 5000
+        itxn_field Amount
+        itxn_submit
+        itxn Amount
+        int 5000
+        ==
+        assert
+        gitxn 0 Fee
+        int 1000
+        ==
+        "#,
+        &mut ledger,
+    )
+    .unwrap();
+    assert!(result);
+
+    assert_eq!(ledger.balance(&vec![1u8; 32]).unwrap(), 1_000_000 + 5000);
+    assert_eq!(
+        ledger.balance(&application_address(APP_ID)).unwrap(),
+        1_000_000 - 5000 - 1000
+    );
+}
+
+#[test]
+fn test_inner_payment_below_min_balance() {
+    let mut ledger = setup_app_ledger();
+    let result = run_app_program(
+        r#"
+        itxn_begin
+        int 1
+        itxn_field TypeEnum
+        txn Sender
+        itxn_field Receiver
+        int 950000
+        itxn_field Amount
+        itxn_submit
+        int 1
+        "#,
+        &mut ledger,
+    );
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_inner_asset_create() {
+    let mut ledger = setup_app_ledger();
+    let result = run_app_program(
+        r#"
+        itxn_begin
+        int 3
+        itxn_field TypeEnum
+        int 1000
+        itxn_field ConfigAssetTotal
+        byte "TOK"
+        itxn_field ConfigAssetUnitName
+        byte "Token"
+        itxn_field ConfigAssetName
+        itxn_submit
+        itxn CreatedAssetID
+        int 1000
+        >
+        "#,
+        &mut ledger,
+    )
+    .unwrap();
+    assert!(result);
+
+    let params = ledger.asset_params(1001).unwrap().unwrap();
+    assert_eq!(params.total, 1000);
+    assert_eq!(params.unit_name, "TOK");
+}
+
+#[test]
+fn test_inner_fee_pooling() {
+    let mut ledger = setup_app_ledger();
+
+    // The first transaction pays for the second
+    let pooled = r#"
+        itxn_begin
+        int 1
+        itxn_field TypeEnum
+        txn Sender
+        itxn_field Receiver
+        int 2000
+        itxn_field Fee
+        itxn_next
+        int 1
+        itxn_field TypeEnum
+        txn Sender
+        itxn_field Receiver
+        int 0
+        itxn_field Fee
+        itxn_submit
+        gitxn 1 GroupIndex
+        "#;
+    assert!(run_app_program(pooled, &mut ledger).unwrap());
+
+    let underpaid = r#"
+        itxn_begin
+        int 1
+        itxn_field TypeEnum
+        txn Sender
+        itxn_field Receiver
+        int 0
+        itxn_field Fee
+        itxn_submit
+        int 1
+        "#;
+    assert!(run_app_program(underpaid, &mut ledger).is_err());
+}
+
+#[test]
+fn test_inner_unauthorized_sender() {
+    let mut ledger = setup_app_ledger();
+    let result = run_app_program(
+        r#"
+        itxn_begin
+        int 1
+        itxn_field TypeEnum
+        txn Sender
+        itxn_field Sender
+        global CurrentApplicationAddress
+        itxn_field Receiver
+        itxn_submit
+        int 1
+        "#,
+        &mut ledger,
+    );
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_inner_group_size_limit() {
+    let mut ledger = setup_app_ledger();
+    let payment = "int 1\nitxn_field TypeEnum\ntxn Sender\nitxn_field Receiver\n";
+
+    let build = |count: usize| {
+        let mut source = format!("itxn_begin\n{payment}");
+        for _ in 1..count {
+            source.push_str(&format!("itxn_next\n{payment}"));
+        }
+        source.push_str("itxn_submit\nint 1\n");
+        source
+    };
+
+    assert!(run_app_program(&build(16), &mut ledger).unwrap());
+    assert!(run_app_program(&build(17), &mut ledger).is_err());
+}
+
+#[test]
+fn test_inner_field_validation() {
+    let mut ledger = setup_app_ledger();
+    assert!(
+        run_app_program(
+            "itxn_begin\nbyte \"short\"\nitxn_field Receiver\nint 1",
+            &mut ledger
+        )
+        .is_err()
+    );
+    assert!(
+        run_app_program(
+            "itxn_begin\nint 6\nitxn_field OnCompletion\nint 1",
+            &mut ledger
+        )
+        .is_err()
+    );
+    assert!(run_app_program("int 1\nitxn_field Amount\nint 1", &mut ledger).is_err());
+    assert!(run_app_program("itxn_submit\nint 1", &mut ledger).is_err());
+}
+
+#[test]
+fn test_itxn_before_submit_fails() {
+    let mut ledger = setup_app_ledger();
+    assert!(run_app_program("itxn Amount", &mut ledger).is_err());
+}
+
+#[test]
+fn test_inner_app_call_runs_approval_program() {
+    let mut ledger = setup_app_ledger();
+    let callee = 777;
+    ledger.set_app_params(
+        callee,
+        AppParams {
+            approval_program: assemble_v8(
+                r#"
+                byte "caller"
+                global CallerApplicationID
+                app_global_put
+                txn NumAppArgs
+                "#,
+            ),
+            clear_state_program: assemble_v8("int 1"),
+            global_state_schema: StateSchema {
+                num_uint: 1,
+                num_byte_slice: 0,
+            },
+            local_state_schema: StateSchema {
+                num_uint: 0,
+                num_byte_slice: 0,
+            },
+            extra_program_pages: 0,
+            creator: vec![1u8; 32],
+        },
+    );
+
+    let call = |args: &str| {
+        format!(
+            "itxn_begin\nint 6\nitxn_field TypeEnum\nint {callee}\nitxn_field ApplicationID\n\
+             {args}itxn_submit\nint 1\n"
+        )
+    };
+
+    let with_arg = call("byte \"go\"\nitxn_field ApplicationArgs\n");
+    assert!(run_app_program(&with_arg, &mut ledger).unwrap());
+    assert_eq!(
+        ledger.app_global_get(callee, b"caller").unwrap(),
+        Some(TealValue::Uint(APP_ID))
+    );
+
+    // A rejecting approval program fails the outer program
+    assert!(run_app_program(&call(""), &mut ledger).is_err());
+}
+
+#[test]
+fn test_inner_clear_state_ignores_only_program_failure() {
+    let mut ledger = setup_app_ledger();
+    let callee = 777;
+    let sender = application_address(APP_ID);
+    ledger.set_app_params(
+        callee,
+        AppParams {
+            approval_program: assemble_v8("int 1"),
+            clear_state_program: assemble_v8("err"),
+            global_state_schema: StateSchema {
+                num_uint: 0,
+                num_byte_slice: 0,
+            },
+            local_state_schema: StateSchema {
+                num_uint: 0,
+                num_byte_slice: 0,
+            },
+            extra_program_pages: 0,
+            creator: vec![1u8; 32],
+        },
+    );
+    ledger.app_opt_in(&sender, callee).unwrap();
+
+    let source = format!(
+        "itxn_begin\nint 6\nitxn_field TypeEnum\nint {callee}\nitxn_field ApplicationID\n\
+         int 3\nitxn_field OnCompletion\nitxn_submit\nint 1\n"
+    );
+
+    // Too deep to run the clear state program at all
+    let vm = setup_vm();
+    let config = test_config().with_run_mode(RunMode::Application);
+    let program = assemble_v8(&source);
+    let mut ctx = vm
+        .create_eval_context(&program, config.clone(), &mut ledger)
+        .unwrap();
+    ctx.set_inner_call_depth(MAX_INNER_CALL_DEPTH);
+    assert!(vm.run(&mut ctx, &config).is_err());
+    drop(ctx);
+
+    // The clear state program failing still opts the sender out
+    assert!(run_app_program(&source, &mut ledger).unwrap());
+    assert!(!ledger.app_opted_in(&sender, callee).unwrap());
+}
+
+#[test]
+fn test_inner_app_call_runs_callee_at_its_own_version() {
+    let mut ledger = setup_app_ledger();
+    let callee = 777;
+    ledger.set_app_params(
+        callee,
+        AppParams {
+            // `proto` is only available from version 8
+            approval_program: assemble_v8("callsub f\nreturn\nf:\nproto 0 1\nint 1\nretsub"),
+            clear_state_program: assemble_v8("int 1"),
+            global_state_schema: StateSchema {
+                num_uint: 0,
+                num_byte_slice: 0,
+            },
+            local_state_schema: StateSchema {
+                num_uint: 0,
+                num_byte_slice: 0,
+            },
+            extra_program_pages: 0,
+            creator: vec![1u8; 32],
+        },
+    );
+
+    let caller = Assembler::new()
+        .assemble(&format!(
+            "#pragma version 6\nitxn_begin\nint appl\nitxn_field TypeEnum\nint {callee}\n\
+             itxn_field ApplicationID\nitxn_submit\nint 1\n"
+        ))
+        .unwrap();
+    let config = test_config().with_run_mode(RunMode::Application);
+    assert!(setup_vm().execute(&caller, config, &mut ledger).unwrap());
+}
@@ -39,11 +39,11 @@ use crate::common::*;
 #[ignore]
 fn test_factorial_computation() {
     // Compute factorial of 5 using subroutines and loops
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
 
     // Main program: compute 5!
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&5u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(5u64));
     bytecode.push(OP_CALLSUB);
     bytecode.extend_from_slice(&0x0006u16.to_be_bytes()); // call factorial
     bytecode = with_assert_equals(bytecode, StackValue::Uint(120)); // 5! = 120
@@ -86,7 +86,7 @@ fn test_factorial_computation() {
 #[ignore]
 fn test_fibonacci_iterative() {
     // Compute 10th Fibonacci number iteratively
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
 
     // Set up integer constants block: [0, 1, 2]
     bytecode.push(OP_INTCBLOCK);
@@ -111,7 +111,7 @@ fn test_fibonacci_iterative() {
     bytecode.push(OP_LOAD); // i
     bytecode.push(2);
     bytecode.push(OP_PUSHINT); // pushint 10
-    bytecode.extend_from_slice(&10u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(10u64));
     bytecode.push(OP_LT); // i < 10
     bytecode.push(OP_BZ); // exit if i >= 10
     bytecode.extend_from_slice(&0x0028u16.to_be_bytes());
@@ -163,7 +163,7 @@ fn test_fibonacci_iterative() {
 #[test]
 fn test_string_manipulation_pipeline() {
     // Complex string manipulation: concatenate, hash, convert to int
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
 
     // Create "Hello" + "World"
     bytecode.push(OP_PUSHBYTES);
@@ -198,7 +198,7 @@ fn test_string_manipulation_pipeline() {
 
     // Result should be non-zero
     bytecode.push(OP_PUSHINT);
-    bytecode.extend_from_slice(&0u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(0u64));
     bytecode.push(OP_GT);
     bytecode.push(OP_RETURN); // return
 
@@ -215,17 +215,17 @@ fn test_string_manipulation_pipeline() {
 #[test]
 fn test_stack_stress_test() {
     // Stress test stack operations with many values
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
 
     // Push 100 consecutive integers
     for i in 1..=100 {
         bytecode.push(OP_PUSHINT); // pushint
-        bytecode.extend_from_slice(&(i as u64).to_be_bytes());
+        bytecode.extend_from_slice(&encode_varuint(i as u64));
     }
 
     // Sum all values using a loop
     bytecode.push(OP_PUSHINT); // sum = 0
-    bytecode.extend_from_slice(&0u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(0u64));
     bytecode.push(OP_STORE);
     bytecode.push(0);
 
@@ -259,7 +259,7 @@ fn test_stack_stress_test() {
 #[ignore]
 fn test_cryptographic_verification_flow() {
     // Simulate a complex cryptographic verification workflow
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
 
     // Create test data
     bytecode.push(OP_PUSHBYTES);
@@ -274,7 +274,7 @@ fn test_cryptographic_verification_flow() {
     bytecode.push(OP_SHA256);
     bytecode.push(OP_LEN);
     bytecode.push(OP_PUSHINT); // pushint 32
-    bytecode.extend_from_slice(&32u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(32u64));
     bytecode.push(OP_EQ);
     bytecode.push(OP_ASSERT); // Assert SHA256 produces 32 bytes
 
@@ -282,7 +282,7 @@ fn test_cryptographic_verification_flow() {
     bytecode.push(OP_KECCAK256);
     bytecode.push(OP_LEN);
     bytecode.push(OP_PUSHINT); // pushint 32
-    bytecode.extend_from_slice(&32u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(32u64));
     bytecode.push(OP_EQ);
     bytecode.push(OP_ASSERT); // Assert Keccak256 produces 32 bytes
 
@@ -318,7 +318,7 @@ fn test_conditional_state_access() {
     ledger.add_transaction(tx);
     ledger.set_current_transaction_index(0);
 
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
 
     // Get transaction amount
     bytecode.push(OP_TXN);
@@ -326,7 +326,7 @@ fn test_conditional_state_access() {
 
     // If amount > 25000, check global state
     bytecode.push(OP_PUSHINT); // pushint 25000
-    bytecode.extend_from_slice(&25000u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(25000u64));
     bytecode.push(OP_GT);
     bytecode.push(OP_BNZ);
     bytecode.extend_from_slice(&0x0014u16.to_be_bytes()); // jump to state check
@@ -342,7 +342,7 @@ fn test_conditional_state_access() {
     bytecode.push(OP_APP_GLOBAL_GET);
     bytecode.push(OP_POP); // Remove exists flag
     bytecode.push(OP_PUSHINT); // pushint 40
-    bytecode.extend_from_slice(&40u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(40u64));
     bytecode.push(OP_GT); // counter > 40
     bytecode.push(OP_RETURN); // return
 
@@ -362,7 +362,7 @@ fn test_conditional_state_access() {
 #[test]
 fn test_multi_asset_balance_check() {
     // Check balances across multiple assets and accounts
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
 
     // Check balance of account 1
     bytecode.push(OP_PUSHBYTES);
@@ -0,0 +1,116 @@
+//! Tests for the log opcode (application mode only)
+
+use avm_rs::{
+    state::{AppParams, StateSchema, application_address},
+    types::RunMode,
+    vm::{ExecutionConfig, ExecutionResult, MAX_LOG_CALLS},
+};
+
+use crate::common::*;
+
+fn app_config() -> ExecutionConfig {
+    test_config().with_run_mode(RunMode::Application)
+}
+
+fn run_logging_program(source: &str) -> ExecutionResult {
+    let mut ledger = setup_mock_ledger();
+    setup_vm()
+        .execute_detailed(&assemble_v8(source), app_config(), &mut ledger)
+        .unwrap()
+}
+
+#[test]
+fn test_logs_collected_in_order() {
+    let result = run_logging_program("byte \"first\"\nlog\nbyte \"second\"\nlog\nint 1");
+    assert!(result.approved());
+    assert_eq!(result.logs, vec![b"first".to_vec(), b"second".to_vec()]);
+}
+
+#[test]
+fn test_log_call_limit() {
+    let within = "byte \"x\"\nlog\n".repeat(MAX_LOG_CALLS) + "int 1";
+    let result = run_logging_program(&within);
+    assert!(result.approved());
+    assert_eq!(result.logs.len(), MAX_LOG_CALLS);
+
+    let over = "byte \"x\"\nlog\n".repeat(MAX_LOG_CALLS + 1) + "int 1";
+    let result = run_logging_program(&over);
+    assert!(result.error().is_some());
+    assert_eq!(result.logs.len(), MAX_LOG_CALLS);
+}
+
+#[test]
+fn test_log_total_size_limit() {
+    // Four 255 byte entries leave room for exactly four more bytes
+    let filler = format!("byte \"{}\"\nlog\n", "a".repeat(255)).repeat(4);
+
+    let within = format!("{filler}byte \"abcd\"\nlog\nint 1");
+    assert!(run_logging_program(&within).approved());
+
+    let over = format!("{filler}byte \"abcde\"\nlog\nint 1");
+    let result = run_logging_program(&over);
+    assert!(result.error().unwrap().1.contains("logs too large"));
+}
+
+#[test]
+fn test_log_not_allowed_in_signature_mode() {
+    let mut ledger = setup_mock_ledger();
+    let result = setup_vm().execute(
+        &assemble_v8("byte \"x\"\nlog\nint 1"),
+        test_config(),
+        &mut ledger,
+    );
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_inner_app_call_logs_visible_through_itxn() {
+    let mut ledger = setup_mock_ledger();
+    ledger.set_current_application_address(application_address(123));
+    ledger.set_balance(application_address(123), 1_000_000);
+    ledger.set_app_params(
+        777,
+        AppParams {
+            approval_program: assemble_v8("byte \"ret\"\nlog\nbyte \"event\"\nlog\nint 1"),
+            clear_state_program: assemble_v8("int 1"),
+            global_state_schema: StateSchema {
+                num_uint: 0,
+                num_byte_slice: 0,
+            },
+            local_state_schema: StateSchema {
+                num_uint: 0,
+                num_byte_slice: 0,
+            },
+            extra_program_pages: 0,
+            creator: vec![1u8; 32],
+        },
+    );
+
+    let source = r#"
+        itxn_begin
+        int 6
+        itxn_field TypeEnum
+        int 777
+        itxn_field ApplicationID
+        itxn_submit
+        itxn NumLogs
+        int 2
+        ==
+        assert
+        itxna Logs 0
+        byte "ret"
+        ==
+        assert
+        itxn LastLog
+        byte "event"
+        ==
+    "#;
+    let result = setup_vm()
+        .execute_detailed(&assemble_v8(source), app_config(), &mut ledger)
+        .unwrap();
+    assert!(result.approved(), "{:?}", result.outcome);
+
+    // The callee's logs belong to the inner transaction, not the caller
+    assert!(result.logs.is_empty());
+    assert_eq!(result.inner_transactions[0].logs.len(), 2);
+}
@@ -0,0 +1,160 @@
+//! Tests for dynamic scratch access and cross-transaction group opcodes
+
+use avm_rs::{
+    state::{AppParams, MockLedger, StateSchema, Transaction, TransactionType},
+    types::{RunMode, StackValue, TealVersion},
+    vm::{ExecutionConfig, GroupResult, GroupTransaction},
+};
+
+use crate::common::*;
+
+const APP_ID: u64 = 123;
+
+fn app_call(app_id: u64) -> Transaction {
+    let mut txn = Transaction::new();
+    txn.tx_type = TransactionType::ApplicationCall;
+    txn.sender = vec![1u8; 32];
+    txn.application_id = Some(app_id);
+    txn
+}
+
+fn payment() -> Transaction {
+    let mut txn = Transaction::new();
+    txn.tx_type = TransactionType::Payment;
+    txn.sender = vec![1u8; 32];
+    txn.receiver = Some(vec![2u8; 32]);
+    txn.amount = Some(1000);
+    txn
+}
+
+fn install_app(ledger: &mut MockLedger, approval: &str) {
+    ledger.set_app_params(
+        APP_ID,
+        AppParams {
+            approval_program: assemble_v8(approval),
+            clear_state_program: assemble_v8("int 1"),
+            global_state_schema: StateSchema {
+                num_uint: 0,
+                num_byte_slice: 0,
+            },
+            local_state_schema: StateSchema {
+                num_uint: 0,
+                num_byte_slice: 0,
+            },
+            extra_program_pages: 0,
+            creator: vec![1u8; 32],
+        },
+    );
+}
+
+fn evaluate(ledger: &mut MockLedger, txns: Vec<Transaction>) -> GroupResult {
+    let group = txns.into_iter().map(GroupTransaction::new).collect();
+    let config = ExecutionConfig::new(TealVersion::V8).with_cost_budget(10_000);
+    setup_vm().evaluate_group(group, &config, ledger).unwrap()
+}
+
+fn app_error(result: &GroupResult, index: usize) -> String {
+    let app_call = result.transactions[index].app_call.as_ref().unwrap();
+    app_call.error().unwrap().1.to_string()
+}
+
+#[test]
+fn test_loads_stores() {
+    let mut ledger = setup_mock_ledger();
+    let program = assemble_v8("int 200\nint 42\nstores\nint 200\nloads\nload 200\n==");
+    let config = test_config().with_run_mode(RunMode::Application);
+    let result = setup_vm()
+        .execute_detailed(&program, config, &mut ledger)
+        .unwrap();
+    assert!(result.approved());
+    assert_eq!(result.scratch[200], StackValue::Uint(42));
+
+    let out_of_range = assemble_v8("int 256\nint 1\nstores\nint 1");
+    assert!(
+        setup_vm()
+            .execute(&out_of_range, test_config(), &mut ledger)
+            .is_err()
+    );
+}
+
+#[test]
+fn test_gload_reads_earlier_app_call_scratch() {
+    let mut ledger = setup_mock_ledger();
+    install_app(
+        &mut ledger,
+        r#"
+        txn GroupIndex
+        bnz reader
+        int 77
+        store 5
+        byte "shared"
+        store 6
+        int 1
+        return
+        reader:
+        gload 0 5
+        int 77
+        ==
+        assert
+        int 0
+        gloads 6
+        byte "shared"
+        ==
+        assert
+        int 0
+        int 5
+        gloadss
+        int 77
+        ==
+        "#,
+    );
+
+    let result = evaluate(&mut ledger, vec![app_call(APP_ID), app_call(APP_ID)]);
+    assert!(result.approved(), "{:?}", result.transactions[1].app_call);
+}
+
+#[test]
+fn test_gload_rejects_current_and_later_transactions() {
+    let mut ledger = setup_mock_ledger();
+    install_app(&mut ledger, "txn GroupIndex\ngloads 0\npop\nint 1");
+
+    let result = evaluate(&mut ledger, vec![app_call(APP_ID), app_call(APP_ID)]);
+    assert_eq!(result.failed_index, Some(0));
+    assert!(app_error(&result, 0).contains("not before the current transaction"));
+}
+
+#[test]
+fn test_gload_rejects_non_app_call_transactions() {
+    let mut ledger = setup_mock_ledger();
+    install_app(&mut ledger, "gload 0 0\npop\nint 1");
+
+    let result = evaluate(&mut ledger, vec![payment(), app_call(APP_ID)]);
+    assert_eq!(result.failed_index, Some(1));
+    assert!(app_error(&result, 1).contains("not an application call"));
+}
+
+#[test]
+fn test_gaid_returns_created_application_id() {
+    let mut ledger = setup_mock_ledger();
+    install_app(
+        &mut ledger,
+        "gaid 0\nint 0\ngaids\n==\nassert\ngaid 0\ngtxn 0 CreatedApplicationID\n==",
+    );
+
+    let mut create = app_call(0);
+    create.approval_program = Some(assemble_v8("int 1"));
+    create.clear_state_program = Some(assemble_v8("int 1"));
+
+    let result = evaluate(&mut ledger, vec![create, app_call(APP_ID)]);
+    assert!(result.approved(), "{:?}", result.transactions[1].app_call);
+}
+
+#[test]
+fn test_gaid_requires_created_id() {
+    let mut ledger = setup_mock_ledger();
+    install_app(&mut ledger, "gaid 0\npop\nint 1");
+
+    let result = evaluate(&mut ledger, vec![payment(), app_call(APP_ID)]);
+    assert_eq!(result.failed_index, Some(1));
+    assert!(app_error(&result, 1).contains("did not create"));
+}
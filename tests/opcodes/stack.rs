@@ -7,11 +7,11 @@ use crate::common::*;
 #[test]
 fn test_op_pop() {
     // Test basic pop operation
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&42u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(42u64));
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&100u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(100u64));
     bytecode.push(OP_POP); // pop the 100
     // Now only 42 should be on stack
     bytecode = with_assert_equals(bytecode, StackValue::Uint(42));
@@ -32,9 +32,9 @@ fn test_op_pop_empty_stack() {
 #[test]
 fn test_op_dup() {
     // Test basic dup operation
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&42u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(42u64));
     bytecode.push(OP_DUP); // duplicate 42
     bytecode.push(OP_EQ); // they should be equal
     bytecode.push(OP_RETURN); // return
@@ -45,17 +45,17 @@ fn test_op_dup() {
 #[test]
 fn test_op_dup2() {
     // Test dup2 operation according to TEAL spec: [A, B] -> [A, B, A, B]
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHINT); // pushint 10
-    bytecode.extend_from_slice(&10u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(10u64));
     bytecode.push(OP_PUSHINT); // pushint 20
-    bytecode.extend_from_slice(&20u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(20u64));
     bytecode.push(OP_DUP2); // duplicate top two: stack is now [10, 20, 10, 20]
 
     // Simple verification: check that the top value is 20 and pop the rest
     // Stack: [10, 20, 10, 20] - verify top is 20, then pop 3 values to leave bottom 10
     bytecode.push(OP_PUSHINT); // pushint 20
-    bytecode.extend_from_slice(&20u64.to_be_bytes()); // [10, 20, 10, 20, 20]
+    bytecode.extend_from_slice(&encode_varuint(20u64)); // [10, 20, 10, 20, 20]
     bytecode.push(OP_EQ); // [10, 20, 10, 1] - top values match
 
     // Now pop the remaining values to leave just 1 on stack
@@ -72,11 +72,11 @@ fn test_op_dup2() {
 #[test]
 fn test_op_swap() {
     // Test swap operation
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&10u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(10u64));
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&20u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(20u64));
     bytecode.push(OP_SWAP); // swap them
     // Stack is now [20, 10], need to pop the bottom value
     bytecode.push(OP_SWAP); // [10, 20]
@@ -90,26 +90,26 @@ fn test_op_swap() {
 #[test]
 fn test_op_select() {
     // Test select with true condition (selects first value)
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&100u64.to_be_bytes()); // A
+    bytecode.extend_from_slice(&encode_varuint(100u64)); // A
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&200u64.to_be_bytes()); // B
+    bytecode.extend_from_slice(&encode_varuint(200u64)); // B
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&1u64.to_be_bytes()); // true
+    bytecode.extend_from_slice(&encode_varuint(1u64)); // true
     bytecode.push(OP_SELECT); // select A (100)
     bytecode = with_assert_equals(bytecode, StackValue::Uint(100));
 
     execute_and_check(&bytecode, true).unwrap();
 
     // Test select with false condition (selects second value)
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&100u64.to_be_bytes()); // A
+    bytecode.extend_from_slice(&encode_varuint(100u64)); // A
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&200u64.to_be_bytes()); // B
+    bytecode.extend_from_slice(&encode_varuint(200u64)); // B
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&0u64.to_be_bytes()); // false
+    bytecode.extend_from_slice(&encode_varuint(0u64)); // false
     bytecode.push(OP_SELECT); // select B (200)
     bytecode = with_assert_equals(bytecode, StackValue::Uint(200));
 
@@ -143,9 +143,9 @@ fn test_op_len() {
 #[test]
 fn test_op_itob() {
     // Test integer to bytes conversion
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&0x0123456789ABCDEFu64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(0x0123456789ABCDEFu64));
     bytecode.push(OP_ITOB); // convert to bytes
     bytecode.push(OP_LEN); // check length
     bytecode = with_assert_equals(bytecode, StackValue::Uint(8));
@@ -153,9 +153,9 @@ fn test_op_itob() {
     execute_and_check(&bytecode, true).unwrap();
 
     // Test zero conversion
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&0u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(0u64));
     bytecode.push(OP_ITOB); // convert to bytes
     bytecode.push(OP_PUSHBYTES); // pushbytes
     bytecode.push(8); // length
@@ -169,7 +169,7 @@ fn test_op_itob() {
 #[test]
 fn test_op_btoi() {
     // Test bytes to integer conversion
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHBYTES); // pushbytes
     bytecode.push(8); // length
     bytecode.extend_from_slice(&0x0123456789ABCDEFu64.to_be_bytes());
@@ -186,7 +186,7 @@ fn test_op_btoi() {
     execute_and_check(&bytecode, true).unwrap();
 
     // Test partial bytes (less than 8)
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHBYTES); // pushbytes
     bytecode.push(4); // length
     bytecode.extend_from_slice(&[0x12, 0x34, 0x56, 0x78]);
@@ -245,7 +245,7 @@ fn test_op_concat() {
 #[test]
 fn test_op_substring() {
     // Test basic substring extraction
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHBYTES); // pushbytes
     bytecode.push(6); // length
     bytecode.extend_from_slice(b"abcdef");
@@ -257,7 +257,7 @@ fn test_op_substring() {
     execute_and_check(&bytecode, true).unwrap();
 
     // Test full string extraction
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHBYTES); // pushbytes
     bytecode.push(4); // length
     bytecode.extend_from_slice(b"test");
@@ -272,7 +272,7 @@ fn test_op_substring() {
 #[test]
 fn test_op_substring_out_of_bounds() {
     // Test substring with out of bounds indices
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHBYTES); // pushbytes
     bytecode.push(4); // length
     bytecode.extend_from_slice(b"test");
@@ -286,28 +286,28 @@ fn test_op_substring_out_of_bounds() {
 #[test]
 fn test_op_substring3() {
     // Test substring3 with stack arguments
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHBYTES); // pushbytes
     bytecode.push(6); // length
     bytecode.extend_from_slice(b"abcdef");
     bytecode.push(OP_PUSHINT); // pushint (start)
-    bytecode.extend_from_slice(&1u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(1u64));
     bytecode.push(OP_PUSHINT); // pushint (end)
-    bytecode.extend_from_slice(&4u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(4u64));
     bytecode.push(OP_SUBSTRING3);
     bytecode = with_assert_equals(bytecode, StackValue::Bytes(b"bcd".to_vec()));
 
     execute_and_check(&bytecode, true).unwrap();
 
     // Test empty substring
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHBYTES); // pushbytes
     bytecode.push(4); // length
     bytecode.extend_from_slice(b"test");
     bytecode.push(OP_PUSHINT); // pushint (start)
-    bytecode.extend_from_slice(&2u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(2u64));
     bytecode.push(OP_PUSHINT); // pushint (end)
-    bytecode.extend_from_slice(&2u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(2u64));
     bytecode.push(OP_SUBSTRING3);
     bytecode = with_assert_equals(bytecode, StackValue::Bytes(vec![]));
 
@@ -317,17 +317,17 @@ fn test_op_substring3() {
 #[test]
 fn test_op_load_store() {
     // Test load and store operations
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
 
     // Store value at index 0
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&42u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(42u64));
     bytecode.push(OP_STORE);
     bytecode.push(0); // scratch index
 
     // Store value at index 1
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&100u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(100u64));
     bytecode.push(OP_STORE);
     bytecode.push(1); // scratch index
 
@@ -341,12 +341,12 @@ fn test_op_load_store() {
     execute_and_check(&bytecode, true).unwrap();
 
     // Test loading from different slots
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
 
     // Store values
     for i in 0..5 {
         bytecode.push(OP_PUSHINT); // pushint
-        bytecode.extend_from_slice(&(i as u64 * 10).to_be_bytes());
+        bytecode.extend_from_slice(&encode_varuint(i as u64 * 10));
         bytecode.push(OP_STORE);
         bytecode.push(i); // scratch index
     }
@@ -362,12 +362,12 @@ fn test_op_load_store() {
 #[test]
 fn test_op_dupn() {
     // Test duplicating N values
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
 
     // Push 3 values
     for i in 1..=3 {
         bytecode.push(OP_PUSHINT); // pushint
-        bytecode.extend_from_slice(&(i as u64).to_be_bytes());
+        bytecode.extend_from_slice(&encode_varuint(i as u64));
     }
 
     // Duplicate top 2 values
@@ -384,9 +384,9 @@ fn test_op_dupn() {
     execute_and_check(&bytecode, true).unwrap();
 
     // Test duplicating 0 values (no-op)
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&42u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(42u64));
     bytecode.push(OP_DUPN);
     bytecode.push(0); // duplicate 0 values
     bytecode = with_assert_equals(bytecode, StackValue::Uint(42));
@@ -397,12 +397,12 @@ fn test_op_dupn() {
 #[test]
 fn test_op_popn() {
     // Test popping N values
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
 
     // Push 5 values
     for i in 1..=5 {
         bytecode.push(OP_PUSHINT); // pushint
-        bytecode.extend_from_slice(&(i as u64).to_be_bytes());
+        bytecode.extend_from_slice(&encode_varuint(i as u64));
     }
 
     // Pop top 3 values
@@ -419,9 +419,9 @@ fn test_op_popn() {
     execute_and_check(&bytecode, true).unwrap();
 
     // Test popping 0 values (no-op)
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&42u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(42u64));
     bytecode.push(OP_POPN);
     bytecode.push(0); // pop 0 values
     bytecode = with_assert_equals(bytecode, StackValue::Uint(42));
@@ -432,13 +432,13 @@ fn test_op_popn() {
 #[test]
 fn test_op_popn_underflow() {
     // Test popping more values than on stack
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
 
     // Push 2 values
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&1u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(1u64));
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&2u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(2u64));
 
     // Try to pop 3 values
     bytecode.push(OP_POPN);
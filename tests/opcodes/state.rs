@@ -2,7 +2,8 @@
 
 use avm_rs::{
     opcodes::*,
-    types::{RunMode, StackValue},
+    state::LedgerAccess,
+    types::{RunMode, StackValue, TealValue},
     vm::ExecutionConfig,
 };
 
@@ -12,21 +13,23 @@ fn app_mode_config() -> ExecutionConfig {
     test_config().with_run_mode(RunMode::Application)
 }
 
+fn run_app_source(source: &str, ledger: &mut avm_rs::state::MockLedger) -> bool {
+    setup_vm()
+        .execute(&assemble_v8(source), app_mode_config(), ledger)
+        .is_ok_and(|approved| approved)
+}
+
 #[test]
 fn test_op_app_global_get() {
     // Test getting existing global state
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHBYTES); // pushbytes
     bytecode.push(7); // length
     bytecode.extend_from_slice(b"counter");
     bytecode.push(OP_APP_GLOBAL_GET);
 
-    // app_global_get returns two values: [value, exists_flag] with exists_flag on top
-    // We want to test exists_flag, so swap to move value to top and pop it
-    bytecode.push(OP_SWAP); // [exists_flag, value]  
-    bytecode.push(OP_POP); // [exists_flag]
-    // Check exists flag is 1
-    bytecode = with_assert_equals(bytecode, StackValue::Uint(1));
+    // app_global_get returns only the value, 42 in the mock ledger
+    bytecode = with_assert_equals(bytecode, StackValue::Uint(42));
 
     let vm = setup_vm();
     let mut ledger = setup_mock_ledger();
@@ -34,34 +37,18 @@ fn test_op_app_global_get() {
         .execute(&bytecode, app_mode_config(), &mut ledger)
         .unwrap();
     assert!(result);
-
-    // Test getting the actual value
-    let mut bytecode = Vec::new();
-    bytecode.push(OP_PUSHBYTES); // pushbytes
-    bytecode.push(7); // length
-    bytecode.extend_from_slice(b"counter");
-    bytecode.push(OP_APP_GLOBAL_GET);
-    bytecode.push(OP_POP); // Pop exists flag
-    // Value should be 42 (from mock ledger)
-    bytecode = with_assert_equals(bytecode, StackValue::Uint(42));
-
-    let result = vm
-        .execute(&bytecode, app_mode_config(), &mut ledger)
-        .unwrap();
-    assert!(result);
 }
 
 #[test]
 fn test_op_app_global_get_nonexistent() {
     // Test getting non-existent global state
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHBYTES); // pushbytes
     bytecode.push(7); // length
     bytecode.extend_from_slice(b"missing");
     bytecode.push(OP_APP_GLOBAL_GET);
 
-    // Should return 0 for both value and exists flag
-    bytecode.push(OP_PLUS); // Add them together
+    // Should return 0 for a missing key
     bytecode = with_assert_equals(bytecode, StackValue::Uint(0));
 
     let vm = setup_vm();
@@ -75,9 +62,9 @@ fn test_op_app_global_get_nonexistent() {
 #[test]
 fn test_op_app_global_get_ex() {
     // Test getting global state from specific app
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&123u64.to_be_bytes()); // App ID
+    bytecode.extend_from_slice(&encode_varuint(123u64)); // App ID
     bytecode.push(OP_PUSHBYTES); // pushbytes
     bytecode.push(7); // length
     bytecode.extend_from_slice(b"message");
@@ -101,12 +88,12 @@ fn test_op_app_global_get_ex() {
 #[test]
 fn test_op_app_global_put() {
     // Test putting global state (currently returns error in implementation)
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHBYTES); // pushbytes
     bytecode.push(3); // length
     bytecode.extend_from_slice(b"key");
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&100u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(100u64));
     bytecode.push(OP_APP_GLOBAL_PUT);
 
     let vm = setup_vm();
@@ -120,7 +107,7 @@ fn test_op_app_global_put() {
 #[test]
 fn test_op_app_global_del() {
     // Test deleting global state (currently returns error in implementation)
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHBYTES); // pushbytes
     bytecode.push(3); // length
     bytecode.extend_from_slice(b"key");
@@ -137,7 +124,7 @@ fn test_op_app_global_del() {
 #[test]
 fn test_op_app_local_get() {
     // Test getting local state
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHBYTES); // pushbytes (account)
     bytecode.push(32); // length
     bytecode.extend_from_slice(&[2u8; 32]); // Account 2
@@ -146,12 +133,8 @@ fn test_op_app_local_get() {
     bytecode.extend_from_slice(b"user_count");
     bytecode.push(OP_APP_LOCAL_GET);
 
-    // app_local_get returns two values: [value, exists_flag] with exists_flag on top
-    // We want to test exists_flag, so swap to move value to top and pop it
-    bytecode.push(OP_SWAP); // [exists_flag, value]  
-    bytecode.push(OP_POP); // [exists_flag]
-    // Check exists flag
-    bytecode = with_assert_equals(bytecode, StackValue::Uint(1));
+    // app_local_get returns only the value
+    bytecode = with_assert_equals(bytecode, StackValue::Uint(5));
 
     let vm = setup_vm();
     let mut ledger = setup_mock_ledger();
@@ -164,12 +147,12 @@ fn test_op_app_local_get() {
 #[test]
 fn test_op_app_local_get_ex() {
     // Test getting local state from specific app
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHBYTES); // pushbytes (account)
     bytecode.push(32); // length
     bytecode.extend_from_slice(&[2u8; 32]); // Account 2
     bytecode.push(OP_PUSHINT); // pushint (app ID)
-    bytecode.extend_from_slice(&123u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(123u64));
     bytecode.push(OP_PUSHBYTES); // pushbytes (key)
     bytecode.push(10); // length
     bytecode.extend_from_slice(b"user_count");
@@ -190,12 +173,12 @@ fn test_op_app_local_get_ex() {
 #[test]
 fn test_op_app_opted_in() {
     // Test checking if account opted into app
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHBYTES); // pushbytes (account)
     bytecode.push(32); // length
     bytecode.extend_from_slice(&[2u8; 32]); // Account 2 (opted in)
     bytecode.push(OP_PUSHINT); // pushint (app ID)
-    bytecode.extend_from_slice(&123u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(123u64));
     bytecode.push(OP_APP_OPTED_IN);
 
     bytecode = with_assert_equals(bytecode, StackValue::Uint(1)); // Opted in
@@ -208,12 +191,12 @@ fn test_op_app_opted_in() {
     assert!(result);
 
     // Test account not opted in
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHBYTES); // pushbytes (account)
     bytecode.push(32); // length
     bytecode.extend_from_slice(&[3u8; 32]); // Account 3 (not opted in)
     bytecode.push(OP_PUSHINT); // pushint (app ID)
-    bytecode.extend_from_slice(&123u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(123u64));
     bytecode.push(OP_APP_OPTED_IN);
 
     bytecode = with_assert_equals(bytecode, StackValue::Uint(0)); // Not opted in
@@ -227,7 +210,7 @@ fn test_op_app_opted_in() {
 #[test]
 fn test_op_balance() {
     // Test getting account balance
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHBYTES); // pushbytes
     bytecode.push(32); // length
     bytecode.extend_from_slice(&[1u8; 32]); // Account 1
@@ -246,7 +229,7 @@ fn test_op_balance() {
 #[test]
 fn test_op_min_balance() {
     // Test getting minimum balance
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHBYTES); // pushbytes
     bytecode.push(32); // length
     bytecode.extend_from_slice(&[1u8; 32]);
@@ -265,12 +248,12 @@ fn test_op_min_balance() {
 #[test]
 fn test_op_asset_holding_get() {
     // Test getting asset holding - balance field
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHBYTES); // pushbytes (account)
     bytecode.push(32); // length
     bytecode.extend_from_slice(&[1u8; 32]);
     bytecode.push(OP_PUSHINT); // pushint (asset ID)
-    bytecode.extend_from_slice(&456u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(456u64));
     bytecode.push(OP_ASSET_HOLDING_GET);
     bytecode.push(0); // AssetBalance field
 
@@ -289,12 +272,12 @@ fn test_op_asset_holding_get() {
     assert!(result);
 
     // Test frozen field
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHBYTES); // pushbytes (account)
     bytecode.push(32); // length
     bytecode.extend_from_slice(&[1u8; 32]);
     bytecode.push(OP_PUSHINT); // pushint (asset ID)
-    bytecode.extend_from_slice(&456u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(456u64));
     bytecode.push(OP_ASSET_HOLDING_GET);
     bytecode.push(1); // AssetFrozen field
 
@@ -310,9 +293,9 @@ fn test_op_asset_holding_get() {
 #[test]
 fn test_op_asset_params_get() {
     // Test getting asset parameters
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHINT); // pushint (asset ID)
-    bytecode.extend_from_slice(&456u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(456u64));
     bytecode.push(OP_ASSET_PARAMS_GET);
     bytecode.push(0); // AssetTotal field
 
@@ -327,9 +310,9 @@ fn test_op_asset_params_get() {
     assert!(result);
 
     // Test decimals field
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHINT); // pushint (asset ID)
-    bytecode.extend_from_slice(&456u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(456u64));
     bytecode.push(OP_ASSET_PARAMS_GET);
     bytecode.push(1); // AssetDecimals field
 
@@ -345,9 +328,9 @@ fn test_op_asset_params_get() {
 #[test]
 fn test_op_app_params_get() {
     // Test getting app parameters
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHINT); // pushint (app ID)
-    bytecode.extend_from_slice(&123u64.to_be_bytes());
+    bytecode.extend_from_slice(&encode_varuint(123u64));
     bytecode.push(OP_APP_PARAMS_GET);
     bytecode.push(2); // AppGlobalNumUint field
 
@@ -365,7 +348,7 @@ fn test_op_app_params_get() {
 #[test]
 fn test_op_acct_params_get() {
     // Test getting account parameters
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHBYTES); // pushbytes (account)
     bytecode.push(32); // length
     bytecode.extend_from_slice(&[1u8; 32]);
@@ -386,7 +369,7 @@ fn test_op_acct_params_get() {
 #[test]
 fn test_state_opcodes_require_app_mode() {
     // Test that state opcodes fail in signature mode
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHBYTES); // pushbytes
     bytecode.push(7); // length
     bytecode.extend_from_slice(b"counter");
@@ -400,3 +383,69 @@ fn test_state_opcodes_require_app_mode() {
     // Should fail in signature mode
     assert!(result.is_err());
 }
+
+#[test]
+fn test_binary_state_keys() {
+    let mut ledger = setup_mock_ledger();
+    let account = [2u8; 32];
+    assert!(run_app_source(
+        r#"
+        int 7
+        itob
+        int 5
+        app_global_put
+        byte 0xff00fe
+        byte 0x01
+        app_global_put
+        byte 0x0202020202020202020202020202020202020202020202020202020202020202
+        byte 0x80
+        int 9
+        app_local_put
+        byte 0xff00fe
+        app_global_get
+        byte 0x01
+        ==
+        "#,
+        &mut ledger,
+    ));
+
+    assert_eq!(
+        ledger.app_global_get(123, &7u64.to_be_bytes()).unwrap(),
+        Some(TealValue::Uint(5))
+    );
+    assert_eq!(
+        ledger.app_global_get(123, &[0xff, 0x00, 0xfe]).unwrap(),
+        Some(TealValue::Bytes(vec![0x01]))
+    );
+    assert_eq!(
+        ledger
+            .app_local_get(&account.to_vec(), 123, &[0x80])
+            .unwrap(),
+        Some(TealValue::Uint(9))
+    );
+}
+
+#[test]
+fn test_state_key_and_value_limits() {
+    let mut ledger = setup_mock_ledger();
+    let bytes = |len: usize| format!("byte 0x{}", "00".repeat(len));
+    let put =
+        |key_len: usize, value: &str| format!("{}\n{value}\napp_global_put\nint 1", bytes(key_len));
+
+    assert!(run_app_source(&put(64, "int 1"), &mut ledger));
+    assert!(!run_app_source(&put(65, "int 1"), &mut ledger));
+    assert!(run_app_source(&put(64, &bytes(64)), &mut ledger));
+    assert!(!run_app_source(&put(64, &bytes(65)), &mut ledger));
+    assert!(!run_app_source(&put(1, &bytes(128)), &mut ledger));
+
+    let local_put = |key_len: usize, value: &str| {
+        format!(
+            "byte 0x{}\n{}\n{value}\napp_local_put\nint 1",
+            "02".repeat(32),
+            bytes(key_len)
+        )
+    };
+    assert!(run_app_source(&local_put(64, "int 1"), &mut ledger));
+    assert!(!run_app_source(&local_put(65, "int 1"), &mut ledger));
+    assert!(!run_app_source(&local_put(64, &bytes(65)), &mut ledger));
+}
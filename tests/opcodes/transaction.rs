@@ -7,7 +7,7 @@ use crate::common::*;
 #[test]
 fn test_op_txn_sender() {
     // Test accessing sender field
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_TXN);
     bytecode.push(0); // Sender field ID
     bytecode.push(OP_LEN); // Check it's 32 bytes
@@ -19,7 +19,7 @@ fn test_op_txn_sender() {
 #[test]
 fn test_op_txn_fee() {
     // Test accessing fee field
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_TXN);
     bytecode.push(1); // Fee field ID
     bytecode = with_assert_equals(bytecode, StackValue::Uint(1000)); // Default test transaction fee
@@ -30,7 +30,7 @@ fn test_op_txn_fee() {
 #[test]
 fn test_op_txn_amount() {
     // Test accessing amount field
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_TXN);
     bytecode.push(8); // Amount field ID
     bytecode = with_assert_equals(bytecode, StackValue::Uint(1000000)); // Current transaction amount (default payment)
@@ -41,7 +41,7 @@ fn test_op_txn_amount() {
 #[test]
 fn test_op_txn_type_enum() {
     // Test accessing type field
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_TXN);
     bytecode.push(16); // TypeEnum field ID
     bytecode = with_assert_equals(bytecode, StackValue::Uint(1)); // Payment type
@@ -52,7 +52,7 @@ fn test_op_txn_type_enum() {
 #[test]
 fn test_op_txn_invalid_field() {
     // Test accessing invalid field
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_TXN);
     bytecode.push(255); // Invalid field ID
 
@@ -69,7 +69,7 @@ fn test_op_txna_application_args() {
     ledger.add_transaction(tx);
     ledger.set_current_transaction_index(0);
 
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_TXNA);
     bytecode.push(26); // ApplicationArgs field ID
     bytecode.push(1); // Index 1
@@ -83,7 +83,7 @@ fn test_op_txna_application_args() {
 #[test]
 fn test_op_txna_out_of_bounds() {
     // Test accessing out of bounds array index
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_TXNA);
     bytecode.push(26); // ApplicationArgs field ID
     bytecode.push(10); // Out of bounds index
@@ -104,9 +104,9 @@ fn test_op_txnas_with_stack_index() {
     ledger.add_transaction(tx);
     ledger.set_current_transaction_index(0);
 
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&1u64.to_be_bytes()); // Index 1
+    bytecode.extend_from_slice(&encode_varuint(1u64)); // Index 1
     bytecode.push(OP_TXNAS);
     bytecode.push(26); // ApplicationArgs field ID
     bytecode = with_assert_equals(bytecode, StackValue::Bytes(b"second".to_vec()));
@@ -119,7 +119,7 @@ fn test_op_txnas_with_stack_index() {
 #[test]
 fn test_op_gtxn_group_index() {
     // Test accessing transaction in group - let's test with the correct amount
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_GTXN);
     bytecode.push(0); // Group index 0 (test first transaction)
     bytecode.push(8); // Amount field
@@ -135,7 +135,7 @@ fn test_op_gtxn_group_index() {
 #[test]
 fn test_op_gtxn_out_of_group() {
     // Test accessing invalid group index
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_GTXN);
     bytecode.push(10); // Out of bounds group index
     bytecode.push(8); // Amount field
@@ -155,7 +155,7 @@ fn test_op_gtxna_group_array() {
     ledger.add_transaction(tx1);
     ledger.add_transaction(tx2);
 
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_GTXNA);
     bytecode.push(1); // Group index 1
     bytecode.push(26); // ApplicationArgs field
@@ -171,9 +171,9 @@ fn test_op_gtxna_group_array() {
 #[test]
 fn test_op_gtxns_with_stack_index() {
     // Test accessing group transaction with stack index
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&1u64.to_be_bytes()); // Group index 1
+    bytecode.extend_from_slice(&encode_varuint(1u64)); // Group index 1
     bytecode.push(OP_GTXNS);
     bytecode.push(7); // Receiver field
     bytecode.push(OP_LEN); // Check receiver is 32 bytes
@@ -187,8 +187,8 @@ fn test_op_gtxns_with_stack_index() {
 }
 
 #[test]
-fn test_op_gtxnsa_with_stack_indices() {
-    // Test accessing group transaction array with stack indices
+fn test_op_gtxnsa_with_stack_group_index() {
+    // Test accessing group transaction array with the group index from the stack
     let mut ledger = setup_mock_ledger();
     let tx1 = test_transaction();
     let mut tx2 = test_transaction();
@@ -197,13 +197,12 @@ fn test_op_gtxnsa_with_stack_indices() {
     ledger.add_transaction(tx1);
     ledger.add_transaction(tx2);
 
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&1u64.to_be_bytes()); // Group index
-    bytecode.push(OP_PUSHINT); // pushint
-    bytecode.extend_from_slice(&2u64.to_be_bytes()); // Array index
+    bytecode.extend_from_slice(&encode_varuint(1u64)); // Group index
     bytecode.push(OP_GTXNSA);
     bytecode.push(26); // ApplicationArgs field
+    bytecode.push(2); // Array index
     bytecode = with_assert_equals(bytecode, StackValue::Bytes(b"arg2".to_vec()));
 
     let vm = setup_vm();
@@ -215,7 +214,7 @@ fn test_op_gtxnsa_with_stack_indices() {
 #[test]
 fn test_op_global_min_txn_fee() {
     // Test accessing global MinTxnFee
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_GLOBAL);
     bytecode.push(0); // MinTxnFee field
     bytecode = with_assert_equals(bytecode, StackValue::Uint(1000)); // Mock ledger default
@@ -226,7 +225,7 @@ fn test_op_global_min_txn_fee() {
 #[test]
 fn test_op_global_min_balance() {
     // Test accessing global MinBalance
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_GLOBAL);
     bytecode.push(1); // MinBalance field
     bytecode = with_assert_equals(bytecode, StackValue::Uint(100000)); // Mock ledger default
@@ -237,7 +236,7 @@ fn test_op_global_min_balance() {
 #[test]
 fn test_op_global_group_size() {
     // Test accessing global GroupSize
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_GLOBAL);
     bytecode.push(4); // GroupSize field
     bytecode = with_assert_equals(bytecode, StackValue::Uint(3)); // Mock ledger has 3 txns (1 default + 2 added)
@@ -248,7 +247,7 @@ fn test_op_global_group_size() {
 #[test]
 fn test_op_global_current_application_id() {
     // Test accessing global CurrentApplicationID
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_GLOBAL);
     bytecode.push(8); // CurrentApplicationID field
     bytecode = with_assert_equals(bytecode, StackValue::Uint(123)); // Mock ledger app ID
@@ -259,7 +258,7 @@ fn test_op_global_current_application_id() {
 #[test]
 fn test_op_global_zero_address() {
     // Test accessing global ZeroAddress
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_GLOBAL);
     bytecode.push(3); // ZeroAddress field
     bytecode.push(OP_LEN); // Should be 32 bytes
@@ -271,7 +270,7 @@ fn test_op_global_zero_address() {
 #[test]
 fn test_op_global_invalid_field() {
     // Test accessing invalid global field
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
     bytecode.push(OP_GLOBAL);
     bytecode.push(255); // Invalid field ID
 
@@ -281,7 +280,7 @@ fn test_op_global_invalid_field() {
 #[test]
 fn test_transaction_field_combinations() {
     // Test multiple transaction field accesses
-    let mut bytecode = Vec::new();
+    let mut bytecode = program_header();
 
     // Get sender
     bytecode.push(OP_TXN);
@@ -0,0 +1,191 @@
+//! Tests for EvalDelta state change reporting
+
+use avm_rs::{
+    state::{
+        AppParams, EvalDelta, MockLedger, StateChange, StateSchema, ValueDelta,
+        application_address, encode_address,
+    },
+    types::{RunMode, TealValue},
+    vm::ExecutionResult,
+};
+use serde_json::json;
+
+use crate::common::*;
+
+const APP_ID: u64 = 123;
+
+fn app_ledger() -> MockLedger {
+    let mut ledger = setup_mock_ledger();
+    ledger.set_current_application_id(APP_ID);
+    ledger.set_current_application_address(application_address(APP_ID));
+    ledger.set_balance(application_address(APP_ID), 1_000_000);
+    ledger
+}
+
+fn run(source: &str, ledger: &mut MockLedger) -> ExecutionResult {
+    let config = test_config().with_run_mode(RunMode::Application);
+    let result = setup_vm()
+        .execute_detailed(&assemble_v8(source), config, ledger)
+        .unwrap();
+    assert!(result.approved(), "{:?}", result.outcome);
+    result
+}
+
+#[test]
+fn test_encode_address() {
+    assert_eq!(
+        encode_address(&[0u8; 32]),
+        "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAY5HFKQ"
+    );
+}
+
+#[test]
+fn test_global_delta_json_matches_algod() {
+    let mut ledger = app_ledger();
+    let result = run(
+        r#"
+        byte "count"
+        int 5
+        app_global_put
+        byte "name"
+        byte "avm"
+        app_global_put
+        byte "temp"
+        int 1
+        app_global_put
+        byte "temp"
+        app_global_del
+        byte "message"
+        app_global_del
+        int 1
+        "#,
+        &mut ledger,
+    );
+
+    assert_eq!(
+        result.eval_delta.global_delta.get(b"count".as_slice()),
+        Some(&ValueDelta::SetUint(5))
+    );
+    assert_eq!(
+        result.eval_delta.to_json(),
+        json!({
+            "global-state-delta": [
+                { "key": "Y291bnQ=", "value": { "action": 2, "uint": 5 } },
+                { "key": "bWVzc2FnZQ==", "value": { "action": 3 } },
+                { "key": "bmFtZQ==", "value": { "action": 1, "bytes": "YXZt" } },
+                { "key": "dGVtcA==", "value": { "action": 3 } },
+            ]
+        })
+    );
+}
+
+#[test]
+fn test_local_and_balance_deltas() {
+    let account = vec![1u8; 32];
+    let changes = vec![
+        StateChange::LocalPut {
+            address: account.clone(),
+            app_id: APP_ID,
+            key: "score".into(),
+            value: TealValue::Uint(10),
+        },
+        // Another application's state is not part of this call's delta
+        StateChange::LocalPut {
+            address: account.clone(),
+            app_id: 999,
+            key: "other".into(),
+            value: TealValue::Uint(1),
+        },
+        StateChange::Balance {
+            address: account.clone(),
+            previous: 100,
+            amount: 70,
+        },
+        StateChange::Balance {
+            address: account.clone(),
+            previous: 70,
+            amount: 90,
+        },
+        StateChange::Balance {
+            address: vec![2u8; 32],
+            previous: 5,
+            amount: 5,
+        },
+    ];
+
+    let delta = EvalDelta::from_changes(APP_ID, &changes, vec![b"done".to_vec()], Vec::new());
+    assert_eq!(delta.balance_deltas[&account].change(), -10);
+    assert_eq!(
+        serde_json::to_value(&delta).unwrap(),
+        json!({
+            "local-state-delta": [{
+                "address": encode_address(&account),
+                "delta": [{ "key": "c2NvcmU=", "value": { "action": 2, "uint": 10 } }],
+            }],
+            "logs": ["ZG9uZQ=="],
+            "balance-delta": [{
+                "address": encode_address(&account),
+                "before": 100,
+                "after": 90,
+            }],
+        })
+    );
+}
+
+#[test]
+fn test_inner_app_call_delta_is_nested() {
+    let mut ledger = app_ledger();
+    ledger.set_app_params(
+        777,
+        AppParams {
+            approval_program: assemble_v8(
+                "byte \"hits\"\nint 1\napp_global_put\nbyte \"hi\"\nlog\nint 1",
+            ),
+            clear_state_program: assemble_v8("int 1"),
+            global_state_schema: StateSchema {
+                num_uint: 1,
+                num_byte_slice: 0,
+            },
+            local_state_schema: StateSchema {
+                num_uint: 0,
+                num_byte_slice: 0,
+            },
+            extra_program_pages: 0,
+            creator: vec![1u8; 32],
+        },
+    );
+
+    let result = run(
+        r#"
+        itxn_begin
+        int 6
+        itxn_field TypeEnum
+        int 777
+        itxn_field ApplicationID
+        itxn_submit
+        byte "calls"
+        int 1
+        app_global_put
+        int 1
+        "#,
+        &mut ledger,
+    );
+
+    let delta = &result.eval_delta;
+    assert_eq!(delta.global_delta.len(), 1);
+    assert!(delta.global_delta.contains_key(b"calls".as_slice()));
+
+    // The inner call's fee came out of the application account
+    let app_balance = &delta.balance_deltas[&application_address(APP_ID)];
+    assert_eq!(app_balance.change(), -1000);
+
+    assert_eq!(delta.inner_txns.len(), 1);
+    let inner = delta.to_json()["inner-txns"][0].clone();
+    assert_eq!(inner["txn"]["txn"]["type"], "appl");
+    assert_eq!(inner["txn"]["txn"]["apid"], 777);
+    assert_eq!(inner["logs"], json!(["aGk="]));
+    assert_eq!(
+        inner["global-state-delta"],
+        json!([{ "key": "aGl0cw==", "value": { "action": 2, "uint": 1 } }])
+    );
+}
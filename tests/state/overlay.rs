@@ -0,0 +1,141 @@
+//! Tests for the copy-on-write ledger overlay
+
+use avm_rs::{
+    state::{AssetHolding, LedgerAccess, MockLedger, OverlayLedger},
+    types::{RunMode, TealValue},
+};
+
+use crate::common::*;
+
+const APP_ID: u64 = 123;
+
+fn counter(ledger: &dyn LedgerAccess) -> Option<TealValue> {
+    ledger.app_global_get(APP_ID, b"counter").unwrap()
+}
+
+#[test]
+fn test_writes_buffered_until_commit() {
+    let mut ledger = setup_mock_ledger();
+    let account = vec![1u8; 32];
+    let holding = AssetHolding {
+        amount: 5,
+        frozen: false,
+    };
+
+    let mut overlay = OverlayLedger::new(&mut ledger);
+    overlay
+        .app_global_put(APP_ID, b"counter", TealValue::Uint(7))
+        .unwrap();
+    overlay.app_global_del(APP_ID, b"message").unwrap();
+    overlay.balance_put(&account, 1).unwrap();
+    overlay
+        .asset_holding_put(&account, 99, holding.clone())
+        .unwrap();
+    assert!(overlay.box_create(APP_ID, b"box", 4).unwrap());
+    overlay.box_write(APP_ID, b"box", 1, b"ab").unwrap();
+
+    assert_eq!(counter(&overlay), Some(TealValue::Uint(7)));
+    assert_eq!(overlay.app_global_get(APP_ID, b"message").unwrap(), None);
+    assert_eq!(overlay.balance(&account).unwrap(), 1);
+    assert_eq!(overlay.box_read(APP_ID, b"box", 0, 4).unwrap(), b"\0ab\0");
+    assert!(!overlay.is_empty());
+
+    overlay.commit().unwrap();
+    assert_eq!(counter(&ledger), Some(TealValue::Uint(7)));
+    assert_eq!(ledger.app_global_get(APP_ID, b"message").unwrap(), None);
+    assert_eq!(ledger.balance(&account).unwrap(), 1);
+    assert_eq!(ledger.asset_holding(&account, 99).unwrap(), Some(holding));
+    assert_eq!(ledger.get_box(APP_ID, b"box"), Some(&b"\0ab\0"[..]));
+}
+
+#[test]
+fn test_discard_leaves_ledger_untouched() {
+    let mut ledger = setup_mock_ledger();
+    ledger.set_box(APP_ID, "box", b"data".to_vec());
+
+    let mut overlay = OverlayLedger::new(&mut ledger);
+    overlay
+        .app_global_put(APP_ID, b"counter", TealValue::Uint(7))
+        .unwrap();
+    overlay.box_write(APP_ID, b"box", 0, b"DA").unwrap();
+    assert!(overlay.box_delete(APP_ID, b"box").unwrap());
+    assert_eq!(overlay.box_len(APP_ID, b"box").unwrap(), None);
+    overlay.discard();
+
+    assert_eq!(counter(&ledger), Some(TealValue::Uint(42)));
+    assert_eq!(ledger.get_box(APP_ID, b"box"), Some(&b"data"[..]));
+}
+
+#[test]
+fn test_nested_overlays() {
+    let mut ledger = setup_mock_ledger();
+    let mut outer = OverlayLedger::new(&mut ledger);
+
+    let mut inner = OverlayLedger::new(&mut outer);
+    inner
+        .app_global_put(APP_ID, b"counter", TealValue::Uint(1))
+        .unwrap();
+    inner.commit().unwrap();
+    assert_eq!(counter(&outer), Some(TealValue::Uint(1)));
+
+    let mut inner = OverlayLedger::new(&mut outer);
+    inner
+        .app_global_put(APP_ID, b"counter", TealValue::Uint(2))
+        .unwrap();
+    inner.discard();
+    assert_eq!(counter(&outer), Some(TealValue::Uint(1)));
+
+    outer.discard();
+    assert_eq!(counter(&ledger), Some(TealValue::Uint(42)));
+}
+
+#[test]
+fn test_opt_out_clears_local_state() {
+    let mut ledger = MockLedger::new();
+    let account = vec![7u8; 32];
+    ledger.set_opted_in(account.clone(), APP_ID, true);
+    ledger.set_local_state(account.clone(), APP_ID, "old", TealValue::Uint(1));
+
+    let mut overlay = OverlayLedger::new(&mut ledger);
+    overlay.app_opt_out(&account, APP_ID).unwrap();
+    assert_eq!(
+        overlay.app_local_get(&account, APP_ID, b"old").unwrap(),
+        None
+    );
+
+    overlay.app_opt_in(&account, APP_ID).unwrap();
+    overlay
+        .app_local_put(&account, APP_ID, b"new", TealValue::Uint(2))
+        .unwrap();
+    overlay.commit().unwrap();
+
+    assert!(ledger.app_opted_in(&account, APP_ID).unwrap());
+    assert_eq!(
+        ledger.app_local_get(&account, APP_ID, b"old").unwrap(),
+        None
+    );
+    assert_eq!(
+        ledger.app_local_get(&account, APP_ID, b"new").unwrap(),
+        Some(TealValue::Uint(2))
+    );
+}
+
+#[test]
+fn test_execute_only_commits_approved_programs() {
+    let config = test_config().with_run_mode(RunMode::Application);
+    let vm = setup_vm();
+    let mut ledger = setup_mock_ledger();
+    ledger.set_current_application_id(APP_ID);
+
+    let failing = assemble_v8("byte \"counter\"\nint 1\napp_global_put\nerr");
+    assert!(vm.execute(&failing, config.clone(), &mut ledger).is_err());
+    assert_eq!(counter(&ledger), Some(TealValue::Uint(42)));
+
+    let rejecting = assemble_v8("byte \"counter\"\nint 2\napp_global_put\nint 0");
+    assert!(!vm.execute(&rejecting, config.clone(), &mut ledger).unwrap());
+    assert_eq!(counter(&ledger), Some(TealValue::Uint(42)));
+
+    let approving = assemble_v8("byte \"counter\"\nint 3\napp_global_put\nint 1");
+    assert!(vm.execute(&approving, config, &mut ledger).unwrap());
+    assert_eq!(counter(&ledger), Some(TealValue::Uint(3)));
+}
@@ -0,0 +1,247 @@
+//! Tests for the breakpoint debugger
+
+use avm_rs::{
+    assembler::Assembler,
+    state::{LedgerAccess, OverlayLedger},
+    types::{RunMode, StackValue},
+    vm::{Condition, Debugger, Expr, Location, StopReason},
+};
+
+use crate::common::*;
+
+const PROGRAM: &str = "#pragma version 8
+int 3
+int 4
+callsub add
+store 1
+load 1
+int 7
+==
+return
+add:
+proto 2 1
+frame_dig -2
+frame_dig -1
++
+retsub
+";
+
+/// Assemble `PROGRAM`, returning its bytecode and the assembler for its source map
+fn assemble() -> (Vec<u8>, Assembler) {
+    let mut assembler = Assembler::new();
+    let bytecode = assembler.assemble(PROGRAM).unwrap();
+    (bytecode, assembler)
+}
+
+#[test]
+fn test_breakpoints_by_label_and_line() {
+    let vm = setup_vm();
+    let mut ledger = setup_mock_ledger();
+    let mut overlay = OverlayLedger::new(&mut ledger);
+    let (bytecode, assembler) = assemble();
+    let mut debugger = Debugger::new(&vm, &bytecode, test_config(), &mut overlay)
+        .unwrap()
+        .with_source_map(assembler.source_map().clone());
+
+    let label = debugger
+        .add_breakpoint(Some(Location::Label("add".into())), None)
+        .unwrap();
+    let line = debugger
+        .add_breakpoint(Some("line 14".parse().unwrap()), None)
+        .unwrap();
+
+    assert_eq!(debugger.resume(), StopReason::Breakpoint(label));
+    assert_eq!(debugger.context().stack().len(), 2);
+    assert_eq!(debugger.resume(), StopReason::Breakpoint(line));
+    assert_eq!(debugger.context().stack().len(), 4);
+    assert_eq!(debugger.resume(), StopReason::Finished);
+    assert!(debugger.result().unwrap());
+    assert_eq!(debugger.breakpoints()[0].hits, 1);
+
+    // Breakpoints must land on an instruction
+    assert!(
+        debugger
+            .add_breakpoint(Some(Location::Pc(2)), None)
+            .is_err()
+    );
+    assert!(
+        debugger
+            .add_breakpoint(Some(Location::Label("missing".into())), None)
+            .is_err()
+    );
+}
+
+#[test]
+fn test_step_over_and_out() {
+    let vm = setup_vm();
+    let mut ledger = setup_mock_ledger();
+    let mut overlay = OverlayLedger::new(&mut ledger);
+    let (bytecode, _) = assemble();
+    let mut debugger = Debugger::new(&vm, &bytecode, test_config(), &mut overlay).unwrap();
+
+    debugger.step();
+    debugger.step();
+    // Over the callsub to the store after it
+    assert_eq!(debugger.step_over(), StopReason::Step);
+    assert_eq!(debugger.context().stack(), [StackValue::Uint(7)]);
+    assert!(debugger.context().call_stack().is_empty());
+
+    let mut ledger = setup_mock_ledger();
+    let mut overlay = OverlayLedger::new(&mut ledger);
+    let mut debugger = Debugger::new(&vm, &bytecode, test_config(), &mut overlay).unwrap();
+    for _ in 0..4 {
+        debugger.step();
+    }
+    assert_eq!(debugger.context().call_stack().len(), 1);
+    assert_eq!(debugger.step_out(), StopReason::Step);
+    assert!(debugger.context().call_stack().is_empty());
+    assert_eq!(debugger.steps(), 8);
+}
+
+#[test]
+fn test_conditional_breakpoints() {
+    let vm = setup_vm();
+    let mut ledger = setup_mock_ledger();
+    let mut overlay = OverlayLedger::new(&mut ledger);
+    let (bytecode, _) = assemble();
+    let mut debugger = Debugger::new(&vm, &bytecode, test_config(), &mut overlay).unwrap();
+
+    // A condition alone stops where it becomes true, and only there
+    let id = debugger
+        .add_breakpoint(None, Some("cost > 5".parse().unwrap()))
+        .unwrap();
+    assert_eq!(debugger.resume(), StopReason::Breakpoint(id));
+    assert_eq!(debugger.context().cost(), 6);
+    assert_eq!(debugger.resume(), StopReason::Finished);
+
+    // A location with a condition stops only while it holds
+    let mut ledger = setup_mock_ledger();
+    let mut overlay = OverlayLedger::new(&mut ledger);
+    let mut debugger = Debugger::new(&vm, &bytecode, test_config(), &mut overlay).unwrap();
+    let condition: Condition = "stack[-1] == 4".parse().unwrap();
+    let id = debugger
+        .add_breakpoint(Some(Location::Pc(1)), Some(condition))
+        .unwrap();
+    debugger.step();
+    assert_eq!(debugger.resume(), StopReason::Finished);
+    assert_eq!(debugger.breakpoints()[0].id, id);
+    assert_eq!(debugger.breakpoints()[0].hits, 0);
+}
+
+#[test]
+fn test_watch_and_set_values() {
+    let vm = setup_vm();
+    let mut ledger = setup_mock_ledger();
+    let mut overlay = OverlayLedger::new(&mut ledger);
+    let (bytecode, assembler) = assemble();
+    let mut debugger = Debugger::new(&vm, &bytecode, test_config(), &mut overlay)
+        .unwrap()
+        .with_source_map(assembler.source_map().clone());
+
+    debugger.add_watch("scratch[1]".parse().unwrap());
+    debugger
+        .add_breakpoint(Some("line 12".parse().unwrap()), None)
+        .unwrap();
+    debugger.resume();
+    assert_eq!(
+        debugger.evaluate(&"frame[-2]".parse().unwrap()).unwrap(),
+        StackValue::Uint(3)
+    );
+
+    // Make the sum 7 + 4
+    debugger
+        .set_value(&Expr::Frame(-2), StackValue::Uint(7))
+        .unwrap();
+    debugger.step_out();
+    debugger.step();
+    assert_eq!(
+        debugger.watches()[0].1.as_ref().unwrap(),
+        &StackValue::Uint(11)
+    );
+
+    // Replace the loaded sum, so the comparison passes after all
+    debugger.step();
+    debugger
+        .set_value(&Expr::Stack(-1), StackValue::Uint(7))
+        .unwrap();
+    assert_eq!(debugger.context().stack(), [StackValue::Uint(7)]);
+    assert!(
+        debugger
+            .set_value(&Expr::Cost, StackValue::Uint(0))
+            .is_err()
+    );
+    assert!(
+        debugger
+            .set_value(&Expr::Stack(5), StackValue::Uint(0))
+            .is_err()
+    );
+    assert_eq!(debugger.resume(), StopReason::Finished);
+    assert!(debugger.result().unwrap());
+}
+
+#[test]
+fn test_parse_expressions() {
+    assert_eq!("stack[-1]".parse::<Expr>().unwrap(), Expr::Stack(-1));
+    assert_eq!(
+        " scratch[ 12 ] ".parse::<Expr>().unwrap(),
+        Expr::Scratch(12)
+    );
+    assert_eq!(
+        "0x10".parse::<Expr>().unwrap(),
+        Expr::Const(StackValue::Uint(16))
+    );
+    assert_eq!(
+        "\"a<b\"".parse::<Expr>().unwrap(),
+        Expr::Const(StackValue::from("a<b"))
+    );
+    assert!("scratch[256]".parse::<Expr>().is_err());
+    assert!("locals[0]".parse::<Expr>().is_err());
+
+    let condition: Condition = "cost>=500".parse().unwrap();
+    assert_eq!(condition.to_string(), "cost >= 500");
+    assert!("cost".parse::<Condition>().is_err());
+    assert_eq!(
+        "main".parse::<Location>().unwrap(),
+        Location::Label("main".into())
+    );
+    assert_eq!("0x1f".parse::<Location>().unwrap(), Location::Pc(31));
+}
+
+#[test]
+fn test_breakpoint_on_first_instruction() {
+    let vm = setup_vm();
+    let mut ledger = setup_mock_ledger();
+    let mut overlay = OverlayLedger::new(&mut ledger);
+    let (bytecode, assembler) = assemble();
+    let mut debugger = Debugger::new(&vm, &bytecode, test_config(), &mut overlay)
+        .unwrap()
+        .with_source_map(assembler.source_map().clone());
+
+    let id = debugger
+        .add_breakpoint(Some("line 2".parse().unwrap()), None)
+        .unwrap();
+    assert_eq!(debugger.resume(), StopReason::Breakpoint(id));
+    assert_eq!(debugger.steps(), 0);
+    // Resuming leaves the breakpoint execution is stopped at
+    assert_eq!(debugger.resume(), StopReason::Finished);
+    assert_eq!(debugger.breakpoints()[0].hits, 1);
+}
+
+#[test]
+fn test_state_writes_are_buffered() {
+    let vm = setup_vm();
+    let mut ledger = setup_mock_ledger();
+    let app_id = ledger.current_application_id().unwrap();
+    let bytecode = Assembler::new()
+        .assemble("#pragma version 8\nbyte \"k\"\nint 1\napp_global_put\nint 0")
+        .unwrap();
+    let config = test_config().with_run_mode(RunMode::Application);
+
+    let mut overlay = OverlayLedger::new(&mut ledger);
+    let mut debugger = Debugger::new(&vm, &bytecode, config, &mut overlay).unwrap();
+    assert_eq!(debugger.resume(), StopReason::Finished);
+    assert!(!debugger.result().unwrap());
+    drop(debugger);
+    drop(overlay);
+    assert_eq!(ledger.app_global_get(app_id, b"k").unwrap(), None);
+}
@@ -0,0 +1,83 @@
+//! Tests for evaluating TEAL one piece at a time, as the REPL does
+
+use avm_rs::{
+    assembler::Assembler,
+    error::AvmError,
+    types::{StackValue, TealVersion},
+};
+
+use crate::common::*;
+
+#[test]
+fn test_load_program_keeps_machine_state() {
+    let vm = setup_vm();
+    let config = test_config();
+    let mut ledger = setup_mock_ledger();
+    let first = assemble_v8("int 7; int 7; int 7; byte \"x\"; store 1");
+    let mut ctx = vm
+        .create_eval_context(&first, config.clone(), &mut ledger)
+        .unwrap();
+    while !ctx.is_finished() {
+        ctx.step(&vm, &config).unwrap();
+    }
+    let cost = ctx.cost();
+
+    // The second program's constant block replaces the first's
+    ctx.load_program(assemble_v8("int 3; int 3; +; load 1; len"))
+        .unwrap();
+    while !ctx.is_finished() {
+        ctx.step(&vm, &config).unwrap();
+    }
+
+    assert_eq!(
+        ctx.stack(),
+        [7, 7, 7, 6, 1].map(StackValue::Uint).as_slice()
+    );
+    assert_eq!(ctx.get_scratch(1).unwrap(), &StackValue::from("x"));
+    assert!(ctx.cost() > cost);
+}
+
+#[test]
+fn test_load_program_rejects_other_versions() {
+    let vm = setup_vm_with_version(TealVersion::V8);
+    let config = test_config_with_version(TealVersion::V8);
+    let mut ledger = setup_mock_ledger();
+    let mut ctx = vm.create_eval_context(&[8], config, &mut ledger).unwrap();
+
+    let result = ctx.load_program(vec![6, 0x81, 0x01]);
+    assert!(
+        matches!(result, Err(AvmError::InvalidProgram(_))),
+        "{result:?}"
+    );
+}
+
+#[test]
+fn test_parse_constant() {
+    let assembler = Assembler::new();
+    assert_eq!(
+        assembler.parse_constant("0x10", false).unwrap(),
+        StackValue::Uint(16)
+    );
+    assert_eq!(
+        assembler.parse_constant("0x10", true).unwrap(),
+        StackValue::Bytes(vec![0x10])
+    );
+    assert_eq!(
+        assembler.parse_constant("\"a b\"", true).unwrap(),
+        StackValue::from("a b")
+    );
+    assert_eq!(
+        assembler.parse_constant("base64 AQI=", true).unwrap(),
+        StackValue::Bytes(vec![1, 2])
+    );
+    assert_eq!(
+        assembler
+            .parse_constant(
+                "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAY5HFKQ",
+                true
+            )
+            .unwrap(),
+        StackValue::Bytes(vec![0; 32])
+    );
+    assert!(assembler.parse_constant("\"x\"", false).is_err());
+}
@@ -0,0 +1,109 @@
+//! Tests for detailed execution results
+
+use avm_rs::{
+    state::StateChange,
+    types::{RunMode, StackValue, TealValue},
+    vm::ExecutionOutcome,
+};
+
+use crate::common::*;
+
+#[test]
+fn test_approve_reports_cost_and_scratch() {
+    let mut ledger = setup_mock_ledger();
+    let config = test_config().with_cost_budget(100);
+    let result = setup_vm()
+        .execute_detailed(&assemble_v8("int 7\nstore 3\nint 1"), config, &mut ledger)
+        .unwrap();
+
+    assert_eq!(result.outcome, ExecutionOutcome::Approve);
+    assert!(result.approved());
+    assert_eq!(result.cost, 3);
+    assert_eq!(result.remaining_budget, 97);
+    assert_eq!(result.stack, vec![StackValue::Uint(1)]);
+    assert_eq!(result.scratch.len(), 256);
+    assert_eq!(
+        result.used_scratch().collect::<Vec<_>>(),
+        vec![(3, &StackValue::Uint(7))]
+    );
+}
+
+#[test]
+fn test_reject_outcome() {
+    let mut ledger = setup_mock_ledger();
+    let result = setup_vm()
+        .execute_detailed(&assemble_v8("int 0"), test_config(), &mut ledger)
+        .unwrap();
+    assert_eq!(result.outcome, ExecutionOutcome::Reject);
+    assert!(result.error().is_none());
+}
+
+#[test]
+fn test_error_outcome_reports_pc() {
+    let mut ledger = setup_mock_ledger();
+    let bytecode = assemble_v8("int 1\nint 0\n/");
+    let result = setup_vm()
+        .execute_detailed(&bytecode, test_config(), &mut ledger)
+        .unwrap();
+
+    let (pc, message) = result.error().expect("division by zero should fail");
+    assert_eq!(pc, bytecode.len() - 1);
+    assert!(message.contains("Division by zero"));
+    assert!(!result.approved());
+}
+
+#[test]
+fn test_final_stack_error() {
+    let mut ledger = setup_mock_ledger();
+    let result = setup_vm()
+        .execute_detailed(&assemble_v8("int 1\nint 1"), test_config(), &mut ledger)
+        .unwrap();
+    assert!(result.error().is_some());
+    assert_eq!(result.stack.len(), 2);
+}
+
+#[test]
+fn test_state_changes_recorded() {
+    let mut ledger = setup_mock_ledger();
+    let config = test_config().with_run_mode(RunMode::Application);
+    let source = r#"
+        byte "counter"
+        int 43
+        app_global_put
+        byte "message"
+        app_global_del
+        byte "box"
+        byte "abc"
+        box_put
+        int 1
+    "#;
+    let result = setup_vm()
+        .execute_detailed(&assemble_v8(source), config, &mut ledger)
+        .unwrap();
+
+    assert!(result.approved());
+    assert_eq!(
+        result.state_changes,
+        vec![
+            StateChange::GlobalPut {
+                app_id: 123,
+                key: b"counter".to_vec(),
+                value: TealValue::Uint(43),
+            },
+            StateChange::GlobalDel {
+                app_id: 123,
+                key: b"message".to_vec(),
+            },
+            StateChange::BoxPut {
+                app_id: 123,
+                name: b"box".to_vec(),
+                contents: vec![0; 3],
+            },
+            StateChange::BoxPut {
+                app_id: 123,
+                name: b"box".to_vec(),
+                contents: b"abc".to_vec(),
+            },
+        ]
+    );
+}
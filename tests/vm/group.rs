@@ -0,0 +1,249 @@
+//! Tests for atomic transaction group evaluation
+
+use avm_rs::{
+    state::{AppParams, LedgerAccess, MockLedger, StateSchema, Transaction, TransactionType},
+    types::{TealValue, TealVersion},
+    vm::{ExecutionConfig, GroupTransaction},
+};
+
+use crate::common::*;
+
+const APP_ID: u64 = 123;
+
+fn group_config() -> ExecutionConfig {
+    ExecutionConfig::new(TealVersion::V8).with_cost_budget(10_000)
+}
+
+fn payment(sender: u8, receiver: u8, amount: u64) -> Transaction {
+    let mut txn = Transaction::new();
+    txn.tx_type = TransactionType::Payment;
+    txn.sender = vec![sender; 32];
+    txn.receiver = Some(vec![receiver; 32]);
+    txn.amount = Some(amount);
+    txn
+}
+
+fn app_call(sender: u8, app_id: u64) -> Transaction {
+    let mut txn = Transaction::new();
+    txn.tx_type = TransactionType::ApplicationCall;
+    txn.sender = vec![sender; 32];
+    txn.application_id = Some(app_id);
+    txn
+}
+
+fn install_app(ledger: &mut MockLedger, app_id: u64, approval: &str) {
+    ledger.set_app_params(
+        app_id,
+        AppParams {
+            approval_program: assemble_v8(approval),
+            clear_state_program: assemble_v8("int 1"),
+            global_state_schema: StateSchema {
+                num_uint: 4,
+                num_byte_slice: 4,
+            },
+            local_state_schema: StateSchema {
+                num_uint: 0,
+                num_byte_slice: 0,
+            },
+            extra_program_pages: 0,
+            creator: vec![1u8; 32],
+        },
+    );
+}
+
+#[test]
+fn test_payment_then_app_call_sees_group() {
+    let mut ledger = setup_mock_ledger();
+    install_app(
+        &mut ledger,
+        APP_ID,
+        r#"
+        txn GroupIndex
+        int 1
+        ==
+        assert
+        gtxn 0 Amount
+        int 5000
+        ==
+        assert
+        gtxn 0 Receiver
+        balance
+        int 105000
+        ==
+        "#,
+    );
+
+    let group = vec![
+        GroupTransaction::new(payment(1, 3, 5000)),
+        GroupTransaction::new(app_call(2, APP_ID)),
+    ];
+    let result = setup_vm()
+        .evaluate_group(group, &group_config(), &mut ledger)
+        .unwrap();
+
+    assert!(result.approved(), "{:?}", result.transactions);
+    assert_eq!(result.transactions.len(), 2);
+    assert!(result.transactions[1].app_call.as_ref().unwrap().approved());
+    assert_eq!(ledger.balance(&vec![3u8; 32]).unwrap(), 105_000);
+    assert_eq!(
+        ledger.balance(&vec![1u8; 32]).unwrap(),
+        1_000_000 - 5000 - 1000
+    );
+}
+
+#[test]
+fn test_state_and_logs_shared_between_app_calls() {
+    let mut ledger = setup_mock_ledger();
+    install_app(
+        &mut ledger,
+        APP_ID,
+        r#"
+        txn GroupIndex
+        bnz second
+        byte "counter"
+        int 100
+        app_global_put
+        byte "first"
+        log
+        int 1
+        return
+        second:
+        byte "counter"
+        app_global_get
+        int 100
+        ==
+        gtxn 0 LastLog
+        byte "first"
+        ==
+        &&
+        "#,
+    );
+
+    let group = vec![
+        GroupTransaction::new(app_call(1, APP_ID)),
+        GroupTransaction::new(app_call(2, APP_ID)),
+    ];
+    let result = setup_vm()
+        .evaluate_group(group, &group_config(), &mut ledger)
+        .unwrap();
+
+    assert!(result.approved(), "{:?}", result.transactions);
+    assert_eq!(result.transactions[0].txn.logs, vec![b"first".to_vec()]);
+    assert_eq!(
+        ledger.app_global_get(APP_ID, b"counter").unwrap(),
+        Some(TealValue::Uint(100))
+    );
+}
+
+#[test]
+fn test_failure_rolls_back_whole_group() {
+    let mut ledger = setup_mock_ledger();
+    install_app(
+        &mut ledger,
+        APP_ID,
+        r#"
+        byte "counter"
+        int 7
+        app_global_put
+        txn GroupIndex
+        int 2
+        !=
+        "#,
+    );
+
+    let group = vec![
+        GroupTransaction::new(payment(1, 3, 5000)),
+        GroupTransaction::new(app_call(2, APP_ID)),
+        GroupTransaction::new(app_call(2, APP_ID)),
+    ];
+    let result = setup_vm()
+        .evaluate_group(group, &group_config(), &mut ledger)
+        .unwrap();
+
+    assert!(!result.approved());
+    assert_eq!(result.failed_index, Some(2));
+    assert!(result.transactions[2].error.is_some());
+    assert!(result.transactions[1].app_call.as_ref().unwrap().approved());
+
+    assert_eq!(ledger.balance(&vec![3u8; 32]).unwrap(), 100_000);
+    assert_eq!(ledger.balance(&vec![1u8; 32]).unwrap(), 1_000_000);
+    assert_eq!(
+        ledger.app_global_get(APP_ID, b"counter").unwrap(),
+        Some(TealValue::Uint(42))
+    );
+}
+
+#[test]
+fn test_logic_sigs_checked_before_effects() {
+    let mut ledger = setup_mock_ledger();
+    let lsig = assemble_v8("arg 0\nbyte \"secret\"\n==\ngtxn 1 Amount\nint 10\n==\n&&");
+
+    let group = vec![
+        GroupTransaction::new(payment(3, 1, 10))
+            .with_logic_sig(lsig.clone(), vec![b"secret".to_vec()]),
+        GroupTransaction::new(payment(3, 2, 10))
+            .with_logic_sig(lsig.clone(), vec![b"wrong".to_vec()]),
+    ];
+    let result = setup_vm()
+        .evaluate_group(group, &group_config(), &mut ledger)
+        .unwrap();
+
+    assert_eq!(result.failed_index, Some(1));
+    assert!(
+        result.transactions[0]
+            .logic_sig
+            .as_ref()
+            .unwrap()
+            .approved()
+    );
+    assert!(
+        !result.transactions[1]
+            .logic_sig
+            .as_ref()
+            .unwrap()
+            .approved()
+    );
+    assert_eq!(ledger.balance(&vec![3u8; 32]).unwrap(), 100_000);
+}
+
+#[test]
+fn test_app_creation_visible_to_later_transactions() {
+    let mut ledger = setup_mock_ledger();
+    let mut create = app_call(1, 0);
+    create.approval_program = Some(assemble_v8("int 1"));
+    create.clear_state_program = Some(assemble_v8("int 1"));
+
+    let check = app_call(2, APP_ID);
+    install_app(
+        &mut ledger,
+        APP_ID,
+        "gtxn 0 CreatedApplicationID\nint 1000\n>",
+    );
+
+    let group = vec![GroupTransaction::new(create), GroupTransaction::new(check)];
+    let result = setup_vm()
+        .evaluate_group(group, &group_config(), &mut ledger)
+        .unwrap();
+
+    assert!(result.approved(), "{:?}", result.transactions);
+    let created = result.transactions[0].txn.created_application_id.unwrap();
+    assert!(ledger.app_params(created).unwrap().is_some());
+}
+
+#[test]
+fn test_group_size_limits() {
+    let mut ledger = setup_mock_ledger();
+    let vm = setup_vm();
+    assert!(
+        vm.evaluate_group(vec![], &group_config(), &mut ledger)
+            .is_err()
+    );
+
+    let too_many = (0..17)
+        .map(|_| GroupTransaction::new(payment(1, 2, 1)))
+        .collect();
+    assert!(
+        vm.evaluate_group(too_many, &group_config(), &mut ledger)
+            .is_err()
+    );
+}
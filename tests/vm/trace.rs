@@ -0,0 +1,213 @@
+//! Tests for the structured step trace
+
+use avm_rs::{
+    assembler::Assembler,
+    state::StateChange,
+    types::{RunMode, StackValue, TealValue},
+    vm::{ExecutionOutcome, ScratchWrite},
+};
+
+use crate::common::*;
+
+const PROGRAM: &str = "#pragma version 8
+int 3
+dup
+store 1
+byte \"k\"
+swap
+app_global_put
+int 1
+bnz end
+err
+end:
+load 1
+";
+
+fn assemble() -> Vec<u8> {
+    Assembler::new().assemble(PROGRAM).unwrap()
+}
+
+#[test]
+fn test_trace_records_step_effects() {
+    let config = test_config()
+        .with_run_mode(RunMode::Application)
+        .with_step_trace(100);
+    let result = setup_vm()
+        .execute_detailed(&assemble(), config, &mut setup_mock_ledger())
+        .unwrap();
+    assert!(result.approved());
+    assert!(!result.trace_truncated);
+
+    let trace = &result.trace;
+    let opcodes: Vec<&str> = trace.iter().map(|step| step.opcode.as_str()).collect();
+    assert_eq!(
+        opcodes,
+        [
+            "pushint",
+            "dup",
+            "store",
+            "pushbytes",
+            "swap",
+            "app_global_put",
+            "pushint",
+            "bnz",
+            "load"
+        ]
+    );
+
+    // `dup` pops its argument and pushes it twice
+    assert_eq!(trace[1].popped, [StackValue::Uint(3)]);
+    assert_eq!(trace[1].pushed, [StackValue::Uint(3), StackValue::Uint(3)]);
+    assert_eq!(
+        trace[2].scratch_writes,
+        [ScratchWrite {
+            slot: 1,
+            value: StackValue::Uint(3)
+        }]
+    );
+    assert_eq!(
+        trace[4].popped,
+        [StackValue::Uint(3), StackValue::from("k")]
+    );
+    assert_eq!(
+        trace[4].pushed,
+        [StackValue::from("k"), StackValue::Uint(3)]
+    );
+    match trace[5].state_writes.as_slice() {
+        [StateChange::GlobalPut { key, value, .. }] => {
+            assert_eq!(key, b"k");
+            assert_eq!(value, &TealValue::Uint(3));
+        }
+        writes => panic!("unexpected state writes {writes:?}"),
+    }
+    assert!(trace[6].state_writes.is_empty());
+
+    // Branch targets are the pc they land on
+    assert_eq!(trace[7].immediates, ["17"]);
+    assert_eq!(trace[7].to_string(), "PC:0013 bnz 17 (cost: 1)");
+    assert_eq!(trace[3].to_string(), "PC:0006 pushbytes \"k\" (cost: 1)");
+}
+
+/// Stack effect of each step of `source`, as popped and pushed values
+fn stack_effects(source: &str) -> Vec<(Vec<StackValue>, Vec<StackValue>)> {
+    let program = Assembler::new().assemble(source).unwrap();
+    let config = test_config().with_step_trace(100);
+    let result = setup_vm()
+        .execute_detailed(&program, config, &mut setup_mock_ledger())
+        .unwrap();
+    assert!(result.approved(), "{:?}", result.outcome);
+    result
+        .trace
+        .into_iter()
+        .map(|step| (step.popped, step.pushed))
+        .collect()
+}
+
+#[test]
+fn test_stack_effect_follows_signature() {
+    let one = StackValue::Uint(1);
+    let two = StackValue::Uint(2);
+
+    // A result equal to the value beneath it is still pushed
+    let effects = stack_effects("#pragma version 8\npushint 1\npushint 1\n==");
+    assert_eq!(
+        effects[2],
+        (vec![one.clone(), one.clone()], vec![one.clone()])
+    );
+    let effects = stack_effects("#pragma version 8\npushint 1\ndup\n==");
+    assert_eq!(
+        effects[1],
+        (vec![one.clone()], vec![one.clone(), one.clone()])
+    );
+
+    // `dig` reaches down to the value it copies
+    let effects = stack_effects("#pragma version 8\npushint 1\npushint 2\ndig 1\npopn 2");
+    assert_eq!(
+        effects[2],
+        (
+            vec![one.clone(), two.clone()],
+            vec![one.clone(), two.clone(), one.clone()]
+        )
+    );
+    assert_eq!(effects[3], (vec![two.clone(), one.clone()], vec![]));
+
+    // `retsub` clears the frame down to the subroutine's arguments
+    let effects = stack_effects(
+        "#pragma version 8\npushint 2\ncallsub f\nreturn\nf:\nproto 1 1\npushint 1\nretsub",
+    );
+    assert_eq!(effects[4], (vec![two, one.clone()], vec![one]));
+}
+
+#[test]
+fn test_trace_retsub_below_frame() {
+    // The subroutine pops its own arguments and the value beneath them
+    let program = Assembler::new()
+        .assemble(
+            "#pragma version 8\npushint 9\npushint 1\npushint 2\ncallsub f\npushint 1\nreturn\n\
+             f:\nproto 2 0\npop\npop\npop\nretsub",
+        )
+        .unwrap();
+    let config = test_config().with_step_trace(100);
+    let result = setup_vm()
+        .execute_detailed(&program, config, &mut setup_mock_ledger())
+        .unwrap();
+    // `retsub` fails instead of the trace reaching past the stack
+    assert!(matches!(
+        result.outcome,
+        ExecutionOutcome::Error { pc: 19, .. }
+    ));
+    assert_eq!(result.trace.last().unwrap().opcode, "pop");
+}
+
+#[test]
+fn test_trace_is_off_by_default() {
+    let config = test_config().with_run_mode(RunMode::Application);
+    let result = setup_vm()
+        .execute_detailed(&assemble(), config, &mut setup_mock_ledger())
+        .unwrap();
+    assert!(result.approved());
+    assert!(result.trace.is_empty());
+    assert!(!result.trace_truncated);
+}
+
+#[test]
+fn test_trace_limit() {
+    let config = test_config()
+        .with_run_mode(RunMode::Application)
+        .with_step_trace(2);
+    let result = setup_vm()
+        .execute_detailed(&assemble(), config, &mut setup_mock_ledger())
+        .unwrap();
+    assert!(result.approved());
+    assert_eq!(result.trace.len(), 2);
+    assert!(result.trace_truncated);
+}
+
+#[test]
+fn test_toggle_tracing_on_context() {
+    let vm = setup_vm();
+    let config = test_config().with_run_mode(RunMode::Application);
+    let program = assemble();
+    let mut ledger = setup_mock_ledger();
+    let mut ctx = vm
+        .create_eval_context(&program, config.clone(), &mut ledger)
+        .unwrap();
+
+    for _ in 0..5 {
+        ctx.step(&vm, &config).unwrap();
+    }
+    ctx.set_trace_enabled(true);
+    ctx.step(&vm, &config).unwrap();
+    ctx.set_trace_enabled(false);
+    assert!(vm.run(&mut ctx, &config).unwrap());
+
+    assert_eq!(ctx.trace().len(), 1);
+    assert_eq!(ctx.trace()[0].opcode, "app_global_put");
+    assert_eq!(ctx.trace()[0].state_writes.len(), 1);
+    // Writes made while tracing still reach the ledger
+    let app_id = ctx.ledger().current_application_id().unwrap();
+    assert_eq!(
+        ctx.ledger().app_global_get(app_id, b"k").unwrap(),
+        Some(TealValue::Uint(3))
+    );
+}
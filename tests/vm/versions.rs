@@ -0,0 +1,146 @@
+//! Tests for per-opcode version gating
+
+use avm_rs::{
+    assembler::{Assembler, disassemble},
+    error::AvmError,
+    opcodes::{OP_BOX_SPLICE, OP_INTC_0, OP_PUSHINT, OpSpec, standard_opcodes},
+    types::{RunMode, TealVersion},
+};
+
+use crate::common::*;
+
+/// Opcodes introduced after v1, with the version just before their introduction
+fn gated_opcodes() -> impl Iterator<Item = (&'static OpSpec, TealVersion)> {
+    standard_opcodes()
+        .iter()
+        .filter(|spec| spec.min_version > 1)
+        .map(|spec| (spec, TealVersion::from_u8(spec.min_version - 1).unwrap()))
+}
+
+#[test]
+fn test_vm_loads_opcodes_from_their_introduction_version() {
+    for (spec, previous) in gated_opcodes() {
+        let introduced = TealVersion::from_u8(spec.min_version).unwrap();
+        assert!(
+            !setup_vm_with_version(previous).has_opcode(spec.opcode),
+            "{} loaded in v{}",
+            spec.name,
+            previous.as_u8()
+        );
+        assert!(
+            setup_vm_with_version(introduced).has_opcode(spec.opcode),
+            "{} missing in v{}",
+            spec.name,
+            spec.min_version
+        );
+    }
+}
+
+#[test]
+fn test_vm_rejects_opcode_one_version_early() {
+    let mut ledger = setup_mock_ledger();
+    for (spec, previous) in gated_opcodes() {
+        let mode = if spec.modes.contains(&RunMode::Signature) {
+            RunMode::Signature
+        } else {
+            RunMode::Application
+        };
+        // v1 application programs are already rejected by their header
+        if mode == RunMode::Application && previous < TealVersion::V2 {
+            continue;
+        }
+        let config = test_config_with_version(previous).with_run_mode(mode);
+        let program = [previous.as_u8(), spec.opcode];
+        let result = setup_vm().execute(&program, config, &mut ledger);
+        assert!(
+            matches!(result, Err(AvmError::OpcodeNotAvailable { version, .. }) if version == previous.as_u8()),
+            "{} ran in v{}: {result:?}",
+            spec.name,
+            previous.as_u8()
+        );
+    }
+}
+
+#[test]
+fn test_assembler_rejects_opcode_one_version_early() {
+    for (spec, previous) in gated_opcodes() {
+        let source = format!("#pragma version {}\n{}", previous.as_u8(), spec.name);
+        let error = Assembler::new().assemble(&source).unwrap_err().to_string();
+        assert!(
+            error.contains(&format!("introduced in v{}", spec.min_version)),
+            "{}: {error}",
+            spec.name
+        );
+
+        let source = format!("#pragma version {}\n{}", spec.min_version, spec.name);
+        if let Err(error) = Assembler::new().assemble(&source) {
+            assert!(!error.to_string().contains("introduced"), "{error}");
+        }
+    }
+}
+
+#[test]
+fn test_version_boundaries() {
+    assert!(!setup_vm_with_version(TealVersion::V2).has_opcode(OP_PUSHINT));
+    assert!(setup_vm_with_version(TealVersion::V3).has_opcode(OP_PUSHINT));
+    assert!(!setup_vm_with_version(TealVersion::V9).has_opcode(OP_BOX_SPLICE));
+
+    let error = Assembler::new()
+        .assemble("#pragma version 9\nbox_splice")
+        .unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("box_splice opcode was introduced in v10")
+    );
+}
+
+#[test]
+fn test_program_header_version() {
+    let mut ledger = setup_mock_ledger();
+    let program = Assembler::new()
+        .assemble("#pragma version 5\nint 1")
+        .unwrap();
+    assert_eq!(program[0], 5);
+
+    let config = test_config_with_version(TealVersion::V5);
+    assert!(setup_vm().execute(&program, config, &mut ledger).unwrap());
+
+    let config = test_config_with_version(TealVersion::V4);
+    let error = setup_vm()
+        .execute(&program, config, &mut ledger)
+        .unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("greater than the maximum supported version 4")
+    );
+
+    let v1 = [1, OP_INTC_0];
+    let config = test_config().with_run_mode(RunMode::Application);
+    let error = setup_vm().execute(&v1, config, &mut ledger).unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("must be >= 2 for application programs")
+    );
+
+    let error = Assembler::new()
+        .assemble("int 1\n#pragma version 5")
+        .unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("only allowed before instructions")
+    );
+}
+
+#[test]
+fn test_disassemble_prints_version() {
+    let program = Assembler::new()
+        .assemble("#pragma version 4\nint 1\nreturn")
+        .unwrap();
+    let source = disassemble(&program).unwrap();
+    assert!(source.starts_with("#pragma version 4\n"), "{source}");
+    assert!(source.contains("return"));
+}